@@ -6,7 +6,7 @@
 
 #[cfg(feature = "tokio-runtime")]
 use tokio::{
-    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt},
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::TcpStream,
 };
 #[cfg(feature = "tokio-runtime")]
@@ -14,7 +14,7 @@ use tokio_rustls::{client::TlsStream, rustls, TlsConnector};
 
 #[cfg(feature = "async-std-runtime")]
 use async_std::{
-    io::{BufReadExt, BufReader, ReadExt, WriteExt},
+    io::{BufReadExt, BufReader, ReadExt, Write as AsyncWrite, WriteExt},
     net::TcpStream,
     prelude::*,
 };
@@ -22,16 +22,28 @@ use async_std::{
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
+    future::Future,
+    io,
+    pin::Pin,
     sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 use crate::{
+    body::asyncBodyReader,
+    body::{lock, Body},
+    client::{decompressBody, errBodyReadTimeout, redirectRequest, stdDuration, CheckRedirectFunc},
     cookies::{Cookie, CookieJar},
     error::{HTTPConnectError, HttpResult},
-    header::Header,
+    header::{CanonicalHeaderKey, Header},
+    httptrace::{self, ClientTrace},
     method::Method,
-    request::Request,
+    proxy::splitProxyUserinfo,
+    request::{readTrailerKeys, Request},
     response::Response,
+    socks, tls,
+    transfer::{transferReader, transferState},
 };
 use bytes::{Bytes, BytesMut};
 use gostd_builtin::*;
@@ -61,10 +73,10 @@ use gostd_url as url;
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
 ///    let url = "https://petstore.swagger.io/v2/pet/findByStatus?status=available";
-///    let response = async_http::Get(url).await?;
+///    let mut response = async_http::Get(url).await?;
 ///    println!(
 ///        "{}",
-///        String::from_utf8(response.Body.expect("return body error").to_vec()).unwrap()
+///        String::from_utf8(response.Body.ReadAllAsync().await?.to_vec()).unwrap()
 ///    );
 ///    Ok(())
 ///}
@@ -95,10 +107,10 @@ pub async fn Head(url: &str) -> HttpResult<Response> {
 ///     let postbody = r#"{"id":0,"category":{"id":0,"name":"string"},"name":"doggie","photoUrls":["string"],"tags":[{"id":0,"name":"string"}],"status":"available"}"#
 ///     .as_bytes()
 ///     .to_vec();
-///     let response = async_http::Post(url, "application/json", Some(postbody.into())).await?;///
+///     let mut response = async_http::Post(url, "application/json", Some(postbody.into())).await?;///
 ///     println!(
 ///         "{}",
-///         String::from_utf8(response.Body.expect("return body error").to_vec()).unwrap()
+///         String::from_utf8(response.Body.ReadAllAsync().await?.to_vec()).unwrap()
 ///     );///
 ///     Ok(())
 /// }
@@ -125,15 +137,32 @@ pub async fn AsyncClient(url: &str) -> HttpResult<Response> {
 // Async Client
 pub struct AsyncClient {
     transport: Transport,
-    jar: Box<dyn CookieJar>,
-    timeout: time::Duration,
+    /// check_redirect specifies the policy for handling redirects, see Client.CheckRedirect. If it is None, the client stops after 10 consecutive requests.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// check_redirect指定处理重定向的策略，参见Client.CheckRedirect。为None时连续10次请求后停止。
+    /// </details>
+    pub check_redirect: Option<CheckRedirectFunc>,
+    /// jar specifies the cookie jar, see Client.Jar. If it is None, cookies are only sent if they are explicitly set on the Request.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// jar指定cookie jar，参见Client.Jar。为None时只发送请求中显式设置的cookie。
+    /// </details>
+    pub jar: Option<Arc<dyn CookieJar>>,
+    /// timeout specifies a time limit for requests made by this client, see Client.Timeout. It includes connection time, any redirects, and reading the response body. A timeout of zero means no timeout.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// timeout指定该客户端发出的请求的时间限制，参见Client.Timeout。超时时间包括建立连接、所有重定向以及读取响应body的时间。为零表示不限制。
+    /// </details>
+    pub timeout: time::Duration,
 }
 
 impl Default for AsyncClient {
     fn default() -> Self {
         Self {
             transport: Transport::default(),
-            jar: Box::new(Cookie::default()),
+            check_redirect: None,
+            jar: None,
             timeout: time::Duration::new(0),
         }
     }
@@ -144,6 +173,18 @@ impl AsyncClient {
         Self::default()
     }
 
+    /// NewWithTransport returns an AsyncClient that sends its requests through transport.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// NewWithTransport返回一个使用transport发送请求的AsyncClient。
+    /// </details>
+    pub fn NewWithTransport(transport: Transport) -> Self {
+        Self {
+            transport,
+            ..Self::default()
+        }
+    }
+
     pub async fn Get(&mut self, url: &str) -> HttpResult<Response> {
         let mut req = Request::New(Method::Get, url, None)?;
         self.Do(&mut req).await
@@ -193,98 +234,162 @@ impl AsyncClient {
         self.done(req).await
     }
 
+    // send sends req with the cookies of the jar added, and records the cookies of the response in the jar.
     async fn send(
         &mut self,
-        req: &mut Request,
-        deadline: time::Time,
-    ) -> HttpResult<(Response, fn() -> bool)> {
-        let (resp, did_timeout) = send(req, self.transport(), deadline).await?;
-        Ok((resp, did_timeout))
+        req: &Request,
+        deadline: Option<Instant>,
+    ) -> (HttpResult<Response>, impl Fn() -> bool) {
+        let mut req = req.clone();
+        if let Some(jar) = &self.jar {
+            for cookie in jar.Cookies(&req.URL) {
+                req.AddCookie(&cookie);
+            }
+        }
+        let (resp, did_timeout) = send(&mut req, self.transport(), deadline).await;
+        if let (Some(jar), Ok(resp)) = (&self.jar, &resp) {
+            let rc = resp.Cookies();
+            if !rc.is_empty() {
+                jar.SetCookies(&req.URL, rc);
+            }
+        }
+        (resp, did_timeout)
     }
 
     async fn done(&mut self, req: &mut Request) -> HttpResult<Response> {
         let deadline = self.deadline();
-        let (resp, _did_timeout) = self.send(req, deadline).await?;
-        Ok(resp)
+        let mut reqs: Vec<Request> = Vec::new();
+        let mut req = req.clone();
+        // Once a redirect leaves the domain, sensitive headers stay dropped for the rest of the chain.
+        let mut strip_sensitive = false;
+        loop {
+            let (resp, did_timeout) = self.send(&req, deadline).await;
+            let mut resp = match resp {
+                Ok(resp) => resp,
+                Err(err) if did_timeout() => {
+                    return Err(HTTPConnectError::TimeoutError(format!(
+                        "{} (Client.Timeout exceeded while awaiting headers)",
+                        err
+                    )));
+                }
+                Err(err) => return Err(err),
+            };
+            match redirectRequest(
+                &mut reqs,
+                req,
+                &mut resp,
+                self.check_redirect.as_ref(),
+                self.jar.is_some(),
+                &mut strip_sensitive,
+            )? {
+                Some(next) => req = next,
+                None => return Ok(resp),
+            }
+        }
     }
 
-    fn deadline(&mut self) -> time::Time {
-        if self.timeout > time::Duration::new(0) {
-            return time::Now().Add(&self.timeout);
-        }
-        time::Time::default()
+    fn deadline(&self) -> Option<Instant> {
+        stdDuration(&self.timeout).map(|d| Instant::now() + d)
     }
 
     fn transport(&self) -> Transport {
-        Transport::default()
+        self.transport.clone()
     }
 }
 
+// send issues an HTTP request, giving up at deadline. The deadline keeps running after the response header arrived and interrupts reading of the body.
 async fn send(
     ireq: &mut Request,
     mut rt: Transport,
-    deadline: time::Time,
-) -> HttpResult<(Response, fn() -> bool)> {
-    let mut resp = Response::default();
-    fn did_timeout() -> bool {
-        false
+    deadline: Option<Instant>,
+) -> (HttpResult<Response>, impl Fn() -> bool) {
+    let did_timeout = move || deadline.is_some_and(|d| Instant::now() >= d);
+    let d = match deadline {
+        None => return (rt.round_trip(ireq).await, did_timeout),
+        Some(d) => d,
     };
-    loop {
-        let mut resp = rt.round_trip(ireq).await?;
-        let loc = resp.Header.Get("Location");
-        let (redirect_method, should_redirect, include_body) =
-            redirect_behavior(ireq.Method.as_str(), &resp, ireq);
-        if !should_redirect {
-            return Ok((resp, did_timeout));
-        }
-        let u = ireq.URL.Parse(loc.as_str())?;
-        let url_ref = referer_for_url(&ireq.URL, &u);
-        ireq.Method = redirect_method.clone();
-        ireq.URL = u.clone();
-        ireq.Header.Set("Referer", url_ref.as_str());
-    }
-}
-
-fn redirect_behavior(req_method: &str, resp: &Response, ireq: &Request) -> (String, bool, bool) {
-    let mut should_redirect = false;
-    let mut include_body = false;
-    match resp.StatusCode {
-        301 | 302 | 303 => return (Method::Get.String().to_string(), true, false),
-        307 | 308 => {
-            if resp.Header.Get("Location") == "" {
-                should_redirect = true;
-                include_body = false;
-            }
-            if ireq.Body.is_none() && ireq.ContentLength != 0 {
-                should_redirect = false;
+    ireq.deadline = deadline;
+    let left = d.saturating_duration_since(Instant::now());
+    let resp = match with_timeout(left, rt.round_trip(ireq)).await {
+        Some(Ok(mut resp)) => {
+            if !resp.Body.isNoBody() {
+                let trailer = resp.Body.trailerSink();
+                resp.Body = Body::fromAsync(deadlineBody {
+                    body: std::mem::take(&mut resp.Body),
+                    timer: sleep(d.saturating_duration_since(Instant::now())),
+                })
+                .withTrailer(trailer);
             }
+            Ok(resp)
         }
-        _ => (),
-    }
-    (req_method.to_string(), should_redirect, include_body)
+        Some(Err(err)) => Err(err),
+        None => Err(HTTPConnectError::TimeoutError(
+            "net/http: request canceled".to_string(),
+        )),
+    };
+    (resp, did_timeout)
 }
 
-trait AsyncRoundTripper {
-    async fn round_trip(&mut self, r: &Request) -> HttpResult<Response>;
+// with_timeout runs f on the enabled runtime, returning None if it does not finish within timeout.
+pub(crate) async fn with_timeout<F: Future>(timeout: Duration, f: F) -> Option<F::Output> {
+    #[cfg(feature = "tokio-runtime")]
+    return tokio::time::timeout(timeout, f).await.ok();
+    #[cfg(feature = "async-std-runtime")]
+    return async_std::future::timeout(timeout, f).await.ok();
 }
 
-fn referer_for_url(last_req: &url::URL, new_req: &url::URL) -> String {
-    if last_req.Scheme == "https" && new_req.Scheme == "http" {
-        return "".to_string();
-    }
-    let mut referer = last_req.String();
-    if let Some(user) = last_req.User.clone() {
-        return referer;
+pub(crate) fn sleep(d: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+    #[cfg(feature = "tokio-runtime")]
+    return Box::pin(tokio::time::sleep(d));
+    #[cfg(feature = "async-std-runtime")]
+    return Box::pin(async_std::task::sleep(d));
+}
+
+// deadlineBody interrupts reading of a response body once the client's deadline has passed.
+struct deadlineBody {
+    body: Body,
+    timer: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl asyncBodyReader for deadlineBody {
+    fn pollRead(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.timer.as_mut().poll(cx).is_ready() {
+            this.body.Close();
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                errBodyReadTimeout,
+            )));
+        }
+        this.body.pollReadInner(cx, buf)
     }
-    let auth = "@";
-    referer = strings::Replace(referer.as_str(), auth, "", 1);
-    referer
 }
 
+trait AsyncRoundTripper {
+    async fn round_trip(&mut self, r: &Request) -> HttpResult<Response>;
+}
+
+/// Transport is the async implementation of the HTTP and HTTPS round trip.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Transport是异步的http、https请求实现。
+/// </details>
 #[derive(Default, Clone)]
-struct Transport {
+pub struct Transport {
     close_idle: bool,
-    proxy: Option<url::URL>,
+    /// proxy, if set, is the "socks5://[user:password@]host[:port]" proxy every connection is dialed through.
+    pub proxy: Option<url::URL>,
+    /// tls_client_config specifies the TLS configuration to use for HTTPS connections, see Transport.TLSClientConfig. If None, the default configuration is used.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// tls_client_config指定HTTPS连接使用的TLS配置，参见Transport.TLSClientConfig。为None时使用默认配置。
+    /// </details>
+    pub tls_client_config: Option<tls::Config>,
+    tls_config_cache: tls::configCache<tlsClientConfig>,
     force_attempt_http2: bool,
     max_idle_conns: i32,
     disable_keep_alives: bool,
@@ -319,24 +424,121 @@ impl Transport {
         treq: &transportRequest,
         cm: connectMethod,
     ) -> HttpResult<(persistConn, TcpStream)> {
-        let conn = self.dial_conn(cm).await?;
-        let pconn = persistConn::default();
+        let trace = treq.Req.trace();
+        trace.getConn(cm.addr().as_str());
+        let conn = self.dial_conn(cm, trace).await?;
+        trace.gotConn(httptrace::GotConnInfo {
+            Reused: false,
+            WasIdle: false,
+            IdleTime: time::Duration::new(0),
+        });
+        let pconn = persistConn {
+            t: self.clone(),
+            ..Default::default()
+        };
         Ok((pconn, conn))
     }
 
-    async fn dial_conn(&mut self, cm: connectMethod) -> HttpResult<TcpStream> {
-        self.dial("tcp", cm.addr().as_str()).await
+    async fn dial_conn(&mut self, cm: connectMethod, trace: &ClientTrace) -> HttpResult<TcpStream> {
+        if let Some(proxy_url) = &cm.proxy_url {
+            let mut conn = self
+                .dial("tcp", canonical_addr(proxy_url).as_str(), trace)
+                .await?;
+            let auth = cm
+                .proxy_userinfo
+                .clone()
+                .map(|(User, Password)| socks::Auth { User, Password });
+            socks::SOCKS5(proxy_url.Host.as_str(), auth)
+                .DialWithConnAsync(&mut conn, cm.addr().as_str())
+                .await?;
+            return Ok(conn);
+        }
+        self.dial("tcp", cm.addr().as_str(), trace).await
     }
 
-    async fn dial(&mut self, network: &str, addr: &str) -> HttpResult<TcpStream> {
-        Ok(TcpStream::connect(addr).await?)
+    async fn dial(
+        &mut self,
+        network: &str,
+        addr: &str,
+        trace: &ClientTrace,
+    ) -> HttpResult<TcpStream> {
+        let host = match strings::LastIndex(addr, ":") {
+            i if i >= 0 => &addr[..i as usize],
+            _ => addr,
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        // IP地址不需要DNS查询，也不调用DNS钩子。
+        let isIP = host.parse::<std::net::IpAddr>().is_ok();
+        if !isIP {
+            trace.dnsStart(host);
+        }
+        #[cfg(feature = "tokio-runtime")]
+        let lookup = tokio::net::lookup_host(addr).await;
+        #[cfg(feature = "async-std-runtime")]
+        let lookup = async_std::net::ToSocketAddrs::to_socket_addrs(addr).await;
+        let addrs: Vec<std::net::SocketAddr> = match lookup {
+            Ok(addrs) => addrs.collect(),
+            Err(err) => {
+                let err = HTTPConnectError::from(err);
+                if !isIP {
+                    trace.dnsDone(vec![], Some(&err));
+                }
+                return Err(err);
+            }
+        };
+        if !isIP {
+            trace.dnsDone(addrs.iter().map(|sa| sa.ip()).collect(), None);
+        }
+        let mut lastErr = io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        );
+        for sa in addrs {
+            let saddr = sa.to_string();
+            trace.connectStart(network, saddr.as_str());
+            match TcpStream::connect(sa).await {
+                Ok(conn) => {
+                    trace.connectDone(network, saddr.as_str(), None);
+                    return Ok(conn);
+                }
+                Err(err) => {
+                    let traced = io::Error::new(err.kind(), err.to_string());
+                    trace.connectDone(network, saddr.as_str(), Some(&traced.into()));
+                    lastErr = err;
+                }
+            }
+        }
+        Err(lastErr.into())
+    }
+
+    // tls_config returns the TLS configuration built from tls_client_config, building it on first use.
+    fn tls_config(&self) -> HttpResult<Arc<tlsClientConfig>> {
+        #[cfg(feature = "tokio-runtime")]
+        return match &self.tls_client_config {
+            Some(cfg) => self.tls_config_cache.get(|| tls::clientConfig(Some(cfg))),
+            None => tls::clientConfig(None),
+        };
+        #[cfg(feature = "async-std-runtime")]
+        return self
+            .tls_config_cache
+            .get(|| tls::asyncStdClientConfig(self.tls_client_config.as_ref()));
     }
 
     fn connect_method_for_request(&mut self, treq: &transportRequest) -> HttpResult<connectMethod> {
         let mut cm = connectMethod::default();
         cm.target_scheme = treq.Req.URL.Scheme.clone();
         cm.target_addr = canonical_addr(&treq.Req.URL.clone());
-        cm.proxy_url = None;
+        if let Some(proxy) = &self.proxy {
+            if proxy.Scheme != "socks5" {
+                return Err(HTTPConnectError::ConnectionFailure(format!(
+                    "proxyconnect: unsupported proxy scheme {:?}",
+                    proxy.Scheme
+                )));
+            }
+            let (userinfo, proxy_url) = splitProxyUserinfo(proxy);
+            cm.proxy_url = Some(proxy_url);
+            cm.proxy_userinfo = userinfo;
+        }
         cm.only_h1 = true;
         Ok(cm)
     }
@@ -394,6 +596,7 @@ struct connectMethod {
     target_scheme: String,
     target_addr: String,
     only_h1: bool,
+    proxy_userinfo: Option<(String, String)>,
 }
 
 impl connectMethod {
@@ -424,7 +627,7 @@ impl persistConn {
     async fn round_trip(
         &mut self,
         req: &mut transportRequest,
-        mut conn: TcpConn,
+        conn: TcpConn,
     ) -> HttpResult<Response> {
         self.num_expected_responses += 1;
         let mut requested_gzip = false;
@@ -434,64 +637,151 @@ impl persistConn {
             && req.Req.Method != "HEAD".to_string()
         {
             requested_gzip = true;
+            req.Req.Header.Set("Accept-Encoding", "gzip");
         }
         if req.Req.Close {
             req.Req.Header.Set("Connection", "close");
         }
 
-        let r = req.Req.Write()?;
+        let mut resp = self.write_request_read_response(req, conn).await?;
+        if requested_gzip {
+            decompressBody(&mut resp);
+        }
+        Ok(resp)
+    }
+
+    async fn write_request_read_response(
+        &mut self,
+        req: &mut transportRequest,
+        mut conn: TcpConn,
+    ) -> HttpResult<Response> {
         #[cfg(feature = "tokio-runtime")]
         {
             if req.Req.isTLS {
-                let mut tls_conn = get_tls_conn(req.Req.Host.as_str(), conn).await?;
-                tls_conn.write_all(r.as_slice()).await?;
-                let mut reader = tokio::io::BufReader::new(tls_conn);
-                let resp = read_response(&mut reader, &req.Req).await?;
+                let mut tls_conn =
+                    get_tls_conn(&self.t, req.Req.Host.as_str(), conn, req.Req.trace()).await?;
+                write_request_traced(&req.Req, &mut tls_conn).await?;
+                let reader = tokio::io::BufReader::new(tls_conn);
+                let resp = read_response(reader, &req.Req).await?;
                 Ok(resp)
             } else {
-                conn.write_all(r.as_slice()).await?;
-                let mut reader = tokio::io::BufReader::new(conn);
-                let resp = read_response(&mut reader, &req.Req).await?;
+                write_request_traced(&req.Req, &mut conn).await?;
+                let reader = tokio::io::BufReader::new(conn);
+                let resp = read_response(reader, &req.Req).await?;
                 Ok(resp)
             }
         }
         #[cfg(feature = "async-std-runtime")]
         {
             if req.Req.isTLS {
-                let mut tls_conn = get_tls_conn(req.Req.Host.as_str(), conn).await?;
-                tls_conn.write_all(r.as_slice()).await?;
-                let mut reader = BufReader::new(tls_conn);
-                let resp = read_response(&mut reader, &req.Req).await?;
+                let mut tls_conn =
+                    get_tls_conn(&self.t, req.Req.Host.as_str(), conn, req.Req.trace()).await?;
+                write_request_traced(&req.Req, &mut tls_conn).await?;
+                let reader = BufReader::new(tls_conn);
+                let resp = read_response(reader, &req.Req).await?;
                 Ok(resp)
             } else {
-                conn.write_all(r.as_slice()).await?;
-                let mut reader = BufReader::new(conn);
-                let resp = read_response(&mut reader, &req.Req).await?;
+                write_request_traced(&req.Req, &mut conn).await?;
+                let reader = BufReader::new(conn);
+                let resp = read_response(reader, &req.Req).await?;
                 Ok(resp)
             }
         }
     }
 }
 
-#[cfg(feature = "tokio-runtime")]
-fn get_tls_config() -> Arc<rustls::ClientConfig> {
-    let mut root_cert_store = rustls::RootCertStore::empty();
-    root_cert_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+// write_request_traced writes req to w and reports the result to the request's ClientTrace.
+async fn write_request_traced<W>(req: &Request, w: &mut W) -> HttpResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let r = write_request(req, w).await;
+    req.trace().wroteRequest(r.as_ref().err());
+    r
+}
+
+// write_request writes req to w in wire format, streaming its body straight to the connection.
+async fn write_request<W>(req: &Request, w: &mut W) -> HttpResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    w.write_all(req.writeHead(false).as_bytes()).await?;
+    req.trace().wroteHeaders();
+    if let Some(body) = &req.Body {
+        write_body(w, &mut body.clone(), req.outgoingLength()).await?;
+    }
+    w.flush().await?;
+    Ok(())
+}
 
-    Arc::new(
-        rustls::ClientConfig::builder()
-            .with_root_certificates(root_cert_store)
-            .with_no_client_auth(),
-    )
+// write_body is the async counterpart of transfer::writeBody.
+async fn write_body<W>(w: &mut W, body: &mut Body, length: i64) -> HttpResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; 32 << 10];
+    if length < 0 {
+        loop {
+            let n = body.readAsync(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            let mut chunk = format!("{:x}\r\n", n).into_bytes();
+            chunk.extend_from_slice(&buf[..n]);
+            chunk.extend_from_slice(b"\r\n");
+            w.write_all(&chunk).await?;
+        }
+        w.write_all(b"0\r\n\r\n").await?;
+        return Ok(());
+    }
+    let mut ncopy: i64 = 0;
+    while ncopy < length {
+        let max = std::cmp::min(buf.len() as i64, length - ncopy) as usize;
+        let n = body.readAsync(&mut buf[..max]).await?;
+        if n == 0 {
+            break;
+        }
+        w.write_all(&buf[..n]).await?;
+        ncopy += n as i64;
+    }
+    if ncopy != length {
+        return Err(HTTPConnectError::ConnectionFailure(format!(
+            "http: ContentLength={} with Body length {}",
+            length, ncopy
+        )));
+    }
+    Ok(())
 }
 
 #[cfg(feature = "tokio-runtime")]
-async fn get_tls_conn(dns_name: &str, socket: TcpStream) -> HttpResult<TlsStream<TcpStream>> {
-    let tls_config = get_tls_config();
-    let server_name = dns_name.to_owned().try_into()?;
-    let connector = TlsConnector::from(tls_config.clone());
-    let tls_stream = connector.connect(server_name, socket).await?;
-    Ok(tls_stream)
+type tlsClientConfig = rustls::ClientConfig;
+#[cfg(feature = "async-std-runtime")]
+type tlsClientConfig = rustls_021::ClientConfig;
+
+#[cfg(feature = "tokio-runtime")]
+async fn get_tls_conn(
+    t: &Transport,
+    dns_name: &str,
+    socket: TcpStream,
+    trace: &ClientTrace,
+) -> HttpResult<TlsStream<TcpStream>> {
+    let tls_config = t.tls_config()?;
+    let name = tls::serverName(t.tls_client_config.as_ref(), dns_name);
+    let server_name = name.clone().try_into()?;
+    let connector = TlsConnector::from(tls_config);
+    trace.tlsHandshakeStart();
+    match connector.connect(server_name, socket).await {
+        Ok(tls_stream) => {
+            let state = tls::connectionState(tls_stream.get_ref().1, name.as_str());
+            trace.tlsHandshakeDone(&state, None);
+            Ok(tls_stream)
+        }
+        Err(err) => {
+            let err = HTTPConnectError::from(err);
+            trace.tlsHandshakeDone(&tls::ConnectionState::default(), Some(&err));
+            Err(err)
+        }
+    }
 }
 
 #[cfg(feature = "async-std-runtime")]
@@ -499,18 +789,36 @@ use async_tls::{client::TlsStream, TlsConnector};
 // 使用 async-std 运行时
 #[cfg(feature = "async-std-runtime")]
 async fn get_tls_conn(
+    t: &Transport,
     dns_name: &str,
     socket: async_std::net::TcpStream,
+    trace: &ClientTrace,
 ) -> HttpResult<TlsStream<async_std::net::TcpStream>> {
-    let server_name = dns_name.to_owned();
-    let connector = TlsConnector::default();
-    let tls_stream = connector.connect(server_name, socket).await?;
-    Ok(tls_stream)
+    let server_name = tls::serverName(t.tls_client_config.as_ref(), dns_name);
+    let connector = TlsConnector::from(t.tls_config()?);
+    trace.tlsHandshakeStart();
+    match connector.connect(server_name.as_str(), socket).await {
+        Ok(tls_stream) => {
+            // async-tls不公开会话信息，只能报告握手已完成和服务器名称。
+            let state = tls::ConnectionState {
+                HandshakeComplete: true,
+                ServerName: server_name,
+                ..Default::default()
+            };
+            trace.tlsHandshakeDone(&state, None);
+            Ok(tls_stream)
+        }
+        Err(err) => {
+            let err = HTTPConnectError::from(err);
+            trace.tlsHandshakeDone(&tls::ConnectionState::default(), Some(&err));
+            Err(err)
+        }
+    }
 }
 #[cfg(feature = "tokio-runtime")]
 async fn read_response<R>(mut r: R, req: &Request) -> HttpResult<Response>
 where
-    R: AsyncBufRead + Unpin,
+    R: AsyncBufRead + Unpin + Send + 'static,
 {
     let mut resp = Response {
         request: req.clone(),
@@ -520,6 +828,9 @@ where
     // Parse status line.
     let mut line = String::new();
     r.read_line(&mut line).await?;
+    if !line.is_empty() {
+        req.trace().gotFirstResponseByte();
+    }
     let parts: Vec<&str> = line.split_whitespace().collect();
     if parts.len() < 3 {
         return Err(HTTPConnectError::ConnectionFailure(
@@ -555,53 +866,31 @@ where
     resp.Header = Header::NewWithHashMap(parse_header(&head_part)?);
     fix_pragma_cache_control(&mut resp.Header);
 
-    // Set body based on transfer encoding or content length.
-    if resp.Header.Get("Transfer-Encoding") == "chunked" {
-        resp.Body = Some(parse_chunked_body(&mut r).await?);
-    } else {
-        let ln: usize = resp
-            .Header
-            .Get("Content-Length")
-            .parse::<usize>()
-            .expect("Content-Length is not exist");
-        let mut buf = vec![0; ln];
-        r.read_exact(&mut buf).await?;
-        resp.Body = Some(BytesMut::from(&buf[..]));
-    }
-
-    resp.ContentLength = resp.Body.as_ref().map_or(0, |b| b.len() as i64);
-    Ok(resp)
-}
-#[cfg(feature = "tokio-runtime")]
-async fn parse_chunked_body<R>(mut r: R) -> HttpResult<BytesMut>
-where
-    R: AsyncBufRead + Unpin,
-{
-    let mut body = BytesMut::new();
-    let mut size_buf = vec![];
-    while r.read_until(b'\n', &mut size_buf).await.is_ok() {
-        if size_buf.ends_with(b"\r\n") {
-            size_buf.truncate(size_buf.len() - 2); // Remove "\r\n"
-            let size_str = std::str::from_utf8(&size_buf)?;
-            if size_str == "0" {
-                break;
-            }
-            let chunk_size = usize::from_str_radix(size_str, 16)?;
-            let mut chunk_data = vec![0u8; chunk_size];
-            r.read_exact(&mut chunk_data).await?;
-            body.extend_from_slice(&chunk_data);
-            let mut crlf = [0u8; 2];
-            r.read_exact(&mut crlf).await?;
-            size_buf.clear();
-        }
+    // The body stays on the connection and is streamed by resp.Body.
+    let state = transferState::forResponse(&resp, req)?;
+    resp.ContentLength = match &state {
+        transferState::Done => 0,
+        transferState::ContentLength(n) => *n as i64,
+        _ => -1,
+    };
+    if let transferState::Chunked(_) = &state {
+        resp.TransferEncoding = vec!["chunked".to_string()];
+        // Trailer is shared with the body, which adds the values once it reaches EOF.
+        resp.Trailer = state.trailer().unwrap_or_default();
+        *lock(&resp.Trailer) = readTrailerKeys(&resp.Header)?;
+        resp.Header.Del("Trailer");
     }
-    Ok(body)
+    if !state.isDone() {
+        let trailer = state.trailer();
+        resp.Body = Body::fromAsync(transferReader::new(r, state)).withTrailer(trailer);
+    }
+    Ok(resp)
 }
 
 #[cfg(feature = "async-std-runtime")]
 async fn read_response<R>(mut r: R, req: &Request) -> HttpResult<Response>
 where
-    R: BufReadExt + Unpin,
+    R: BufReadExt + Unpin + Send + 'static,
 {
     let mut resp = Response {
         request: req.clone(),
@@ -610,8 +899,10 @@ where
 
     // Parse status line.
     let mut line = String::new();
-
     r.read_line(&mut line).await?;
+    if !line.is_empty() {
+        req.trace().gotFirstResponseByte();
+    }
     let parts: Vec<&str> = line.split_whitespace().collect();
     if parts.len() < 3 {
         return Err(HTTPConnectError::ConnectionFailure(
@@ -647,56 +938,32 @@ where
     resp.Header = Header::NewWithHashMap(parse_header(&head_part)?);
     fix_pragma_cache_control(&mut resp.Header);
 
-    // Set body based on transfer encoding or content length.
-    if resp.Header.Get("Transfer-Encoding") == "chunked" {
-        resp.Body = Some(parse_chunked_body(&mut r).await?);
-    } else {
-        let ln: usize = resp
-            .Header
-            .Get("Content-Length")
-            .parse::<usize>()
-            .expect("Content-Length is not exist");
-        let mut buf = vec![0; ln];
-        r.read_exact(&mut buf).await?;
-        resp.Body = Some(BytesMut::from(&buf[..]));
-    }
-
-    resp.ContentLength = resp.Body.as_ref().map_or(0, |b| b.len() as i64);
+    // The body stays on the connection and is streamed by resp.Body.
+    let state = transferState::forResponse(&resp, req)?;
+    resp.ContentLength = match &state {
+        transferState::Done => 0,
+        transferState::ContentLength(n) => *n as i64,
+        _ => -1,
+    };
+    if let transferState::Chunked(_) = &state {
+        resp.TransferEncoding = vec!["chunked".to_string()];
+        // Trailer is shared with the body, which adds the values once it reaches EOF.
+        resp.Trailer = state.trailer().unwrap_or_default();
+        *lock(&resp.Trailer) = readTrailerKeys(&resp.Header)?;
+        resp.Header.Del("Trailer");
+    }
+    if !state.isDone() {
+        let trailer = state.trailer();
+        resp.Body = Body::fromAsync(transferReader::new(r, state)).withTrailer(trailer);
+    }
     Ok(resp)
 }
 
-#[cfg(feature = "async-std-runtime")]
-async fn parse_chunked_body<R>(mut r: R) -> HttpResult<BytesMut>
-where
-    R: BufReadExt + Unpin,
-{
-    let mut body = BytesMut::new();
-    let mut size_buf = vec![];
-    while r.read_until(b'\n', &mut size_buf).await.is_ok() {
-        if size_buf.ends_with(b"\r\n") {
-            size_buf.truncate(size_buf.len() - 2); // Remove "\r\n"
-            let size_str = std::str::from_utf8(&size_buf)?;
-            if size_str == "0" {
-                break;
-            }
-            let chunk_size = usize::from_str_radix(size_str, 16)?;
-            let mut chunk_data = vec![0u8; chunk_size];
-            r.read_exact(&mut chunk_data).await?;
-            body.extend_from_slice(&chunk_data);
-            let mut crlf = [0u8; 2];
-            r.read_exact(&mut crlf).await?;
-            size_buf.clear();
-        }
-    }
-    Ok(body)
-}
 pub type MIMEHeader = HashMap<String, Vec<String>>;
 
 fn fix_pragma_cache_control(header: &mut Header) {
-    if let Some(hp) = header.0.get("Pragma") {
-        if hp.len() > 0 && &hp[0] == "no-cache" && header.0.get("Cache-Control").is_none() {
-            header.Set("Cache-Control", "no-cache");
-        }
+    if header.Get("Pragma") == "no-cache" && header.Values("Cache-Control").is_empty() {
+        header.Set("Cache-Control", "no-cache");
     }
 }
 
@@ -706,7 +973,7 @@ fn parse_header(head_part: &[u8]) -> HttpResult<MIMEHeader> {
 
     for kv in lines.split("\r\n") {
         if let Some((key, value)) = kv.split_once(':') {
-            let key = canonical_mime_header_key(key);
+            let key = CanonicalHeaderKey(key);
             if key.is_empty() {
                 continue;
             }
@@ -737,25 +1004,6 @@ fn start_index_of_body(response: &Vec<u8>) -> Option<usize> {
     None
 }
 
-const TO_LOWER: u8 = b'a' - b'A';
-
-fn canonical_mime_header_key(s: &str) -> String {
-    let mut upper = true;
-    let mut new = String::with_capacity(s.len());
-    for &byte in s.as_bytes() {
-        let c = if upper && byte >= b'a' && byte <= b'z' {
-            byte - TO_LOWER
-        } else if !upper && byte >= b'A' && byte <= b'Z' {
-            byte + TO_LOWER
-        } else {
-            byte
-        };
-        upper = c == b'-';
-        new.push(c as char);
-    }
-    new
-}
-
 pub fn parse_http_version(vers: &str) -> (isize, isize, bool) {
     let big: isize = 1_000_000;
 
@@ -0,0 +1,1221 @@
+//! Package async_server provides an HTTP/1.1 server for the tokio or async-std runtime, whichever is enabled. It reads requests with the same parser as the sync server, routes them with the same ServeMux patterns and frames responses the same way, so a handler written against AsyncHandler runs unchanged on either runtime.
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! async_server包提供运行在tokio或async-std运行时(取决于启用的feature)上的HTTP/1.1服务器。它与同步服务器使用相同的请求解析、相同的ServeMux模式路由和相同的响应格式，所以基于AsyncHandler编写的handler无需修改就可以在两种运行时上运行。
+//! </details>
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gostd_http as http;
+//! use http::async_server::{ServeMux, Server};
+//!
+//! # #[cfg(feature = "tokio-runtime")]
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut mux = ServeMux::default();
+//!     mux.HandleFunc("GET /hello/{name}", |w, r| {
+//!         Box::pin(async move {
+//!             let msg = format!("hello, {}\n", r.PathValue("name"));
+//!             w.Write(msg.as_bytes()).await.unwrap();
+//!         })
+//!     });
+//!     Server::New("127.0.0.1:8080", mux).ListenAndServe().await?;
+//!     Ok(())
+//! }
+//! # #[cfg(not(feature = "tokio-runtime"))]
+//! # fn main() {}
+//! ```
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+#[cfg(feature = "tokio-runtime")]
+use tokio::{
+    io::{AsyncBufRead, AsyncWrite, BufReader, BufWriter},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+};
+
+#[cfg(feature = "async-std-runtime")]
+use async_std::{
+    io::{BufRead as AsyncBufRead, BufReader, BufWriter, Write as AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
+
+use std::{
+    convert::TryFrom,
+    future::{poll_fn, Future},
+    io::{self, Cursor},
+    net::{self, Shutdown},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use gostd_builtin::*;
+use gostd_time as time;
+
+use crate::{
+    async_http::{sleep, with_timeout},
+    body::{asyncBodyReader, lock, Body},
+    client::stdDuration,
+    error::{HTTPConnectError, HttpResult},
+    header::Header,
+    pattern::{route, router},
+    request::{readRequest, Request},
+    server::{
+        checkRequest, errorHeader, errorReply, listenAddr, maxPostHandlerReadBytes, panicMessage,
+        redirectBody, redirectHeader, requestError, responseState, serverState, shutdownDeadline,
+        shutdownPollIntervalMin, ConnState, ConnStateFunc, DefaultMaxHeaderBytes, ErrAbortHandler,
+        ErrorLogFunc,
+    },
+    status::StatusText,
+    transfer::transferState,
+};
+
+/// HandlerFuture is the future returned by AsyncHandler::ServeHttp. It borrows the ResponseWriter and the Request for as long as the handler runs.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HandlerFuture是AsyncHandler::ServeHttp返回的future，handler运行期间借用ResponseWriter和Request。
+/// </details>
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// WriteFuture is the future returned by AsyncResponseWriter::Write.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// WriteFuture是AsyncResponseWriter::Write返回的future。
+/// </details>
+pub type WriteFuture<'a> = Pin<Box<dyn Future<Output = HttpResult<usize>> + Send + 'a>>;
+
+/// FlushFuture is the future returned by AsyncResponseWriter::Flush.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// FlushFuture是AsyncResponseWriter::Flush返回的future。
+/// </details>
+pub type FlushFuture<'a> = Pin<Box<dyn Future<Output = HttpResult<()>> + Send + 'a>>;
+
+/// An AsyncHandler responds to an HTTP request on the async server.
+///
+/// ServeHttp returns a future that writes reply headers and data to the AsyncResponseWriter. Its completion signals that the request is finished; the AsyncResponseWriter and the request Body must not be used afterwards. If the future panics, the server logs the panic and closes the connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// AsyncHandler在异步服务器上响应一个HTTP请求。
+///
+/// ServeHttp返回一个future，把响应头和数据写入AsyncResponseWriter。future完成表示请求已经处理完成，之后不能再使用AsyncResponseWriter和请求的Body。如果future发生panic，服务器记录panic信息并关闭连接。
+/// </details>
+pub trait AsyncHandler: Send + Sync {
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a>;
+}
+
+impl<H: AsyncHandler + ?Sized> AsyncHandler for Arc<H> {
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a> {
+        (**self).ServeHttp(w, r)
+    }
+}
+
+impl<H: AsyncHandler + ?Sized> AsyncHandler for Box<H> {
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a> {
+        (**self).ServeHttp(w, r)
+    }
+}
+
+/// The AsyncHandlerFunc type is an adapter to allow the use of closures returning a boxed future as async HTTP handlers. AsyncHandlerFunc(f) is an AsyncHandler that calls f.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// AsyncHandlerFunc是一个适配器，允许把返回boxed future的闭包用作异步HTTP处理器。AsyncHandlerFunc(f)是调用f的AsyncHandler。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use http::async_server::AsyncHandlerFunc;
+///
+/// let hello = AsyncHandlerFunc(|w, r| {
+///     Box::pin(async move {
+///         let _ = w.Write(format!("hello from {}", r.URL.Path).as_bytes()).await;
+///     })
+/// });
+/// ```
+pub struct AsyncHandlerFunc<F>(pub F)
+where
+    F: for<'a> Fn(&'a mut dyn AsyncResponseWriter, &'a mut Request) -> HandlerFuture<'a>
+        + Send
+        + Sync;
+
+impl<F> AsyncHandler for AsyncHandlerFunc<F>
+where
+    F: for<'a> Fn(&'a mut dyn AsyncResponseWriter, &'a mut Request) -> HandlerFuture<'a>
+        + Send
+        + Sync,
+{
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a> {
+        (self.0)(w, r)
+    }
+}
+
+/// An AsyncResponseWriter is used by an AsyncHandler to construct an HTTP response. It buffers and frames the body like the ResponseWriter of the sync server.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// AsyncHandler使用AsyncResponseWriter构造HTTP响应，body的缓存和格式与同步服务器的ResponseWriter相同。
+/// </details>
+pub trait AsyncResponseWriter: Send {
+    /// Header returns the header map that will be sent by WriteHeader. Changing the header after a call to WriteHeader (or Write) has no effect, except for trailers declared with the Trailer header or set with TrailerPrefix.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Header返回WriteHeader将要发送的header。调用WriteHeader(或Write)之后修改header没有效果，Trailer头声明的字段和TrailerPrefix开头的字段除外。
+    /// </details>
+    fn Header(&mut self) -> &mut Header;
+
+    /// Write writes the data to the connection as part of an HTTP reply. If WriteHeader has not yet been called, Write calls WriteHeader(200) before writing the data.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Write把数据作为HTTP响应的一部分写入连接。如果还没有调用WriteHeader，Write会先调用WriteHeader(200)。
+    /// </details>
+    fn Write<'a>(&'a mut self, buf: &'a [u8]) -> WriteFuture<'a>;
+
+    /// WriteHeader records the status code of the response; the header is sent with the body. Informational 1xx codes other than 101 are sent with the next Write or Flush and may be followed by the final status code.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// WriteHeader记录响应的状态码，响应头与body一起发送。101以外的1xx信息状态码在下一次Write或Flush时发送，之后还可以设置最终的状态码。
+    /// </details>
+    fn WriteHeader(&mut self, status_code: u16);
+
+    /// Flush sends the header and any buffered data to the client.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Flush把响应头和所有缓存的数据发送给客户端。
+    /// </details>
+    fn Flush(&mut self) -> FlushFuture<'_>;
+}
+
+/// Error replies to the request with the specified error message and HTTP code. It does not otherwise end the request; the caller should ensure no further writes are done to w. The error message should be plain text.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Error使用指定的错误信息和HTTP状态码回复请求。它不会结束请求，调用者应该确保之后不再写入w。错误信息应该是纯文本。
+/// </details>
+pub async fn Error(w: &mut dyn AsyncResponseWriter, error: &str, code: u16) {
+    errorHeader(w.Header());
+    w.WriteHeader(code);
+    let _ = w.Write(format!("{}\n", error).as_bytes()).await;
+}
+
+/// NotFound replies to the request with an HTTP 404 not found error.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NotFound使用HTTP 404 not found错误回复请求。
+/// </details>
+pub async fn NotFound(w: &mut dyn AsyncResponseWriter, r: &mut Request) {
+    Error(w, "404 page not found", 404).await
+}
+
+/// NotFoundHandler returns a simple request handler that replies to each request with a "404 page not found" reply.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NotFoundHandler返回一个简单的处理器，对每个请求回复"404 page not found"。
+/// </details>
+pub fn NotFoundHandler() -> impl AsyncHandler {
+    AsyncHandlerFunc(|w, r| Box::pin(NotFound(w, r)))
+}
+
+/// Redirect replies to the request with a redirect to url, which may be a path relative to the request path, like server::Redirect.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Redirect回复一个重定向到url的响应，url可以是相对于请求路径的路径，与server::Redirect相同。
+/// </details>
+pub async fn Redirect(w: &mut dyn AsyncResponseWriter, r: &Request, url: &str, code: u16) {
+    let body = redirectHeader(w.Header(), r, url);
+    w.WriteHeader(code);
+    if let Some(body) = body {
+        let _ = w.Write(redirectBody(&body, code).as_bytes()).await;
+    }
+}
+
+/// RedirectHandler returns a request handler that redirects each request it receives to the given url using the given status code.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// RedirectHandler返回一个处理器，使用指定的状态码把收到的每个请求重定向到url。
+/// </details>
+pub fn RedirectHandler(url: &str, code: u16) -> impl AsyncHandler {
+    redirectHandler {
+        url: url.to_string(),
+        code,
+    }
+}
+
+struct redirectHandler {
+    url: String,
+    code: u16,
+}
+
+impl AsyncHandler for redirectHandler {
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a> {
+        Box::pin(Redirect(w, r, &self.url, self.code))
+    }
+}
+
+// methodNotAllowedHandler replies 405 Method Not Allowed to a path registered for other methods only.
+struct methodNotAllowedHandler {
+    allow: String,
+}
+
+impl AsyncHandler for methodNotAllowedHandler {
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a> {
+        w.Header().Set("Allow", &self.allow);
+        Box::pin(Error(w, StatusText(405), 405))
+    }
+}
+
+/// ServeMux is an HTTP request multiplexer for AsyncHandlers. Patterns, precedence, redirects and 405 replies are those of server::ServeMux.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ServeMux是AsyncHandler的HTTP请求多路复用器。模式、优先级、重定向和405回复与server::ServeMux相同。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use http::async_server::ServeMux;
+///
+/// let mut mux = ServeMux::default();
+/// mux.HandleFunc("GET /items/{id}", |w, r| {
+///     Box::pin(async move {
+///         let _ = w.Write(r.PathValue("id").as_bytes()).await;
+///     })
+/// });
+///
+/// let req = http::Request::New(http::Method::Get, "http://example.com/items/42", None).unwrap();
+/// assert_eq!(mux.Handler(&req).1, "GET /items/{id}");
+/// ```
+#[derive(Default)]
+pub struct ServeMux {
+    router: router<Arc<dyn AsyncHandler>>,
+}
+
+/// NewServeMux allocates and returns a new ServeMux.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewServeMux创建并返回一个新的ServeMux。
+/// </details>
+pub fn NewServeMux() -> ServeMux {
+    ServeMux::default()
+}
+
+impl ServeMux {
+    /// Handle registers the handler for the given pattern. It panics if the pattern is invalid or conflicts with a pattern that is already registered.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Handle为指定的模式注册处理器。如果模式无效或者与已注册的模式冲突，Handle会panic。
+    /// </details>
+    pub fn Handle(&mut self, pattern: &str, handler: impl AsyncHandler + 'static) {
+        if let Err(err) = self.router.register(pattern, Arc::new(handler)) {
+            panic!("http: {}", err);
+        }
+    }
+
+    /// HandleFunc registers the handler function for the given pattern. It panics like Handle.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// HandleFunc为指定的模式注册处理函数，与Handle一样会panic。
+    /// </details>
+    pub fn HandleFunc<F>(&mut self, pattern: &str, handler: F)
+    where
+        F: for<'a> Fn(&'a mut dyn AsyncResponseWriter, &'a mut Request) -> HandlerFuture<'a>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.Handle(pattern, AsyncHandlerFunc(handler))
+    }
+
+    /// Handler returns the handler to use for the given request together with the pattern that matched it, like server::ServeMux::Handler.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Handler返回请求使用的处理器以及匹配的模式，与server::ServeMux::Handler相同。
+    /// </details>
+    pub fn Handler(&self, r: &Request) -> (Arc<dyn AsyncHandler>, String) {
+        let (h, pattern, _) = self.findHandler(r);
+        (h, pattern)
+    }
+
+    fn findHandler(&self, r: &Request) -> (Arc<dyn AsyncHandler>, String, Vec<(String, String)>) {
+        match self.router.findRequest(r) {
+            route::found(h, pattern, values) => (h.clone(), pattern.to_string(), values),
+            route::redirect(url) => (
+                Arc::new(RedirectHandler(&url, 301)),
+                String::new(),
+                Vec::new(),
+            ),
+            route::methodNotAllowed(allow) => (
+                Arc::new(methodNotAllowedHandler {
+                    allow: allow.join(", "),
+                }),
+                String::new(),
+                Vec::new(),
+            ),
+            route::notFound => (Arc::new(NotFoundHandler()), String::new(), Vec::new()),
+        }
+    }
+}
+
+impl AsyncHandler for ServeMux {
+    fn ServeHttp<'a>(
+        &'a self,
+        w: &'a mut dyn AsyncResponseWriter,
+        r: &'a mut Request,
+    ) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            if r.RequestURI == "*" {
+                if r.ProtoAtLeast(1, 1) {
+                    w.Header().Set("Connection", "close");
+                }
+                w.WriteHeader(400);
+                return;
+            }
+            let (h, _, values) = self.findHandler(r);
+            for (name, value) in values {
+                r.SetPathValue(&name, &value);
+            }
+            h.ServeHttp(w, r).await
+        })
+    }
+}
+
+/// A Server defines parameters for running an async HTTP server.
+///
+/// Each connection is served by its own task on the enabled runtime. Requests on a connection are served one after the other; the connection is kept alive between them unless the client or the handler asks to close it.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Server定义运行异步HTTP服务器的参数。
+///
+/// 每个连接在已启用的运行时上由单独的任务处理。同一个连接上的请求依次处理，除非客户端或handler要求关闭，请求之间保持连接。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use gostd_time as time;
+/// use http::async_server::{AsyncHandlerFunc, Server};
+/// use std::sync::Arc;
+///
+/// # #[cfg(feature = "tokio-runtime")]
+/// #[tokio::main]
+/// async fn main() {
+///     let srv = Arc::new(Server::New(
+///         ":8080",
+///         AsyncHandlerFunc(|w, _r| {
+///             Box::pin(async move {
+///                 w.Write(b"ok").await.unwrap();
+///             })
+///         }),
+///     ));
+///     let s = srv.clone();
+///     let serving = tokio::spawn(async move { s.ListenAndServe().await });
+///
+///     // ... on SIGTERM:
+///     let mut deadline = time::Now();
+///     srv.Shutdown(deadline.Add(&time::Duration::new(10 * time::Second)))
+///         .await
+///         .unwrap();
+///     assert!(serving.await.unwrap().is_err());
+/// }
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {}
+/// ```
+pub struct Server {
+    /// Addr optionally specifies the TCP address for the server to listen on, in the form "host:port". A missing host, as in ":8080", listens on all interfaces; if Addr is empty, ":80" is used.
+    pub Addr: String,
+    /// Handler to invoke for every request.
+    pub Handler: Arc<dyn AsyncHandler>,
+    /// ErrorLog specifies an optional logger for errors accepting connections, unexpected behavior from handlers and handler panics. If None, logging goes to standard error.
+    pub ErrorLog: Option<ErrorLogFunc>,
+    /// ReadHeaderTimeout is the amount of time allowed to read the request line and header of a request. Like ReadTimeout it is a deadline counted from the start of the request, not a limit on each read. It also bounds discarding what the handler left unread of the request body. If ReadHeaderTimeout is zero, the value of ReadTimeout is used. If both are zero, there is no timeout.
+    pub ReadHeaderTimeout: time::Duration,
+    /// ReadTimeout is the maximum duration for reading the entire request, including the body. It is a deadline for the whole request rather than a limit on each read: it starts when the connection is accepted for the first request and when the first byte arrives for the following ones, and reads of the body fail with a timeout once it has passed. A zero or negative value means there will be no timeout.
+    pub ReadTimeout: time::Duration,
+    /// WriteTimeout is the maximum duration a single write of the response to the connection may take. A zero or negative value means there will be no timeout.
+    pub WriteTimeout: time::Duration,
+    /// IdleTimeout is the maximum amount of time to wait for the next request when keep-alives are enabled. If IdleTimeout is zero, the value of ReadHeaderTimeout is used. If both are zero, there is no timeout.
+    pub IdleTimeout: time::Duration,
+    /// MaxHeaderBytes controls the maximum number of bytes the server will read parsing the request line and header fields. If zero, DefaultMaxHeaderBytes is used.
+    pub MaxHeaderBytes: int,
+    /// ConnState specifies an optional callback function that is called when a client connection changes state, with the same states as the sync server except StateHijacked.
+    pub ConnState: Option<ConnStateFunc>,
+    state: Arc<serverState>,
+}
+
+impl Server {
+    /// New returns a Server listening on addr and serving every request with handler.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// New返回一个监听addr，并用handler处理每个请求的Server。
+    /// </details>
+    pub fn New(addr: &str, handler: impl AsyncHandler + 'static) -> Server {
+        Server {
+            Addr: addr.to_string(),
+            Handler: Arc::new(handler),
+            ErrorLog: None,
+            ReadHeaderTimeout: time::Duration::new(0),
+            ReadTimeout: time::Duration::new(0),
+            WriteTimeout: time::Duration::new(0),
+            IdleTimeout: time::Duration::new(0),
+            MaxHeaderBytes: 0,
+            ConnState: None,
+            state: Arc::new(serverState::default()),
+        }
+    }
+
+    /// ListenAndServe listens on the TCP network address Addr and then calls Serve to handle requests on incoming connections.
+    ///
+    /// ListenAndServe always returns a non-nil error. After Shutdown or Close, the returned error is ErrServerClosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ListenAndServe监听TCP网络地址Addr，然后调用Serve处理传入连接上的请求。
+    ///
+    /// ListenAndServe总是返回错误。调用Shutdown或Close之后，返回的错误是ErrServerClosed。
+    /// </details>
+    pub async fn ListenAndServe(&self) -> HttpResult<()> {
+        if self.state.shuttingDown() {
+            return Err(HTTPConnectError::ErrServerClosed);
+        }
+        let l = net::TcpListener::bind(listenAddr(&self.Addr, "80"))?;
+        self.Serve(l).await
+    }
+
+    /// Serve accepts incoming connections on the Listener l, spawning a task for each that reads requests and calls Handler to reply to them. l is a std listener so that the same code serves on either runtime.
+    ///
+    /// Serve always returns a non-nil error. After Shutdown or Close, the returned error is ErrServerClosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Serve在监听器l上接受传入的连接，为每个连接启动一个任务读取请求，并调用Handler回复。l是标准库的监听器，同样的代码可以在两种运行时上运行。
+    ///
+    /// Serve总是返回错误。调用Shutdown或Close之后，返回的错误是ErrServerClosed。
+    /// </details>
+    pub async fn Serve(&self, l: net::TcpListener) -> HttpResult<()> {
+        let addr = l.local_addr()?;
+        let l = listen(l)?;
+        self.state.addListener(addr);
+        let r = self.acceptLoop(&l).await;
+        self.state.removeListener(addr);
+        r
+    }
+
+    async fn acceptLoop(&self, l: &TcpListener) -> HttpResult<()> {
+        let readTimeout = stdDuration(&self.ReadTimeout);
+        let readHeaderTimeout = stdDuration(&self.ReadHeaderTimeout).or(readTimeout);
+        let srv = Arc::new(connServer {
+            handler: self.Handler.clone(),
+            errorLog: self.ErrorLog.clone(),
+            readHeaderTimeout,
+            readTimeout,
+            writeTimeout: stdDuration(&self.WriteTimeout),
+            idleTimeout: stdDuration(&self.IdleTimeout).or(readHeaderTimeout),
+            maxHeaderBytes: if self.MaxHeaderBytes > 0 {
+                self.MaxHeaderBytes
+            } else {
+                DefaultMaxHeaderBytes
+            },
+            connState: self.ConnState.clone(),
+            state: self.state.clone(),
+        });
+        let mut tempDelay = Duration::ZERO;
+        loop {
+            if self.state.shuttingDown() {
+                return Err(HTTPConnectError::ErrServerClosed);
+            }
+            let s = match l.accept().await {
+                Ok((s, _)) => s,
+                Err(_) if self.state.shuttingDown() => {
+                    return Err(HTTPConnectError::ErrServerClosed);
+                }
+                Err(err) => {
+                    // 接受连接的错误(例如文件描述符用尽)通常是暂时的，等待一段时间后重试。
+                    tempDelay =
+                        (tempDelay * 2).clamp(Duration::from_millis(5), Duration::from_secs(1));
+                    srv.logf(&format!(
+                        "http: Accept error: {}; retrying in {:?}",
+                        err, tempDelay
+                    ));
+                    sleep(tempDelay).await;
+                    continue;
+                }
+            };
+            tempDelay = Duration::ZERO;
+            // Shutdown为了唤醒accept建立的连接在这里被丢弃。
+            if self.state.shuttingDown() {
+                return Err(HTTPConnectError::ErrServerClosed);
+            }
+            let c = match conn::new(&srv, s) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+            if !srv.setState(&c, ConnState::StateNew) {
+                continue;
+            }
+            let srv = srv.clone();
+            spawn(async move { serveConn(&srv, c).await });
+        }
+    }
+
+    /// Shutdown gracefully shuts down the server without interrupting any active connections, like server::Server::Shutdown: it stops the Serve loops, closes idle connections and waits for the others to become idle. If the deadline passes before the shutdown is complete, Shutdown returns HTTPConnectError::TimeoutError.
+    ///
+    /// Serve and ListenAndServe return ErrServerClosed as soon as Shutdown is called, while connections are still served by their tasks. Keep the runtime running until Shutdown returns.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Shutdown在不中断活动连接的情况下平滑地关闭服务器，与server::Server::Shutdown相同：停止Serve循环，关闭空闲连接，并等待其余连接变为空闲。如果在关闭完成之前超过了deadline，Shutdown返回HTTPConnectError::TimeoutError。
+    ///
+    /// 调用Shutdown时Serve和ListenAndServe立即返回ErrServerClosed，连接仍然由各自的任务处理。要保持运行时运行，直到Shutdown返回。
+    /// </details>
+    pub async fn Shutdown(&self, deadline: time::Time) -> HttpResult<()> {
+        self.state.startShutdown();
+        let deadline = shutdownDeadline(&deadline);
+        let mut interval = shutdownPollIntervalMin;
+        while let Some(wait) = self.state.pollShutdown(deadline, &mut interval)? {
+            sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Close immediately closes all active listeners and all connections in state StateNew, StateActive, or StateIdle. For a graceful shutdown, use Shutdown.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Close立即关闭所有活动的监听器以及所有处于StateNew、StateActive或StateIdle状态的连接。要平滑地关闭服务器，使用Shutdown。
+    /// </details>
+    pub fn Close(&self) -> HttpResult<()> {
+        self.state.close();
+        Ok(())
+    }
+
+    /// RegisterOnShutdown registers a function to call on Shutdown, on its own thread. This function should start protocol-specific graceful shutdown, but should not wait for shutdown to complete.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// RegisterOnShutdown注册一个在Shutdown时调用的函数，该函数在单独的线程中调用。它应该开始特定协议的平滑关闭，但不应该等待关闭完成。
+    /// </details>
+    pub fn RegisterOnShutdown(&self, f: impl Fn() + Send + Sync + 'static) {
+        self.state.registerOnShutdown(Arc::new(f));
+    }
+}
+
+/// ListenAndServe listens on the TCP network address addr and then serves requests on incoming connections with handler.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ListenAndServe监听TCP网络地址addr，然后使用handler处理传入连接上的请求。
+/// </details>
+pub async fn ListenAndServe(addr: &str, handler: impl AsyncHandler + 'static) -> HttpResult<()> {
+    Server::New(addr, handler).ListenAndServe().await
+}
+
+/// Serve accepts incoming HTTP connections on the listener l and serves their requests with handler.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Serve在监听器l上接受传入的HTTP连接，并使用handler处理请求。
+/// </details>
+pub async fn Serve(l: net::TcpListener, handler: impl AsyncHandler + 'static) -> HttpResult<()> {
+    Server::New("", handler).Serve(l).await
+}
+
+// connReader and connWriter are the buffered halves of a connection.
+#[cfg(feature = "tokio-runtime")]
+type connReader = BufReader<OwnedReadHalf>;
+#[cfg(feature = "tokio-runtime")]
+type connWriter = BufWriter<OwnedWriteHalf>;
+#[cfg(feature = "async-std-runtime")]
+type connReader = BufReader<TcpStream>;
+#[cfg(feature = "async-std-runtime")]
+type connWriter = BufWriter<TcpStream>;
+
+// listen turns l into a listener of the enabled runtime.
+fn listen(l: net::TcpListener) -> io::Result<TcpListener> {
+    l.set_nonblocking(true)?;
+    #[cfg(feature = "tokio-runtime")]
+    return TcpListener::from_std(l);
+    #[cfg(feature = "async-std-runtime")]
+    return Ok(TcpListener::from(l));
+}
+
+// split returns the buffered halves of the accepted stream s, together with a std handle of the same socket through which the server tracks and closes the connection.
+fn split(s: TcpStream) -> io::Result<(net::TcpStream, connReader, connWriter)> {
+    #[cfg(feature = "tokio-runtime")]
+    {
+        let s = s.into_std()?;
+        let rwc = s.try_clone()?;
+        let (r, w) = TcpStream::from_std(s)?.into_split();
+        Ok((rwc, BufReader::new(r), BufWriter::new(w)))
+    }
+    #[cfg(feature = "async-std-runtime")]
+    {
+        let s = net::TcpStream::try_from(s)?;
+        let rwc = s.try_clone()?;
+        let s = TcpStream::from(s);
+        Ok((rwc, BufReader::new(s.clone()), BufWriter::new(s)))
+    }
+}
+
+fn spawn(f: impl Future<Output = ()> + Send + 'static) {
+    #[cfg(feature = "tokio-runtime")]
+    tokio::spawn(f);
+    #[cfg(feature = "async-std-runtime")]
+    async_std::task::spawn(f);
+}
+
+// untilDeadline returns the time left until deadline, which is zero once it has passed.
+fn untilDeadline(deadline: Option<Instant>) -> Option<Duration> {
+    deadline.map(|d| d.saturating_duration_since(Instant::now()))
+}
+
+// timeout runs f, failing with TimedOut if it does not finish within d.
+async fn timeout<T, E: From<io::Error>>(
+    d: Option<Duration>,
+    f: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    match d {
+        Some(d) => match with_timeout(d, f).await {
+            Some(r) => r,
+            None => Err(io::Error::from(io::ErrorKind::TimedOut).into()),
+        },
+        None => f.await,
+    }
+}
+
+// connServer is the part of a Server its connections need, shared with the tasks that serve them.
+struct connServer {
+    handler: Arc<dyn AsyncHandler>,
+    errorLog: Option<ErrorLogFunc>,
+    readHeaderTimeout: Option<Duration>,
+    readTimeout: Option<Duration>,
+    writeTimeout: Option<Duration>,
+    idleTimeout: Option<Duration>,
+    maxHeaderBytes: int,
+    connState: Option<ConnStateFunc>,
+    state: Arc<serverState>,
+}
+
+impl connServer {
+    fn logf(&self, msg: &str) {
+        match &self.errorLog {
+            Some(f) => f(msg),
+            None => eprintln!("{}", msg),
+        }
+    }
+
+    // setState records the new state of c and calls the ConnState hook. It reports false if Shutdown or Close has already closed c.
+    fn setState(&self, c: &conn, state: ConnState) -> bool {
+        let tracked = self.state.trackConn(c.id, &c.rwc, state);
+        if tracked {
+            if let Some(f) = &self.connState {
+                f(&c.rwc, state);
+            }
+        }
+        tracked
+    }
+}
+
+// conn is the server side of an async HTTP connection. The request Body and the ResponseWriter share br and bw, which are only locked while they are polled, never across an await. rwc is a std handle of the socket, used to close the connection.
+struct conn {
+    id: u64,
+    rwc: net::TcpStream,
+    remoteAddr: String,
+    br: Arc<Mutex<connReader>>,
+    bw: Arc<Mutex<connWriter>>,
+    writeTimeout: Option<Duration>,
+}
+
+impl conn {
+    fn new(srv: &connServer, s: TcpStream) -> io::Result<conn> {
+        let remoteAddr = s.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        let (rwc, r, w) = split(s)?;
+        Ok(conn {
+            id: srv.state.newConnId(),
+            rwc,
+            remoteAddr,
+            br: Arc::new(Mutex::new(r)),
+            bw: Arc::new(Mutex::new(w)),
+            writeTimeout: srv.writeTimeout,
+        })
+    }
+
+    // waitForRequest waits for the first byte of the next request. It returns false if the client closed the connection.
+    async fn waitForRequest(&self) -> io::Result<bool> {
+        poll_fn(|cx| {
+            let mut br = lock(&self.br);
+            let r = Pin::new(&mut *br)
+                .poll_fill_buf(cx)
+                .map_ok(|b| !b.is_empty());
+            r
+        })
+        .await
+    }
+
+    // readHead reads the request line and header of a request, up to and including the empty line that ends them. Empty lines before the request line are skipped. ErrTooLarge is returned once the head is longer than limit.
+    async fn readHead(&self, limit: int) -> HttpResult<Vec<u8>> {
+        let mut head = Vec::new();
+        let mut lineStart = 0;
+        poll_fn(|cx| {
+            let mut br = lock(&self.br);
+            loop {
+                let avail = match ready!(Pin::new(&mut *br).poll_fill_buf(cx)) {
+                    Ok([]) => {
+                        return Poll::Ready(Err(
+                            io::Error::from(io::ErrorKind::UnexpectedEof).into()
+                        ))
+                    }
+                    Ok(avail) => avail,
+                    Err(err) => return Poll::Ready(Err(err.into())),
+                };
+                let (n, eol) = match avail.iter().position(|&b| b == b'\n') {
+                    Some(i) => (i + 1, true),
+                    None => (avail.len(), false),
+                };
+                head.extend_from_slice(&avail[..n]);
+                Pin::new(&mut *br).consume(n);
+                if head.len() as int > limit {
+                    return Poll::Ready(Err(HTTPConnectError::ErrTooLarge));
+                }
+                if !eol {
+                    continue;
+                }
+                let line = &head[lineStart..];
+                if line == b"\n" || line == b"\r\n" {
+                    // RFC 7230 3.5: 请求行之前的空行应当忽略。
+                    if lineStart == 0 {
+                        head.clear();
+                        continue;
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                lineStart = head.len();
+            }
+        })
+        .await?;
+        Ok(head)
+    }
+
+    // write writes b to the connection's buffer.
+    async fn write(&self, mut b: &[u8]) -> io::Result<()> {
+        timeout(self.writeTimeout, async {
+            while !b.is_empty() {
+                let n = poll_fn(|cx| Pin::new(&mut *lock(&self.bw)).poll_write(cx, b)).await?;
+                if n == 0 {
+                    return Err(io::Error::from(io::ErrorKind::WriteZero));
+                }
+                b = &b[n..];
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn flush(&self) -> io::Result<()> {
+        timeout(
+            self.writeTimeout,
+            poll_fn(|cx| Pin::new(&mut *lock(&self.bw)).poll_flush(cx)),
+        )
+        .await
+    }
+
+    // writeError replies to a request that could not be served with a plain text error, after which the connection is closed.
+    async fn writeError(&self, code: u16, msg: &str) {
+        if self.write(errorReply(code, msg).as_bytes()).await.is_ok() {
+            let _ = self.flush().await;
+        }
+    }
+
+    async fn close(&self) {
+        let _ = self.flush().await;
+        let _ = self.rwc.shutdown(Shutdown::Both);
+    }
+}
+
+// serveConn reads requests from c and serves them one after the other until the connection is closed.
+async fn serveConn(srv: &connServer, c: conn) {
+    let mut idle = false;
+    loop {
+        // 等待下一个请求，对方关闭连接或超时时结束。第一个请求受ReadHeaderTimeout限制，之后受IdleTimeout限制。
+        let waitStart = Instant::now();
+        let wait = if idle {
+            srv.idleTimeout
+        } else {
+            srv.readHeaderTimeout
+        };
+        match timeout(wait, c.waitForRequest()).await {
+            Ok(true) => {}
+            _ => break,
+        }
+        if !srv.setState(&c, ConnState::StateActive) {
+            break;
+        }
+        // 期限从请求开始计算：第一个请求从连接建立开始，之后的请求从第一个字节到达开始。
+        let start = if idle { Instant::now() } else { waitStart };
+        let headerDeadline = srv.readHeaderTimeout.map(|d| start + d);
+        let deadline = srv.readTimeout.map(|d| start + d);
+        let r = timeout(
+            untilDeadline(headerDeadline),
+            c.readHead(srv.maxHeaderBytes),
+        )
+        .await
+        .and_then(|head| readRequest(&mut Cursor::new(head), srv.maxHeaderBytes));
+        let (mut req, state) = match r {
+            Ok(r) => r,
+            Err(err) => {
+                if let Some((code, msg)) = requestError(&err) {
+                    c.writeError(code, &msg).await;
+                }
+                break;
+            }
+        };
+        if let Some((code, msg)) = checkRequest(&req) {
+            c.writeError(code, msg).await;
+            break;
+        }
+        req.RemoteAddr = c.remoteAddr.clone();
+        let keepAlive = serveRequest(srv, &c, req, state, deadline).await;
+        if !keepAlive || srv.state.shuttingDown() || !srv.setState(&c, ConnState::StateIdle) {
+            break;
+        }
+        idle = true;
+    }
+    c.close().await;
+    srv.setState(&c, ConnState::StateClosed);
+}
+
+// serveRequest runs the handler for req and finishes its response. Reads of the request body fail once deadline has passed. It reports whether the connection can be used for another request.
+async fn serveRequest(
+    srv: &connServer,
+    c: &conn,
+    mut req: Request,
+    state: transferState,
+    deadline: Option<Instant>,
+) -> bool {
+    let mut body = None;
+    if !state.isDone() {
+        // 客户端等待"100 Continue"时，handler第一次读取body之前发送。
+        let cont = if req.expectsContinue() && req.ProtoAtLeast(1, 1) {
+            Some(Arc::new(continueWriter {
+                bw: c.bw.clone(),
+                canWrite: AtomicBool::new(true),
+                sending: Mutex::new(None),
+            }))
+        } else {
+            None
+        };
+        let trailer = state.trailer();
+        let b = connBody {
+            br: c.br.clone(),
+            state: Arc::new(Mutex::new(state)),
+            cont,
+            timer: untilDeadline(deadline).map(|d| Arc::new(Mutex::new(Some(sleep(d))))),
+        };
+        req.Body = Some(Body::fromAsync(b.clone()).withTrailer(trailer));
+        body = Some(b);
+    }
+    req.Header.Del("Expect");
+
+    let mut w = response::new(srv, c, &req, body);
+    let handler = srv.handler.clone();
+    let r = {
+        let mut f = handler.ServeHttp(&mut w, &mut req);
+        // 每次poll都在catch_unwind中进行，handler的panic不会结束整个任务。
+        poll_fn(
+            |cx| match panic::catch_unwind(AssertUnwindSafe(|| f.as_mut().poll(cx))) {
+                Ok(p) => p.map(Ok),
+                Err(err) => Poll::Ready(Err(err)),
+            },
+        )
+        .await
+    };
+    if let Some(form) = req.MultipartForm.take() {
+        // 删除ParseMultipartForm创建的临时文件。
+        let _ = form.RemoveAll();
+    }
+    if let Err(err) = r {
+        if !err.is::<ErrAbortHandler>() {
+            srv.logf(&format!(
+                "http: panic serving {}: {}",
+                c.remoteAddr,
+                panicMessage(&*err)
+            ));
+        }
+        return false;
+    }
+    w.finishRequest().await;
+    !w.st.closeAfterReply
+}
+
+const continueReply: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+// continueWriter sends "100 Continue" the first time the body of a request with "Expect: 100-continue" is read, unless the response has already started.
+struct continueWriter {
+    bw: Arc<Mutex<connWriter>>,
+    canWrite: AtomicBool,
+    // sending是正在发送的"100 Continue"已经写入的字节数。
+    sending: Mutex<Option<usize>>,
+}
+
+impl continueWriter {
+    fn pollSend(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut sending = lock(&self.sending);
+        if sending.is_none() && self.canWrite.swap(false, Ordering::SeqCst) {
+            *sending = Some(0);
+        }
+        while let Some(n) = *sending {
+            let mut bw = lock(&self.bw);
+            let r = if n < continueReply.len() {
+                match Pin::new(&mut *bw).poll_write(cx, &continueReply[n..]) {
+                    Poll::Ready(Ok(0)) => Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    p => p.map_ok(|m| Some(n + m)),
+                }
+            } else {
+                Pin::new(&mut *bw).poll_flush(cx).map_ok(|_| None)
+            };
+            match ready!(r) {
+                Ok(next) => *sending = next,
+                Err(err) => {
+                    *sending = None;
+                    return Poll::Ready(Err(err));
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+// connBody reads the body of a request from the connection.
+#[derive(Clone)]
+struct connBody {
+    br: Arc<Mutex<connReader>>,
+    state: Arc<Mutex<transferState>>,
+    cont: Option<Arc<continueWriter>>,
+    timer: Option<Arc<Mutex<Option<deadlineTimer>>>>,
+}
+
+// deadlineTimer completes when the ReadTimeout of a request has passed. It is dropped once it has fired, after which reads of the body fail with a timeout.
+type deadlineTimer = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+impl asyncBodyReader for connBody {
+    fn pollRead(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(timer) = &self.timer {
+            let mut timer = lock(timer);
+            // 完成的timer不能再poll。
+            if timer
+                .as_mut()
+                .is_none_or(|t| t.as_mut().poll(cx).is_ready())
+            {
+                *timer = None;
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::TimedOut, "i/o timeout")));
+            }
+        }
+        if let Some(cont) = &self.cont {
+            ready!(cont.pollSend(cx))?;
+        }
+        let mut br = lock(&self.br);
+        lock(&self.state).pollReadAsync(&mut *br, cx, buf)
+    }
+}
+
+// response is the AsyncResponseWriter handed to the handler of a request.
+struct response<'a> {
+    srv: &'a connServer,
+    conn: &'a conn,
+    body: Option<connBody>,
+    st: responseState,
+    // informational是WriteHeader记录的1xx响应，在下一次写入连接时发送。
+    informational: Vec<u8>,
+}
+
+impl<'a> response<'a> {
+    fn new(
+        srv: &'a connServer,
+        conn: &'a conn,
+        req: &Request,
+        body: Option<connBody>,
+    ) -> response<'a> {
+        response {
+            srv,
+            conn,
+            body,
+            st: responseState::new(req),
+            informational: Vec::new(),
+        }
+    }
+
+    // stopContinue keeps the request body from sending "100 Continue" once the final response has started.
+    fn stopContinue(&self) {
+        if let Some(cont) = self.body.as_ref().and_then(|b| b.cont.as_ref()) {
+            cont.canWrite.store(false, Ordering::SeqCst);
+        }
+    }
+
+    // discardRequestBody reads what the handler left of the request body, so that the next request can be read from the connection. Bodies that are too large, or that the client is still waiting to be asked for, close the connection instead.
+    async fn discardRequestBody(&mut self) {
+        let mut body = match &self.body {
+            Some(b) if !self.st.closeAfterReply => b.clone(),
+            _ => return,
+        };
+        if lock(&body.state).isDone() {
+            return;
+        }
+        // 客户端在等待100 Continue，body可能不会发送。
+        if body.cont.is_some() {
+            self.st.closeAfterReply = true;
+            return;
+        }
+        let mut buf = [0u8; 4096];
+        let mut n = 0;
+        let discard = async {
+            loop {
+                match poll_fn(|cx| Pin::new(&mut body).pollRead(cx, &mut buf)).await? {
+                    0 => return Ok(()),
+                    m => n += m as u64,
+                }
+                if n > maxPostHandlerReadBytes {
+                    return Err(io::Error::other("request body too large"));
+                }
+            }
+        };
+        if timeout(self.srv.readHeaderTimeout, discard).await.is_err() {
+            self.st.closeAfterReply = true;
+        }
+    }
+
+    // writeRaw writes b to the connection's buffer, after any informational responses recorded by WriteHeader.
+    async fn writeRaw(&mut self, b: &[u8]) -> HttpResult<()> {
+        if !self.informational.is_empty() {
+            let info = std::mem::take(&mut self.informational);
+            self.conn.write(&info).await?;
+        }
+        if !b.is_empty() {
+            self.conn.write(b).await?;
+        }
+        Ok(())
+    }
+
+    // commit writes the status line and header, then the buffered body. After that the body is written straight to the connection.
+    async fn commit(&mut self) -> HttpResult<()> {
+        if !self.st.startCommit(self.srv.state.shuttingDown()) {
+            return Ok(());
+        }
+        self.discardRequestBody().await;
+        let b = self.st.commitHeader();
+        self.writeRaw(&b).await
+    }
+
+    async fn write(&mut self, data: &[u8]) -> HttpResult<usize> {
+        if !self.st.wroteHeader {
+            self.WriteHeader(200);
+        }
+        let b = self.st.write(data)?;
+        let r = match b {
+            Some(b) => self.writeRaw(&b).await,
+            None if self.st.needsCommit() => self.commit().await,
+            None => Ok(()),
+        };
+        if let Err(err) = r {
+            self.st.closeAfterReply = true;
+            return Err(err);
+        }
+        Ok(data.len())
+    }
+
+    async fn flush(&mut self) -> HttpResult<()> {
+        if !self.st.wroteHeader {
+            self.WriteHeader(200);
+        }
+        let r = self.send(Vec::new()).await;
+        if r.is_err() {
+            self.st.closeAfterReply = true;
+        }
+        r
+    }
+
+    // send commits the header, writes end after it and flushes the connection.
+    async fn send(&mut self, end: Vec<u8>) -> HttpResult<()> {
+        self.commit().await?;
+        self.writeRaw(&end).await?;
+        self.conn.flush().await?;
+        Ok(())
+    }
+
+    // finishRequest completes the response once the handler has returned: it commits the header if the handler never flushed, ends a chunked body with its trailers and flushes the connection.
+    async fn finishRequest(&mut self) {
+        self.st.handlerDone = true;
+        if !self.st.wroteHeader {
+            self.WriteHeader(200);
+        }
+        // commit之后才知道是否使用chunked编码。
+        let r = match self.commit().await {
+            Ok(()) => {
+                let end = self.st.finish();
+                self.send(end).await
+            }
+            Err(err) => Err(err),
+        };
+        if r.is_err() {
+            self.st.closeAfterReply = true;
+        }
+        self.st.checkLength();
+    }
+}
+
+impl AsyncResponseWriter for response<'_> {
+    fn Header(&mut self) -> &mut Header {
+        &mut self.st.handlerHeader
+    }
+
+    fn Write<'b>(&'b mut self, buf: &'b [u8]) -> WriteFuture<'b> {
+        Box::pin(self.write(buf))
+    }
+
+    fn WriteHeader(&mut self, code: u16) {
+        let srv = self.srv;
+        if let Some(b) = self.st.writeHeader(code, |msg| srv.logf(msg)) {
+            self.informational.extend(b);
+        }
+        if code == 100 || self.st.wroteHeader {
+            self.stopContinue();
+        }
+    }
+
+    fn Flush(&mut self) -> FlushFuture<'_> {
+        Box::pin(self.flush())
+    }
+}
@@ -0,0 +1,541 @@
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes, BytesMut};
+use flate2::{bufread, write};
+use gostd_builtin::*;
+use gostd_io as gio;
+
+#[cfg(feature = "async-std-runtime")]
+use async_std::io::Read as AsyncRead;
+#[cfg(feature = "tokio-runtime")]
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::error::HttpResult;
+use crate::header::Header;
+
+/// Body is the body of a Request or Response. A response body is streamed from the connection as it is read rather than buffered up front; a request body is written to the connection straight from its reader.
+///
+/// Cloning a Body is cheap. Clones of a body built from bytes read independently, while clones of a reader backed body share the reader, like copies of an io.Reader in Go.
+///
+/// The body implements std::io::Read, gostd's io::Reader and, when an async runtime feature is enabled, AsyncRead. Reading it to EOF or dropping it releases the underlying connection: a body read to EOF hands a keep-alive connection back to the Transport for reuse, while a body closed or dropped early closes the connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Body是Request或Response的主体。Response的body读取时直接从连接上流式读取，而不是预先全部缓存到内存；Request的body直接从其Reader写入连接。
+///
+/// 克隆Body的开销很小。由字节构造的body的各个克隆独立读取，而基于Reader的body的克隆共享同一个Reader，与Go中复制io.Reader的行为一致。
+///
+/// Body实现了std::io::Read、gostd的io::Reader，启用异步运行时特性时还实现了AsyncRead。读到EOF或者被丢弃时会释放底层连接：读到EOF的keep-alive连接会交还给Transport复用，提前关闭或丢弃的Body会关闭连接。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use std::io::Read;
+///
+/// let mut resp = http::Get("http://example.com/").unwrap();
+/// let mut buf = [0u8; 4096];
+/// loop {
+///     let n = resp.Body.read(&mut buf).unwrap();
+///     if n == 0 {
+///         break;
+///     }
+///     println!("read {} bytes", n);
+/// }
+/// ```
+#[derive(Default, Clone)]
+pub struct Body {
+    inner: bodyInner,
+    // trailer收集chunked消息末尾的trailer字段，读到EOF之后才有值。
+    trailer: Option<Arc<Mutex<Header>>>,
+}
+
+#[derive(Default, Clone)]
+enum bodyInner {
+    #[default]
+    NoBody,
+    Bytes(Bytes),
+    Reader(Arc<Mutex<Box<dyn Read + Send>>>),
+    #[cfg(feature = "async-http")]
+    AsyncReader(Arc<Mutex<Pin<Box<dyn asyncBodyReader>>>>),
+}
+
+// lock ignores poisoning: a panic while reading leaves the reader no worse than a failed read.
+pub(crate) fn lock<T: ?Sized>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// asyncBodyReader is the runtime independent read half of an async body; the public AsyncRead impls of Body adapt it to tokio or async-std.
+#[cfg(feature = "async-http")]
+pub(crate) trait asyncBodyReader: Send {
+    fn pollRead(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+impl Body {
+    /// New returns a Body that reads from r.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// New返回一个从r读取数据的Body。
+    /// </details>
+    pub fn New(r: impl Read + Send + 'static) -> Body {
+        Body {
+            inner: bodyInner::Reader(Arc::new(Mutex::new(Box::new(r)))),
+            trailer: None,
+        }
+    }
+
+    /// NewAsync returns a Body that reads from the async reader r.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// NewAsync返回一个从异步读取器r读取数据的Body。
+    /// </details>
+    #[cfg(feature = "async-http")]
+    pub fn NewAsync(r: impl AsyncRead + Send + Unpin + 'static) -> Body {
+        Body::fromAsync(asyncReadAdapter(r))
+    }
+
+    #[cfg(feature = "async-http")]
+    pub(crate) fn fromAsync(r: impl asyncBodyReader + 'static) -> Body {
+        Body {
+            inner: bodyInner::AsyncReader(Arc::new(Mutex::new(Box::pin(r)))),
+            trailer: None,
+        }
+    }
+
+    // knownLen returns the length of a body held in memory, or None for a reader backed body whose length is unknown.
+    pub(crate) fn knownLen(&self) -> Option<usize> {
+        match &self.inner {
+            bodyInner::NoBody => Some(0),
+            bodyInner::Bytes(b) => Some(b.len()),
+            _ => None,
+        }
+    }
+
+    // isNoBody reports whether the body is known to be empty without reading it, as for responses to HEAD requests or 204 and 304 responses.
+    pub(crate) fn isNoBody(&self) -> bool {
+        match &self.inner {
+            bodyInner::NoBody => true,
+            bodyInner::Bytes(b) => b.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// ReadAll reads the body until EOF and returns the data it read.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ReadAll读取body直到EOF，返回读取到的数据。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use gostd_http as http;
+    ///
+    /// let mut resp = http::Get("http://example.com/").unwrap();
+    /// let body = resp.Body.ReadAll().unwrap();
+    /// println!("{}", String::from_utf8_lossy(&body));
+    /// ```
+    pub fn ReadAll(&mut self) -> HttpResult<Bytes> {
+        if let bodyInner::Bytes(b) = &mut self.inner {
+            return Ok(std::mem::take(b));
+        }
+        let mut buf = Vec::new();
+        self.read_to_end(&mut buf)?;
+        Ok(Bytes::from(buf))
+    }
+
+    /// ReadAllAsync is the async counterpart of ReadAll.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ReadAllAsync是ReadAll的异步版本。
+    /// </details>
+    #[cfg(feature = "async-http")]
+    pub async fn ReadAllAsync(&mut self) -> HttpResult<Bytes> {
+        let r = match &mut self.inner {
+            bodyInner::AsyncReader(r) => r,
+            _ => return self.ReadAll(),
+        };
+        let mut buf = BytesMut::new();
+        let mut chunk = vec![0u8; 8192];
+        loop {
+            let n = std::future::poll_fn(|cx| lock(r).as_mut().pollRead(cx, &mut chunk)).await?;
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Trailer returns the trailer fields sent after a chunked body, or after the DATA frames of an HTTP/2 stream. Their values are only available once the body has been read to EOF. Before that the returned Header holds at most the keys announced by the Trailer header, and for bodies without trailers it is empty. For a response read by the client this is the same Header as Response.Trailer.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Trailer返回chunked body之后，或者HTTP/2流的DATA帧之后发送的trailer字段。只有body读到EOF之后才能取得它们的值。在此之前，返回的Header最多只包含Trailer头声明的key；没有trailer的body返回空的Header。对于客户端读取的响应，它与Response.Trailer是同一个Header。
+    /// </details>
+    pub fn Trailer(&self) -> Header {
+        match &self.trailer {
+            Some(t) => lock(t).clone(),
+            None => Header::default(),
+        }
+    }
+
+    pub(crate) fn withTrailer(mut self, trailer: Option<Arc<Mutex<Header>>>) -> Body {
+        self.trailer = trailer;
+        self
+    }
+
+    // trailerSink returns where the trailer of the body is collected, so that a Body wrapping this one can report the same trailer.
+    pub(crate) fn trailerSink(&self) -> Option<Arc<Mutex<Header>>> {
+        self.trailer.clone()
+    }
+
+    /// Close discards the rest of the body. If it was not read to EOF, the underlying connection is closed instead of being reused.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Close丢弃body剩余的数据。如果body还没有读到EOF，底层连接会被关闭而不是复用。
+    /// </details>
+    pub fn Close(&mut self) {
+        self.inner = bodyInner::NoBody;
+    }
+
+    // decode wraps the body in a decompressor for the given Content-Encoding, "gzip" or "deflate". Other encodings leave the body unchanged.
+    pub(crate) fn decode(self, encoding: &str) -> Body {
+        let kind = match encoding {
+            "gzip" | "x-gzip" => decoderKind::Gzip,
+            "deflate" => decoderKind::Deflate,
+            _ => return self,
+        };
+        if self.isNoBody() {
+            return self;
+        }
+        let trailer = self.trailerSink();
+        #[cfg(feature = "async-http")]
+        {
+            if let bodyInner::AsyncReader(_) = self.inner {
+                return Body::fromAsync(asyncDecoder::new(self, kind)).withTrailer(trailer);
+            }
+        }
+        match kind {
+            decoderKind::Gzip => Body::New(bufread::MultiGzDecoder::new(BufReader::new(self))),
+            decoderKind::Deflate => Body::New(deflateReader::Sniff(Some(BufReader::new(self)))),
+        }
+        .withTrailer(trailer)
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            bodyInner::NoBody => f.write_str("Body(NoBody)"),
+            bodyInner::Bytes(b) => f.debug_tuple("Body").field(b).finish(),
+            _ => f.write_str("Body { .. }"),
+        }
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(b: Bytes) -> Body {
+        Body {
+            inner: bodyInner::Bytes(b),
+            trailer: None,
+        }
+    }
+}
+
+impl From<BytesMut> for Body {
+    fn from(b: BytesMut) -> Body {
+        Body::from(b.freeze())
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(b: Vec<u8>) -> Body {
+        Body::from(Bytes::from(b))
+    }
+}
+
+impl From<String> for Body {
+    fn from(s: String) -> Body {
+        Body::from(Bytes::from(s))
+    }
+}
+
+impl From<&'static str> for Body {
+    fn from(s: &'static str) -> Body {
+        Body::from(Bytes::from_static(s.as_bytes()))
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            bodyInner::NoBody => Ok(0),
+            bodyInner::Bytes(b) => {
+                let n = b.len().min(buf.len());
+                buf[..n].copy_from_slice(&b[..n]);
+                b.advance(n);
+                Ok(n)
+            }
+            bodyInner::Reader(r) => lock(r).read(buf),
+            #[cfg(feature = "async-http")]
+            bodyInner::AsyncReader(_) => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "http: body of an async response must be read asynchronously",
+            )),
+        }
+    }
+}
+
+impl gio::Reader for Body {
+    /// Read implements the io.Reader interface. It returns an UnexpectedEof "EOF" error once the body is exhausted.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Read实现了io.Reader接口，body读完后返回UnexpectedEof类型的"EOF"错误。
+    /// </details>
+    fn Read(&mut self, mut b: Vec<byte>) -> Result<int, io::Error> {
+        if b.is_empty() {
+            return Ok(0);
+        }
+        match self.read(&mut b)? {
+            0 => Err(io::Error::new(ErrorKind::UnexpectedEof, "EOF")),
+            n => Ok(n as int),
+        }
+    }
+}
+
+#[cfg(feature = "async-http")]
+impl Body {
+    // readAsync reads from any kind of body in an async context.
+    pub(crate) async fn readAsync(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        std::future::poll_fn(|cx| self.pollReadInner(cx, buf)).await
+    }
+
+    pub(crate) fn pollReadInner(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.inner {
+            bodyInner::AsyncReader(r) => lock(r).as_mut().pollRead(cx, buf),
+            // 内存中的body或用户提供的同步Reader直接读取。
+            _ => Poll::Ready(self.read(buf)),
+        }
+    }
+}
+
+#[cfg(feature = "tokio-runtime")]
+impl AsyncRead for Body {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = match self.get_mut().pollReadInner(cx, buf.initialize_unfilled()) {
+            Poll::Ready(Ok(n)) => n,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async-std-runtime")]
+impl AsyncRead for Body {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().pollReadInner(cx, buf)
+    }
+}
+
+#[cfg(feature = "async-http")]
+struct asyncReadAdapter<R>(R);
+
+#[cfg(all(feature = "async-http", feature = "tokio-runtime"))]
+impl<R: AsyncRead + Send + Unpin> asyncBodyReader for asyncReadAdapter<R> {
+    fn pollRead(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut rb = ReadBuf::new(buf);
+        match Pin::new(&mut self.0).poll_read(cx, &mut rb) {
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(rb.filled().len())),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(feature = "async-http", feature = "async-std-runtime"))]
+impl<R: AsyncRead + Send + Unpin> asyncBodyReader for asyncReadAdapter<R> {
+    fn pollRead(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum decoderKind {
+    Gzip,
+    Deflate,
+}
+
+// isZlibHeader reports whether b starts with a zlib stream header. "deflate" is specified as zlib wrapped data (RFC 9110), but some servers send raw deflate data.
+fn isZlibHeader(b: &[u8]) -> bool {
+    b.len() >= 2 && b[0] & 0x0f == 8 && (u16::from(b[0]) << 8 | u16::from(b[1])) % 31 == 0
+}
+
+// deflateReader inflates a "deflate" encoded body, choosing between zlib and raw deflate data on the first read.
+enum deflateReader {
+    Sniff(Option<BufReader<Body>>),
+    Zlib(bufread::ZlibDecoder<BufReader<Body>>),
+    Raw(bufread::DeflateDecoder<BufReader<Body>>),
+}
+
+impl Read for deflateReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self {
+                deflateReader::Sniff(r) => {
+                    let mut r = r.take().expect("deflateReader: missing reader");
+                    *self = if isZlibHeader(r.fill_buf()?) {
+                        deflateReader::Zlib(bufread::ZlibDecoder::new(r))
+                    } else {
+                        deflateReader::Raw(bufread::DeflateDecoder::new(r))
+                    };
+                }
+                deflateReader::Zlib(r) => return r.read(buf),
+                deflateReader::Raw(r) => return r.read(buf),
+            }
+        }
+    }
+}
+
+// writeDecoder is a push style decompressor; compressed data written to it is appended, inflated, to its output buffer.
+#[cfg(feature = "async-http")]
+enum writeDecoder {
+    Sniff,
+    Gzip(write::MultiGzDecoder<Vec<u8>>),
+    Zlib(write::ZlibDecoder<Vec<u8>>),
+    Raw(write::DeflateDecoder<Vec<u8>>),
+}
+
+#[cfg(feature = "async-http")]
+impl writeDecoder {
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        if let writeDecoder::Sniff = self {
+            *self = if isZlibHeader(data) {
+                writeDecoder::Zlib(write::ZlibDecoder::new(Vec::new()))
+            } else {
+                writeDecoder::Raw(write::DeflateDecoder::new(Vec::new()))
+            };
+        }
+        match self {
+            writeDecoder::Gzip(d) => d.write_all(data),
+            writeDecoder::Zlib(d) => d.write_all(data),
+            writeDecoder::Raw(d) => d.write_all(data),
+            writeDecoder::Sniff => Ok(()),
+        }
+    }
+
+    fn try_finish(&mut self) -> io::Result<()> {
+        match self {
+            writeDecoder::Gzip(d) => d.try_finish(),
+            writeDecoder::Zlib(d) => d.try_finish(),
+            writeDecoder::Raw(d) => d.try_finish(),
+            writeDecoder::Sniff => Ok(()),
+        }
+    }
+
+    fn output(&mut self) -> Option<&mut Vec<u8>> {
+        match self {
+            writeDecoder::Gzip(d) => Some(d.get_mut()),
+            writeDecoder::Zlib(d) => Some(d.get_mut()),
+            writeDecoder::Raw(d) => Some(d.get_mut()),
+            writeDecoder::Sniff => None,
+        }
+    }
+}
+
+// asyncDecoder inflates an async body as it is read.
+#[cfg(feature = "async-http")]
+struct asyncDecoder {
+    body: Body,
+    dec: writeDecoder,
+    off: usize,
+    eof: bool,
+}
+
+#[cfg(feature = "async-http")]
+impl asyncDecoder {
+    fn new(body: Body, kind: decoderKind) -> asyncDecoder {
+        let dec = match kind {
+            decoderKind::Gzip => writeDecoder::Gzip(write::MultiGzDecoder::new(Vec::new())),
+            decoderKind::Deflate => writeDecoder::Sniff,
+        };
+        asyncDecoder {
+            body,
+            dec,
+            off: 0,
+            eof: false,
+        }
+    }
+}
+
+#[cfg(feature = "async-http")]
+impl asyncBodyReader for asyncDecoder {
+    fn pollRead(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        loop {
+            if let Some(out) = this.dec.output() {
+                if this.off < out.len() {
+                    let n = (out.len() - this.off).min(buf.len());
+                    buf[..n].copy_from_slice(&out[this.off..this.off + n]);
+                    this.off += n;
+                    if this.off == out.len() {
+                        out.clear();
+                        this.off = 0;
+                    }
+                    return Poll::Ready(Ok(n));
+                }
+            }
+            if this.eof {
+                return Poll::Ready(Ok(0));
+            }
+            let mut chunk = [0u8; 8192];
+            match this.body.pollReadInner(cx, &mut chunk) {
+                Poll::Ready(Ok(0)) => {
+                    this.dec.try_finish()?;
+                    this.eof = true;
+                }
+                Poll::Ready(Ok(n)) => this.dec.write_all(&chunk[..n])?,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
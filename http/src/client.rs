@@ -20,6 +20,7 @@ use gostd_url as url;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::time::Instant;
 // use tokio::io::AsyncBufRead;
 // use tokio::io::AsyncRead;
 
@@ -32,12 +33,12 @@ const DefaultMaxIdleConnsPerHost: int32 = 2;
 /// TimeFormat is the time format to use when generating times in HTTP headers. It is like time.RFC1123 but hard-codes GMT as the time zone. The time being formatted must be in UTC for Format to generate the correct format.
 ///
 /// For parsing this time format, see ParseTime.
-const TimeFormat: &str = "Mon, 02 Jan 2006 15:04:05 GMT";
+pub const TimeFormat: &str = "Mon, 02 Jan 2006 15:04:05 GMT";
 
 /// TrailerPrefix is a magic prefix for ResponseWriter.Header map keys that, if present, signals that the map entry is actually for the response trailers, and not the response headers. The prefix is stripped after the ServeHTTP call finishes and the values are sent in the trailers.
 ///
 /// This mechanism is intended only for trailers that are not known prior to the headers being written. If the set of trailers is fixed or known before the header is written, the normal Go trailers mechanism is preferred:
-const TrailerPrefix: &str = "Trailer:";
+pub const TrailerPrefix: &str = "Trailer:";
 
 /// Get issues a GET to the specified URL. If the response is one of the following redirect codes, Get follows the redirect,up to a maximum of 10 redirects:
 /// ```text
@@ -61,10 +62,10 @@ const TrailerPrefix: &str = "Trailer:";
 /// // or use gostd::net::http;
 /// fn main() -> Result<(), Box<dyn std::error::Error>> {
 ///     let url = "https://petstore.swagger.io/v2/pet/findByStatus?status=available";
-///     let response = http::Get(url)?;///
+///     let mut response = http::Get(url)?;///
 ///     println!(
 ///         "{}",
-///         String::from_utf8(response.Body.expect("return body error").to_vec()).unwrap()
+///         String::from_utf8(response.Body.ReadAll()?.to_vec()).unwrap()
 ///     );
 ///     Ok(())
 /// }
@@ -94,11 +95,11 @@ pub fn Head(url: &str) -> HttpResult<Response> {
 ///     let postbody = r#"{"id":0,"category":{"id":0,"name":"string"},"name":"doggie","photoUrls":["string"],"tags":[{"id":0,"name":"string"}],"status":"available"}"#
 ///    .as_bytes()
 ///    .to_vec();
-///    let response = http::Post(url, "application/json", Some(postbody.into()))?;
+///    let mut response = http::Post(url, "application/json", Some(postbody.into()))?;
 ///
 ///    println!(
 ///        "{}",
-///        String::from_utf8(response.Body.expect("return body error").to_vec()).unwrap()
+///        String::from_utf8(response.Body.ReadAll()?.to_vec()).unwrap()
 ///    );
 ///
 ///    Ok(())
@@ -125,18 +126,104 @@ pub fn Delete(url: &str) -> HttpResult<Response> {
     Client::New().Delete(url)
 }
 
+/// CheckRedirectFunc specifies the policy for handling redirects. It is called with the upcoming request req and the requests made already, oldest first, in via.
+///
+/// If it returns an error, the Client returns that error instead of issuing req. As a special case, if it returns ErrUseLastResponse, the most recent response is returned with its body unclosed.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// CheckRedirectFunc指定处理重定向的策略。参数req是即将发出的请求，via是已经发出的请求，最早的在前。
+///
+/// 如果返回错误，Client会返回该错误而不再发出req。特殊情况：返回ErrUseLastResponse时，Client返回最近一次的响应，且不关闭其body。
+/// </details>
+pub type CheckRedirectFunc = Arc<dyn Fn(&Request, &[Request]) -> HttpResult<()> + Send + Sync>;
+
 pub struct Client {
-    Transport: Box<dyn RoundTripper>,
-    // CheckRedirect: fn(req: &Request, via: Vec<&Request>) -> Result<(), Error>,
-    Jar: Box<dyn CookieJar>,
-    Timeout: time::Duration,
+    /// Transport specifies the mechanism by which individual HTTP requests are made. Client::New uses a new Transport with default settings. Wrappers such as middleware::RetryTransport can be layered on top of it.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Transport指定发出单个HTTP请求的机制。Client::New使用默认设置的新Transport。可以在其上叠加middleware::RetryTransport等包装。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    ///
+    /// let mut tr = http::Transport::default();
+    /// tr.DisableCompression = true;
+    /// let mut client = http::Client::New();
+    /// client.Transport = Box::new(tr);
+    /// ```
+    pub Transport: Box<dyn RoundTripper>,
+    /// CheckRedirect specifies the policy for handling redirects. If CheckRedirect is None, the Client uses its default policy, which is to stop after 10 consecutive requests.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// CheckRedirect指定处理重定向的策略。如果CheckRedirect为None，Client使用默认策略：连续10次请求后停止。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    /// use std::sync::Arc;
+    ///
+    /// let mut client = http::Client::New();
+    /// // 不跟随重定向，直接返回3xx响应。
+    /// client.CheckRedirect = Some(Arc::new(|_, _| Err(http::HTTPConnectError::ErrUseLastResponse)));
+    /// ```
+    pub CheckRedirect: Option<CheckRedirectFunc>,
+    /// Jar specifies the cookie jar.
+    ///
+    /// The Jar is used to insert relevant cookies into every outbound Request and is updated with the cookie values of every inbound Response. The Jar is consulted for every redirect that the Client follows.
+    ///
+    /// If Jar is None, cookies are only sent if they are explicitly set on the Request.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Jar指定cookie jar。
+    ///
+    /// Jar用于在每个发出的请求中插入相关的cookie，并用每个收到的响应中的cookie更新自身。Client跟随的每一次重定向都会使用Jar。
+    ///
+    /// 如果Jar为None，只发送请求中显式设置的cookie。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    /// use std::sync::Arc;
+    ///
+    /// let mut client = http::Client::New();
+    /// client.Jar = Some(Arc::new(http::cookiejar::New(None).unwrap()));
+    /// ```
+    pub Jar: Option<Arc<dyn CookieJar>>,
+    /// Timeout specifies a time limit for requests made by this Client. The timeout includes connection time, any redirects, and reading the response body. The timer remains running after Get, Head, Post, or Do return and will interrupt reading of the Response.Body.
+    ///
+    /// A Timeout of zero means no timeout. When the limit is exceeded the request fails with HTTPConnectError::TimeoutError.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Timeout指定该Client发出的请求的时间限制。超时时间包括建立连接、所有重定向以及读取响应body的时间。Get、Head、Post或Do返回后计时仍然继续，超时后会中断Response.Body的读取。
+    ///
+    /// Timeout为零表示不限制。超过限制时请求返回HTTPConnectError::TimeoutError。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    /// use gostd_time as time;
+    ///
+    /// let mut client = http::Client::New();
+    /// client.Timeout = time::Duration::new(10 * time::Second);
+    /// ```
+    pub Timeout: time::Duration,
 }
 impl Default for Client {
     fn default() -> Self {
         Self {
             Transport: Box::new(Transport::default()),
+            CheckRedirect: None,
             Timeout: time::Duration::new(0),
-            Jar: Box::new(Cookie::default()),
+            Jar: None,
         }
     }
 }
@@ -146,6 +233,18 @@ impl Client {
         Self::default()
     }
 
+    /// NewWithTransport returns a Client that sends its requests through transport.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// NewWithTransport返回一个使用transport发送请求的Client。
+    /// </details>
+    pub fn NewWithTransport<T: RoundTripper + 'static>(transport: T) -> Self {
+        Self {
+            Transport: Box::new(transport),
+            ..Self::default()
+        }
+    }
+
     pub fn Get(&mut self, url: &str) -> HttpResult<Response> {
         let mut req = Request::New(Method::Get, url, None)?;
         self.Do(&mut req)
@@ -194,75 +293,280 @@ impl Client {
         self.done(req)
     }
 
+    // send sends req with the cookies of the Jar added, and records the cookies of the response in the Jar. req itself is left unchanged, so that redirects start from the headers given by the caller.
     fn send(
         &mut self,
-        req: &mut Request,
-        deadline: time::Time,
-    ) -> HttpResult<(Response, fn() -> bool)> {
-        let (resp, didTimeout) = send(req, self.transport(), deadline)?;
-        Ok((resp, didTimeout))
+        req: &Request,
+        deadline: Option<Instant>,
+    ) -> (HttpResult<Response>, impl Fn() -> bool) {
+        let mut req = req.clone();
+        if let Some(jar) = &self.Jar {
+            for cookie in jar.Cookies(&req.URL) {
+                req.AddCookie(&cookie);
+            }
+        }
+        let (resp, didTimeout) = send(&mut req, self.transport(), deadline);
+        if let (Some(jar), Ok(resp)) = (&self.Jar, &resp) {
+            let rc = resp.Cookies();
+            if !rc.is_empty() {
+                jar.SetCookies(&req.URL, rc);
+            }
+        }
+        (resp, didTimeout)
     }
 
     fn done(&mut self, req: &mut Request) -> HttpResult<Response> {
         let deadline = self.deadline();
-        let (resp, didTimeout) = self.send(req, deadline)?;
-        Ok(resp)
+        let mut reqs: Vec<Request> = Vec::new();
+        let mut req = req.clone();
+        // 一旦重定向到其他域名，之后的每一跳都不再发送敏感header，即使又回到了原来的域名。
+        let mut stripSensitive = false;
+        loop {
+            let (resp, didTimeout) = self.send(&req, deadline);
+            let mut resp = match resp {
+                Ok(resp) => resp,
+                Err(err) if didTimeout() => {
+                    return Err(HTTPConnectError::TimeoutError(format!(
+                        "{} (Client.Timeout exceeded while awaiting headers)",
+                        err
+                    )));
+                }
+                Err(err) => return Err(err),
+            };
+            match redirectRequest(
+                &mut reqs,
+                req,
+                &mut resp,
+                self.CheckRedirect.as_ref(),
+                self.Jar.is_some(),
+                &mut stripSensitive,
+            )? {
+                Some(next) => req = next,
+                None => return Ok(resp),
+            }
+        }
     }
 
-    fn deadline(&mut self) -> time::Time {
-        if self.Timeout > time::Duration::new(0) {
-            return time::Now().Add(&self.Timeout);
-        }
-        time::Time::default()
+    // deadline returns the point in time at which requests started now must be done, or None when Timeout is zero.
+    fn deadline(&self) -> Option<Instant> {
+        stdDuration(&self.Timeout).map(|d| Instant::now() + d)
     }
 
-    fn transport(&self) -> Box<dyn RoundTripper> {
-        Box::new(Transport::default())
+    fn transport(&mut self) -> &mut dyn RoundTripper {
+        self.Transport.as_mut()
     }
 }
 
+// send issues an HTTP request with the given deadline. The returned didTimeout reports whether the deadline has passed, so that an error can be reported as a Client.Timeout.
 fn send(
     ireq: &mut Request,
-    mut rt: Box<dyn RoundTripper>,
-    deadline: time::Time,
-) -> HttpResult<(Response, fn() -> bool)> {
-    let mut resp = Response::default();
-    fn didTimeout() -> bool {
-        return false;
+    rt: &mut dyn RoundTripper,
+    deadline: Option<Instant>,
+) -> (HttpResult<Response>, impl Fn() -> bool) {
+    let didTimeout = move || deadline.is_some_and(|d| Instant::now() >= d);
+    if didTimeout() {
+        return (
+            Err(HTTPConnectError::TimeoutError(
+                "net/http: request canceled".to_string(),
+            )),
+            didTimeout,
+        );
+    }
+    ireq.deadline = deadline;
+    (rt.RoundTrip(ireq), didTimeout)
+}
+
+// stdDuration converts a gostd Duration to a std Duration. Zero or negative durations mean no limit and yield None.
+pub(crate) fn stdDuration(d: &time::Duration) -> Option<std::time::Duration> {
+    if d.Nanoseconds() <= 0 {
+        return None;
+    }
+    Some(std::time::Duration::from_nanos(d.Nanoseconds() as u64))
+}
+
+// earliestDeadline returns the earlier of deadline and limit from now.
+fn earliestDeadline(
+    limit: Option<std::time::Duration>,
+    deadline: Option<Instant>,
+) -> Option<Instant> {
+    let d = limit.map(|l| Instant::now() + l);
+    match (d, deadline) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+// isTimeout reports whether err comes from a read or write timeout on a socket.
+fn isTimeout(err: &io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock)
+}
+
+// timeoutFor returns the smaller of limit and the time left before deadline. It fails with a timeout error once the deadline has passed.
+fn timeoutFor(
+    limit: Option<std::time::Duration>,
+    deadline: Option<Instant>,
+) -> io::Result<Option<std::time::Duration>> {
+    let left = match deadline {
+        None => return Ok(limit),
+        Some(d) => d.saturating_duration_since(Instant::now()),
     };
-    loop {
-        let mut resp = rt.RoundTrip(ireq)?;
-        let mut loc = resp.Header.Get("Location");
-        let (redirectMethod, shouldRedirect, includeBody) =
-            redirectBehavior(ireq.Method.as_str(), &resp, ireq);
-        if !shouldRedirect {
-            return Ok((resp, didTimeout));
+    if left.is_zero() {
+        return Err(io::Error::from(ErrorKind::TimedOut));
+    }
+    Ok(Some(limit.map_or(left, |l| l.min(left))))
+}
+
+// redirectRequest decides what to do after resp was received for req, the last of the requests in reqs. It returns the next request to send when the redirect should be followed, or None when resp is the final response. hasJar and stripSensitive are passed on to copyHeadersOnRedirect; stripSensitive must be kept by the caller across the whole redirect chain.
+pub(crate) fn redirectRequest(
+    reqs: &mut Vec<Request>,
+    req: Request,
+    resp: &mut Response,
+    checkRedirect: Option<&CheckRedirectFunc>,
+    hasJar: bool,
+    stripSensitive: &mut bool,
+) -> HttpResult<Option<Request>> {
+    let ireq = reqs.first().unwrap_or(&req);
+    let (redirectMethod, shouldRedirect, includeBody) =
+        redirectBehavior(req.Method.as_str(), resp, ireq);
+    if !shouldRedirect {
+        return Ok(None);
+    }
+    let loc = resp.Header.Get("Location");
+    if loc.is_empty() {
+        // While most 3xx responses include a Location, it is not required and 3xx responses without a Location have never been followed.
+        return Ok(None);
+    }
+    let u = match req.URL.Parse(loc.as_str()) {
+        Ok(u) => u,
+        Err(err) => {
+            resp.Body.Close();
+            return Err(HTTPConnectError::ConnectionFailure(format!(
+                "failed to parse Location header {:?}: {}",
+                loc, err
+            )));
+        }
+    };
+    // If the caller specified a custom host header and the redirect location is relative, preserve the Host header through the redirect.
+    let mut host = String::new();
+    if !req.Host.is_empty() && req.Host != req.URL.Host {
+        if let Ok(lu) = url::Parse(loc.as_str()) {
+            if lu.Scheme.is_empty() {
+                host = req.Host.clone();
+            }
         }
-        let mut u = ireq.URL.Parse(loc.as_str())?;
-        let urlRef = refererForURL(&ireq.URL, &u);
-        ireq.Method = redirectMethod.clone();
-        ireq.URL = u.clone();
-        ireq.Header.Set("Referer", urlRef.as_str());
     }
+    reqs.push(req);
+    let ireq = &reqs[0];
+    let last = &reqs[len!(reqs) - 1];
+
+    let mut next = ireq.clone();
+    next.Method = redirectMethod;
+    next.Host = host;
+    next.isTLS = u.Scheme == "https";
+    next.Header = copyHeadersOnRedirect(ireq, &u, hasJar, stripSensitive);
+    if includeBody && ireq.GetBody.is_some() {
+        next.rewindBody()?;
+    } else {
+        next.Body = None;
+        next.GetBody = None;
+        next.ContentLength = 0;
+    }
+    next.URL = u;
+    // Add the Referer header from the most recent request URL to the new one, if it's not https->http.
+    let referer = refererForURL(&last.URL, &next.URL);
+    if !referer.is_empty() {
+        next.Header.Set("Referer", referer.as_str());
+    }
+
+    let policy = match checkRedirect {
+        Some(f) => f(&next, reqs),
+        None => defaultCheckRedirect(&next, reqs),
+    };
+    if let Err(HTTPConnectError::ErrUseLastResponse) = policy {
+        return Ok(None);
+    }
+    // Close the previous response's body. But read at least some of the body so if it's small the underlying TCP connection will be re-used. No need to check for errors: if it fails, the Transport won't reuse it anyway.
+    const maxBodySlurpSize: u64 = 2 << 10;
+    if resp.ContentLength == -1 || resp.ContentLength <= maxBodySlurpSize as int64 {
+        io::copy(
+            &mut (&mut resp.Body).take(maxBodySlurpSize),
+            &mut io::sink(),
+        );
+    }
+    resp.Body.Close();
+    policy?;
+    Ok(Some(next))
+}
+
+// defaultCheckRedirect is the redirect policy used when Client.CheckRedirect is None: it stops after 10 consecutive requests.
+pub(crate) fn defaultCheckRedirect(req: &Request, via: &[Request]) -> HttpResult<()> {
+    if len!(via) >= 10 {
+        return Err("stopped after 10 redirects".into());
+    }
+    Ok(())
+}
+
+// copyHeadersOnRedirect returns the headers of the initial request ireq to send along with a redirect to dest. Sensitive headers such as Authorization and Cookie are only forwarded to the same domain or its subdomains. Once a redirect leaves that domain, stripSensitive is set and they are dropped for the rest of the chain. With a cookie jar the Cookie header is always dropped, the jar adds the cookies for dest.
+pub(crate) fn copyHeadersOnRedirect(
+    ireq: &Request,
+    dest: &url::URL,
+    hasJar: bool,
+    stripSensitive: &mut bool,
+) -> Header {
+    let mut header = ireq.Header.clone();
+    header.Del("Referer");
+    if hasJar {
+        header.Del("Cookie");
+    }
+    if !*stripSensitive {
+        let ihost = ireq.URL.Hostname().to_ascii_lowercase();
+        let dhost = dest.Hostname().to_ascii_lowercase();
+        *stripSensitive = !isDomainOrSubdomain(&dhost, &ihost);
+    }
+    if *stripSensitive {
+        for k in ["Authorization", "Www-Authenticate", "Cookie", "Cookie2"] {
+            header.Del(k);
+        }
+    }
+    header
+}
+
+// isDomainOrSubdomain reports whether sub is a subdomain (or exact match) of the parent domain.
+fn isDomainOrSubdomain(sub: &str, parent: &str) -> bool {
+    if sub == parent {
+        return true;
+    }
+    // If sub contains a :, it's probably an IPv6 address (and is definitely not a hostname). Don't check the suffix in that case.
+    if sub.contains([':', '%']) {
+        return false;
+    }
+    sub.ends_with(format!(".{}", parent).as_str())
 }
 
 pub fn redirectBehavior(reqMethod: &str, resp: &Response, ireq: &Request) -> (String, bool, bool) {
+    let mut redirectMethod = reqMethod.to_string();
     let mut shouldRedirect = false;
     let mut includeBody = false;
     match resp.StatusCode {
-        301 | 302 | 303 => return (Method::Get.String().to_string(), true, false),
-        307 | 308 => {
-            if resp.Header.Get("Location") == "" {
-                shouldRedirect = true;
-                includeBody = false;
+        301..=303 => {
+            shouldRedirect = true;
+            // RFC 2616 allowed automatic redirection only with GET and HEAD requests. RFC 7231 lifts this restriction, but we still restrict other methods to GET to maintain compatibility.
+            if reqMethod != "GET" && reqMethod != "HEAD" {
+                redirectMethod = Method::Get.String().to_string();
             }
-            if ireq.Body.is_none() && ireq.ContentLength != 0 {
+        }
+        307 | 308 => {
+            shouldRedirect = true;
+            includeBody = true;
+            // Treat 307 and 308 specially, since they're new in Go 1.8, and they also require re-sending the request body.
+            if ireq.GetBody.is_none() && ireq.outgoingLength() != 0 {
+                // We had a request body, and 307/308 require re-sending it, but GetBody is not defined. So just return this response to the user instead of an error, like we did in Go 1.7 and earlier.
                 shouldRedirect = false;
             }
         }
         _ => (),
     }
-    (reqMethod.to_string(), shouldRedirect, includeBody)
+    (redirectMethod, shouldRedirect, includeBody)
 }
 
 pub trait RoundTripper {
@@ -285,29 +589,180 @@ pub fn refererForURL(lastReq: &url::URL, newReq: &url::URL) -> String {
 use std::io;
 use std::iter::FromIterator;
 use std::sync;
-#[derive(Default, Clone)]
+/// Protocols is a set of HTTP protocols. The zero value is an empty set of protocols.
+///
+/// The supported protocols are HTTP/1 ("HTTP/1.0" and "HTTP/1.1"), HTTP/2 over TLS, negotiated with ALPN, and unencrypted HTTP/2 sent with prior knowledge (h2c).
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Protocols表示一组HTTP协议，零值为空集合。
+///
+/// 支持的协议有HTTP/1("HTTP/1.0"和"HTTP/1.1")、通过ALPN协商的基于TLS的HTTP/2，以及预先知道服务端支持时直接使用的未加密HTTP/2(h2c)。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+///
+/// let mut p = http::Protocols::default();
+/// p.SetHTTP1(true);
+/// p.SetUnencryptedHTTP2(true);
+/// assert_eq!(p.String(), "{HTTP1,UnencryptedHTTP2}");
+///
+/// let mut tr = http::Transport::default();
+/// tr.Protocols = Some(p);
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Protocols {
+    bits: u8,
+}
+
+const protoHTTP1: u8 = 1 << 0;
+const protoHTTP2: u8 = 1 << 1;
+const protoUnencryptedHTTP2: u8 = 1 << 2;
+
+impl Protocols {
+    /// HTTP1 reports whether p includes HTTP/1.
+    pub fn HTTP1(&self) -> bool {
+        self.bits & protoHTTP1 != 0
+    }
+
+    /// SetHTTP1 adds or removes HTTP/1 from p.
+    pub fn SetHTTP1(&mut self, ok: bool) {
+        self.setBit(protoHTTP1, ok);
+    }
+
+    /// HTTP2 reports whether p includes HTTP/2.
+    pub fn HTTP2(&self) -> bool {
+        self.bits & protoHTTP2 != 0
+    }
+
+    /// SetHTTP2 adds or removes HTTP/2 from p.
+    pub fn SetHTTP2(&mut self, ok: bool) {
+        self.setBit(protoHTTP2, ok);
+    }
+
+    /// UnencryptedHTTP2 reports whether p includes unencrypted HTTP/2.
+    pub fn UnencryptedHTTP2(&self) -> bool {
+        self.bits & protoUnencryptedHTTP2 != 0
+    }
+
+    /// SetUnencryptedHTTP2 adds or removes unencrypted HTTP/2 from p.
+    pub fn SetUnencryptedHTTP2(&mut self, ok: bool) {
+        self.setBit(protoUnencryptedHTTP2, ok);
+    }
+
+    /// String returns the set of protocols, such as "{HTTP1,HTTP2}".
+    pub fn String(&self) -> String {
+        let mut names = Vec::new();
+        if self.HTTP1() {
+            names.push("HTTP1");
+        }
+        if self.HTTP2() {
+            names.push("HTTP2");
+        }
+        if self.UnencryptedHTTP2() {
+            names.push("UnencryptedHTTP2");
+        }
+        format!("{{{}}}", names.join(","))
+    }
+
+    fn setBit(&mut self, bit: u8, ok: bool) {
+        if ok {
+            self.bits |= bit;
+        } else {
+            self.bits &= !bit;
+        }
+    }
+}
+
+/// Transport is an implementation of RoundTripper that supports HTTP and HTTPS.
+///
+/// By default, Transport caches connections for future re-use. Connections are keyed by their connectMethod, so requests to the same scheme and host share a pool of idle (keep-alive) connections. Clones of a Transport share the same pool.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Transport类型实现了RoundTripper接口，支持http、https。
+///
+/// Transport类型默认会缓存连接以便重用，连接按connectMethod区分，同一协议和主机的请求共享空闲(keep-alive)连接池。Transport的克隆共享同一个连接池。
+/// </details>
+#[derive(Clone)]
 pub struct Transport {
-    // idleMu: sync::Mutex,
+    idleMu: Arc<(sync::Mutex<idleConnPool>, sync::Condvar)>,
     closeIdle: bool,
-    // idleConn:HashMap<String, Vec<>>
-    Proxy: Option<url::URL>,
+    /// Proxy specifies a function to return a proxy for a given Request. If the function returns an error, the request is aborted with the provided error.
+    ///
+    /// The proxy type is determined by the URL scheme. "http", "https", and "socks5" are supported. If the scheme is empty, "http" is assumed.
+    ///
+    /// If Proxy is None or returns Ok(None), no proxy is used.
+    pub Proxy: Option<ProxyFunc>,
     // Dial: fn(network: &str, addr: &str) -> Result<net::TcpConn, Error>,
-    ForceAttemptHTTP2: bool,
-    MaxIdleConns: int,
-    // IdleConnTimeout:       90 * time.Second,
-    // TLSHandshakeTimeout:   10 * time.Second,
-    // ExpectContinueTimeout: 1 * time.Second,
-    DisableKeepAlives: bool,
-
-    DisableCompression: bool,
-    iMaxIdleConnsPerHost: int,
-    MaxConnsPerHost: int,
-    MaxResponseHeaderBytes: int64,
-    WriteBufferSize: int,
-    ReadBufferSize: int,
+    /// ForceAttemptHTTP2 controls whether HTTP/2 is enabled when a non-default TLSClientConfig is provided. By default, use of a custom TLS configuration conservatively disables HTTP/2. Ignored when Protocols is set.
+    pub ForceAttemptHTTP2: bool,
+    /// Protocols is the set of protocols supported by the transport. If None, the default is usually HTTP/1 only, or HTTP/1 and HTTP/2 if ForceAttemptHTTP2 is true or TLSClientConfig is None.
+    ///
+    /// HTTP/2 over TLS is negotiated with ALPN. Unencrypted HTTP/2 is used with prior knowledge for "http" URLs when UnencryptedHTTP2 is set and HTTP1 is not.
+    pub Protocols: Option<Protocols>,
+    /// MaxIdleConns controls the maximum number of idle (keep-alive) connections across all hosts. Zero means no limit.
+    pub MaxIdleConns: int,
+    /// IdleConnTimeout is the maximum amount of time an idle (keep-alive) connection will remain idle before closing itself. Zero means no limit.
+    pub IdleConnTimeout: time::Duration,
+    /// DialTimeout is the maximum amount of time a dial will wait for a TCP connect to complete, including any SOCKS or CONNECT proxy handshake. Zero means no timeout.
+    pub DialTimeout: time::Duration,
+    /// TLSHandshakeTimeout specifies the maximum amount of time to wait for a TLS handshake. Zero means no timeout.
+    pub TLSHandshakeTimeout: time::Duration,
+    /// ResponseHeaderTimeout, if non-zero, specifies the amount of time to wait for a server's response headers after fully writing the request (including its body, if any). This time does not include the time to read the response body.
+    pub ResponseHeaderTimeout: time::Duration,
+    /// ExpectContinueTimeout, if non-zero, specifies the amount of time to wait for a server's first response headers after fully writing the request headers if the request has an "Expect: 100-continue" header. Zero means no timeout and causes the body to be sent immediately, without waiting for the server to approve. This time does not include the time to send the request header.
+    pub ExpectContinueTimeout: time::Duration,
+    /// TLSClientConfig specifies the TLS configuration to use for HTTPS connections, including connections to HTTPS proxies. If None, the default configuration is used.
+    ///
+    /// The configuration is built on first use and shared by every connection of this Transport and its clones, so it must not be changed afterwards.
+    pub TLSClientConfig: Option<tls::Config>,
+    tlsConfigCache: tls::configCache<ClientConfig>,
+    tlsH2ConfigCache: tls::configCache<ClientConfig>,
+    /// DisableKeepAlives, if true, disables HTTP keep-alives and will only use the connection to the server for a single HTTP request.
+    pub DisableKeepAlives: bool,
+
+    pub DisableCompression: bool,
+    /// iMaxIdleConnsPerHost, if non-zero, controls the maximum idle (keep-alive) connections to keep per-host. If zero, DefaultMaxIdleConnsPerHost is used.
+    pub iMaxIdleConnsPerHost: int,
+    /// MaxConnsPerHost optionally limits the total number of connections per host, including connections in the dialing, active, and idle states. On limit violation, dials will block. Zero means no limit.
+    pub MaxConnsPerHost: int,
+    pub MaxResponseHeaderBytes: int64,
+    pub WriteBufferSize: int,
+    pub ReadBufferSize: int,
     tlsNextProtoWasNil: bool,
 }
 
+impl Default for Transport {
+    fn default() -> Self {
+        Self {
+            idleMu: Arc::default(),
+            closeIdle: false,
+            Proxy: None,
+            ForceAttemptHTTP2: false,
+            Protocols: None,
+            MaxIdleConns: 0,
+            IdleConnTimeout: time::Duration::new(0),
+            DialTimeout: time::Duration::new(30 * time::Second),
+            TLSHandshakeTimeout: time::Duration::new(10 * time::Second),
+            ResponseHeaderTimeout: time::Duration::new(0),
+            ExpectContinueTimeout: time::Duration::new(time::Second),
+            TLSClientConfig: None,
+            tlsConfigCache: tls::configCache::default(),
+            tlsH2ConfigCache: tls::configCache::default(),
+            DisableKeepAlives: false,
+            DisableCompression: false,
+            iMaxIdleConnsPerHost: 0,
+            MaxConnsPerHost: 0,
+            MaxResponseHeaderBytes: 0,
+            WriteBufferSize: 0,
+            ReadBufferSize: 0,
+            tlsNextProtoWasNil: false,
+        }
+    }
+}
+
 use std::net;
 use std::sync::mpsc;
 impl RoundTripper for Transport {
@@ -322,59 +777,427 @@ impl Transport {
             extra: None,
         };
         let cm = self.connectMethodForRequest(treq)?;
-        let (mut pconn, mut conn) = self.getConn(treq, cm)?;
-        // conn.set_write_timeout(Some(std::time::Duration::new(5, 0)));
-        // conn.set_read_timeout(Some(std::time::Duration::new(5, 0)));
+        let mut h2Retries = 0;
+        loop {
+            let pconn = match self.getConn(treq, &cm)? {
+                pooledConn::H1(pconn) => *pconn,
+                pooledConn::H2(cc) => match cc.RoundTrip(self, &treq.Req) {
+                    // 请求没有被服务端处理(连接已不可用、流被拒绝或GOAWAY)，换一个连接重试。
+                    Err(err) if http2::isConnUnusable(&err) && h2Retries < maxH2Retries => {
+                        h2Retries += 1;
+                        treq.Req.rewindBody()?;
+                        continue;
+                    }
+                    r => return r,
+                },
+            };
+            let reused = pconn.reused;
+            match pconn.roundTrip(self, treq) {
+                Ok(resp) => return Ok(resp),
+                Err(err) => {
+                    if !shouldRetryRequest(reused, &treq.Req, &err) {
+                        return Err(err);
+                    }
+                    // 重试前用GetBody取得新的body，已经写出的body不能再次读取。
+                    treq.Req.rewindBody()?;
+                }
+            }
+        }
+    }
 
-        pconn.roundTrip(treq, conn)
+    /// CloseIdleConnections closes any connections which were previously connected from previous requests but are now sitting idle in a "keep-alive" state. It does not interrupt any connections currently in use.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// CloseIdleConnections关闭所有之前的请求建立但目前处于空闲状态的连接。本方法不会关闭正在使用的连接。
+    /// </details>
+    pub fn CloseIdleConnections(&self) {
+        let (mu, cond) = &*self.idleMu;
+        let mut pool = mu.lock().unwrap();
+        for cc in pool.h2Conns.values().flatten() {
+            if cc.idleFor().is_some() {
+                cc.Close();
+            }
+        }
+        let keys: Vec<connectMethodKey> = pool.idleConn.keys().cloned().collect();
+        for key in keys {
+            if let Some(idles) = pool.idleConn.remove(&key) {
+                for _ in idles {
+                    pool.decConnsPerHost(&key);
+                }
+            }
+        }
+        cond.notify_all();
+    }
+
+    // getConn returns an HTTP/2 connection with a free stream or an idle connection for cm if one is available, otherwise it dials a new one, waiting while MaxConnsPerHost is reached.
+    fn getConn(&mut self, treq: &transportRequest, cm: &connectMethod) -> HttpResult<pooledConn> {
+        let trace = treq.Req.trace();
+        trace.getConn(cm.addr().as_str());
+        let key = cm.key();
+        let idle = {
+            let (mu, cond) = &*self.idleMu;
+            let mut pool = mu.lock().unwrap();
+            loop {
+                if !cm.onlyH1 {
+                    if let Some(cc) = pool.h2Conn(&key, &self.IdleConnTimeout) {
+                        let info = cc.gotConnInfo();
+                        break Some((pooledConn::H2(cc), info));
+                    }
+                }
+                let mut idle = None;
+                while let Some(mut pconn) = pool.idleConn.get_mut(&key).and_then(|v| v.pop()) {
+                    if pconn.isExpired(&self.IdleConnTimeout) || pconn.isStale() {
+                        pool.decConnsPerHost(&key);
+                        continue;
+                    }
+                    pconn.reused = true;
+                    idle = Some(pconn);
+                    break;
+                }
+                if let Some(pconn) = idle {
+                    let info = httptrace::GotConnInfo {
+                        Reused: true,
+                        WasIdle: true,
+                        IdleTime: time::Duration::new(
+                            time::Now().UnixNano() - pconn.idleAt.UnixNano(),
+                        ),
+                    };
+                    break Some((pooledConn::H1(Box::new(pconn)), info));
+                }
+                if self.MaxConnsPerHost <= 0 || pool.connsPerHost(&key) < self.MaxConnsPerHost {
+                    pool.incConnsPerHost(&key);
+                    break None;
+                }
+                pool = match timeoutFor(None, treq.Req.deadline)? {
+                    None => cond.wait(pool).unwrap(),
+                    Some(timeout) => cond.wait_timeout(pool, timeout).unwrap().0,
+                };
+            }
+        };
+        // 钩子在释放连接池的锁之后调用。
+        if let Some((conn, info)) = idle {
+            trace.gotConn(info);
+            return Ok(conn);
+        }
+        match self.dialConn(cm, treq.Req.deadline, trace) {
+            Ok(conn) => {
+                trace.gotConn(httptrace::GotConnInfo {
+                    Reused: false,
+                    WasIdle: false,
+                    IdleTime: time::Duration::new(0),
+                });
+                Ok(conn)
+            }
+            Err(err) => {
+                self.decConnsPerHost(&key);
+                Err(err)
+            }
+        }
     }
 
-    fn getConn(
+    // dialConn connects to cm's target, going through its proxy if any. The dial and proxy handshake are bounded by DialTimeout and each TLS handshake by TLSHandshakeTimeout, all of them by the request deadline as well.
+    fn dialConn(
         &mut self,
-        treq: &transportRequest,
-        cm: connectMethod,
-    ) -> HttpResult<(persistConn, TcpConn)> {
-        let conn = self.dialConn(cm)?;
-        let pconn = persistConn::default();
-        Ok((pconn, conn))
-    }
-
-    fn dialConn(&mut self, cm: connectMethod) -> HttpResult<TcpConn> {
-        // pconn.t = self;
-        // pconn.reqch = mpsc::channel();
-        // pconn.writech = mpsc::channel();
-        // pconn.writeLoopDone = mpsc::channel();
-        self.dial("tcp", cm.addr().as_str())
-        // pconn.conn = conn;
-        // pconn.br = bufio::NewReaderSize(pconn, self.readBufferSize());
-        // pconn.bw = bufio::NewWriterSize(persistConnWriter { pconn }, self.writeBufferSize());
+        cm: &connectMethod,
+        deadline: Option<Instant>,
+        trace: &ClientTrace,
+    ) -> HttpResult<pooledConn> {
+        let dialDeadline = earliestDeadline(stdDuration(&self.DialTimeout), deadline);
+        let tlsTimeout = stdDuration(&self.TLSHandshakeTimeout);
+        let tlsDeadline = || earliestDeadline(tlsTimeout, deadline);
+        let mut conn = match &cm.proxyURL {
+            Some(proxyURL) if proxyURL.Scheme == "socks5" => {
+                // SOCKS5代理：握手后连接即为到目标地址的隧道，目标域名由代理解析。
+                let mut conn =
+                    self.dial("tcp", canonicalAddr(proxyURL).as_str(), dialDeadline, trace)?;
+                let auth = cm
+                    .proxyUserinfo
+                    .clone()
+                    .map(|(User, Password)| socks::Auth { User, Password });
+                socks::SOCKS5(proxyURL.Host.as_str(), auth)
+                    .DialWithConn(&mut conn, cm.addr().as_str())?;
+                netConn::Plain(conn)
+            }
+            Some(proxyURL) => {
+                let conn = netConn::Plain(self.dial(
+                    "tcp",
+                    canonicalAddr(proxyURL).as_str(),
+                    dialDeadline,
+                    trace,
+                )?);
+                if proxyURL.Scheme == "https" {
+                    netConn::Tls(Box::new(self.getTLSConn(
+                        proxyURL.Hostname().as_str(),
+                        conn,
+                        tlsDeadline(),
+                        false,
+                        trace,
+                    )?))
+                } else {
+                    conn
+                }
+            }
+            None => netConn::Plain(self.dial("tcp", cm.addr().as_str(), dialDeadline, trace)?),
+        };
+        let mut isProxy = false;
+        if cm.proxyURL.as_ref().is_some_and(|u| u.Scheme != "socks5") {
+            if cm.scheme() == "http" {
+                // 代理普通http请求，请求行使用绝对URL，连接可以给不同目标复用。
+                isProxy = true;
+            } else if cm.scheme() == "https" {
+                // 代理https请求，先通过CONNECT建立到目标地址的隧道。
+                conn = proxyConnect(cm, conn)?;
+            }
+        }
+        if cm.scheme() == "https" {
+            let tlsConn = self.getTLSConn(
+                cm.tlsHost().as_str(),
+                conn,
+                tlsDeadline(),
+                !cm.onlyH1,
+                trace,
+            )?;
+            if tlsConn.conn.alpn_protocol() == Some(b"h2") {
+                return self.newH2Conn(cm, netConn::Tls(Box::new(tlsConn)));
+            }
+            if !cm.onlyH1 && !self.protocols().HTTP1() {
+                return Err(HTTPConnectError::ConnectionFailure(
+                    "http: server does not support HTTP/2".to_string(),
+                ));
+            }
+            conn = netConn::Tls(Box::new(tlsConn));
+        } else if !cm.onlyH1 {
+            // 未加密的HTTP/2(h2c)，直接发送连接前言(prior knowledge)。
+            return self.newH2Conn(cm, conn);
+        }
         // 待实现读写进程
         /* go pconn.readLoop()
         go pconn.writeLoop() */
-        // Ok(pconn)
+        Ok(pooledConn::H1(Box::new(persistConn {
+            cacheKey: cm.key(),
+            br: BufReader::with_capacity(self.readBufferSize() as usize, conn),
+            nwrite: 0,
+            isProxy,
+            proxyAuth: cm.proxyAuth(),
+            sawEOF: false,
+            readLimit: 0,
+            numExpectedResponses: 0,
+            broken: false,
+            closed: false,
+            reused: false,
+            sawFirstByte: false,
+            idleAt: time::Time::default(),
+        })))
+    }
+
+    // newH2Conn starts an HTTP/2 connection over conn and adds it to the pool, where it serves concurrent requests for cm until it is closed.
+    fn newH2Conn(&self, cm: &connectMethod, conn: netConn) -> HttpResult<pooledConn> {
+        let key = cm.key();
+        let (r, w, closer) = conn.split()?;
+        let idleMu = self.idleMu.clone();
+        let onCloseKey = key.clone();
+        let cc = http2::NewClientConn(
+            r,
+            w,
+            closer,
+            self.maxHeaderListSize(),
+            Box::new(move |cc| {
+                let (mu, cond) = &*idleMu;
+                let mut pool = mu.lock().unwrap();
+                if let Some(conns) = pool.h2Conns.get_mut(&onCloseKey) {
+                    conns.retain(|c| !c.ptrEq(cc));
+                }
+                pool.decConnsPerHost(&onCloseKey);
+                cond.notify_all();
+            }),
+        )?;
+        let (mu, cond) = &*self.idleMu;
+        mu.lock()
+            .unwrap()
+            .h2Conns
+            .entry(key)
+            .or_default()
+            .push(cc.clone());
+        cond.notify_all();
+        Ok(pooledConn::H2(cc))
     }
 
-    fn dial(&mut self, network: &str, addr: &str) -> HttpResult<TcpConn> {
-        Ok(net::TcpStream::connect(addr)?)
+    // dial connects to addr, giving up at deadline. The connection keeps the remaining time as its read and write timeout so that a proxy handshake on it is bounded by the same deadline.
+    fn dial(
+        &mut self,
+        network: &str,
+        addr: &str,
+        deadline: Option<Instant>,
+        trace: &ClientTrace,
+    ) -> HttpResult<TcpConn> {
+        let dialTimeout =
+            || HTTPConnectError::TimeoutError(format!("dial {} {}: i/o timeout", network, addr));
+        let host = match strings::LastIndex(addr, ":") {
+            i if i >= 0 => &addr[..i as usize],
+            _ => addr,
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        // IP地址不需要DNS查询，也不调用DNS钩子。
+        let isIP = host.parse::<net::IpAddr>().is_ok();
+        if !isIP {
+            trace.dnsStart(host);
+        }
+        let addrs: Vec<net::SocketAddr> = match net::ToSocketAddrs::to_socket_addrs(addr) {
+            Ok(addrs) => addrs.collect(),
+            Err(err) => {
+                let err = HTTPConnectError::from(err);
+                if !isIP {
+                    trace.dnsDone(vec![], Some(&err));
+                }
+                return Err(err);
+            }
+        };
+        if !isIP {
+            trace.dnsDone(addrs.iter().map(|sa| sa.ip()).collect(), None);
+        }
+        let mut lastErr = io::Error::new(
+            ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        );
+        let mut conn = None;
+        for sa in addrs {
+            let timeout = timeoutFor(None, deadline).map_err(|_| dialTimeout())?;
+            let saddr = sa.to_string();
+            trace.connectStart(network, saddr.as_str());
+            let r = match timeout {
+                None => net::TcpStream::connect(sa),
+                Some(timeout) => net::TcpStream::connect_timeout(&sa, timeout),
+            };
+            match r {
+                Ok(c) => {
+                    trace.connectDone(network, saddr.as_str(), None);
+                    conn = Some(c);
+                    break;
+                }
+                Err(err) => {
+                    let traced = io::Error::new(err.kind(), err.to_string());
+                    trace.connectDone(network, saddr.as_str(), Some(&traced.into()));
+                    lastErr = err;
+                }
+            }
+        }
+        let conn = match conn {
+            Some(c) => c,
+            None if lastErr.kind() == ErrorKind::TimedOut => return Err(dialTimeout()),
+            None => return Err(lastErr.into()),
+        };
+        let timeout = timeoutFor(None, deadline).map_err(|_| dialTimeout())?;
+        conn.set_read_timeout(timeout)?;
+        conn.set_write_timeout(timeout)?;
+        Ok(conn)
+    }
+
+    // tryPutIdleConn adds pconn to the list of idle persistent connections awaiting a new request. If pconn is no longer needed or not in a good state, it is closed instead.
+    fn tryPutIdleConn(&self, mut pconn: persistConn) {
+        if self.DisableKeepAlives || pconn.broken || pconn.closed {
+            self.closeConn(pconn);
+            return;
+        }
+        let (mu, cond) = &*self.idleMu;
+        let mut pool = mu.lock().unwrap();
+        pool.removeExpired(&self.IdleConnTimeout);
+        let key = pconn.cacheKey.clone();
+        let idles = pool.idleConn.entry(key.clone()).or_default();
+        if len!(idles) as int >= self.maxIdleConnsPerHost() {
+            pool.decConnsPerHost(&key);
+            cond.notify_all();
+            return;
+        }
+        pconn.idleAt = time::Now();
+        idles.push(pconn);
+        if self.MaxIdleConns > 0 && pool.len() > self.MaxIdleConns {
+            pool.removeOldest();
+        }
+        cond.notify_all();
+    }
+
+    fn closeConn(&self, pconn: persistConn) {
+        self.decConnsPerHost(&pconn.cacheKey);
+    }
+
+    fn decConnsPerHost(&self, key: &connectMethodKey) {
+        let (mu, cond) = &*self.idleMu;
+        mu.lock().unwrap().decConnsPerHost(key);
+        cond.notify_all();
+    }
+
+    fn maxHeaderListSize(&self) -> u32 {
+        if self.MaxResponseHeaderBytes > 0 {
+            return self.MaxResponseHeaderBytes.min(u32::MAX as int64) as u32;
+        }
+        10 << 20
+    }
+
+    // protocols returns the set of protocols the transport may use, see the Protocols field.
+    fn protocols(&self) -> Protocols {
+        if let Some(p) = self.Protocols {
+            if p.HTTP1() || p.HTTP2() || p.UnencryptedHTTP2() {
+                return p;
+            }
+        }
+        let mut p = Protocols::default();
+        p.SetHTTP1(true);
+        if self.Protocols.is_none() && (self.ForceAttemptHTTP2 || self.TLSClientConfig.is_none()) {
+            p.SetHTTP2(true);
+        }
+        p
+    }
+
+    fn maxIdleConnsPerHost(&self) -> int {
+        if self.iMaxIdleConnsPerHost != 0 {
+            return self.iMaxIdleConnsPerHost;
+        }
+        DefaultMaxIdleConnsPerHost as int
     }
 
     fn connectMethodForRequest(&mut self, treq: &transportRequest) -> HttpResult<connectMethod> {
         let mut cm = connectMethod::default();
         cm.targetScheme = treq.Req.URL.Scheme.clone();
         cm.targetAddr = canonicalAddr(&treq.Req.URL.clone());
-        cm.proxyURL = None;
-        cm.onlyH1 = true; //待优化
+        if let Some(proxy) = &self.Proxy {
+            if let Some(proxyURL) = proxy(&treq.Req)? {
+                match proxyURL.Scheme.as_str() {
+                    "" | "http" | "https" | "socks5" => (),
+                    scheme => {
+                        return Err(HTTPConnectError::ConnectionFailure(format!(
+                            "proxyconnect: unsupported proxy scheme {:?}",
+                            scheme
+                        )))
+                    }
+                }
+                let (userinfo, mut proxyURL) = splitProxyUserinfo(&proxyURL);
+                if proxyURL.Scheme.is_empty() {
+                    proxyURL.Scheme = "http".to_string();
+                }
+                cm.proxyURL = Some(proxyURL);
+                cm.proxyUserinfo = userinfo;
+            }
+        }
+        let p = self.protocols();
+        let viaHTTPProxy = cm.proxyURL.as_ref().is_some_and(|u| u.Scheme != "socks5");
+        cm.onlyH1 = treq.Req.requiresHTTP1()
+            || match cm.targetScheme.as_str() {
+                // https代理之上的TLS隧道无法拆分读写，只使用HTTP/1。
+                "https" => !p.HTTP2() || cm.proxyURL.as_ref().is_some_and(|u| u.Scheme == "https"),
+                "http" => !p.UnencryptedHTTP2() || p.HTTP1() || viaHTTPProxy,
+                _ => true,
+            };
         Ok(cm)
     }
 
-    fn wirteBufferSize(self) -> int {
+    fn wirteBufferSize(&self) -> int {
         if self.WriteBufferSize > 0 {
             return self.WriteBufferSize;
         }
         4 << 10
     }
 
-    fn readBufferSize(self) -> int {
+    fn readBufferSize(&self) -> int {
         if self.ReadBufferSize > 0 {
             return self.ReadBufferSize;
         }
@@ -382,6 +1205,103 @@ impl Transport {
     }
 }
 
+// idleConnPool holds the idle connections of a Transport, keyed by connectMethodKey, together with the number of live connections per key.
+#[derive(Default)]
+struct idleConnPool {
+    idleConn: HashMap<connectMethodKey, Vec<persistConn>>,
+    h2Conns: HashMap<connectMethodKey, Vec<http2::ClientConn>>, // HTTP/2 connections are shared, not taken out of the pool
+    connsPerHost: HashMap<connectMethodKey, int>,
+}
+
+impl idleConnPool {
+    // h2Conn returns an HTTP/2 connection for key that can take a new request. Connections idle longer than timeout are closed; they leave the pool once their read loop exits.
+    fn h2Conn(
+        &mut self,
+        key: &connectMethodKey,
+        timeout: &time::Duration,
+    ) -> Option<http2::ClientConn> {
+        let conns = self.h2Conns.get_mut(key)?;
+        conns.retain(|cc| !cc.isClosed());
+        let idleTimeout = stdDuration(timeout);
+        for cc in conns.iter() {
+            if let (Some(idle), Some(limit)) = (cc.idleFor(), idleTimeout) {
+                if idle > limit {
+                    cc.Close();
+                    continue;
+                }
+            }
+            if cc.CanTakeNewRequest() {
+                return Some(cc.clone());
+            }
+        }
+        None
+    }
+
+    fn len(&self) -> int {
+        self.idleConn.values().map(|v| len!(v) as int).sum()
+    }
+
+    fn connsPerHost(&self, key: &connectMethodKey) -> int {
+        *self.connsPerHost.get(key).unwrap_or(&0)
+    }
+
+    fn incConnsPerHost(&mut self, key: &connectMethodKey) {
+        *self.connsPerHost.entry(key.clone()).or_insert(0) += 1;
+    }
+
+    fn decConnsPerHost(&mut self, key: &connectMethodKey) {
+        if let Some(n) = self.connsPerHost.get_mut(key) {
+            *n -= 1;
+            if *n <= 0 {
+                self.connsPerHost.remove(key);
+            }
+        }
+    }
+
+    // removeExpired closes every idle connection that has been idle longer than timeout.
+    fn removeExpired(&mut self, timeout: &time::Duration) {
+        let mut expired = Vec::new();
+        for (key, idles) in self.idleConn.iter_mut() {
+            idles.retain(|pconn| {
+                if pconn.isExpired(timeout) {
+                    expired.push(key.clone());
+                    return false;
+                }
+                true
+            });
+        }
+        for key in expired {
+            self.decConnsPerHost(&key);
+        }
+        self.idleConn.retain(|_, idles| !idles.is_empty());
+    }
+
+    // removeOldest closes the connection that has been idle the longest.
+    fn removeOldest(&mut self) {
+        let mut oldest: Option<(connectMethodKey, usize)> = None;
+        for (key, idles) in self.idleConn.iter() {
+            for (i, pconn) in idles.iter().enumerate() {
+                let older = match &oldest {
+                    Some((k, j)) => pconn.idleAt.Before(&self.idleConn[k][*j].idleAt),
+                    None => true,
+                };
+                if older {
+                    oldest = Some((key.clone(), i));
+                }
+            }
+        }
+        if let Some((key, i)) = oldest {
+            if let Some(idles) = self.idleConn.get_mut(&key) {
+                idles.remove(i);
+                if idles.is_empty() {
+                    self.idleConn.remove(&key);
+                }
+            }
+            self.decConnsPerHost(&key);
+        }
+    }
+}
+
 fn canonicalAddr(url: &url::URL) -> String {
     let portMap: HashMap<String, String> = [
         ("http".to_string(), "80".to_string()),
@@ -423,8 +1343,17 @@ struct connectMethod {
     // be reused for different targetAddr values.
     targetAddr: String,
     onlyH1: bool, // whether to disable HTTP/2 and force HTTP/1
+    proxyUserinfo: Option<(String, String)>, // username and password taken from the proxy URL
 }
 impl connectMethod {
+    // proxyAuth returns the Proxy-Authorization header to set on requests, if applicable.
+    fn proxyAuth(&self) -> String {
+        match &self.proxyUserinfo {
+            Some((username, password)) => format!("Basic {}", basicAuth(username, password)),
+            None => "".to_string(),
+        }
+    }
+
     fn scheme(&self) -> String {
         self.targetScheme.clone()
     }
@@ -432,25 +1361,242 @@ impl connectMethod {
     pub fn addr(&self) -> String {
         self.targetAddr.clone()
     }
+
+    // tlsHost returns the host name to match against the peer's TLS certificate.
+    fn tlsHost(&self) -> String {
+        let h = self.targetAddr.as_str();
+        if hasPort(h) {
+            return h[..strings::LastIndex(h, ":") as usize].to_string();
+        }
+        h.to_string()
+    }
+
+    fn key(&self) -> connectMethodKey {
+        let mut proxyStr = "".to_string();
+        let mut targetAddr = self.targetAddr.clone();
+        if let Some(proxyURL) = &self.proxyURL {
+            proxyStr = proxyURL.String();
+            if (proxyURL.Scheme == "http" || proxyURL.Scheme == "https")
+                && self.targetScheme == "http"
+            {
+                targetAddr = "".to_string();
+            }
+        }
+        connectMethodKey {
+            proxy: proxyStr,
+            scheme: self.targetScheme.clone(),
+            addr: targetAddr,
+            onlyH1: self.onlyH1,
+        }
+    }
 }
+
+// connectMethodKey is the map key version of connectMethod, with a stringified proxy URL (or the empty string) instead of a pointer to a URL.
+#[derive(Default, PartialEq, Eq, Hash, Clone, Debug)]
+struct connectMethodKey {
+    proxy: String,
+    scheme: String,
+    addr: String,
+    onlyH1: bool,
+}
+
 type TcpConn = TcpStream;
+
+// netConn is the stream a persistConn talks over: either a plain TCP connection or a rustls stream on top of one.
+enum netConn {
+    Plain(TcpConn),
+    Tls(Box<StreamOwned<ClientConnection, netConn>>),
+}
+
+impl netConn {
+    fn tcp(&self) -> &TcpConn {
+        match self {
+            netConn::Plain(conn) => conn,
+            netConn::Tls(tlsConn) => tlsConn.get_ref().tcp(),
+        }
+    }
+
+    // split returns independent read and write halves of the connection and a function that shuts it down. HTTP/2 connections read frames on their own thread while requests write to the same connection.
+    fn split(self) -> HttpResult<splitConn> {
+        let tcp = self.tcp().try_clone()?;
+        tcp.set_read_timeout(None)?;
+        tcp.set_write_timeout(None)?;
+        let shutdown = tcp.try_clone()?;
+        let closer = Box::new(move || {
+            let _ = shutdown.shutdown(net::Shutdown::Both);
+        });
+        match self {
+            netConn::Plain(conn) => Ok((Box::new(tcp), Box::new(conn), closer)),
+            netConn::Tls(tlsConn) => {
+                let StreamOwned {
+                    conn: session,
+                    sock,
+                } = *tlsConn;
+                if let netConn::Tls(_) = sock {
+                    return Err(HTTPConnectError::ConnectionFailure(
+                        "http2: TLS over a TLS connection is not supported".to_string(),
+                    ));
+                }
+                let session = Arc::new(sync::Mutex::new(session));
+                let wtcp = Arc::new(sync::Mutex::new(tcp.try_clone()?));
+                let r = tlsReader {
+                    session: session.clone(),
+                    tcp,
+                    wtcp: wtcp.clone(),
+                    pending: Vec::new(),
+                };
+                Ok((Box::new(r), Box::new(tlsWriter { session, wtcp }), closer))
+            }
+        }
+    }
+}
+
+type splitConn = (
+    Box<dyn Read + Send>,
+    Box<dyn Write + Send>,
+    Box<dyn Fn() + Send + Sync>,
+);
+
+// tlsReader is the read half of a TLS connection split for HTTP/2. The rustls session is shared with the tlsWriter; wtcp serializes the TLS records written by both halves.
+struct tlsReader {
+    session: Arc<sync::Mutex<ClientConnection>>,
+    tcp: TcpConn,
+    wtcp: Arc<sync::Mutex<TcpConn>>,
+    pending: Vec<u8>, // TLS bytes read from tcp but not yet given to the session
+}
+
+impl Read for tlsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.pending.is_empty() {
+                let wantsWrite = {
+                    let mut session = self.session.lock().unwrap();
+                    match session.reader().read(buf) {
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                        r => return r,
+                    }
+                    let mut rd = self.pending.as_slice();
+                    session.read_tls(&mut rd)?;
+                    let consumed = len!(self.pending) - rd.len();
+                    self.pending.drain(..consumed);
+                    session
+                        .process_new_packets()
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+                    session.wants_write()
+                };
+                if wantsWrite {
+                    // 会话需要回应(如密钥更新)，由写锁保护发送。
+                    flushTLS(&self.session, &self.wtcp, &[])?;
+                }
+                continue;
+            }
+            {
+                let mut session = self.session.lock().unwrap();
+                match session.reader().read(buf) {
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                    r => return r,
+                }
+            }
+            let mut raw = [0u8; 16 << 10];
+            let n = self.tcp.read(&mut raw)?;
+            if n == 0 {
+                let mut session = self.session.lock().unwrap();
+                session.read_tls(&mut &raw[..0])?;
+                return match session.reader().read(buf) {
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(0),
+                    r => r,
+                };
+            }
+            self.pending.extend_from_slice(&raw[..n]);
+        }
+    }
+}
+
+// tlsWriter is the write half of a TLS connection split for HTTP/2.
+struct tlsWriter {
+    session: Arc<sync::Mutex<ClientConnection>>,
+    wtcp: Arc<sync::Mutex<TcpConn>>,
+}
+
+impl Write for tlsWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        flushTLS(&self.session, &self.wtcp, buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// flushTLS encrypts plaintext and writes it, together with any other TLS records the session has pending, to the connection. wtcp is locked before session so that records go out in the order the session produced them.
+fn flushTLS(
+    session: &sync::Mutex<ClientConnection>,
+    wtcp: &sync::Mutex<TcpConn>,
+    plaintext: &[u8],
+) -> io::Result<()> {
+    let mut tcp = wtcp.lock().unwrap();
+    let mut records = Vec::new();
+    {
+        let mut session = session.lock().unwrap();
+        session.writer().write_all(plaintext)?;
+        while session.wants_write() {
+            session.write_tls(&mut records)?;
+        }
+    }
+    tcp.write_all(&records)
+}
+
+// pooledConn is a connection from the Transport's pool: an HTTP/1 connection owned by one request at a time, or a shared HTTP/2 connection.
+enum pooledConn {
+    H1(Box<persistConn>),
+    H2(http2::ClientConn),
+}
+
+impl Read for netConn {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            netConn::Plain(conn) => conn.read(buf),
+            netConn::Tls(tlsConn) => tlsConn.read(buf),
+        }
+    }
+}
+
+impl Write for netConn {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            netConn::Plain(conn) => conn.write(buf),
+            netConn::Tls(tlsConn) => tlsConn.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            netConn::Plain(conn) => conn.flush(),
+            netConn::Tls(tlsConn) => tlsConn.flush(),
+        }
+    }
+}
+
 use std::sync::mpsc::channel;
-#[derive(Default, Clone)]
 struct persistConn {
-    t: Transport,
-    // br: bufio.Reader,
-    // bw: bufio.Writer,
+    cacheKey: connectMethodKey,
+    br: BufReader<netConn>,
     nwrite: int64,
     // reqch: channel,
     // writech: channel,
     isProxy: bool,
+    proxyAuth: String, // Proxy-Authorization sent on every request when isProxy
     sawEOF: bool,
     readLimit: int64,
     // writeErrch: channel,
     // writeLoopDone: channel,
     numExpectedResponses: int,
-    broken: bool,
-    reused: bool,
+    broken: bool, // an error has happened on this connection; marked broken so it's not reused.
+    closed: bool, // the peer or the request asked for the connection to be closed after this response.
+    reused: bool, // whether conn has had successful request/response and is being reused.
+    sawFirstByte: bool, // the first byte of the current request's response has been reported to its ClientTrace.
+    idleAt: time::Time,
 }
 
 use bytes::Bytes;
@@ -463,84 +1609,415 @@ use std::net::Shutdown;
 use std::net::TcpStream;
 use std::rc::Rc;
 use std::sync::Arc;
-use webpki_roots::TLS_SERVER_ROOTS;
 impl persistConn {
-    fn roundTrip(&mut self, req: &mut transportRequest, mut conn: TcpConn) -> HttpResult<Response> {
+    // roundTrip sends req on the connection and reads the response header. The connection moves into the returned Body until the body is read to EOF, or is closed right away on error.
+    fn roundTrip(mut self, t: &Transport, req: &mut transportRequest) -> HttpResult<Response> {
         self.numExpectedResponses += 1;
         let mut requestedGzip = false;
-        if !self.t.DisableCompression
+        if !t.DisableCompression
             && req.Req.Header.Get("Accept-Encoding") == ""
             && req.Req.Header.Get("Range") == ""
             && req.Req.Method != "HEAD".to_string()
         {
+            // Request gzip only, not deflate. Deflate is ambiguous and
+            // not as universally supported anyway.
+            // See: https://zlib.net/zlib_faq.html#faq39
             requestedGzip = true;
-            // req.extra = Some(req.Req.Header.clone());
-            // let mut hd = req.extra.take().unwrap();
-            // // hd.Set("Accept-Encoding", "gzip");
-
-            // req.extra = Some(hd.clone());
-            // req.Req.Header = hd;
+            req.Req.Header.Set("Accept-Encoding", "gzip");
         }
-        if req.Req.Close {
+        if req.Req.Close || t.DisableKeepAlives {
             req.Req.Header.Set("Connection", "close");
         }
 
-        let r = req.Req.Write()?;
+        if self.isProxy && !self.proxyAuth.is_empty() {
+            req.Req
+                .Header
+                .Set("Proxy-Authorization", self.proxyAuth.as_str());
+        }
 
-        if req.Req.isTLS {
-            let mut tlsConn = getTLSConn(req.Req.Host.as_str(), conn)?;
-            tlsConn.write(r.as_slice())?;
-            let mut reader = BufReader::new(tlsConn);
-            let resp = ReadResponse(reader, &req.Req)?;
-            Ok(resp)
+        let (mut resp, state) = match self.writeRequestReadResponse(t, &req.Req) {
+            Ok(r) => r,
+            Err(err) => {
+                self.broken = true;
+                t.closeConn(self);
+                return Err(err);
+            }
+        };
+        if resp.Close || req.Req.Close || t.DisableKeepAlives {
+            self.closed = true;
+        }
+        // 没有body的响应可以立即归还连接，否则连接由Body持有，读到EOF后再归还。
+        if state.isDone() {
+            t.tryPutIdleConn(self);
         } else {
-            conn.write(r.as_slice())?;
-            let mut reader = BufReader::new(conn);
-            let resp = ReadResponse(reader, &req.Req)?;
-            Ok(resp)
+            let trailer = state.trailer();
+            resp.Body = Body::New(bodyEOFSignal {
+                pc: Some(self),
+                t: t.clone(),
+                state,
+                deadline: req.Req.deadline,
+            })
+            .withTrailer(trailer);
+        }
+        if requestedGzip {
+            decompressBody(&mut resp);
+        }
+        Ok(resp)
+    }
+
+    // writeRequestReadResponse writes req and reads the response header. Writes are bounded by the request deadline, the wait for the header by ResponseHeaderTimeout as well. A request with "Expect: 100-continue" sends its body only after the server agreed to it or ExpectContinueTimeout passed.
+    fn writeRequestReadResponse(
+        &mut self,
+        t: &Transport,
+        req: &Request,
+    ) -> HttpResult<(Response, transferState)> {
+        let trace = req.trace();
+        self.sawFirstByte = false;
+        match self.writeRequest(t, req) {
+            Err(err) => {
+                trace.wroteRequest(Some(&err));
+                return Err(err);
+            }
+            Ok(early) => {
+                trace.wroteRequest(None);
+                if let Some(r) = early {
+                    return Ok(r);
+                }
+            }
+        }
+        let r = self.readResponseHeader(t, req, false)?;
+        // body的读取超时由bodyEOFSignal按请求的截止时间设置。
+        self.br.get_ref().tcp().set_read_timeout(None)?;
+        Ok(r)
+    }
+
+    // writeRequest writes req to the connection. If the server answers an Expect: 100-continue request with a final response before the body is sent, that response is returned and the body is not written.
+    fn writeRequest(
+        &mut self,
+        t: &Transport,
+        req: &Request,
+    ) -> HttpResult<Option<(Response, transferState)>> {
+        // 连接可能来自空闲池，每次请求都重新设置读写超时。
+        self.br
+            .get_ref()
+            .tcp()
+            .set_write_timeout(timeoutFor(None, req.deadline)?)?;
+        let expectContinue = stdDuration(&t.ExpectContinueTimeout)
+            .filter(|_| req.expectsContinue() && req.outgoingLength() != 0);
+        match expectContinue {
+            // header和body直接写入连接，body边读边写，不在内存中拼接整个请求。
+            None => req.write(self.br.get_mut(), self.isProxy)?,
+            Some(limit) => {
+                let w = self.br.get_mut();
+                w.write_all(req.writeHead(self.isProxy).as_bytes())?;
+                w.flush()?;
+                req.trace().wroteHeaders();
+                self.br
+                    .get_ref()
+                    .tcp()
+                    .set_read_timeout(timeoutFor(Some(limit), req.deadline)?)?;
+                match self.br.fill_buf() {
+                    // 等待100 Continue超时，照常发送body。
+                    Err(err) if isTimeout(&err) => {
+                        timeoutFor(None, req.deadline)?;
+                    }
+                    Err(err) => return Err(err.into()),
+                    Ok(_) => {
+                        let (resp, state) = self.readResponseHeader(t, req, true)?;
+                        if resp.StatusCode != 100 {
+                            // 服务端没有同意接收body就给出了最终响应，body未发送，连接不能复用。
+                            self.closed = true;
+                            self.br.get_ref().tcp().set_read_timeout(None)?;
+                            return Ok(Some((resp, state)));
+                        }
+                    }
+                }
+                req.writeBody(self.br.get_mut())?;
+            }
         }
+        Ok(None)
     }
+
+    // readResponseHeader reads the next final response header, skipping 1xx informational responses other than 101 Switching Protocols. If want100 is true a 100 Continue response is returned as well.
+    fn readResponseHeader(
+        &mut self,
+        t: &Transport,
+        req: &Request,
+        want100: bool,
+    ) -> HttpResult<(Response, transferState)> {
+        let headerTimeout = stdDuration(&t.ResponseHeaderTimeout);
+        loop {
+            self.br
+                .get_ref()
+                .tcp()
+                .set_read_timeout(timeoutFor(headerTimeout, req.deadline)?)?;
+            let r = match self.br.fill_buf() {
+                Ok(buf) => {
+                    if !buf.is_empty() && !self.sawFirstByte {
+                        self.sawFirstByte = true;
+                        req.trace().gotFirstResponseByte();
+                    }
+                    readResponse(&mut self.br, req)
+                }
+                Err(err) => Err(err.into()),
+            };
+            let (resp, state) = match r {
+                Err(err)
+                    if err.Timeout()
+                        && headerTimeout.is_some()
+                        && timeoutFor(None, req.deadline).is_ok() =>
+                {
+                    return Err(HTTPConnectError::TimeoutError(
+                        "net/http: timeout awaiting response headers".to_string(),
+                    ));
+                }
+                r => r?,
+            };
+            if resp.StatusCode == 100 && want100 {
+                return Ok((resp, state));
+            }
+            if (100..200).contains(&resp.StatusCode) && resp.StatusCode != 101 {
+                continue;
+            }
+            return Ok((resp, state));
+        }
+    }
+
+    fn isExpired(&self, timeout: &time::Duration) -> bool {
+        *timeout > time::Duration::new(0)
+            && time::Now().UnixNano() - self.idleAt.UnixNano() > timeout.Nanoseconds()
+    }
+
+    // isStale reports whether the server closed the idle connection, or sent data on it unprompted, while it sat in the pool.
+    fn isStale(&mut self) -> bool {
+        if !self.br.buffer().is_empty() {
+            return true;
+        }
+        if self.br.get_ref().tcp().set_nonblocking(true).is_err() {
+            return true;
+        }
+        let mut b = [0u8; 1];
+        let stale = !matches!(self.br.get_mut().read(&mut b), Err(ref e) if e.kind() == ErrorKind::WouldBlock);
+        self.br.get_ref().tcp().set_nonblocking(false).is_err() || stale
+    }
+}
+
+// maxH2Retries limits how many times a request is sent again on another HTTP/2 connection when the server did not process it.
+const maxH2Retries: int = 6;
+
+// shouldRetryRequest reports whether we should retry sending a failed HTTP request on a new connection. A reused keep-alive connection may have been closed by the server while it sat idle, so idempotent requests are retried once the pool hands out a fresh connection.
+fn shouldRetryRequest(reused: bool, req: &Request, err: &HTTPConnectError) -> bool {
+    reused && req.isReplayable() && matches!(err, HTTPConnectError::IoError(_))
 }
+
+// bodyEOFSignal streams a response body off its persistConn. Once the body has been read to EOF the connection goes back to the idle pool; if reading fails or the body is dropped first, the connection is closed.
+struct bodyEOFSignal {
+    pc: Option<persistConn>,
+    t: Transport,
+    state: transferState,
+    deadline: Option<Instant>, // Client.Timeout对应的截止时间，读取body同样受其限制。
+}
+
+pub(crate) const errBodyReadTimeout: &str =
+    "net/http: request canceled (Client.Timeout or context cancellation while reading body)";
+
+impl Read for bodyEOFSignal {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let pc = match self.pc.as_mut() {
+            Some(pc) => pc,
+            None if self.state.isDone() => return Ok(0),
+            None => return Err(io::Error::other("http: read on closed response body")),
+        };
+        if self.deadline.is_some() {
+            if let Err(err) = timeoutFor(None, self.deadline)
+                .and_then(|timeout| pc.br.get_ref().tcp().set_read_timeout(timeout))
+            {
+                if let Some(mut pc) = self.pc.take() {
+                    pc.broken = true;
+                    self.t.closeConn(pc);
+                }
+                return Err(io::Error::new(ErrorKind::TimedOut, errBodyReadTimeout));
+            }
+        }
+        match self.state.read(&mut pc.br, buf) {
+            Ok(n) => {
+                if self.state.isDone() {
+                    if let Some(pc) = self.pc.take() {
+                        self.t.tryPutIdleConn(pc);
+                    }
+                }
+                Ok(n)
+            }
+            Err(err) => {
+                if let Some(mut pc) = self.pc.take() {
+                    pc.broken = true;
+                    self.t.closeConn(pc);
+                }
+                if self.deadline.is_some() && isTimeout(&err) {
+                    return Err(io::Error::new(ErrorKind::TimedOut, errBodyReadTimeout));
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Drop for bodyEOFSignal {
+    fn drop(&mut self) {
+        if let Some(pc) = self.pc.take() {
+            self.t.closeConn(pc);
+        }
+    }
+}
+
 use bytes::{Buf, BytesMut};
 use rustls::pki_types::ServerName;
-use rustls::{ClientConfig, RootCertStore};
+use rustls::ClientConfig;
 use std::io::ErrorKind;
 
+use crate::body::{lock, Body};
 use crate::cookies::Cookie;
 use crate::cookies::CookieJar;
 use crate::error;
-use crate::header::Header;
+use crate::header::{CanonicalHeaderKey, Header};
+use crate::http2;
+use crate::httptrace::{self, ClientTrace};
 use crate::method::Method;
-use crate::request::Request;
+use crate::proxy::{basicAuth, splitProxyUserinfo, ProxyFunc};
+use crate::request::{hasPort, readTrailerKeys, Request};
 use crate::response::Response;
+use crate::socks;
+use crate::tls;
+use crate::transfer::{shouldClose, transferReader, transferState};
+
+impl Transport {
+    // tlsConfig returns the rustls configuration built from TLSClientConfig, building it on first use.
+    fn tlsConfig(&self) -> HttpResult<Arc<ClientConfig>> {
+        match &self.TLSClientConfig {
+            Some(cfg) => self.tlsConfigCache.get(|| tls::clientConfig(Some(cfg))),
+            None => tls::clientConfig(None),
+        }
+    }
 
-fn get_tls_config() -> Arc<ClientConfig> {
-    let mut clientRootCert = RootCertStore::from_iter(TLS_SERVER_ROOTS.iter().cloned());
+    // h2TLSConfig returns the TLS configuration offering "h2" through ALPN before the protocols of TLSClientConfig.NextProtos, and "http/1.1" unless HTTP/1 is disabled.
+    fn h2TLSConfig(&self) -> HttpResult<Arc<ClientConfig>> {
+        let mut config = (*self.tlsConfig()?).clone();
+        let mut protos = vec![b"h2".to_vec()];
+        if self.protocols().HTTP1() {
+            protos.push(b"http/1.1".to_vec());
+        }
+        for p in config.alpn_protocols.drain(..) {
+            if !protos.contains(&p) {
+                protos.push(p);
+            }
+        }
+        config.alpn_protocols = protos;
+        Ok(Arc::new(config))
+    }
 
-    Arc::new(
-        ClientConfig::builder()
-            .with_root_certificates(clientRootCert)
-            .with_no_client_auth(),
-    )
+    // getTLSConn performs the TLS handshake with dnsName over socket, failing with a timeout error if it is not complete by deadline.
+    fn getTLSConn(
+        &self,
+        dnsName: &str,
+        socket: netConn,
+        deadline: Option<Instant>,
+        h2: bool,
+        trace: &ClientTrace,
+    ) -> HttpResult<StreamOwned<ClientConnection, netConn>> {
+        let tlsconfig = if h2 {
+            self.tlsH2ConfigCache.get(|| self.h2TLSConfig())?
+        } else {
+            self.tlsConfig()?
+        };
+        let serverName = tls::serverName(self.TLSClientConfig.as_ref(), dnsName);
+        trace.tlsHandshakeStart();
+        match getTLSConn(tlsconfig, serverName.as_str(), socket, deadline) {
+            Ok(c) => {
+                trace.tlsHandshakeDone(&tls::connectionState(&c.conn, serverName.as_str()), None);
+                Ok(c)
+            }
+            Err(err) => {
+                trace.tlsHandshakeDone(&tls::ConnectionState::default(), Some(&err));
+                Err(err)
+            }
+        }
+    }
 }
 
+// getTLSConn performs the TLS handshake over socket, failing with a timeout error if it is not complete by deadline.
 fn getTLSConn(
+    tlsconfig: Arc<ClientConfig>,
     dnsName: &str,
-    socket: TcpConn,
-) -> HttpResult<StreamOwned<ClientConnection, TcpConn>> {
-    let tlsconfig = get_tls_config();
+    socket: netConn,
+    deadline: Option<Instant>,
+) -> HttpResult<StreamOwned<ClientConnection, netConn>> {
+    let handshakeTimeout =
+        || HTTPConnectError::TimeoutError("net/http: TLS handshake timeout".to_string());
     let serverName = ServerName::try_from(dnsName.to_owned())?;
     let mut tlsClient = ClientConnection::new(tlsconfig, serverName)?;
     let mut tlsConn = StreamOwned::new(tlsClient, socket);
+    while tlsConn.conn.is_handshaking() {
+        let timeout = timeoutFor(None, deadline).map_err(|_| handshakeTimeout())?;
+        tlsConn.sock.tcp().set_read_timeout(timeout)?;
+        tlsConn.sock.tcp().set_write_timeout(timeout)?;
+        if let Err(err) = tlsConn.conn.complete_io(&mut tlsConn.sock) {
+            if isTimeout(&err) {
+                return Err(handshakeTimeout());
+            }
+            return Err(err.into());
+        }
+    }
     Ok(tlsConn)
 }
 
-pub fn ReadResponse(mut r: impl BufRead, req: &Request) -> HttpResult<Response> {
+// proxyConnect asks the proxy behind conn to open a tunnel to cm's target address with a CONNECT request.
+fn proxyConnect(cm: &connectMethod, mut conn: netConn) -> HttpResult<netConn> {
+    let mut connectReq = Request::default();
+    connectReq.Method = "CONNECT".to_string();
+    connectReq.Host = cm.targetAddr.clone();
+    let mut buf = strings::Builder::new();
+    buf.WriteString(format!("CONNECT {} HTTP/1.1\r\n", cm.targetAddr).as_str());
+    buf.WriteString(format!("Host: {}\r\n", cm.targetAddr).as_str());
+    let pa = cm.proxyAuth();
+    if !pa.is_empty() {
+        buf.WriteString(format!("Proxy-Authorization: {}\r\n", pa).as_str());
+    }
+    buf.WriteString("\r\n");
+    conn.write_all(buf.String().as_bytes())?;
+    conn.flush()?;
+    let (resp, _) = readResponse(&mut BufReader::new(&mut conn), &connectReq)?;
+    if resp.StatusCode != 200 {
+        return Err(HTTPConnectError::ConnectionFailure(format!(
+            "proxyconnect tcp: {}",
+            resp.Status
+        )));
+    }
+    Ok(conn)
+}
+
+/// ReadResponse reads and returns an HTTP response from r. The req parameter optionally specifies the Request that corresponds to this Response. The returned Body streams the rest of the message from r.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ReadResponse从r读取并返回一个HTTP响应。参数req指定与该响应对应的请求。返回的Body从r上流式读取剩余的消息内容。
+/// </details>
+pub fn ReadResponse<R: BufRead + Send + 'static>(mut r: R, req: &Request) -> HttpResult<Response> {
+    let (mut resp, state) = readResponse(&mut r, req)?;
+    if !state.isDone() {
+        let trailer = state.trailer();
+        resp.Body = Body::New(transferReader::new(r, state)).withTrailer(trailer);
+    }
+    Ok(resp)
+}
+
+// readResponse reads the status line and header of a response, leaving its body on r. It returns the framing of the body still to be read.
+fn readResponse(r: &mut impl BufRead, req: &Request) -> HttpResult<(Response, transferState)> {
     let mut resp = Response::default();
     resp.Request = req.clone();
     // parse status line。
     let mut line = String::new();
-    r.read_line(&mut line)?;
+    if r.read_line(&mut line)? == 0 {
+        return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+    }
     let parts: Vec<&str> = line.split_whitespace().collect();
     if parts.len() < 3 {
         return Err(HTTPConnectError::ConnectionFailure(
@@ -563,8 +2040,11 @@ pub fn ReadResponse(mut r: impl BufRead, req: &Request) -> HttpResult<Response>
     // 1. 获取response的header部分，到第一个 '\r\b'独立行为header的结束。
     let mut headPart = BytesMut::new();
     let mut head_line = String::new();
-    while r.read_line(&mut head_line).is_ok() {
-        if head_line.as_bytes() == b"\r\n" {
+    loop {
+        if r.read_line(&mut head_line)? == 0 {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        if head_line.as_bytes() == b"\r\n" || head_line.as_bytes() == b"\n" {
             break;
         }
         headPart.extend_from_slice(head_line.as_bytes());
@@ -574,69 +2054,53 @@ pub fn ReadResponse(mut r: impl BufRead, req: &Request) -> HttpResult<Response>
     // parse headPart
     resp.Header = Header::NewWithHashMap(parseHeader(&headPart)?);
     fixPragmaCacheControl(&mut resp.Header);
-
-    // set Body
-    if resp.Header.Get("Transfer-Encoding").as_str() == "chunked" {
-        // 2.chunked方式传输方式。获取body数据。
-        resp.Body = Some(parseChunkedBody(r)?);
-    } else {
-        // 3. 除chunked外的其他传输方式，都有Content-Length字段,根据长度获取body
-        let ln: usize = resp
-            .Header
-            .Get("Content-Length")
-            .as_str()
-            .parse::<usize>()
-            .expect("Content-Length is not exist");
-
-        let mut buf = vec![0; ln]; // 生成固定长度的数组，用于读取定长数据;
-        r.read_exact(&mut buf)?;
-        resp.Body = Some(BytesMut::from(&buf[..]));
-    }
-    resp.ContentLength = resp.Body.as_ref().map_or(0, |b| b.len() as i64);
-    Ok(resp)
+    resp.Close = shouldClose(resp.ProtoMajor, resp.ProtoMinor, &resp.Header);
+
+    // 2. 根据chunked或Content-Length确定body的边界，body本身留在连接上由Body流式读取。
+    let state = transferState::forResponse(&resp, req)?;
+    resp.ContentLength = match &state {
+        transferState::Done => 0,
+        transferState::ContentLength(n) => *n as int64,
+        transferState::Chunked(_) => {
+            resp.TransferEncoding = vec!["chunked".to_string()];
+            resp.Header.Del("Content-Length");
+            // Trailer头声明的key放入resp.Trailer，它与Body共享，body读到EOF之后填入值。
+            resp.Trailer = state.trailer().unwrap_or_default();
+            *lock(&resp.Trailer) = readTrailerKeys(&resp.Header)?;
+            resp.Header.Del("Trailer");
+            -1
+        }
+        // 3. 既没有chunked也没有Content-Length，body一直读到连接关闭为止，连接不能复用。
+        transferState::UntilEOF => {
+            resp.Close = true;
+            -1
+        }
+    };
+    Ok((resp, state))
 }
 
-// chunk数据是以16位数据长度 7acc\r\n独立行开头+ [data] 下一行以\r\n结尾数据段形式，所以数据的结尾用0\r\n表示。
-fn parseChunkedBody(mut r: impl BufRead) -> HttpResult<BytesMut> {
-    let mut body = BytesMut::new();
-    let mut size_buf = vec![];
-    while r.read_until(b'\n', &mut size_buf).is_ok() {
-        // 校验开头行是\r\n结尾的chuank size行
-        if size_buf.ends_with(b"\r\n") {
-            // 删除尾部的\r\n,只保留表示大小的字符串
-            size_buf.truncate(size_buf.len() - 2); // Remove "\r\n"
-
-            // 16进制chunk大小字符串
-            let size_str = std::str::from_utf8(&size_buf)?;
-
-            // 如果字符串等于"0"，已经到最后一个chunk数据段。
-            if size_str == "0" {
-                break;
-            }
-
-            // 按chunk长度读取分段的实际数据
-            let chunk_size = usize::from_str_radix(size_str, 16)?;
-
-            let mut chunk_data = vec![0u8; chunk_size];
-            r.read_exact(&mut chunk_data)?;
-            body.extend_from_slice(&chunk_data);
-            //读取每个chunk data 结尾的\r\n，并丢弃掉
-            let mut crlf = [0u8; 2];
-            r.read_exact(&mut crlf)?;
-            //chuank size 行的数据要清空
-            size_buf.clear();
-        }
+// decompressBody transparently inflates a gzip or deflate encoded body of a response to a request the transport itself asked to be compressed. The Content-Encoding and Content-Length headers are removed, ContentLength is set to -1 and Uncompressed to true.
+pub(crate) fn decompressBody(resp: &mut Response) {
+    let encoding = resp
+        .Header
+        .Get("Content-Encoding")
+        .trim()
+        .to_ascii_lowercase();
+    if resp.Body.isNoBody() || !matches!(encoding.as_str(), "gzip" | "x-gzip" | "deflate") {
+        return;
     }
-    Ok(body)
+    resp.Body = std::mem::take(&mut resp.Body).decode(encoding.as_str());
+    resp.Header.Del("Content-Encoding");
+    resp.Header.Del("Content-Length");
+    resp.ContentLength = -1;
+    resp.Uncompressed = true;
 }
 
 pub type MIMEHeader = HashMap<String, Vec<String>>;
 
-fn fixPragmaCacheControl(header: &mut Header) {
-    if let Some(hp) = header.0.get("Pragma") {
-        if len!(hp) > 0 && &hp[0] == "no-cache" && header.0.get("Cache-Control").is_none() {
-            header.Set("Cache-Control", "no-cache");
-        }
+pub(crate) fn fixPragmaCacheControl(header: &mut Header) {
+    if header.Get("Pragma") == "no-cache" && header.Values("Cache-Control").is_empty() {
+        header.Set("Cache-Control", "no-cache");
     }
 }
 
@@ -646,7 +2110,7 @@ fn parseHeader(headPart: &[u8]) -> HttpResult<MIMEHeader> {
 
     for kv in lines.split("\r\n") {
         if let Some((key, value)) = kv.split_once(':') {
-            let key = canonicalMIMEHeaderKey(key);
+            let key = CanonicalHeaderKey(key);
             if key.is_empty() {
                 continue;
             }
@@ -677,25 +2141,6 @@ fn startIndexOfBody(response: &Vec<u8>) -> Option<usize> {
     None
 }
 
-// Header KE规范化 content-length|CONTENT-LENGTH => Content-Length
-const toLower: byte = (b'a' - b'A');
-fn canonicalMIMEHeaderKey(s: &str) -> String {
-    let mut upper = true;
-    let mut new = String::with_capacity(s.len());
-    for &byte in s.as_bytes() {
-        let c = if upper && byte >= b'a' && byte <= b'z' {
-            byte - toLower
-        } else if !upper && byte >= b'A' && byte <= b'Z' {
-            byte + toLower
-        } else {
-            byte
-        };
-        upper = c == b'-';
-        new.push(c as char);
-    }
-    new
-}
-
 pub fn ParseHTTPVersion(vers: &str) -> (int, int, bool) {
     let big: int = 1_000_000;
 
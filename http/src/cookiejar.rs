@@ -0,0 +1,657 @@
+//! Package cookiejar implements an in-memory RFC 6265-compliant http.CookieJar.
+//!
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! cookiejar包实现了一个符合RFC 6265、保存在内存中的http.CookieJar。
+//! </details>
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use gostd_builtin::*;
+use gostd_strings as strings;
+use gostd_time as time;
+use gostd_url as url;
+
+use crate::cookies::{Cookie, CookieJar, SameSite};
+use crate::error::{HTTPConnectError, HttpResult};
+
+/// PublicSuffixList provides the public suffix of a domain. For example:
+/// ```text
+/// the public suffix of "example.com" is "com",
+/// the public suffix of "foo1.foo2.foo3.co.uk" is "co.uk", and
+/// the public suffix of "bar.pvt.k12.ma.us" is "pvt.k12.ma.us".
+/// ```
+/// Implementations of PublicSuffixList must be safe for concurrent use by multiple threads.
+///
+/// An implementation that always returns "" is valid and may be useful for testing but it is not secure: it means that the HTTP server for foo.com can set a cookie for bar.com.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// PublicSuffixList提供域名的公共后缀。例如"example.com"的公共后缀是"com"，"foo1.foo2.foo3.co.uk"的公共后缀是"co.uk"，"bar.pvt.k12.ma.us"的公共后缀是"pvt.k12.ma.us"。
+///
+/// PublicSuffixList的实现必须可以被多个线程安全地并发使用。
+///
+/// 总是返回""的实现是合法的，可用于测试，但并不安全：foo.com的HTTP服务器将可以为bar.com设置cookie。
+/// </details>
+pub trait PublicSuffixList: Send + Sync {
+    /// PublicSuffix returns the public suffix of domain.
+    fn PublicSuffix(&self, domain: &str) -> String;
+
+    /// String returns a description of the source of this public suffix list. The description will typically contain something like a time stamp or version number.
+    fn String(&self) -> String;
+}
+
+/// Options are the options for creating a new Jar.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Options是创建Jar时的选项。
+/// </details>
+#[derive(Default, Clone)]
+pub struct Options {
+    /// PublicSuffixList is the public suffix list that determines whether an HTTP server can set a cookie for a domain.
+    ///
+    /// A None value is valid and may be useful for testing but it is not secure: it means that the HTTP server for foo.co.uk can set a cookie for bar.co.uk.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// PublicSuffixList是公共后缀列表，用于判断HTTP服务器能否为某个域名设置cookie。
+    ///
+    /// None是合法的值，可用于测试，但并不安全：foo.co.uk的HTTP服务器将可以为bar.co.uk设置cookie。
+    /// </details>
+    pub PublicSuffixList: Option<Arc<dyn PublicSuffixList>>,
+}
+
+/// Jar implements the http.CookieJar interface from the gostd_http package.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Jar实现了gostd_http包中的http.CookieJar接口。
+/// </details>
+pub struct Jar {
+    psList: Option<Arc<dyn PublicSuffixList>>,
+
+    // mu locks the remaining fields.
+    mu: Mutex<jarEntries>,
+}
+
+#[derive(Default)]
+struct jarEntries {
+    // entries is a set of entries, keyed by their eTLD+1 and subkeyed by their name/domain/path.
+    entries: HashMap<String, HashMap<String, entry>>,
+
+    // nextSeqNum is the next sequence number assigned to a new cookie created SetCookies.
+    nextSeqNum: uint64,
+}
+
+/// New returns a new cookie jar. A None Options is equivalent to a zero Options.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// New返回一个新的cookie jar。Options为None等同于零值的Options。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use std::sync::Arc;
+///
+/// let jar = http::cookiejar::New(None).unwrap();
+/// let mut client = http::Client::New();
+/// client.Jar = Some(Arc::new(jar));
+/// ```
+pub fn New(o: Option<&Options>) -> HttpResult<Jar> {
+    Ok(Jar {
+        psList: o.and_then(|o| o.PublicSuffixList.clone()),
+        mu: Mutex::new(jarEntries::default()),
+    })
+}
+
+// entry is the internal representation of a cookie.
+//
+// This struct type is not used outside of this package per se, but the exported fields are those of RFC 6265.
+#[derive(Clone, Debug)]
+struct entry {
+    Name: String,
+    Value: String,
+    Domain: String,
+    Path: String,
+    SameSite: String,
+    Secure: bool,
+    HttpOnly: bool,
+    Persistent: bool,
+    HostOnly: bool,
+    Expires: time::Time,
+    Creation: time::Time,
+    LastAccess: time::Time,
+
+    // seqNum is a sequence number so that Cookies returns cookies in a deterministic order, even for cookies that have equal Path length and equal Creation time. This simplifies testing.
+    seqNum: uint64,
+}
+
+impl entry {
+    // id returns the domain;path;name triple of e as an id.
+    fn id(&self) -> String {
+        format!("{};{};{}", self.Domain, self.Path, self.Name)
+    }
+
+    // shouldSend determines whether e's cookie qualifies to be included in a request to host/path. It is the caller's responsibility to check if the cookie is expired.
+    fn shouldSend(&self, https: bool, host: &str, path: &str) -> bool {
+        self.domainMatch(host) && self.pathMatch(path) && (https || !self.Secure)
+    }
+
+    // domainMatch checks whether e's Domain allows sending e back to host. It differs from "domain-match" of RFC 6265 section 5.1.3 because we treat a cookie with an IP address in the Domain always as a host cookie.
+    fn domainMatch(&self, host: &str) -> bool {
+        if self.Domain == host {
+            return true;
+        }
+        !self.HostOnly && hasDotSuffix(host, &self.Domain)
+    }
+
+    // pathMatch implements "path-match" according to RFC 6265 section 5.1.4.
+    fn pathMatch(&self, requestPath: &str) -> bool {
+        if requestPath == self.Path {
+            return true;
+        }
+        if strings::HasPrefix(requestPath, &self.Path) {
+            if self.Path.ends_with('/') {
+                return true; // The "/any/" matches "/any/path" case.
+            } else if requestPath.as_bytes()[len!(self.Path)] == b'/' {
+                return true; // The "/any" matches "/any/path" case.
+            }
+        }
+        false
+    }
+}
+
+// hasDotSuffix reports whether s ends in "."+suffix.
+fn hasDotSuffix(s: &str, suffix: &str) -> bool {
+    len!(s) > len!(suffix)
+        && s.as_bytes()[len!(s) - len!(suffix) - 1] == b'.'
+        && s.ends_with(suffix)
+}
+
+// endOfTime is the time when session (non-persistent) cookies expire. This instant is representable in most date/time formats (not just Go's time.Time) and should be far enough in the future.
+fn endOfTime() -> time::Time {
+    time::Date(9999, 12, 31, 23, 59, 59, 0, time::UTC.clone())
+}
+
+impl CookieJar for Jar {
+    /// Cookies implements the Cookies method of the http.CookieJar interface.
+    ///
+    /// It returns an empty slice if the URL's scheme is not HTTP or HTTPS.
+    fn Cookies(&self, u: &url::URL) -> Vec<Cookie> {
+        self.cookies(u, time::Now())
+    }
+
+    /// SetCookies implements the SetCookies method of the http.CookieJar interface.
+    ///
+    /// It does nothing if the URL's scheme is not HTTP or HTTPS.
+    fn SetCookies(&self, u: &url::URL, cookies: Vec<Cookie>) {
+        self.setCookies(u, cookies, time::Now())
+    }
+}
+
+impl Jar {
+    // cookies is like Cookies but takes the current time as a parameter.
+    fn cookies(&self, u: &url::URL, now: time::Time) -> Vec<Cookie> {
+        let mut cookies = Vec::new();
+        if u.Scheme != "http" && u.Scheme != "https" {
+            return cookies;
+        }
+        let host = match canonicalHost(&u.Host) {
+            Ok(host) => host,
+            Err(_) => return cookies,
+        };
+        let key = jarKey(&host, self.psList.as_deref());
+
+        let mut j = self.mu.lock().unwrap_or_else(|e| e.into_inner());
+        let submap = match j.entries.get_mut(&key) {
+            Some(submap) => submap,
+            None => return cookies,
+        };
+
+        let https = u.Scheme == "https";
+        let path = if u.Path.is_empty() {
+            "/"
+        } else {
+            u.Path.as_str()
+        };
+
+        let mut selected: Vec<entry> = Vec::new();
+        submap.retain(|_, e| {
+            if e.Persistent && !e.Expires.After(&now) {
+                return false;
+            }
+            if e.shouldSend(https, &host, path) {
+                e.LastAccess = now.clone();
+                selected.push(e.clone());
+            }
+            true
+        });
+        if submap.is_empty() {
+            j.entries.remove(&key);
+        }
+
+        // sort according to RFC 6265 section 5.4 point 2: by longest path and then by earliest creation time.
+        selected.sort_by(|a, b| {
+            len!(b.Path)
+                .cmp(&len!(a.Path))
+                .then_with(|| {
+                    (a.Creation.Unix(), a.Creation.Nanosecond())
+                        .cmp(&(b.Creation.Unix(), b.Creation.Nanosecond()))
+                })
+                .then_with(|| a.seqNum.cmp(&b.seqNum))
+        });
+        for e in selected {
+            cookies.push(Cookie {
+                Name: e.Name,
+                Value: e.Value,
+                ..Cookie::default()
+            });
+        }
+        cookies
+    }
+
+    // setCookies is like SetCookies but takes the current time as parameter.
+    fn setCookies(&self, u: &url::URL, cookies: Vec<Cookie>, now: time::Time) {
+        if cookies.is_empty() {
+            return;
+        }
+        if u.Scheme != "http" && u.Scheme != "https" {
+            return;
+        }
+        let host = match canonicalHost(&u.Host) {
+            Ok(host) => host,
+            Err(_) => return,
+        };
+        let key = jarKey(&host, self.psList.as_deref());
+        let defPath = defaultPath(&u.Path);
+
+        let mut j = self.mu.lock().unwrap_or_else(|e| e.into_inner());
+        let mut submap = j.entries.remove(&key).unwrap_or_default();
+
+        for cookie in &cookies {
+            let (mut e, remove) = match self.newEntry(cookie, &now, defPath, &host) {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+            let id = e.id();
+            if remove {
+                submap.remove(&id);
+                continue;
+            }
+            match submap.get(&id) {
+                Some(old) => {
+                    e.Creation = old.Creation.clone();
+                    e.seqNum = old.seqNum;
+                }
+                None => {
+                    e.Creation = now.clone();
+                    e.seqNum = j.nextSeqNum;
+                    j.nextSeqNum += 1;
+                }
+            }
+            e.LastAccess = now.clone();
+            submap.insert(id, e);
+        }
+
+        if !submap.is_empty() {
+            j.entries.insert(key, submap);
+        }
+    }
+
+    // newEntry creates an entry from a http.Cookie c. now is the current time and is compared to c.Expires to determine deletion of c. defPath and host are the default-path and the canonical host name of the URL c was received from.
+    //
+    // remove records whether the jar should delete this cookie, as it has already expired with respect to now. In this case, e may be incomplete, but it will be valid to call e.id (which depends on e's Name, Domain and Path).
+    //
+    // A malformed c.Domain will result in an error.
+    fn newEntry(
+        &self,
+        c: &Cookie,
+        now: &time::Time,
+        defPath: &str,
+        host: &str,
+    ) -> HttpResult<(entry, bool)> {
+        let (Domain, HostOnly) = self.domainAndType(host, &c.Domain)?;
+        let mut e = entry {
+            Name: c.Name.clone(),
+            Value: String::new(),
+            Domain,
+            Path: if c.Path.starts_with('/') {
+                c.Path.clone()
+            } else {
+                defPath.to_string()
+            },
+            SameSite: String::new(),
+            Secure: false,
+            HttpOnly: false,
+            Persistent: false,
+            HostOnly,
+            Expires: time::Time::default(),
+            Creation: time::Time::default(),
+            LastAccess: time::Time::default(),
+            seqNum: 0,
+        };
+
+        // MaxAge takes precedence over Expires.
+        if c.MaxAge < 0 {
+            return Ok((e, true));
+        } else if c.MaxAge > 0 {
+            e.Expires = now
+                .clone()
+                .Add(&time::Duration::new(c.MaxAge as int64 * time::Second));
+            e.Persistent = true;
+        } else if c.Expires.IsZero() {
+            e.Expires = endOfTime();
+            e.Persistent = false;
+        } else {
+            if !c.Expires.After(now) {
+                return Ok((e, true));
+            }
+            e.Expires = c.Expires.clone();
+            e.Persistent = true;
+        }
+
+        e.Value = c.Value.clone();
+        e.Secure = c.Secure;
+        e.HttpOnly = c.HttpOnly;
+
+        e.SameSite = match c.SameSite {
+            SameSite::SameSiteDefaultMode => "SameSite",
+            SameSite::SameSiteStrictMode => "SameSite=Strict",
+            SameSite::SameSiteLaxMode => "SameSite=Lax",
+            _ => "",
+        }
+        .to_string();
+
+        Ok((e, false))
+    }
+
+    // domainAndType determines the cookie's domain and hostOnly attribute.
+    fn domainAndType(&self, host: &str, domain: &str) -> HttpResult<(String, bool)> {
+        if domain.is_empty() {
+            // No domain attribute in the SetCookie header indicates a host cookie.
+            return Ok((host.to_string(), true));
+        }
+
+        if isIP(host) {
+            // RFC 6265 is not super clear here, a sensible interpretation is that cookies with an IP address in the domain-attribute are allowed.
+            //
+            // The processing and validation steps in RFC 6265 just collapse to: the domain must equal the IP, and such cookies are treated as host-only cookies.
+            if host != domain {
+                return Err(errIllegalDomain());
+            }
+            return Ok((host.to_string(), true));
+        }
+
+        // From here on: If the cookie is valid, it is a domain cookie (with the one exception of a public suffix below). See RFC 6265 section 5.2.3.
+        let domain = domain.strip_prefix('.').unwrap_or(domain);
+
+        if domain.is_empty() || domain.starts_with('.') {
+            // Received either "Domain=." or "Domain=..some.thing", both are illegal.
+            return Err(errMalformedDomain());
+        }
+
+        if !domain.is_ascii() {
+            // Received non-ASCII domain, e.g. "perché.com" instead of "xn--perch-fsa.com"
+            return Err(errMalformedDomain());
+        }
+        let domain = domain.to_ascii_lowercase();
+
+        if domain.ends_with('.') {
+            // We received stuff like "Domain=www.example.com.". Browsers do handle such stuff (actually differently) but RFC 6265 seems to be clear here (e.g. section 4.1.2.3) in requiring a reject.
+            return Err(errMalformedDomain());
+        }
+
+        // See RFC 6265 section 5.3 #5.
+        if let Some(psList) = &self.psList {
+            let ps = psList.PublicSuffix(&domain);
+            if !ps.is_empty() && !hasDotSuffix(&domain, &ps) {
+                if host == domain {
+                    // This is the one exception in which a cookie with a domain attribute is a host cookie.
+                    return Ok((host.to_string(), true));
+                }
+                return Err(errIllegalDomain());
+            }
+        }
+
+        // The domain must domain-match host: www.mycompany.com cannot set cookies for .ourcompetitors.com.
+        if host != domain && !hasDotSuffix(host, &domain) {
+            return Err(errIllegalDomain());
+        }
+
+        Ok((domain, false))
+    }
+}
+
+fn errIllegalDomain() -> HTTPConnectError {
+    "cookiejar: illegal cookie domain attribute".into()
+}
+
+fn errMalformedDomain() -> HTTPConnectError {
+    "cookiejar: malformed cookie domain attribute".into()
+}
+
+// canonicalHost strips port from host if present and returns the canonicalized host name.
+fn canonicalHost(host: &str) -> HttpResult<String> {
+    // gostd_url把userinfo保存在Host中，先去掉。
+    let mut host = match host.rfind('@') {
+        Some(i) => &host[i + 1..],
+        None => host,
+    };
+    if hasPort(host) {
+        host = splitHostPort(host)?;
+    }
+    // Strip trailing dot from fully qualified domain names.
+    let host = host.strip_suffix('.').unwrap_or(host);
+    Ok(toASCII(host)?.to_ascii_lowercase())
+}
+
+// hasPort reports whether host contains a port number. host may be a host name, an IPv4 or an IPv6 address.
+fn hasPort(host: &str) -> bool {
+    let colons = strings::Count(host, ":");
+    if colons == 0 {
+        return false;
+    }
+    if colons == 1 {
+        return true;
+    }
+    host.starts_with('[') && host.contains("]:")
+}
+
+fn splitHostPort(hostport: &str) -> HttpResult<&str> {
+    let i = match hostport.rfind(':') {
+        Some(i) => i,
+        None => return Err(format!("address {}: missing port in address", hostport).into()),
+    };
+    let host = &hostport[..i];
+    if let Some(h) = host.strip_prefix('[') {
+        return match h.strip_suffix(']') {
+            Some(h) => Ok(h),
+            None => Err(format!("address {}: missing ']' in address", hostport).into()),
+        };
+    }
+    Ok(host)
+}
+
+// jarKey returns the key to use for a jar.
+fn jarKey(host: &str, psl: Option<&dyn PublicSuffixList>) -> String {
+    if isIP(host) {
+        return host.to_string();
+    }
+
+    let i = match psl {
+        None => match host.rfind('.') {
+            Some(i) if i > 0 => i,
+            _ => return host.to_string(),
+        },
+        Some(psl) => {
+            let suffix = psl.PublicSuffix(host);
+            if suffix == host {
+                return host.to_string();
+            }
+            let i = len!(host) as isize - len!(suffix) as isize;
+            if i <= 0 || host.as_bytes()[i as usize - 1] != b'.' {
+                // The provided public suffix list psl is broken. Storing cookies under host is a safe stopgap.
+                return host.to_string();
+            }
+            // Only len(suffix) is used to determine the jar key from here on, so it is okay if psl.PublicSuffix("www.buggy.psl") returns "com" as the jar key is generated from host.
+            i as usize
+        }
+    };
+    match host[..i - 1].rfind('.') {
+        Some(prevDot) => host[prevDot + 1..].to_string(),
+        None => host.to_string(),
+    }
+}
+
+// isIP reports whether host is an IP address.
+fn isIP(host: &str) -> bool {
+    if host.contains([':', '%']) {
+        // Probable IPv6 address. Note: zone-id values are not valid in a domain name.
+        return true;
+    }
+    host.parse::<IpAddr>().is_ok()
+}
+
+// defaultPath returns the directory part of a URL's path according to RFC 6265 section 5.1.4.
+fn defaultPath(path: &str) -> &str {
+    if path.is_empty() || !path.starts_with('/') {
+        return "/"; // Path is empty or malformed.
+    }
+
+    let i = path.rfind('/').unwrap_or(0); // Path starts with "/", so i != -1.
+    if i == 0 {
+        return "/"; // Path has the form "/abc".
+    }
+    &path[..i] // Path is either of form "/abc/xyz" or "/abc/xyz/".
+}
+
+// These parameter values are specified in section 5.
+//
+// All computation is done with int32s, so that overflow behavior is identical regardless of whether int is 32-bit or 64-bit.
+const base: int32 = 36;
+const damp: int32 = 700;
+const initialBias: int32 = 72;
+const initialN: int32 = 128;
+const skew: int32 = 38;
+const tmax: int32 = 26;
+const tmin: int32 = 1;
+
+// encode encodes a string as specified in section 6.3 and prepends prefix to the result.
+//
+// The "while h < length(input)" line in the specification becomes "for remaining != 0" in the Go code, because len(s) in Go is in bytes, not runes.
+fn encode(prefix: &str, s: &str) -> HttpResult<String> {
+    let invalidLabel = || HTTPConnectError::from(format!("cookiejar: invalid label {:?}", s));
+    let mut output = String::with_capacity(len!(prefix) + 1 + 2 * len!(s));
+    output.push_str(prefix);
+    let (mut delta, mut n, mut bias) = (0i32, initialN, initialBias);
+    let (mut b, mut remaining) = (0i32, 0i32);
+    for r in s.chars() {
+        if r.is_ascii() {
+            b += 1;
+            output.push(r);
+        } else {
+            remaining += 1;
+        }
+    }
+    let mut h = b;
+    if b > 0 {
+        output.push('-');
+    }
+    while remaining != 0 {
+        let mut m = 0x7fffffff;
+        for r in s.chars().map(|r| r as int32) {
+            if m > r && r >= n {
+                m = r;
+            }
+        }
+        delta = (m - n)
+            .checked_mul(h + 1)
+            .and_then(|d| delta.checked_add(d))
+            .ok_or_else(invalidLabel)?;
+        n = m;
+        for r in s.chars().map(|r| r as int32) {
+            if r < n {
+                delta = delta.checked_add(1).ok_or_else(invalidLabel)?;
+                continue;
+            }
+            if r > n {
+                continue;
+            }
+            let mut q = delta;
+            let mut k = base;
+            loop {
+                let t = if k <= bias {
+                    tmin
+                } else if k >= bias + tmax {
+                    tmax
+                } else {
+                    k - bias
+                };
+                if q < t {
+                    break;
+                }
+                output.push(encodeDigit(t + (q - t) % (base - t)));
+                q = (q - t) / (base - t);
+                k += base;
+            }
+            output.push(encodeDigit(q));
+            bias = adapt(delta, h + 1, h == b);
+            delta = 0;
+            h += 1;
+            remaining -= 1;
+        }
+        delta += 1;
+        n += 1;
+    }
+    Ok(output)
+}
+
+fn encodeDigit(digit: int32) -> char {
+    match digit {
+        0..=25 => (digit as u8 + b'a') as char,
+        26..=35 => (digit as u8 - 26 + b'0') as char,
+        _ => panic!("cookiejar: internal error in punycode encoding"),
+    }
+}
+
+// adapt is the bias adaptation function specified in section 6.1.
+fn adapt(mut delta: int32, numPoints: int32, firstTime: bool) -> int32 {
+    if firstTime {
+        delta /= damp;
+    } else {
+        delta /= 2;
+    }
+    delta += delta / numPoints;
+    let mut k = 0;
+    while delta > ((base - tmin) * tmax) / 2 {
+        delta /= base - tmin;
+        k += base;
+    }
+    k + (base - tmin + 1) * delta / (delta + skew)
+}
+
+// Strictly speaking, the remaining code below deals with IDNA (RFC 5890 and friends) and not Punycode (RFC 3492) per se.
+
+// acePrefix is the ASCII Compatible Encoding prefix.
+const acePrefix: &str = "xn--";
+
+// toASCII converts a domain or domain label to its ASCII form. For example, toASCII("bücher.example.com") is "xn--bcher-kva.example.com", and toASCII("golang") is "golang".
+fn toASCII(s: &str) -> HttpResult<String> {
+    if s.is_ascii() {
+        return Ok(s.to_string());
+    }
+    let mut labels = Vec::new();
+    for label in s.split('.') {
+        if label.is_ascii() {
+            labels.push(label.to_string());
+        } else {
+            labels.push(encode(acePrefix, label)?);
+        }
+    }
+    Ok(labels.join("."))
+}
@@ -5,19 +5,23 @@
 #![allow(non_camel_case_types)]
 use std::collections::HashSet;
 
-use crate::{header::Header, response::Response};
+use crate::{
+    client::TimeFormat, error::HttpResult, header::Header, response::Response,
+    server::ResponseWriter,
+};
 use gostd_builtin::*;
+use gostd_io::*;
 use gostd_strings as strings;
 use gostd_time as time;
 use gostd_url as url;
 
 fn validHeaderFieldByte(b: byte) -> bool {
     let isTokenTable: HashSet<char> = [
-        '!', '#', '$', '%', '&', '\'', '*', '+', '.', '0', '1', '2', '3', '4', '5', '6', '7', '8',
-        '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q',
-        'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '^', '_', '`', 'a', 'b', 'c', 'd', 'e', 'f',
-        'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x',
-        'y', 'z', '|', '~',
+        '!', '#', '$', '%', '&', '\'', '*', '+', '-', '.', '0', '1', '2', '3', '4', '5', '6', '7',
+        '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P',
+        'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '^', '_', '`', 'a', 'b', 'c', 'd', 'e',
+        'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w',
+        'x', 'y', 'z', '|', '~',
     ]
     .iter()
     .cloned()
@@ -64,12 +68,12 @@ impl Response {
 }
 
 fn readSetCookies(h: &Header) -> Vec<Cookie> {
-    let cookieCount = len!(h.0.get(&"Set-Cookie".to_string()).unwrap());
-    if cookieCount == 0 {
+    let lines = h.Values("Set-Cookie");
+    if lines.is_empty() {
         return vec![];
     }
-    let mut cookies = Vec::with_capacity(cookieCount);
-    for line in h.0.get("Set-Cookie").unwrap() {
+    let mut cookies = Vec::with_capacity(len!(lines));
+    for line in lines {
         let mut parts = strings::Split(strings::TrimSpace(line.as_str()), ";");
         if len!(parts) == 1 && parts[0] == "" {
             continue;
@@ -105,8 +109,8 @@ fn readSetCookies(h: &Header) -> Vec<Cookie> {
             let mut val = "";
             let j = strings::Index(attr, "=");
             if j >= 0 {
-                attr = &attr[..j as usize];
-                val = &attr[j as usize + 1..];
+                attr = &parts[i][..j as usize];
+                val = &parts[i][j as usize + 1..];
             }
             if !attr.is_ascii() {
                 continue;
@@ -121,7 +125,7 @@ fn readSetCookies(h: &Header) -> Vec<Cookie> {
             }
             let lowerAttr = strings::ToLower(attr);
             match lowerAttr.as_str() {
-                "sameste" => {
+                "samesite" => {
                     if !val.is_ascii() {
                         c.SameSite = SameSite::SameSiteDefaultMode;
                         continue;
@@ -148,12 +152,10 @@ fn readSetCookies(h: &Header) -> Vec<Cookie> {
                     continue;
                 }
                 "max-age" => {
-                    let mut secs: int = 0;
-                    let res = val.parse::<int>();
-                    if res.is_err() || (secs != 0 && val.bytes().nth(0) == Some(b'0')) {
-                        continue;
-                    }
-                    secs = res.unwrap();
+                    let mut secs = match val.parse::<int>() {
+                        Ok(secs) if secs == 0 || !val.starts_with('0') => secs,
+                        _ => continue,
+                    };
                     if secs <= 0 {
                         secs = -1;
                     }
@@ -200,40 +202,302 @@ impl Default for SameSite {
         SameSite::SameSiteDefaultMode
     }
 }
-pub trait CookieJar {
-    fn SetCookies(&mut self, u: &url::URL, cookies: Vec<Cookie>);
+/// A CookieJar manages storage and use of cookies in HTTP requests.
+///
+/// Implementations of CookieJar must be safe for concurrent use by multiple threads. The cookiejar module provides a CookieJar implementation.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// CookieJar管理HTTP请求中cookie的存储和使用。
+///
+/// CookieJar的实现必须可以被多个线程安全地并发使用。cookiejar模块提供了一个CookieJar的实现。
+/// </details>
+pub trait CookieJar: Send + Sync {
+    /// SetCookies handles the receipt of the cookies in a reply for the given URL. It may or may not choose to save the cookies, depending on the jar's policy and implementation.
+    fn SetCookies(&self, u: &url::URL, cookies: Vec<Cookie>);
 
+    /// Cookies returns the cookies to send in a request for the given URL. It is up to the implementation to honor the standard cookie use restrictions such as in RFC 6265.
     fn Cookies(&self, u: &url::URL) -> Vec<Cookie>;
 }
 
+/// A Cookie represents an HTTP cookie as sent in the Set-Cookie header of an HTTP response or the Cookie header of an HTTP request.
+///
+/// See <https://tools.ietf.org/html/rfc6265> for details.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Cookie代表一个HTTP cookie，可以出现在HTTP响应的Set-Cookie头或HTTP请求的Cookie头中。
+///
+/// 详见<https://tools.ietf.org/html/rfc6265>。
+/// </details>
 #[derive(Default, PartialEq, PartialOrd, Debug, Clone)]
 pub struct Cookie {
-    Name: String,
-    Value: String,
-    Path: String,        // optional
-    Domain: String,      // optional
-    Expires: time::Time, // optional
-    RawExpires: String,  // for reading cookies only
+    pub Name: String,
+    pub Value: String,
+    pub Path: String,        // optional
+    pub Domain: String,      // optional
+    pub Expires: time::Time, // optional
+    pub RawExpires: String,  // for reading cookies only
 
     // MaxAge=0 means no 'Max-Age' attribute specified.
     // MaxAge<0 means delete cookie now, equivalently 'Max-Age: 0'
     // MaxAge>0 means Max-Age attribute present and given in seconds
-    MaxAge: int,
-    Secure: bool,
-    HttpOnly: bool,
-    SameSite: SameSite,
-    Raw: String,
-    Unparsed: Vec<String>, // Raw text of unparsed attribute-value pairs
+    pub MaxAge: int,
+    pub Secure: bool,
+    pub HttpOnly: bool,
+    pub SameSite: SameSite,
+    pub Raw: String,
+    pub Unparsed: Vec<String>, // Raw text of unparsed attribute-value pairs
+}
+
+impl Cookie {
+    /// String returns the serialization of the cookie for use in a Cookie header (if only Name and Value are set) or a Set-Cookie response header (if other fields are set). If c has an invalid Name, the empty string is returned.
+    ///
+    /// Invalid bytes in Value and Path are dropped, and an invalid Domain is left out, which turns the cookie into a host-only cookie. Use Valid to detect such problems.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// String返回cookie的序列化结果，用于Cookie请求头(只设置了Name和Value时)或Set-Cookie响应头(设置了其他字段时)。如果Name不合法，返回空字符串。
+    ///
+    /// Value和Path中不合法的字节会被丢弃，不合法的Domain会被省略，cookie因此变为host-only cookie。可以用Valid检测这些问题。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    ///
+    /// let mut c = http::Cookie::default();
+    /// c.Name = "session".to_string();
+    /// c.Value = "abc".to_string();
+    /// c.Path = "/".to_string();
+    /// c.MaxAge = 3600;
+    /// c.HttpOnly = true;
+    /// assert_eq!(c.String(), "session=abc; Path=/; Max-Age=3600; HttpOnly");
+    /// ```
+    pub fn String(&self) -> String {
+        if !isCookieNameValid(&self.Name) {
+            return "".to_string();
+        }
+        let mut b = strings::Builder::new();
+        b.WriteString(&self.Name);
+        b.WriteString("=");
+        b.WriteString(&sanitizeCookieValue(&self.Value));
+
+        if !self.Path.is_empty() {
+            b.WriteString("; Path=");
+            b.WriteString(&sanitizeCookiePath(&self.Path));
+        }
+        if !self.Domain.is_empty() && validCookieDomain(&self.Domain) {
+            // A c.Domain containing illegal characters is not sanitized but simply dropped which turns the cookie into a host-only cookie. A leading dot is okay but won't be sent.
+            b.WriteString("; Domain=");
+            b.WriteString(strings::TrimPrefix(&self.Domain, "."));
+        }
+        if validCookieExpires(&self.Expires) {
+            b.WriteString("; Expires=");
+            b.WriteString(&self.Expires.clone().UTC().Format(TimeFormat));
+        }
+        if self.MaxAge > 0 {
+            b.WriteString(&format!("; Max-Age={}", self.MaxAge));
+        } else if self.MaxAge < 0 {
+            b.WriteString("; Max-Age=0");
+        }
+        if self.HttpOnly {
+            b.WriteString("; HttpOnly");
+        }
+        if self.Secure {
+            b.WriteString("; Secure");
+        }
+        match self.SameSite {
+            // Skip, default mode is obtained by not emitting the attribute.
+            SameSite::SameSiteDefaultMode => (),
+            SameSite::SameSiteNoneModepub => {
+                b.WriteString("; SameSite=None");
+            }
+            SameSite::SameSiteLaxMode => {
+                b.WriteString("; SameSite=Lax");
+            }
+            SameSite::SameSiteStrictMode => {
+                b.WriteString("; SameSite=Strict");
+            }
+        }
+        b.String()
+    }
+
+    /// Valid reports whether the cookie is valid.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Valid检查cookie是否合法。
+    /// </details>
+    pub fn Valid(&self) -> HttpResult<()> {
+        if !isCookieNameValid(&self.Name) {
+            return Err("http: invalid Cookie.Name".into());
+        }
+        if !self.Expires.IsZero() && !validCookieExpires(&self.Expires) {
+            return Err("http: invalid Cookie.Expires".into());
+        }
+        if let Some(b) = self.Value.bytes().find(|&b| !validCookieValueByte(b)) {
+            return Err(format!("http: invalid byte {:?} in Cookie.Value", b as char).into());
+        }
+        if let Some(b) = self.Path.bytes().find(|&b| !validCookiePathByte(b)) {
+            return Err(format!("http: invalid byte {:?} in Cookie.Path", b as char).into());
+        }
+        if !self.Domain.is_empty() && !validCookieDomain(&self.Domain) {
+            return Err("http: invalid Cookie.Domain".into());
+        }
+        Ok(())
+    }
+}
+
+/// SetCookie adds a Set-Cookie header to the provided ResponseWriter's headers. The provided cookie must have a valid Name. Invalid cookies may be silently dropped.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// SetCookie在w的头部中添加Set-Cookie头。cookie必须有合法的Name，不合法的cookie会被直接丢弃。
+/// </details>
+pub fn SetCookie(w: &mut dyn ResponseWriter, cookie: &Cookie) {
+    let v = cookie.String();
+    if !v.is_empty() {
+        w.Header().Add("Set-Cookie", &v);
+    }
+}
+
+// readCookies parses all "Cookie" values from the header h and returns the successfully parsed Cookies.
+//
+// if filter isn't empty, only cookies of that name are returned.
+pub(crate) fn readCookies(h: &Header, filter: &str) -> Vec<Cookie> {
+    let lines = h.Values("Cookie");
+    if lines.is_empty() {
+        return vec![];
+    }
+    let mut cookies = Vec::with_capacity(len!(lines) + strings::Count(&lines[0], ";") as usize);
+    for line in lines {
+        for part in line.split(';') {
+            let part = strings::TrimSpace(part);
+            if part.is_empty() {
+                continue;
+            }
+            let (name, val, _) = strings::Cut(part, "=");
+            let name = strings::TrimSpace(name);
+            if !isCookieNameValid(name) {
+                continue;
+            }
+            if !filter.is_empty() && filter != name {
+                continue;
+            }
+            let (val, ok) = parseCookieValue(val, true);
+            if !ok {
+                continue;
+            }
+            cookies.push(Cookie {
+                Name: name.to_string(),
+                Value: val,
+                ..Cookie::default()
+            });
+        }
+    }
+    cookies
 }
 
-impl CookieJar for Cookie {
-    fn SetCookies(&mut self, u: &url::URL, cookies: Vec<Cookie>) {
-        todo!()
+// validCookieDomain reports whether v is a valid cookie domain-value.
+fn validCookieDomain(v: &str) -> bool {
+    if isCookieDomainName(v) {
+        return true;
     }
+    v.parse::<std::net::IpAddr>().is_ok() && !v.contains(':')
+}
+
+// validCookieExpires reports whether v is a valid cookie expires-value.
+fn validCookieExpires(t: &time::Time) -> bool {
+    // IETF RFC 6265 Section 5.1.1.5, the year must not be less than 1601
+    t.Year() >= 1601
+}
+
+// isCookieDomainName reports whether s is a valid domain name or a valid domain name with a leading dot '.'. It is almost a direct copy of package net's isDomainName.
+fn isCookieDomainName(s: &str) -> bool {
+    if s.is_empty() || len!(s) > 255 {
+        return false;
+    }
+    let s = strings::TrimPrefix(s, ".");
+    let mut last = b'.';
+    let mut ok = false; // Ok once we've seen a letter.
+    let mut partlen = 0;
+    for c in s.bytes() {
+        match c {
+            b'a'..=b'z' | b'A'..=b'Z' => {
+                // No '_' allowed here (in contrast to package net).
+                ok = true;
+                partlen += 1;
+            }
+            b'0'..=b'9' => {
+                // fine
+                partlen += 1;
+            }
+            b'-' => {
+                // Byte before dash cannot be dot.
+                if last == b'.' {
+                    return false;
+                }
+                partlen += 1;
+            }
+            b'.' => {
+                // Byte before dot cannot be dot, dash.
+                if last == b'.' || last == b'-' {
+                    return false;
+                }
+                if partlen > 63 || partlen == 0 {
+                    return false;
+                }
+                partlen = 0;
+            }
+            _ => return false,
+        }
+        last = c;
+    }
+    if last == b'-' || partlen > 63 {
+        return false;
+    }
+    ok
+}
+
+// sanitizeCookieName replaces line breaks in a cookie name.
+pub(crate) fn sanitizeCookieName(n: &str) -> String {
+    n.replace(['\n', '\r'], "-")
+}
+
+// sanitizeCookieValue produces a suitable cookie-value from v.
+// https://tools.ietf.org/html/rfc6265#section-4.1.1
+//
+//	cookie-value      = *cookie-octet / ( DQUOTE *cookie-octet DQUOTE )
+//	cookie-octet      = %x21 / %x23-2B / %x2D-3A / %x3C-5B / %x5D-7E
+//	          ; US-ASCII characters excluding CTLs,
+//	          ; whitespace DQUOTE, comma, semicolon,
+//	          ; and backslash
+//
+// We loosen this as spaces and commas are common in cookie values thus we produce a quoted cookie-value if v contains commas or spaces.
+pub(crate) fn sanitizeCookieValue(v: &str) -> String {
+    let v = sanitizeOrWarn(validCookieValueByte, v);
+    if v.is_empty() {
+        return v;
+    }
+    if v.contains([' ', ',']) {
+        return format!("\"{}\"", v);
+    }
+    v
+}
+
+fn validCookiePathByte(b: byte) -> bool {
+    (0x20..0x7f).contains(&b) && b != b';'
+}
+
+// path-av           = "Path=" path-value
+// path-value        = <any CHAR except CTLs or ";">
+fn sanitizeCookiePath(v: &str) -> String {
+    sanitizeOrWarn(validCookiePathByte, v)
+}
 
-    fn Cookies(&self, u: &url::URL) -> Vec<Cookie> {
-        todo!()
+fn sanitizeOrWarn(valid: fn(byte) -> bool, v: &str) -> String {
+    if v.bytes().all(valid) {
+        return v.to_string();
     }
+    let buf: Vec<byte> = v.bytes().filter(|&b| valid(b)).collect();
+    String::from_utf8(buf).unwrap_or_default()
 }
 // SameSite allows a server to define a cookie attribute making it impossible for
 // the browser to send this cookie along with cross-site requests. The main
@@ -18,7 +18,15 @@ pub enum HTTPConnectError {
     TlsHandshakeFailure(#[from] rustls::Error),
 
     #[error("IO error: {0}")]
-    IoError(#[from] std::io::Error),
+    IoError(std::io::Error),
+
+    /// TimeoutError is returned when a request exceeds Client.Timeout or one of the Transport timeouts, such as DialTimeout, TLSHandshakeTimeout or ResponseHeaderTimeout. Reads of a response body past Client.Timeout fail with it as well.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// TimeoutError在请求超过Client.Timeout或Transport的某个超时设置(如DialTimeout、TLSHandshakeTimeout、ResponseHeaderTimeout)时返回。超过Client.Timeout后读取响应body也会返回该错误。
+    /// </details>
+    #[error("{0}")]
+    TimeoutError(String),
 
     #[error("From UTF-8 error: {0}")]
     FromUtf8Error(#[from] Utf8Error),
@@ -37,6 +45,112 @@ pub enum HTTPConnectError {
 
     #[error("unused")]
     ErrWriteAfterFlush,
+
+    /// ErrUseLastResponse can be returned by Client.CheckRedirect hooks to control how redirects are processed. If returned, the next request is not sent and the most recent response is returned with its body unclosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ErrUseLastResponse可以由Client.CheckRedirect返回，用于控制重定向的处理。返回该错误时不会发出下一个请求，而是返回最近一次的响应，且不关闭其body。
+    /// </details>
+    #[error("net/http: use last response")]
+    ErrUseLastResponse,
+
+    /// ProtocolError is returned when a message received from the peer is not valid HTTP, such as a malformed request line or header field.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 收到的消息不符合HTTP协议时(例如请求行或header字段格式错误)返回ProtocolError。
+    /// </details>
+    #[error("{0}")]
+    ProtocolError(String),
+
+    /// ErrTooLarge is returned by ReadRequest when the request line and header fields are larger than the allowed header size.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 请求行和header字段超过允许的大小时，ReadRequest返回ErrTooLarge。
+    /// </details>
+    #[error("http: request too large")]
+    ErrTooLarge,
+
+    /// ErrServerClosed is returned by the Server's Serve and ListenAndServe methods after a call to Shutdown or Close.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 调用Shutdown或Close之后，Server的Serve和ListenAndServe方法返回ErrServerClosed。
+    /// </details>
+    #[error("http: Server closed")]
+    ErrServerClosed,
+
+    /// ErrNoCookie is returned by Request's Cookie method when a cookie is not found.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 找不到cookie时，Request的Cookie方法返回ErrNoCookie。
+    /// </details>
+    #[error("http: named cookie not present")]
+    ErrNoCookie,
+
+    /// ErrNotMultipart is returned by Request's ParseMultipartForm and FormFile when the Content-Type of the request is not multipart/form-data.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 请求的Content-Type不是multipart/form-data时，Request的ParseMultipartForm和FormFile方法返回ErrNotMultipart。
+    /// </details>
+    #[error("request Content-Type isn't multipart/form-data")]
+    ErrNotMultipart,
+
+    /// ErrMissingBoundary is returned when a multipart/form-data Content-Type has no boundary parameter.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// multipart/form-data类型的Content-Type缺少boundary参数时返回ErrMissingBoundary。
+    /// </details>
+    #[error("no multipart boundary param in Content-Type")]
+    ErrMissingBoundary,
+
+    /// ErrMissingFile is returned by Request's FormFile when the provided file field name is either not present in the request or not a file field.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 请求中不存在指定名称的文件字段，或者该字段不是文件时，Request的FormFile方法返回ErrMissingFile。
+    /// </details>
+    #[error("http: no such file")]
+    ErrMissingFile,
+
+    /// ErrMessageTooLarge is returned by ParseForm and multipart's ReadForm when the form data is too large to be processed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 表单数据太大无法处理时，ParseForm和multipart的ReadForm返回ErrMessageTooLarge。
+    /// </details>
+    #[error("multipart: message too large")]
+    ErrMessageTooLarge,
+
+    /// ErrNoLocation is returned by Response's Location method when no Location header is present.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 响应中没有Location头时，Response的Location方法返回ErrNoLocation。
+    /// </details>
+    #[error("http: no Location header in response")]
+    ErrNoLocation,
+}
+
+impl HTTPConnectError {
+    /// Timeout reports whether the error is a timeout.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Timeout报告该错误是否为超时错误。
+    /// </details>
+    pub fn Timeout(&self) -> bool {
+        matches!(self, HTTPConnectError::TimeoutError(_))
+    }
+}
+
+// 读写超时的socket返回TimedOut或WouldBlock，统一转换为TimeoutError。
+impl From<std::io::Error> for HTTPConnectError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::TimedOut if err.get_ref().is_some() => {
+                HTTPConnectError::TimeoutError(err.to_string())
+            }
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => {
+                HTTPConnectError::TimeoutError("i/o timeout".to_string())
+            }
+            _ => HTTPConnectError::IoError(err),
+        }
+    }
 }
 
 impl From<String> for HTTPConnectError {
@@ -0,0 +1,916 @@
+//! HTTP file system request handler: FileServer serves a tree of files, ServeFile a single file and ServeContent any seekable content, with directory listings, conditional requests and byte ranges.
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! HTTP文件系统请求处理器：FileServer提供一个目录树中的文件，ServeFile提供单个文件，ServeContent提供任意可seek的内容，支持目录列表、条件请求和字节范围请求。
+//! </details>
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gostd_http::{fs, server};
+//!
+//! let mut mux = server::NewServeMux();
+//! mux.Handle("/", fs::FileServer(fs::Dir("./static")));
+//! server::ListenAndServe("127.0.0.1:8080", mux).unwrap();
+//! ```
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, MAIN_SEPARATOR},
+    time::UNIX_EPOCH,
+};
+
+use gostd_builtin::*;
+use gostd_time as time;
+use gostd_url as url;
+
+use crate::{
+    client::TimeFormat,
+    error::{HTTPConnectError, HttpResult},
+    header::Header,
+    multipart,
+    pattern::cleanPath,
+    request::Request,
+    server::{htmlEscape, Error, Handler, ResponseWriter},
+    sniff::{sniffLen, DetectContentType},
+};
+
+/// A FileSystem implements access to a collection of named files. The elements in a file path are separated by slash ('/') characters, regardless of host operating system convention.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// FileSystem实现了对一组命名文件的访问。无论主机操作系统的约定如何，文件路径中的元素都以斜杠('/')分隔。
+/// </details>
+pub trait FileSystem: Send + Sync {
+    fn Open(&self, name: &str) -> HttpResult<Box<dyn File>>;
+}
+
+/// A File is returned by a FileSystem's Open method and can be served by the FileServer implementation.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// File由FileSystem的Open方法返回，可以由FileServer提供服务。
+/// </details>
+pub trait File: Read + Seek + Send {
+    /// Readdir returns the entries of the directory, in no particular order.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Readdir返回目录中的条目，不保证顺序。
+    /// </details>
+    fn Readdir(&mut self) -> HttpResult<Vec<FileInfo>>;
+
+    /// Stat returns the FileInfo describing the file.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Stat返回描述该文件的FileInfo。
+    /// </details>
+    fn Stat(&self) -> HttpResult<FileInfo>;
+}
+
+/// A FileInfo describes a file and is returned by File's Stat and Readdir.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// FileInfo描述一个文件，由File的Stat和Readdir返回。
+/// </details>
+#[derive(Default, Debug, Clone)]
+pub struct FileInfo {
+    pub Name: String,
+    pub Size: int64,
+    pub ModTime: time::Time,
+    pub IsDir: bool,
+}
+
+/// A Dir implements FileSystem using the native file system restricted to a specific directory tree. An empty Dir is treated as ".".
+///
+/// Dir follows symbolic links pointing out of the directory tree, and serves files whose names begin with a period, such as a .git directory. Do not point it at a directory holding anything that should stay private.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Dir使用本地文件系统实现FileSystem，访问范围限制在指定的目录树中。空的Dir按"."处理。
+///
+/// Dir会跟随指向目录树之外的符号链接，也会提供以句点开头的文件，例如.git目录。不要让它指向包含私密内容的目录。
+/// </details>
+#[derive(Default, Debug, Clone)]
+pub struct Dir<P: AsRef<Path>>(pub P);
+
+impl<P: AsRef<Path> + Send + Sync> FileSystem for Dir<P> {
+    fn Open(&self, name: &str) -> HttpResult<Box<dyn File>> {
+        if name.contains('\0') || (MAIN_SEPARATOR != '/' && name.contains(MAIN_SEPARATOR)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "http: invalid character in file path",
+            )
+            .into());
+        }
+        let mut dir = self.0.as_ref();
+        if dir.as_os_str().is_empty() {
+            dir = Path::new(".");
+        }
+        // cleanPath去掉了所有".."，拼接后的路径不会离开dir。
+        let path = dir.join(cleanPath(name).trim_start_matches('/'));
+        let file = fs::File::open(&path)?;
+        Ok(Box::new(osFile { file, path }))
+    }
+}
+
+// osFile is a file of the native file system opened by Dir.
+struct osFile {
+    file: fs::File,
+    path: std::path::PathBuf,
+}
+
+impl Read for osFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Seek for osFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl File for osFile {
+    fn Readdir(&mut self) -> HttpResult<Vec<FileInfo>> {
+        let mut list = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            list.push(fileInfo(name, &entry.metadata()?));
+        }
+        Ok(list)
+    }
+
+    fn Stat(&self) -> HttpResult<FileInfo> {
+        let name = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(fileInfo(name, &self.file.metadata()?))
+    }
+}
+
+// fileInfo builds the FileInfo of the file called name from its metadata.
+fn fileInfo(name: String, meta: &fs::Metadata) -> FileInfo {
+    let ModTime = match meta.modified().map(|t| t.duration_since(UNIX_EPOCH)) {
+        Ok(Ok(d)) => time::Unix(d.as_secs() as int64, d.subsec_nanos() as int64),
+        _ => time::Time::default(),
+    };
+    FileInfo {
+        Name: name,
+        Size: meta.len() as int64,
+        ModTime,
+        IsDir: meta.is_dir(),
+    }
+}
+
+/// FileServer returns a handler that serves HTTP requests with the contents of the file system rooted at root.
+///
+/// As a special case, the returned file server redirects any request ending in "/index.html" to the same path, without the final "index.html". Directories without an index.html are answered with a listing of their entries.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// FileServer返回一个处理器，用以root为根的文件系统的内容响应HTTP请求。
+///
+/// 特殊情况下，返回的文件服务器会把所有以"/index.html"结尾的请求重定向到去掉"index.html"后的路径。没有index.html的目录返回其条目的列表。
+/// </details>
+pub fn FileServer(root: impl FileSystem + 'static) -> impl Handler {
+    fileHandler { root }
+}
+
+struct fileHandler<F> {
+    root: F,
+}
+
+impl<F: FileSystem> Handler for fileHandler<F> {
+    fn ServeHttp(&self, w: &mut dyn ResponseWriter, r: &mut Request) {
+        if !r.URL.Path.starts_with('/') {
+            r.URL.Path = format!("/{}", r.URL.Path);
+        }
+        // 和path.Clean一样去掉结尾的/，目录的规范路径由serveFile重定向。
+        let mut upath = cleanPath(&r.URL.Path);
+        if upath.len() > 1 && upath.ends_with('/') {
+            upath.pop();
+        }
+        serveFile(w, r, &self.root, &upath, true);
+    }
+}
+
+/// ServeFile replies to the request with the contents of the named file or directory.
+///
+/// Requests whose URL path contains a ".." element are rejected with 400 Bad Request. As a special case, ServeFile redirects any request where r.URL.Path ends in "/index.html" to the same path, without the final "index.html".
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ServeFile用指定文件或目录的内容回复请求。
+///
+/// URL路径中包含".."元素的请求会被拒绝，返回400 Bad Request。特殊情况下，ServeFile会把r.URL.Path以"/index.html"结尾的请求重定向到去掉"index.html"后的路径。
+/// </details>
+pub fn ServeFile(w: &mut dyn ResponseWriter, r: &Request, name: &str) {
+    if containsDotDot(&r.URL.Path) {
+        Error(w, "invalid URL path", 400);
+        return;
+    }
+    let (dir, file) = name.split_at(name.rfind('/').map_or(0, |i| i + 1));
+    serveFile(w, r, &Dir(dir), file, false);
+}
+
+/// ServeContent replies to the request using the content in the provided reader. The main benefit of ServeContent over io::copy is that it handles Range requests properly, sets the MIME type, and handles If-Match, If-Unmodified-Since, If-None-Match, If-Modified-Since, and If-Range requests.
+///
+/// If the response's Content-Type header is not set, ServeContent first tries to deduce the type from name's file extension and, if that fails, falls back to reading the first block of the content and passing it to DetectContentType.
+///
+/// If modtime is not the zero time or Unix epoch, ServeContent includes it in a Last-Modified header of the response. If the request includes an If-Modified-Since header, ServeContent uses modtime to decide whether the content needs to be sent at all. If the caller has set w's ETag header, ServeContent uses it to handle requests using If-Match, If-None-Match, or If-Range.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ServeContent使用content中的内容回复请求。相比io::copy，ServeContent的主要好处是能正确处理Range请求，设置MIME类型，并处理If-Match、If-Unmodified-Since、If-None-Match、If-Modified-Since和If-Range请求。
+///
+/// 如果没有设置响应的Content-Type头，ServeContent先尝试根据name的扩展名推断类型，失败时读取内容的第一块数据交给DetectContentType判断。
+///
+/// 如果modtime不是零值时间或Unix纪元，ServeContent会把它写入响应的Last-Modified头。如果请求带有If-Modified-Since头，ServeContent根据modtime决定是否需要发送内容。如果调用者设置了w的ETag头，ServeContent用它处理If-Match、If-None-Match或If-Range请求。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http::{fs, httptest};
+/// use gostd_time as time;
+/// use std::io::Cursor;
+///
+/// let mut r = httptest::NewRequest("GET", "/hello.txt", None);
+/// r.Header.Set("Range", "bytes=0-4");
+/// let mut w = httptest::NewRecorder();
+/// let mut content = Cursor::new(b"hello, world".to_vec());
+/// fs::ServeContent(&mut w, &r, "hello.txt", &time::Unix(0, 0), &mut content);
+///
+/// assert_eq!(w.Code, 206);
+/// assert_eq!(w.HeaderMap.Get("Content-Range"), "bytes 0-4/12");
+/// assert_eq!(w.Body, b"hello".to_vec());
+/// ```
+pub fn ServeContent<R: Read + Seek + ?Sized>(
+    w: &mut dyn ResponseWriter,
+    r: &Request,
+    name: &str,
+    modtime: &time::Time,
+    content: &mut R,
+) {
+    let size = match content
+        .seek(SeekFrom::End(0))
+        .and_then(|size| content.seek(SeekFrom::Start(0)).map(|_| size))
+    {
+        Ok(size) => size as int64,
+        Err(_) => {
+            Error(w, "seeker can't seek", 500);
+            return;
+        }
+    };
+    serveContent(w, r, name, modtime, size, content);
+}
+
+// serveContent replies to the request using the content of size bytes. It is shared by ServeContent and serveFile, which already know the size of the content.
+fn serveContent<R: Read + Seek + ?Sized>(
+    w: &mut dyn ResponseWriter,
+    r: &Request,
+    name: &str,
+    modtime: &time::Time,
+    size: int64,
+    content: &mut R,
+) {
+    setLastModified(w, modtime);
+    let (done, rangeReq) = checkPreconditions(w, r, modtime);
+    if done {
+        return;
+    }
+
+    let mut code = 200;
+
+    // 没有设置Content-Type时，先按扩展名判断，再嗅探内容。
+    let mut ctype = w.Header().Get("Content-Type");
+    if w.Header().Values("Content-Type").is_empty() {
+        ctype = typeByExtension(name).to_string();
+        if ctype.is_empty() {
+            let mut buf = Vec::with_capacity(sniffLen);
+            let read = (&mut *content).take(sniffLen as u64).read_to_end(&mut buf);
+            ctype = DetectContentType(&buf).to_string();
+            if read.is_err() || content.seek(SeekFrom::Start(0)).is_err() {
+                Error(w, "seeker can't seek", 500);
+                return;
+            }
+        }
+        w.Header().Set("Content-Type", &ctype);
+    }
+
+    // 处理Range请求，sendSize是需要发送的字节数。
+    let mut sendSize = size;
+    let mut ranges = Vec::new();
+    let mut boundary = String::new();
+    if size >= 0 {
+        ranges = match parseRange(&rangeReq, size) {
+            Ok(ranges) => ranges,
+            Err(err) => {
+                if err == errNoOverlap {
+                    w.Header()
+                        .Set("Content-Range", &format!("bytes */{}", size));
+                }
+                Error(w, err, 416);
+                return;
+            }
+        };
+        if sumRangesSize(&ranges) > size {
+            // 请求的总字节数超过了内容大小，可能是攻击，直接发送全部内容。
+            ranges.clear();
+        }
+        if ranges.len() == 1 {
+            let ra = &ranges[0];
+            if content.seek(SeekFrom::Start(ra.start as u64)).is_err() {
+                Error(w, "seeker can't seek", 416);
+                return;
+            }
+            sendSize = ra.length;
+            code = 206;
+            w.Header().Set("Content-Range", &ra.contentRange(size));
+        } else if ranges.len() > 1 {
+            let (rangesSize, b) = rangesMIMESize(&ranges, &ctype, size);
+            sendSize = rangesSize;
+            boundary = b;
+            code = 206;
+            w.Header().Set(
+                "Content-Type",
+                &format!("multipart/byteranges; boundary={}", boundary),
+            );
+        }
+        w.Header().Set("Accept-Ranges", "bytes");
+        if w.Header().Get("Content-Encoding").is_empty() {
+            w.Header().Set("Content-Length", &sendSize.to_string());
+        }
+    }
+
+    w.WriteHeader(code);
+    if r.Method == "HEAD" {
+        return;
+    }
+
+    if ranges.len() > 1 {
+        // 响应头已经发出，写入失败时只能中断body。
+        let _ = writeRanges(w, &ranges, &boundary, &ctype, size, content);
+    } else {
+        let _ = io::copy(&mut (&mut *content).take(sendSize as u64), &mut *w);
+    }
+}
+
+// writeRanges writes the given ranges of content as a multipart/byteranges body delimited by boundary.
+fn writeRanges<R: Read + Seek + ?Sized>(
+    w: &mut dyn ResponseWriter,
+    ranges: &[httpRange],
+    boundary: &str,
+    ctype: &str,
+    size: int64,
+    content: &mut R,
+) -> HttpResult<()> {
+    let mut mw = multipart::NewWriter(&mut *w);
+    mw.SetBoundary(boundary)?;
+    for ra in ranges {
+        let mut part = mw.CreatePart(&ra.mimeHeader(ctype, size))?;
+        content.seek(SeekFrom::Start(ra.start as u64))?;
+        io::copy(&mut (&mut *content).take(ra.length as u64), &mut part)?;
+    }
+    mw.Close()
+}
+
+// serveFile serves the file or directory called name, which is '/'-separated, from fs. With redirect set, requests for a directory without a trailing slash, or for a file with one, are redirected to the canonical path.
+fn serveFile(
+    w: &mut dyn ResponseWriter,
+    r: &Request,
+    fs: &dyn FileSystem,
+    name: &str,
+    redirect: bool,
+) {
+    const indexPage: &str = "/index.html";
+
+    // 把.../index.html重定向到.../
+    if r.URL.Path.ends_with(indexPage) {
+        localRedirect(w, r, "./");
+        return;
+    }
+
+    let mut f = match fs.Open(name) {
+        Ok(f) => f,
+        Err(err) => {
+            let (msg, code) = toHTTPError(&err);
+            Error(w, msg, code);
+            return;
+        }
+    };
+    let mut d = match f.Stat() {
+        Ok(d) => d,
+        Err(err) => {
+            let (msg, code) = toHTTPError(&err);
+            Error(w, msg, code);
+            return;
+        }
+    };
+
+    if redirect {
+        // 重定向到规范路径：目录以/结尾，文件不以/结尾。
+        let url = &r.URL.Path;
+        if d.IsDir {
+            if !url.ends_with('/') {
+                let base = url.rsplit('/').next().unwrap_or_default();
+                localRedirect(w, r, &format!("{}/", base));
+                return;
+            }
+        } else if url.ends_with('/') {
+            let trimmed = url.trim_end_matches('/');
+            let base = trimmed.rsplit('/').next().unwrap_or_default();
+            localRedirect(w, r, &format!("../{}", base));
+            return;
+        }
+    }
+
+    if d.IsDir {
+        let url = &r.URL.Path;
+        // 目录路径不以/结尾时重定向，否则相对链接会出错。
+        if !url.is_empty() && !url.ends_with('/') {
+            let base = url.rsplit('/').next().unwrap_or_default();
+            localRedirect(w, r, &format!("{}/", base));
+            return;
+        }
+
+        // 目录中有index.html时使用它。
+        let index = format!("{}{}", name.trim_end_matches('/'), indexPage);
+        if let Ok(ff) = fs.Open(&index) {
+            if let Ok(dd) = ff.Stat() {
+                d = dd;
+                f = ff;
+            }
+        }
+    }
+
+    // 仍然是目录，返回目录列表。
+    if d.IsDir {
+        if checkIfModifiedSince(r, &d.ModTime) == condResult::False {
+            writeNotModified(w);
+            return;
+        }
+        setLastModified(w, &d.ModTime);
+        dirList(w, r, &mut *f);
+        return;
+    }
+
+    serveContent(w, r, &d.Name, &d.ModTime, d.Size, &mut *f);
+}
+
+// toHTTPError returns a non-specific HTTP error message and status code for a given file system error, so that internal details do not leak to the client.
+fn toHTTPError(err: &HTTPConnectError) -> (&'static str, u16) {
+    if let HTTPConnectError::IoError(e) = err {
+        match e.kind() {
+            io::ErrorKind::NotFound => return ("404 page not found", 404),
+            io::ErrorKind::PermissionDenied => return ("403 Forbidden", 403),
+            _ => {}
+        }
+    }
+    ("500 Internal Server Error", 500)
+}
+
+// localRedirect gives a Moved Permanently response. It does not convert relative paths to absolute paths like Redirect does, and it keeps the query of the request.
+fn localRedirect(w: &mut dyn ResponseWriter, r: &Request, newPath: &str) {
+    let mut newPath = newPath.to_string();
+    if !r.URL.RawQuery.is_empty() {
+        newPath = format!("{}?{}", newPath, r.URL.RawQuery);
+    }
+    w.Header().Set("Location", &newPath);
+    w.WriteHeader(301);
+}
+
+// containsDotDot reports whether v has a ".." path element.
+fn containsDotDot(v: &str) -> bool {
+    v.contains("..") && v.split(['/', '\\']).any(|ent| ent == "..")
+}
+
+// dirList replies with an HTML listing of the entries of the directory f, sorted by name.
+fn dirList(w: &mut dyn ResponseWriter, r: &Request, f: &mut dyn File) {
+    let mut dirs = match f.Readdir() {
+        Ok(dirs) => dirs,
+        Err(_) => {
+            Error(w, "Error reading directory", 500);
+            return;
+        }
+    };
+    dirs.sort_by(|a, b| a.Name.cmp(&b.Name));
+
+    w.Header().Set("Content-Type", "text/html; charset=utf-8");
+    let mut b = String::from(
+        "<!doctype html>\n<meta name=\"viewport\" content=\"width=device-width\">\n<pre>\n",
+    );
+    for d in dirs {
+        let mut name = d.Name;
+        let mut href = url::PathEscape(&name);
+        if d.IsDir {
+            name.push('/');
+            href.push('/');
+        }
+        // 第一段带冒号的相对路径会被当成scheme，加上"./"。
+        if href.contains(':') {
+            href = format!("./{}", href);
+        }
+        b.push_str(&format!(
+            "<a href=\"{}\">{}</a>\n",
+            htmlEscape(&href),
+            htmlEscape(&name)
+        ));
+    }
+    b.push_str("</pre>\n");
+    let _ = w.Write(b.as_bytes());
+}
+
+// condResult is the result of evaluating an HTTP RFC 7232 precondition header.
+#[derive(PartialEq, Clone, Copy)]
+enum condResult {
+    None,
+    True,
+    False,
+}
+
+// checkPreconditions evaluates request preconditions and reports whether a precondition resulted in sending 304 Not Modified or 412 Precondition Failed, in which case the response is done. The returned rangeHeader is the Range header to use, empty if an If-Range did not match.
+fn checkPreconditions(
+    w: &mut dyn ResponseWriter,
+    r: &Request,
+    modtime: &time::Time,
+) -> (bool, String) {
+    // 按RFC 7232第6节的顺序判断。
+    let mut ch = checkIfMatch(w, r);
+    if ch == condResult::None {
+        ch = checkIfUnmodifiedSince(r, modtime);
+    }
+    if ch == condResult::False {
+        w.WriteHeader(412);
+        return (true, String::new());
+    }
+    match checkIfNoneMatch(w, r) {
+        condResult::False => {
+            if r.Method == "GET" || r.Method == "HEAD" {
+                writeNotModified(w);
+                return (true, String::new());
+            }
+            w.WriteHeader(412);
+            return (true, String::new());
+        }
+        condResult::None => {
+            if checkIfModifiedSince(r, modtime) == condResult::False {
+                writeNotModified(w);
+                return (true, String::new());
+            }
+        }
+        condResult::True => {}
+    }
+
+    let mut rangeHeader = r.Header.Get("Range");
+    if !rangeHeader.is_empty() && checkIfRange(w, r, modtime) == condResult::False {
+        rangeHeader = String::new();
+    }
+    (false, rangeHeader)
+}
+
+fn checkIfMatch(w: &mut dyn ResponseWriter, r: &Request) -> condResult {
+    let im = r.Header.Get("If-Match");
+    if im.is_empty() {
+        return condResult::None;
+    }
+    let etag = w.Header().Get("Etag");
+    let mut im = im.as_str();
+    loop {
+        im = im.trim_start_matches([' ', '\t']);
+        if im.is_empty() {
+            break;
+        }
+        if let Some(rest) = im.strip_prefix(',') {
+            im = rest;
+            continue;
+        }
+        if im.starts_with('*') {
+            return condResult::True;
+        }
+        let Some((eTag, remain)) = scanETag(im) else {
+            break;
+        };
+        if etagStrongMatch(eTag, &etag) {
+            return condResult::True;
+        }
+        im = remain;
+    }
+    condResult::False
+}
+
+fn checkIfUnmodifiedSince(r: &Request, modtime: &time::Time) -> condResult {
+    let ius = r.Header.Get("If-Unmodified-Since");
+    if ius.is_empty() || isZeroTime(modtime) {
+        return condResult::None;
+    }
+    match parseTime(&ius) {
+        // 头部时间只精确到秒。
+        Some(t) if modtime.Unix() <= t.Unix() => condResult::True,
+        Some(_) => condResult::False,
+        None => condResult::None,
+    }
+}
+
+fn checkIfNoneMatch(w: &mut dyn ResponseWriter, r: &Request) -> condResult {
+    let inm = r.Header.Get("If-None-Match");
+    if inm.is_empty() {
+        return condResult::None;
+    }
+    let etag = w.Header().Get("Etag");
+    let mut buf = inm.as_str();
+    loop {
+        buf = buf.trim_start_matches([' ', '\t']);
+        if buf.is_empty() {
+            break;
+        }
+        if let Some(rest) = buf.strip_prefix(',') {
+            buf = rest;
+            continue;
+        }
+        if buf.starts_with('*') {
+            return condResult::False;
+        }
+        let Some((eTag, remain)) = scanETag(buf) else {
+            break;
+        };
+        if etagWeakMatch(eTag, &etag) {
+            return condResult::False;
+        }
+        buf = remain;
+    }
+    condResult::True
+}
+
+fn checkIfModifiedSince(r: &Request, modtime: &time::Time) -> condResult {
+    if r.Method != "GET" && r.Method != "HEAD" {
+        return condResult::None;
+    }
+    let ims = r.Header.Get("If-Modified-Since");
+    if ims.is_empty() || isZeroTime(modtime) {
+        return condResult::None;
+    }
+    match parseTime(&ims) {
+        // 头部时间只精确到秒。
+        Some(t) if modtime.Unix() <= t.Unix() => condResult::False,
+        Some(_) => condResult::True,
+        None => condResult::None,
+    }
+}
+
+fn checkIfRange(w: &mut dyn ResponseWriter, r: &Request, modtime: &time::Time) -> condResult {
+    if r.Method != "GET" && r.Method != "HEAD" {
+        return condResult::None;
+    }
+    let ir = r.Header.Get("If-Range");
+    if ir.is_empty() {
+        return condResult::None;
+    }
+    if let Some((etag, _)) = scanETag(&ir) {
+        if etagStrongMatch(etag, &w.Header().Get("Etag")) {
+            return condResult::True;
+        }
+        return condResult::False;
+    }
+    // If-Range的值不是ETag时必须是日期，并且要和modtime完全相等。
+    if isZeroTime(modtime) {
+        return condResult::False;
+    }
+    match parseTime(&ir) {
+        Some(t) if t.Unix() == modtime.Unix() => condResult::True,
+        _ => condResult::False,
+    }
+}
+
+// writeNotModified replies with 304 Not Modified, removing the representation headers the response must not carry.
+fn writeNotModified(w: &mut dyn ResponseWriter) {
+    // RFC 7232第4.1节：304响应不应包含表示层的头部。
+    let h = w.Header();
+    h.Del("Content-Type");
+    h.Del("Content-Length");
+    h.Del("Content-Encoding");
+    if !h.Get("Etag").is_empty() {
+        h.Del("Last-Modified");
+    }
+    w.WriteHeader(304);
+}
+
+// setLastModified sets the Last-Modified header to modtime in TimeFormat, unless modtime is unspecified.
+fn setLastModified(w: &mut dyn ResponseWriter, modtime: &time::Time) {
+    if !isZeroTime(modtime) {
+        w.Header()
+            .Set("Last-Modified", &modtime.clone().UTC().Format(TimeFormat));
+    }
+}
+
+// isZeroTime reports whether t is obviously unspecified, either zero or Unix epoch.
+fn isZeroTime(t: &time::Time) -> bool {
+    t.IsZero() || t.Unix() == 0
+}
+
+// parseTime parses a time header such as If-Modified-Since, trying each of the three formats allowed by HTTP/1.1: TimeFormat, RFC850 and ANSIC.
+fn parseTime(text: &str) -> Option<time::Time> {
+    [TimeFormat, time::RFC850, time::ANSIC]
+        .iter()
+        .find_map(|layout| time::Parse(layout, text).ok())
+}
+
+// scanETag determines if a syntactically valid ETag is present at s. If so, the ETag and remaining text after consuming the ETag are returned.
+fn scanETag(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start_matches([' ', '\t']);
+    let start = if s.starts_with("W/") { 2 } else { 0 };
+    let b = s.as_bytes();
+    if b.len() - start < 2 || b[start] != b'"' {
+        return None;
+    }
+    // ETag是带引号的字符串，引号内不能有空白和控制字符。
+    for i in start + 1..b.len() {
+        match b[i] {
+            0x21 | 0x23..=0x7E | 0x80..=0xFF => {}
+            b'"' => return Some((&s[..i + 1], &s[i + 1..])),
+            _ => return None,
+        }
+    }
+    None
+}
+
+// etagStrongMatch reports whether a and b match using strong ETag comparison.
+fn etagStrongMatch(a: &str, b: &str) -> bool {
+    a == b && !a.is_empty() && a.starts_with('"')
+}
+
+// etagWeakMatch reports whether a and b match using weak ETag comparison.
+fn etagWeakMatch(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+// errNoOverlap is returned by parseRange if the first-byte-pos of all of the byte-range-spec values is greater than the content size.
+const errNoOverlap: &str = "invalid range: failed to overlap";
+
+// httpRange specifies the byte range to be sent to the client.
+struct httpRange {
+    start: int64,
+    length: int64,
+}
+
+impl httpRange {
+    fn contentRange(&self, size: int64) -> String {
+        format!(
+            "bytes {}-{}/{}",
+            self.start,
+            self.start + self.length - 1,
+            size
+        )
+    }
+
+    fn mimeHeader(&self, contentType: &str, size: int64) -> Header {
+        let mut h = Header::default();
+        h.Set("Content-Range", &self.contentRange(size));
+        h.Set("Content-Type", contentType);
+        h
+    }
+}
+
+// parseRange parses a Range header string as per RFC 7233. errNoOverlap is returned if none of the ranges overlap.
+fn parseRange(s: &str, size: int64) -> Result<Vec<httpRange>, &'static str> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    const b: &str = "bytes=";
+    let Some(s) = s.strip_prefix(b) else {
+        return Err("invalid range");
+    };
+    let mut ranges = Vec::new();
+    let mut noOverlap = false;
+    for ra in s.split(',') {
+        let ra = ra.trim_matches([' ', '\t']);
+        if ra.is_empty() {
+            continue;
+        }
+        let Some((start, end)) = ra.split_once('-') else {
+            return Err("invalid range");
+        };
+        let (start, end) = (
+            start.trim_matches([' ', '\t']),
+            end.trim_matches([' ', '\t']),
+        );
+        let mut r = httpRange {
+            start: 0,
+            length: 0,
+        };
+        if start.is_empty() {
+            // 没有起始位置时，end表示最后的字节数，例如"-5"表示最后5个字节。
+            if end.is_empty() || end.starts_with('-') {
+                return Err("invalid range");
+            }
+            let mut i: int64 = end.parse().map_err(|_| "invalid range")?;
+            if i > size {
+                i = size;
+            }
+            r.start = size - i;
+            r.length = size - r.start;
+        } else {
+            let i: int64 = start.parse().map_err(|_| "invalid range")?;
+            if i < 0 {
+                return Err("invalid range");
+            }
+            if i >= size {
+                // 范围在内容之后，忽略它。
+                noOverlap = true;
+                continue;
+            }
+            r.start = i;
+            if end.is_empty() {
+                // 没有结束位置时，一直到内容末尾。
+                r.length = size - r.start;
+            } else {
+                let mut i: int64 = end.parse().map_err(|_| "invalid range")?;
+                if r.start > i {
+                    return Err("invalid range");
+                }
+                if i >= size {
+                    i = size - 1;
+                }
+                r.length = i - r.start + 1;
+            }
+        }
+        ranges.push(r);
+    }
+    if noOverlap && ranges.is_empty() {
+        // 指定的范围都在内容之外。
+        return Err(errNoOverlap);
+    }
+    Ok(ranges)
+}
+
+// countingWriter counts how many bytes have been written to it.
+struct countingWriter(int64);
+
+impl Write for countingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as int64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// rangesMIMESize returns the number of bytes it takes to encode the provided ranges as a multipart response, together with the boundary it used.
+fn rangesMIMESize(ranges: &[httpRange], contentType: &str, contentSize: int64) -> (int64, String) {
+    let mut w = countingWriter(0);
+    let mut mw = multipart::NewWriter(&mut w);
+    let mut encSize = 0;
+    for ra in ranges {
+        let _ = mw.CreatePart(&ra.mimeHeader(contentType, contentSize));
+        encSize += ra.length;
+    }
+    let boundary = mw.Boundary().to_string();
+    let _ = mw.Close();
+    encSize += w.0;
+    (encSize, boundary)
+}
+
+fn sumRangesSize(ranges: &[httpRange]) -> int64 {
+    ranges.iter().map(|ra| ra.length).sum()
+}
+
+// typeByExtension returns the MIME type associated with the file extension of name, or "" if there is none.
+fn typeByExtension(name: &str) -> &'static str {
+    let Some(i) = name.rfind('.') else {
+        return "";
+    };
+    if name[i..].contains('/') {
+        return "";
+    }
+    match name[i + 1..].to_ascii_lowercase().as_str() {
+        "avif" => "image/avif",
+        "css" => "text/css; charset=utf-8",
+        "gif" => "image/gif",
+        "htm" | "html" => "text/html; charset=utf-8",
+        "ico" => "image/vnd.microsoft.icon",
+        "jpeg" | "jpg" => "image/jpeg",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "map" => "application/json",
+        "md" => "text/markdown; charset=utf-8",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        "txt" => "text/plain; charset=utf-8",
+        "wasm" => "application/wasm",
+        "webp" => "image/webp",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "xml" => "text/xml; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "mp4" => "video/mp4",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        _ => "",
+    }
+}
@@ -3,32 +3,202 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
+use std::collections::hash_map;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
 
+use crate::error::HttpResult;
+
+/// A Header represents the key-value pairs in an HTTP header.
+///
+/// The keys should be in canonical form, as returned by CanonicalHeaderKey. All methods canonicalize the key they are given, so "content-type" and "Content-Type" name the same field.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Header表示HTTP头中的键值对。
+///
+/// 键应该是CanonicalHeaderKey返回的规范格式。所有方法都会把传入的键规范化，所以"content-type"和"Content-Type"表示同一个字段。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+///
+/// let mut h = http::header::Header::default();
+/// h.Add("set-cookie", "a=1");
+/// h.Add("Set-Cookie", "b=2");
+/// assert_eq!(h.Get("SET-COOKIE"), "a=1");
+/// assert_eq!(h.Values("Set-Cookie"), ["a=1", "b=2"]);
+///
+/// let mut buf = Vec::new();
+/// h.Write(&mut buf).unwrap();
+/// assert_eq!(buf, b"Set-Cookie: a=1\r\nSet-Cookie: b=2\r\n");
+/// ```
 #[derive(Default, PartialEq, Debug, Clone)]
 pub struct Header(pub HashMap<String, Vec<String>>);
 
 impl Header {
+    /// NewWithHashMap returns a Header holding the fields of m. Keys are canonicalized; values of keys that only differ in case are merged.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// NewWithHashMap返回包含m中字段的Header。键会被规范化，只有大小写不同的键的值会合并在一起。
+    /// </details>
     pub fn NewWithHashMap(m: HashMap<String, Vec<String>>) -> Header {
-        Header(m)
+        let mut h = Header(HashMap::with_capacity(m.len()));
+        for (k, v) in m {
+            h.0.entry(CanonicalHeaderKey(&k)).or_default().extend(v);
+        }
+        h
     }
+
+    /// Add adds the key, value pair to the header. It appends to any existing values associated with key.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Add添加键值对到header中，追加到key已有的值之后。
+    /// </details>
     pub fn Add(&mut self, key: &str, value: &str) {
         self.0
-            .get_mut(&key.to_string())
-            .unwrap()
+            .entry(CanonicalHeaderKey(key))
+            .or_default()
             .push(value.to_string())
     }
 
+    /// Set sets the header entries associated with key to the single element value. It replaces any existing values associated with key.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Set把key对应的值设置为单个元素value，替换key已有的所有值。
+    /// </details>
     pub fn Set(&mut self, key: &str, value: &str) {
-        self.0.insert(key.to_string(), vec![value.to_string()]);
+        self.0
+            .insert(CanonicalHeaderKey(key), vec![value.to_string()]);
     }
 
+    /// Get gets the first value associated with the given key. If there are no values associated with the key, Get returns "". To access multiple values of a key, use Values.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Get返回key对应的第一个值，没有值时返回""。要获取一个key的多个值，使用Values。
+    /// </details>
     pub fn Get(&self, key: &str) -> String {
-        self.0
-            .get(key)
-            .unwrap_or(&vec!["".to_string()])
-            .get(0)
-            .unwrap()
-            .to_string()
+        self.Values(key).first().cloned().unwrap_or_default()
+    }
+
+    /// Values returns all values associated with the given key. The returned slice is not a copy.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Values返回key对应的所有值，返回的切片不是副本。
+    /// </details>
+    pub fn Values(&self, key: &str) -> &[String] {
+        match self.0.get(&CanonicalHeaderKey(key)) {
+            Some(v) => v.as_slice(),
+            None => &[],
+        }
+    }
+
+    /// Del deletes the values associated with key.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Del删除key对应的所有值。
+    /// </details>
+    pub fn Del(&mut self, key: &str) {
+        self.0.remove(&CanonicalHeaderKey(key));
+    }
+
+    /// Clone returns a copy of h.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Clone返回h的副本。
+    /// </details>
+    pub fn Clone(&self) -> Header {
+        self.clone()
+    }
+
+    /// Write writes a header in wire format, one "Key: value" line per value, sorted by key.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Write以线路格式写入header，每个值一行"Key: value"，按key排序。
+    /// </details>
+    pub fn Write(&self, w: &mut dyn io::Write) -> HttpResult<()> {
+        self.WriteSubset(w, &HashSet::new())
+    }
+
+    /// WriteSubset writes a header in wire format. Keys in exclude are not written. Newlines in values are replaced by spaces and surrounding whitespace is trimmed, so a value cannot inject extra header lines.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// WriteSubset以线路格式写入header，不写入exclude中的key。值中的换行符被替换为空格并去掉首尾空白，防止注入额外的header行。
+    /// </details>
+    pub fn WriteSubset(&self, w: &mut dyn io::Write, exclude: &HashSet<String>) -> HttpResult<()> {
+        let mut keys: Vec<&String> = self.0.keys().filter(|k| !exclude.contains(*k)).collect();
+        keys.sort();
+        for k in keys {
+            for v in &self.0[k] {
+                let v = v.replace(['\r', '\n'], " ");
+                w.write_all(format!("{}: {}\r\n", k, v.trim_matches([' ', '\t'])).as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// iter visits every key with its values, in no particular order.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// iter遍历所有key及其值，顺序不固定。
+    /// </details>
+    pub fn iter(&self) -> hash_map::Iter<'_, String, Vec<String>> {
+        self.0.iter()
     }
+
+    /// Len returns the number of keys in the header.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Len返回header中key的数量。
+    /// </details>
+    pub fn Len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<'a> IntoIterator for &'a Header {
+    type Item = (&'a String, &'a Vec<String>);
+    type IntoIter = hash_map::Iter<'a, String, Vec<String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Header {
+    type Item = (String, Vec<String>);
+    type IntoIter = hash_map::IntoIter<String, Vec<String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// CanonicalHeaderKey returns the canonical format of the header key s. The canonicalization converts the first letter and any letter following a hyphen to upper case; the rest are converted to lowercase. For example, the canonical key for "accept-encoding" is "Accept-Encoding". If s contains a space or invalid header field bytes, it is returned without modifications.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// CanonicalHeaderKey返回header键s的规范格式：首字母和连字符后的字母转为大写，其余字母转为小写。例如"accept-encoding"的规范格式是"Accept-Encoding"。如果s包含空格或非法的字段字符，原样返回。
+/// </details>
+pub fn CanonicalHeaderKey(s: &str) -> String {
+    if !s.bytes().all(validHeaderFieldByte) {
+        return s.to_string();
+    }
+    let mut upper = true;
+    let mut new = String::with_capacity(s.len());
+    for c in s.chars() {
+        new.push(if upper {
+            c.to_ascii_uppercase()
+        } else {
+            c.to_ascii_lowercase()
+        });
+        upper = c == '-';
+    }
+    new
+}
+
+// validHeaderFieldByte reports whether b is a valid byte in a header field name, that is a token character as defined by RFC 7230.
+pub(crate) fn validHeaderFieldByte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
 }
@@ -0,0 +1,700 @@
+//! Package hpack implements HPACK, a compression format for efficiently representing HTTP header fields in the context of HTTP/2. See RFC 7541.
+//!
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! hpack包实现了HPACK，HTTP/2中用于高效表示HTTP头字段的压缩格式，参见RFC 7541。
+//! </details>
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use gostd_builtin::*;
+
+use crate::error::{HTTPConnectError, HttpResult};
+
+/// A HeaderField is a name-value pair. Both the name and value are treated as opaque sequences of octets.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HeaderField是一个名字-值对，名字和值都被当作不透明的字节序列处理。
+/// </details>
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct HeaderField {
+    pub Name: String,
+    pub Value: String,
+    /// Sensitive means that this header field should never be indexed.
+    pub Sensitive: bool,
+}
+
+impl HeaderField {
+    /// IsPseudo reports whether the header field is an http2 pseudo header. That is, it reports whether it starts with a colon. It is not otherwise guaranteed to be a valid pseudo header field, though.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// IsPseudo报告该头字段是否是http2伪头字段，即名字是否以冒号开头。
+    /// </details>
+    pub fn IsPseudo(&self) -> bool {
+        self.Name.starts_with(':')
+    }
+
+    /// Size returns the size of an entry per RFC 7541 section 4.1.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Size返回RFC 7541第4.1节定义的表项大小。
+    /// </details>
+    pub fn Size(&self) -> u32 {
+        // http://http2.github.io/http2-spec/compression.html#rfc.section.4.1
+        // "The size of the dynamic table is the sum of the size of
+        // its entries. The size of an entry is the sum of its name's
+        // length in octets (as defined in Section 5.2), its value's
+        // length in octets (see Section 5.2), plus 32.  The size of
+        // an entry is calculated using the length of the name and
+        // value without any Huffman encoding applied."
+        (len!(self.Name) + len!(self.Value) + 32) as u32
+    }
+}
+
+fn pair(name: &str, value: &str) -> HeaderField {
+    HeaderField {
+        Name: name.to_string(),
+        Value: value.to_string(),
+        Sensitive: false,
+    }
+}
+
+// staticTable is the table of RFC 7541 Appendix A. Index 1 is the first entry.
+const staticTable: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+const initialHeaderTableSize: u32 = 4096;
+
+// dynamicTable is the dynamic table of RFC 7541 section 2.3.2. The newest entry is at the front.
+#[derive(Default, Debug, Clone)]
+struct dynamicTable {
+    ents: VecDeque<HeaderField>,
+    size: u32,
+    maxSize: u32,        // current maxSize
+    allowedMaxSize: u32, // maxSize may go up to this, inclusive
+}
+
+impl dynamicTable {
+    fn new(maxSize: u32) -> dynamicTable {
+        dynamicTable {
+            ents: VecDeque::new(),
+            size: 0,
+            maxSize,
+            allowedMaxSize: maxSize,
+        }
+    }
+
+    fn setMaxSize(&mut self, v: u32) {
+        self.maxSize = v;
+        self.evict();
+    }
+
+    fn add(&mut self, f: HeaderField) {
+        self.size += f.Size();
+        self.ents.push_front(f);
+        self.evict();
+    }
+
+    // evict entries from the table until the size is less than or equal to maxSize.
+    fn evict(&mut self) {
+        while self.size > self.maxSize {
+            match self.ents.pop_back() {
+                Some(f) => self.size -= f.Size(),
+                None => break,
+            }
+        }
+    }
+
+    // at returns the entry with the given absolute index (static entries first).
+    fn at(&self, i: u64) -> Option<HeaderField> {
+        if i == 0 {
+            return None;
+        }
+        if i <= len!(staticTable) as u64 {
+            let (name, value) = staticTable[i as usize - 1];
+            return Some(pair(name, value));
+        }
+        self.ents
+            .get((i - len!(staticTable) as u64 - 1) as usize)
+            .cloned()
+    }
+
+    // search returns the index of an exact match of f, or failing that of an entry with the same name. The bool reports whether the match is exact.
+    fn search(&self, f: &HeaderField) -> (u64, bool) {
+        let mut nameIndex = 0;
+        for (i, (name, value)) in staticTable.iter().enumerate() {
+            if *name != f.Name {
+                continue;
+            }
+            if *value == f.Value && !f.Sensitive {
+                return (i as u64 + 1, true);
+            }
+            if nameIndex == 0 {
+                nameIndex = i as u64 + 1;
+            }
+        }
+        for (i, e) in self.ents.iter().enumerate() {
+            if e.Name != f.Name {
+                continue;
+            }
+            let index = (len!(staticTable) + i + 1) as u64;
+            if e.Value == f.Value && !f.Sensitive {
+                return (index, true);
+            }
+            if nameIndex == 0 {
+                nameIndex = index;
+            }
+        }
+        (nameIndex, false)
+    }
+}
+
+/// An Encoder encodes header fields into header blocks. It keeps the dynamic table state shared with the peer's decoder, so one Encoder must be used for all header blocks of a connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Encoder把头字段编码为头部块。Encoder保存与对端解码器共享的动态表状态，一个连接上的所有头部块必须使用同一个Encoder。
+/// </details>
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    dynTab: dynamicTable,
+    // minSize is the minimum table size set by SetMaxDynamicTableSize after the previous Header Table Size Update.
+    minSize: u32,
+    // maxSizeLimit is the maximum table size this encoder supports. This will protect the encoder from too large size.
+    maxSizeLimit: u32,
+    // tableSizeUpdate indicates whether "Header Table Size Update" is required.
+    tableSizeUpdate: bool,
+}
+
+/// NewEncoder returns a new Encoder with a 4096 byte dynamic table.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewEncoder返回一个动态表大小为4096字节的Encoder。
+/// </details>
+pub fn NewEncoder() -> Encoder {
+    Encoder {
+        dynTab: dynamicTable::new(initialHeaderTableSize),
+        minSize: u32::MAX,
+        maxSizeLimit: initialHeaderTableSize,
+        tableSizeUpdate: false,
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        NewEncoder()
+    }
+}
+
+impl Encoder {
+    /// WriteField encodes f into a single field representation appended to buf.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// WriteField把f编码为一个字段表示并追加到buf。
+    /// </details>
+    pub fn WriteField(&mut self, buf: &mut Vec<u8>, f: HeaderField) {
+        if self.tableSizeUpdate {
+            self.tableSizeUpdate = false;
+            if self.minSize < self.dynTab.maxSize {
+                appendVarInt(buf, 5, self.minSize as u64, 0x20);
+            }
+            self.minSize = u32::MAX;
+            appendVarInt(buf, 5, self.dynTab.maxSize as u64, 0x20);
+        }
+
+        let (idx, nameValueMatch) = self.dynTab.search(&f);
+        if nameValueMatch {
+            appendVarInt(buf, 7, idx, 0x80);
+            return;
+        }
+
+        let indexing = self.shouldIndex(&f);
+        if indexing {
+            appendVarInt(buf, 6, idx, 0x40);
+        } else if f.Sensitive {
+            appendVarInt(buf, 4, idx, 0x10);
+        } else {
+            appendVarInt(buf, 4, idx, 0);
+        }
+        if idx == 0 {
+            appendHpackString(buf, &f.Name);
+        }
+        appendHpackString(buf, &f.Value);
+        if indexing {
+            self.dynTab.add(f);
+        }
+    }
+
+    /// SetMaxDynamicTableSize changes the dynamic header table size to v. The actual size is bounded by the value passed to SetMaxDynamicTableSizeLimit.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetMaxDynamicTableSize把动态表大小修改为v，实际大小不超过SetMaxDynamicTableSizeLimit设置的上限。
+    /// </details>
+    pub fn SetMaxDynamicTableSize(&mut self, mut v: u32) {
+        if v > self.maxSizeLimit {
+            v = self.maxSizeLimit;
+        }
+        if v < self.minSize {
+            self.minSize = v;
+        }
+        self.tableSizeUpdate = true;
+        self.dynTab.setMaxSize(v);
+    }
+
+    /// MaxDynamicTableSize returns the current dynamic header table size.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// MaxDynamicTableSize返回当前的动态表大小。
+    /// </details>
+    pub fn MaxDynamicTableSize(&self) -> u32 {
+        self.dynTab.maxSize
+    }
+
+    /// SetMaxDynamicTableSizeLimit changes the maximum value that can be specified in SetMaxDynamicTableSize to v. By default, it is set to 4096, which is the same size of the default dynamic header table size described in HPACK specification. If the current maximum dynamic header table size is strictly greater than v, "Header Table Size Update" will be done in the next WriteField call and the maximum dynamic header table size is truncated to v.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetMaxDynamicTableSizeLimit把SetMaxDynamicTableSize可以设置的最大值修改为v，默认为HPACK规范中的默认动态表大小4096。如果当前动态表大小大于v，下一次WriteField时会发出"Header Table Size Update"并把动态表大小截断为v。
+    /// </details>
+    pub fn SetMaxDynamicTableSizeLimit(&mut self, v: u32) {
+        self.maxSizeLimit = v;
+        if self.dynTab.maxSize > v {
+            self.tableSizeUpdate = true;
+            self.dynTab.setMaxSize(v);
+        }
+    }
+
+    // shouldIndex reports whether f should be indexed.
+    fn shouldIndex(&self, f: &HeaderField) -> bool {
+        !f.Sensitive && f.Size() <= self.dynTab.maxSize
+    }
+}
+
+// appendVarInt appends i, as encoded in variable integer form using n bit prefix, to dst. The first byte carries the representation bits given in first. See RFC 7541 section 5.1.
+fn appendVarInt(dst: &mut Vec<u8>, n: u32, mut i: u64, first: u8) {
+    let k = (1u64 << n) - 1;
+    if i < k {
+        dst.push(first | i as u8);
+        return;
+    }
+    dst.push(first | k as u8);
+    i -= k;
+    while i >= 128 {
+        dst.push(0x80 | (i & 0x7f) as u8);
+        i >>= 7;
+    }
+    dst.push(i as u8);
+}
+
+// appendHpackString appends s, as encoded in "String Literal" representation, to dst. s will be encoded in Huffman codes only when it produces strictly shorter byte string.
+fn appendHpackString(dst: &mut Vec<u8>, s: &str) {
+    let huffmanLength = HuffmanEncodeLength(s);
+    if huffmanLength < len!(s) as u64 {
+        appendVarInt(dst, 7, huffmanLength, 0x80);
+        AppendHuffmanString(dst, s);
+    } else {
+        appendVarInt(dst, 7, len!(s) as u64, 0);
+        dst.extend_from_slice(s.as_bytes());
+    }
+}
+
+/// A Decoder is the decoding context for incremental processing of header blocks.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Decoder是增量处理头部块的解码上下文。
+/// </details>
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    dynTab: dynamicTable,
+    maxStrLen: usize, // 0 means unlimited
+}
+
+/// NewDecoder returns a new decoder with the provided maximum dynamic table size.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewDecoder返回一个动态表最大为maxDynamicTableSize的解码器。
+/// </details>
+pub fn NewDecoder(maxDynamicTableSize: u32) -> Decoder {
+    Decoder {
+        dynTab: dynamicTable::new(maxDynamicTableSize),
+        maxStrLen: 0,
+    }
+}
+
+fn decodingError(msg: &str) -> HTTPConnectError {
+    HTTPConnectError::ConnectionFailure(format!("decoding error: {}", msg))
+}
+
+impl Decoder {
+    /// SetMaxStringLength sets the maximum size of a HeaderField name or value string. If a string exceeds this length, DecodeFull will return an error. Zero means unlimited.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetMaxStringLength设置头字段名字或值的最大长度，超出时DecodeFull返回错误。零表示不限制。
+    /// </details>
+    pub fn SetMaxStringLength(&mut self, n: usize) {
+        self.maxStrLen = n;
+    }
+
+    /// SetAllowedMaxDynamicTableSize sets the upper bound that the encoded stream (via dynamic table size updates) may set the maximum size to.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetAllowedMaxDynamicTableSize设置对端通过动态表大小更新所能设置的上限。
+    /// </details>
+    pub fn SetAllowedMaxDynamicTableSize(&mut self, v: u32) {
+        self.dynTab.allowedMaxSize = v;
+    }
+
+    /// DecodeFull decodes an entire block.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// DecodeFull解码一个完整的头部块。
+    /// </details>
+    pub fn DecodeFull(&mut self, mut p: &[u8]) -> HttpResult<Vec<HeaderField>> {
+        let mut hf = Vec::new();
+        let mut sawField = false;
+        while !p.is_empty() {
+            let b = p[0];
+            if b & 0x80 != 0 {
+                // Indexed representation. High bit set?
+                // http://http2.github.io/http2-spec/compression.html#rfc.section.6.1
+                let (idx, rest) = readVarInt(7, p)?;
+                p = rest;
+                match self.dynTab.at(idx) {
+                    Some(f) => hf.push(f),
+                    None => {
+                        return Err(decodingError(&format!(
+                            "invalid indexed representation index {}",
+                            idx
+                        )))
+                    }
+                }
+                sawField = true;
+            } else if b & 0xc0 == 0x40 {
+                // 6.2.1 Literal Header Field with Incremental Indexing
+                p = self.parseFieldLiteral(6, p, true, false, &mut hf)?;
+                sawField = true;
+            } else if b & 0xf0 == 0 {
+                // 6.2.2 Literal Header Field without Indexing
+                p = self.parseFieldLiteral(4, p, false, false, &mut hf)?;
+                sawField = true;
+            } else if b & 0xf0 == 0x10 {
+                // 6.2.3 Literal Header Field never Indexed
+                p = self.parseFieldLiteral(4, p, false, true, &mut hf)?;
+                sawField = true;
+            } else {
+                // 6.3 Dynamic Table Size Update, b & 0xe0 == 0x20
+                // RFC 7541 section 4.2: This dynamic table size update MUST occur at the beginning of the first header block following the change to the dynamic table size.
+                if sawField {
+                    return Err(decodingError(
+                        "dynamic table size update MUST occur at the beginning of a header block",
+                    ));
+                }
+                let (size, rest) = readVarInt(5, p)?;
+                p = rest;
+                if size > self.dynTab.allowedMaxSize as u64 {
+                    return Err(decodingError("dynamic table size update too large"));
+                }
+                self.dynTab.setMaxSize(size as u32);
+            }
+        }
+        Ok(hf)
+    }
+
+    fn parseFieldLiteral<'a>(
+        &mut self,
+        n: u32,
+        p: &'a [u8],
+        indexing: bool,
+        sensitive: bool,
+        hf: &mut Vec<HeaderField>,
+    ) -> HttpResult<&'a [u8]> {
+        let (nameIdx, mut p) = readVarInt(n, p)?;
+        let name = if nameIdx > 0 {
+            match self.dynTab.at(nameIdx) {
+                Some(f) => f.Name,
+                None => {
+                    return Err(decodingError(&format!(
+                        "invalid indexed representation index {}",
+                        nameIdx
+                    )))
+                }
+            }
+        } else {
+            let (name, rest) = self.readString(p)?;
+            p = rest;
+            name
+        };
+        let (value, p) = self.readString(p)?;
+        let f = HeaderField {
+            Name: name,
+            Value: value,
+            Sensitive: sensitive,
+        };
+        if indexing {
+            self.dynTab.add(f.clone());
+        }
+        hf.push(f);
+        Ok(p)
+    }
+
+    // readString decodes an hpack string from p. See RFC 7541 section 5.2.
+    fn readString<'a>(&self, p: &'a [u8]) -> HttpResult<(String, &'a [u8])> {
+        if p.is_empty() {
+            return Err(errNeedMore());
+        }
+        let isHuff = p[0] & 0x80 != 0;
+        let (strLen, p) = readVarInt(7, p)?;
+        if self.maxStrLen != 0 && strLen > self.maxStrLen as u64 {
+            return Err("hpack: string too long".into());
+        }
+        if (len!(p) as u64) < strLen {
+            return Err(errNeedMore());
+        }
+        let (raw, rest) = p.split_at(strLen as usize);
+        let s = if isHuff {
+            HuffmanDecodeToString(raw)?
+        } else {
+            String::from_utf8_lossy(raw).into_owned()
+        };
+        Ok((s, rest))
+    }
+}
+
+fn errNeedMore() -> HTTPConnectError {
+    "need more data".into()
+}
+
+// readVarInt reads an unsigned variable length integer off the beginning of p. n is the parameter as described in RFC 7541 section 5.1. The remaining bytes of p are returned after the value.
+fn readVarInt(n: u32, p: &[u8]) -> HttpResult<(u64, &[u8])> {
+    if p.is_empty() {
+        return Err(errNeedMore());
+    }
+    let mut i = (p[0] & ((1u16 << n) - 1) as u8) as u64;
+    if i < (1u64 << n) - 1 {
+        return Ok((i, &p[1..]));
+    }
+    let mut m = 0;
+    for (j, &b) in p[1..].iter().enumerate() {
+        i += ((b & 127) as u64) << m;
+        if b & 128 == 0 {
+            return Ok((i, &p[j + 2..]));
+        }
+        m += 7;
+        if m >= 63 {
+            return Err(decodingError("varint integer overflow"));
+        }
+    }
+    Err(errNeedMore())
+}
+
+// huffmanCodeLen holds the length in bits of the Huffman code of each byte, from RFC 7541 Appendix B. The code is canonical, so the codes themselves follow from the lengths.
+const huffmanCodeLen: [u8; 256] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28, //
+    28, 28, 28, 28, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 28, //
+    6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6, //
+    5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10, //
+    13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, //
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6, //
+    15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6, 6, 5, //
+    6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28, //
+    20, 22, 20, 20, 22, 22, 22, 23, 22, 23, 23, 23, 23, 23, 24, 23, //
+    24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24, //
+    22, 21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23, //
+    21, 21, 22, 21, 23, 22, 23, 23, 20, 22, 22, 22, 23, 22, 22, 23, //
+    26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25, //
+    19, 21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27, //
+    20, 24, 20, 21, 22, 21, 21, 23, 22, 22, 25, 25, 24, 24, 26, 23, //
+    26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26, //
+];
+
+// eosLen is the length of the EOS symbol, which never appears in encoded data.
+const eosLen: u8 = 30;
+
+// huffmanTable holds the canonical Huffman code derived from huffmanCodeLen.
+struct huffmanTable {
+    codes: [u32; 256],
+    // symbols sorted by code length and then by value, as the canonical code assigns them.
+    sorted: Vec<u16>,
+    // first code and index into sorted of each code length.
+    firstCode: [u32; 31],
+    firstIndex: [usize; 31],
+    count: [usize; 31],
+}
+
+fn huffman() -> &'static huffmanTable {
+    static table: OnceLock<huffmanTable> = OnceLock::new();
+    table.get_or_init(|| {
+        let codeLen = |s: u16| -> u8 {
+            if s == 256 {
+                eosLen
+            } else {
+                huffmanCodeLen[s as usize]
+            }
+        };
+        let mut sorted: Vec<u16> = (0..=256).collect();
+        sorted.sort_by_key(|&s| (codeLen(s), s));
+        let mut t = huffmanTable {
+            codes: [0; 256],
+            sorted,
+            firstCode: [0; 31],
+            firstIndex: [0; 31],
+            count: [0; 31],
+        };
+        let mut code = 0u32;
+        let mut prevLen = codeLen(t.sorted[0]);
+        for (i, &s) in t.sorted.iter().enumerate() {
+            let l = codeLen(s);
+            code <<= l - prevLen;
+            prevLen = l;
+            if t.count[l as usize] == 0 {
+                t.firstCode[l as usize] = code;
+                t.firstIndex[l as usize] = i;
+            }
+            t.count[l as usize] += 1;
+            if s < 256 {
+                t.codes[s as usize] = code;
+            }
+            code += 1;
+        }
+        t
+    })
+}
+
+/// HuffmanEncodeLength returns the number of bytes required to encode s in Huffman codes. The result is round up to byte boundary.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HuffmanEncodeLength返回s用Huffman编码后需要的字节数，结果向上取整到字节边界。
+/// </details>
+pub fn HuffmanEncodeLength(s: &str) -> u64 {
+    let n: u64 = s.bytes().map(|b| huffmanCodeLen[b as usize] as u64).sum();
+    n.div_ceil(8)
+}
+
+/// AppendHuffmanString appends s, as encoded in Huffman codes, to dst.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// AppendHuffmanString把s的Huffman编码追加到dst。
+/// </details>
+pub fn AppendHuffmanString(dst: &mut Vec<u8>, s: &str) {
+    let t = huffman();
+    let mut x: u64 = 0; // buffered bits, right aligned
+    let mut n: u32 = 0; // number of buffered bits
+    for b in s.bytes() {
+        let l = huffmanCodeLen[b as usize] as u32;
+        x = (x << l) | t.codes[b as usize] as u64;
+        n += l;
+        while n >= 8 {
+            n -= 8;
+            dst.push((x >> n) as u8);
+        }
+    }
+    // Add padding bits if necessary, the most significant bits of EOS.
+    if n > 0 {
+        dst.push(((x << (8 - n)) as u8) | (0xff >> n));
+    }
+}
+
+/// HuffmanDecodeToString decodes the Huffman encoded v into a string.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HuffmanDecodeToString把Huffman编码的v解码为字符串。
+/// </details>
+pub fn HuffmanDecodeToString(v: &[u8]) -> HttpResult<String> {
+    let errInvalidHuffman = || HTTPConnectError::from("hpack: invalid Huffman-encoded data");
+    let t = huffman();
+    let mut out = Vec::with_capacity(len!(v) * 8 / 5);
+    let mut code = 0u32;
+    let mut l = 0usize;
+    let mut ones = true; // whether the pending bits are all ones
+    for &b in v {
+        for shift in (0..8).rev() {
+            let bit = ((b >> shift) & 1) as u32;
+            code = (code << 1) | bit;
+            l += 1;
+            ones = ones && bit == 1;
+            if l > eosLen as usize {
+                return Err(errInvalidHuffman());
+            }
+            if t.count[l] > 0 && code >= t.firstCode[l] && code - t.firstCode[l] < t.count[l] as u32
+            {
+                let sym = t.sorted[t.firstIndex[l] + (code - t.firstCode[l]) as usize];
+                if sym == 256 {
+                    return Err(errInvalidHuffman());
+                }
+                out.push(sym as u8);
+                code = 0;
+                l = 0;
+                ones = true;
+            }
+        }
+    }
+    // The remaining bits must be a prefix of EOS shorter than a byte.
+    if l > 7 || !ones {
+        return Err(errInvalidHuffman());
+    }
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
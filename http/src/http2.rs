@@ -0,0 +1,1346 @@
+//! HTTP/2 client connections, used by Transport when the server selects "h2" through ALPN or when unencrypted HTTP/2 is configured with prior knowledge. See RFC 9113.
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bytes::{Buf, BytesMut};
+use gostd_builtin::*;
+use gostd_time as time;
+
+use crate::body::{lock, Body};
+use crate::client::{decompressBody, errBodyReadTimeout, stdDuration, Transport};
+use crate::error::{HTTPConnectError, HttpResult};
+use crate::header::Header;
+use crate::hpack;
+use crate::httptrace;
+use crate::request::{readTrailerKeys, Request};
+use crate::response::Response;
+use crate::status::StatusText;
+
+// ClientPreface is the string that must be sent by new connections from clients.
+pub(crate) const ClientPreface: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const frameHeaderLen: usize = 9;
+
+// initialWindowSize is the flow control window of new streams and of the connection until SETTINGS say otherwise.
+const initialWindowSize: i64 = 65535;
+
+// initialMaxFrameSize is the largest frame payload either side may send until told otherwise.
+pub(crate) const initialMaxFrameSize: u32 = 16384;
+
+// transportDefaultConnFlow is how many connection-level flow control tokens we give the server at start-up, past the default 64k.
+const transportDefaultConnFlow: u32 = 1 << 30;
+
+// transportDefaultStreamFlow is how many stream-level flow control tokens we announce to the peer, and how many bytes we buffer per stream.
+const transportDefaultStreamFlow: u32 = 4 << 20;
+
+// initialMaxConcurrentStreams is a connections maxConcurrentStreams until it's updated by the server's SETTINGS frame.
+const initialMaxConcurrentStreams: u32 = 100;
+
+// defaultMaxConcurrentStreams is a connections default maxConcurrentStreams if the server doesn't include a value in its SETTINGS frame.
+const defaultMaxConcurrentStreams: u32 = 1000;
+
+const defaultUserAgent: &str = "rust-http-client/2.0";
+
+// errClientConnUnusable is returned for requests that were never processed by the server, so they can be retried on another connection.
+pub(crate) const errClientConnUnusable: &str = "http2: client conn not usable";
+
+// errStreamClosed is returned when a stream that was already sent to the server is gone. The server may have processed it, so it is not retried.
+const errStreamClosed: &str = "http2: stream closed";
+
+// isConnUnusable reports whether err means the request was not processed by the server and may be sent again on another connection.
+pub(crate) fn isConnUnusable(err: &HTTPConnectError) -> bool {
+    matches!(err, HTTPConnectError::ConnectionFailure(msg) if msg == errClientConnUnusable)
+}
+
+pub(crate) type FrameType = u8;
+pub(crate) const FrameData: FrameType = 0x0;
+pub(crate) const FrameHeaders: FrameType = 0x1;
+pub(crate) const FramePriority: FrameType = 0x2;
+pub(crate) const FrameRSTStream: FrameType = 0x3;
+pub(crate) const FrameSettings: FrameType = 0x4;
+pub(crate) const FramePushPromise: FrameType = 0x5;
+pub(crate) const FramePing: FrameType = 0x6;
+pub(crate) const FrameGoAway: FrameType = 0x7;
+pub(crate) const FrameWindowUpdate: FrameType = 0x8;
+pub(crate) const FrameContinuation: FrameType = 0x9;
+
+// Frame-specific FrameHeader flag bits.
+pub(crate) type Flags = u8;
+pub(crate) const FlagDataEndStream: Flags = 0x1;
+pub(crate) const FlagDataPadded: Flags = 0x8;
+pub(crate) const FlagHeadersEndStream: Flags = 0x1;
+pub(crate) const FlagHeadersEndHeaders: Flags = 0x4;
+pub(crate) const FlagHeadersPadded: Flags = 0x8;
+pub(crate) const FlagHeadersPriority: Flags = 0x20;
+pub(crate) const FlagSettingsAck: Flags = 0x1;
+pub(crate) const FlagPingAck: Flags = 0x1;
+pub(crate) const FlagContinuationEndHeaders: Flags = 0x4;
+
+// A SettingID is an HTTP/2 setting as defined in RFC 9113 section 6.5.2.
+pub(crate) type SettingID = u16;
+pub(crate) const SettingHeaderTableSize: SettingID = 0x1;
+pub(crate) const SettingEnablePush: SettingID = 0x2;
+pub(crate) const SettingMaxConcurrentStreams: SettingID = 0x3;
+pub(crate) const SettingInitialWindowSize: SettingID = 0x4;
+pub(crate) const SettingMaxFrameSize: SettingID = 0x5;
+pub(crate) const SettingMaxHeaderListSize: SettingID = 0x6;
+
+// An ErrCode is an unsigned 32-bit error code as defined in the HTTP/2 spec.
+pub(crate) type ErrCode = u32;
+pub(crate) const ErrCodeNo: ErrCode = 0x0;
+pub(crate) const ErrCodeProtocol: ErrCode = 0x1;
+pub(crate) const ErrCodeInternal: ErrCode = 0x2;
+pub(crate) const ErrCodeFlowControl: ErrCode = 0x3;
+pub(crate) const ErrCodeSettingsTimeout: ErrCode = 0x4;
+pub(crate) const ErrCodeStreamClosed: ErrCode = 0x5;
+pub(crate) const ErrCodeFrameSize: ErrCode = 0x6;
+pub(crate) const ErrCodeRefusedStream: ErrCode = 0x7;
+pub(crate) const ErrCodeCancel: ErrCode = 0x8;
+pub(crate) const ErrCodeCompression: ErrCode = 0x9;
+pub(crate) const ErrCodeConnect: ErrCode = 0xa;
+pub(crate) const ErrCodeEnhanceYourCalm: ErrCode = 0xb;
+pub(crate) const ErrCodeInadequateSecurity: ErrCode = 0xc;
+pub(crate) const ErrCodeHTTP11Required: ErrCode = 0xd;
+
+fn errCodeName(e: ErrCode) -> String {
+    match e {
+        ErrCodeNo => "NO_ERROR".to_string(),
+        ErrCodeProtocol => "PROTOCOL_ERROR".to_string(),
+        ErrCodeInternal => "INTERNAL_ERROR".to_string(),
+        ErrCodeFlowControl => "FLOW_CONTROL_ERROR".to_string(),
+        ErrCodeSettingsTimeout => "SETTINGS_TIMEOUT".to_string(),
+        ErrCodeStreamClosed => "STREAM_CLOSED".to_string(),
+        ErrCodeFrameSize => "FRAME_SIZE_ERROR".to_string(),
+        ErrCodeRefusedStream => "REFUSED_STREAM".to_string(),
+        ErrCodeCancel => "CANCEL".to_string(),
+        ErrCodeCompression => "COMPRESSION_ERROR".to_string(),
+        ErrCodeConnect => "CONNECT_ERROR".to_string(),
+        ErrCodeEnhanceYourCalm => "ENHANCE_YOUR_CALM".to_string(),
+        ErrCodeInadequateSecurity => "INADEQUATE_SECURITY".to_string(),
+        ErrCodeHTTP11Required => "HTTP_1_1_REQUIRED".to_string(),
+        _ => format!("unknown error code 0x{:x}", e),
+    }
+}
+
+// connError is an error that terminates the whole connection with a GOAWAY carrying Code.
+struct connError {
+    Code: ErrCode,
+    Reason: String,
+}
+
+fn connErr(code: ErrCode, reason: &str) -> connError {
+    connError {
+        Code: code,
+        Reason: reason.to_string(),
+    }
+}
+
+// A Frame is a single HTTP/2 frame: its header fields and its raw payload.
+pub(crate) struct Frame {
+    pub(crate) Type: FrameType,
+    pub(crate) Flags: Flags,
+    pub(crate) StreamID: u32,
+    pub(crate) Payload: Vec<u8>,
+}
+
+impl Frame {
+    fn has(&self, f: Flags) -> bool {
+        self.Flags & f == f
+    }
+
+    // data returns the payload of a DATA or HEADERS frame without its padding and, for HEADERS, its priority fields.
+    fn data(&self) -> Result<&[u8], connError> {
+        let mut p = self.Payload.as_slice();
+        let mut padLen = 0;
+        if self.has(FlagDataPadded) {
+            if p.is_empty() {
+                return Err(connErr(ErrCodeProtocol, "pad length missing"));
+            }
+            padLen = p[0] as usize;
+            p = &p[1..];
+        }
+        if self.Type == FrameHeaders && self.has(FlagHeadersPriority) {
+            if len!(p) < 5 {
+                return Err(connErr(ErrCodeFrameSize, "HEADERS priority too short"));
+            }
+            p = &p[5..];
+        }
+        if padLen > len!(p) {
+            // "If the length of the padding is the length of the frame payload or greater, the recipient MUST treat this as a connection error."
+            return Err(connErr(
+                ErrCodeProtocol,
+                "pad size larger than data payload",
+            ));
+        }
+        Ok(&p[..len!(p) - padLen])
+    }
+}
+
+// ReadFrame reads a single frame from r. Payloads larger than maxSize are a FRAME_SIZE_ERROR.
+pub(crate) fn ReadFrame(r: &mut impl Read, maxSize: u32) -> HttpResult<Frame> {
+    let mut hdr = [0u8; frameHeaderLen];
+    r.read_exact(&mut hdr)?;
+    let length = u32::from_be_bytes([0, hdr[0], hdr[1], hdr[2]]);
+    if length > maxSize {
+        return Err("http2: frame too large".into());
+    }
+    let mut payload = vec![0u8; length as usize];
+    r.read_exact(&mut payload)?;
+    Ok(Frame {
+        Type: hdr[3],
+        Flags: hdr[4],
+        StreamID: u32::from_be_bytes([hdr[5], hdr[6], hdr[7], hdr[8]]) & ((1 << 31) - 1),
+        Payload: payload,
+    })
+}
+
+// appendFrame appends a frame with the given header fields and payload to buf.
+pub(crate) fn appendFrame(
+    buf: &mut Vec<u8>,
+    typ: FrameType,
+    flags: Flags,
+    streamID: u32,
+    payload: &[u8],
+) {
+    let length = len!(payload) as u32;
+    buf.extend_from_slice(&length.to_be_bytes()[1..]);
+    buf.push(typ);
+    buf.push(flags);
+    buf.extend_from_slice(&streamID.to_be_bytes());
+    buf.extend_from_slice(payload);
+}
+
+pub(crate) fn appendSettings(buf: &mut Vec<u8>, settings: &[(SettingID, u32)]) {
+    let mut payload = Vec::with_capacity(6 * len!(settings));
+    for (id, val) in settings {
+        payload.extend_from_slice(&id.to_be_bytes());
+        payload.extend_from_slice(&val.to_be_bytes());
+    }
+    appendFrame(buf, FrameSettings, 0, 0, &payload);
+}
+
+pub(crate) fn appendWindowUpdate(buf: &mut Vec<u8>, streamID: u32, incr: u32) {
+    appendFrame(buf, FrameWindowUpdate, 0, streamID, &incr.to_be_bytes());
+}
+
+pub(crate) fn appendRSTStream(buf: &mut Vec<u8>, streamID: u32, code: ErrCode) {
+    appendFrame(buf, FrameRSTStream, 0, streamID, &code.to_be_bytes());
+}
+
+pub(crate) fn appendGoAway(buf: &mut Vec<u8>, maxStreamID: u32, code: ErrCode) {
+    let mut payload = maxStreamID.to_be_bytes().to_vec();
+    payload.extend_from_slice(&code.to_be_bytes());
+    appendFrame(buf, FrameGoAway, 0, 0, &payload);
+}
+
+// appendHeaders appends a header block as a HEADERS frame followed by as many CONTINUATION frames as maxFrameSize requires.
+pub(crate) fn appendHeaders(
+    buf: &mut Vec<u8>,
+    streamID: u32,
+    endStream: bool,
+    block: &[u8],
+    maxFrameSize: u32,
+) {
+    let mut chunks = block.chunks(maxFrameSize as usize).peekable();
+    let mut first = true;
+    // 空的头部块也要发送一个HEADERS帧。
+    let empty: &[u8] = &[];
+    let mut next = chunks.next().or(Some(empty));
+    while let Some(chunk) = next {
+        next = chunks.next();
+        let mut flags = 0;
+        if next.is_none() {
+            flags |= FlagHeadersEndHeaders;
+        }
+        if first {
+            if endStream {
+                flags |= FlagHeadersEndStream;
+            }
+            appendFrame(buf, FrameHeaders, flags, streamID, chunk);
+        } else {
+            appendFrame(buf, FrameContinuation, flags, streamID, chunk);
+        }
+        first = false;
+    }
+}
+
+// ClientConn is the state of a single HTTP/2 client connection to an HTTP/2 server. Clones share the connection; requests on it run concurrently as separate streams.
+#[derive(Clone)]
+pub(crate) struct ClientConn {
+    cc: Arc<clientConn>,
+}
+
+struct clientConn {
+    mu: Mutex<ccState>,
+    cond: Condvar,        // signaled on any change of mu's state
+    wmu: Mutex<ccWriter>, // held while writing frames; frames are never interleaved
+    closer: Box<dyn Fn() + Send + Sync>,
+}
+
+struct ccWriter {
+    w: Box<dyn Write + Send>,
+    henc: hpack::Encoder,
+}
+
+struct ccState {
+    streams: HashMap<u32, clientStream>,
+    nextStreamID: u32,
+    maxConcurrentStreams: u32,
+    maxFrameSize: u32,      // peer's SETTINGS_MAX_FRAME_SIZE
+    initialWindowSize: i64, // peer's SETTINGS_INITIAL_WINDOW_SIZE for new streams
+    flow: i64,              // our connection-level send window
+    inflowUnacked: u32,     // bytes received on the connection not yet returned with WINDOW_UPDATE
+    seenSettings: bool,     // true if we've seen a settings frame, false otherwise
+    goAway: Option<u32>,    // last stream ID of a GOAWAY frame received from the server
+    closed: bool,
+    err: String, // why the connection closed
+    idleSince: Instant,
+}
+
+// clientStream is the state for a single HTTP/2 stream.
+struct clientStream {
+    flow: i64,     // send window
+    gotHead: bool, // the final response header has been received
+    head: Option<(int, Header, bool)>,
+    body: BytesMut,              // received but unread response body
+    bodyDone: bool,              // END_STREAM received
+    unacked: u32, // stream bytes read by the caller not yet returned with WINDOW_UPDATE
+    err: Option<(String, bool)>, // stream error and whether the request may be retried
+    trailer: Arc<Mutex<Header>>, // trailer fields received after the body, shared with the response Body
+}
+
+// NewClientConn starts an HTTP/2 connection over the halves of an established connection: it writes the client preface and starts the goroutine-like read loop. onClose runs once the connection is gone.
+pub(crate) fn NewClientConn(
+    r: Box<dyn Read + Send>,
+    mut w: Box<dyn Write + Send>,
+    closer: Box<dyn Fn() + Send + Sync>,
+    maxHeaderListSize: u32,
+    onClose: Box<dyn FnOnce(&ClientConn) + Send>,
+) -> HttpResult<ClientConn> {
+    let mut buf = ClientPreface.to_vec();
+    appendSettings(
+        &mut buf,
+        &[
+            (SettingEnablePush, 0),
+            (SettingInitialWindowSize, transportDefaultStreamFlow),
+            (SettingMaxHeaderListSize, maxHeaderListSize),
+        ],
+    );
+    appendWindowUpdate(&mut buf, 0, transportDefaultConnFlow);
+    w.write_all(&buf)?;
+    w.flush()?;
+    let cc = ClientConn {
+        cc: Arc::new(clientConn {
+            mu: Mutex::new(ccState {
+                streams: HashMap::new(),
+                nextStreamID: 1,
+                maxConcurrentStreams: initialMaxConcurrentStreams,
+                maxFrameSize: initialMaxFrameSize,
+                initialWindowSize,
+                flow: initialWindowSize,
+                inflowUnacked: 0,
+                seenSettings: false,
+                goAway: None,
+                closed: false,
+                err: "".to_string(),
+                idleSince: Instant::now(),
+            }),
+            cond: Condvar::new(),
+            wmu: Mutex::new(ccWriter {
+                w,
+                henc: hpack::NewEncoder(),
+            }),
+            closer,
+        }),
+    };
+    let rl = clientConnReadLoop {
+        cc: cc.clone(),
+        hdec: hpack::NewDecoder(4096),
+        continuation: None,
+        maxHeaderListSize,
+    };
+    thread::spawn(move || rl.run(r, onClose));
+    Ok(cc)
+}
+
+impl ClientConn {
+    fn lock(&self) -> MutexGuard<'_, ccState> {
+        self.cc.mu.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // wait blocks until the connection state changes or deadline passes.
+    fn wait<'a>(
+        &self,
+        st: MutexGuard<'a, ccState>,
+        deadline: Option<Instant>,
+    ) -> MutexGuard<'a, ccState> {
+        match deadline {
+            None => self.cc.cond.wait(st).unwrap_or_else(|e| e.into_inner()),
+            Some(d) => {
+                let timeout = d.saturating_duration_since(Instant::now());
+                self.cc
+                    .cond
+                    .wait_timeout(st, timeout)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0
+            }
+        }
+    }
+
+    // writeFrames writes buf, holding the write lock. A failed write breaks the connection.
+    fn writeFrames(&self, buf: &[u8]) -> io::Result<()> {
+        let mut cw = self.cc.wmu.lock().unwrap_or_else(|e| e.into_inner());
+        let r = cw.w.write_all(buf).and_then(|_| cw.w.flush());
+        if r.is_err() {
+            (self.cc.closer)();
+        }
+        r
+    }
+
+    // CanTakeNewRequest reports whether the connection can take a new request, meaning it has not been closed or received or sent a GOAWAY, and has a free stream slot.
+    pub(crate) fn CanTakeNewRequest(&self) -> bool {
+        let st = self.lock();
+        !st.closed
+            && st.goAway.is_none()
+            && (len!(st.streams) as u32) < st.maxConcurrentStreams
+            && st.nextStreamID < (1 << 31) - 1
+    }
+
+    pub(crate) fn isClosed(&self) -> bool {
+        self.lock().closed
+    }
+
+    // idleFor returns how long the connection has had no active streams, or None if it is in use.
+    pub(crate) fn idleFor(&self) -> Option<Duration> {
+        let st = self.lock();
+        if st.streams.is_empty() {
+            return Some(st.idleSince.elapsed());
+        }
+        None
+    }
+
+    // gotConnInfo describes the connection to ClientTrace.GotConn when a request is about to use it.
+    pub(crate) fn gotConnInfo(&self) -> httptrace::GotConnInfo {
+        let st = self.lock();
+        let reused = st.nextStreamID > 1;
+        let idle = reused && st.streams.is_empty();
+        let idleTime = if idle {
+            st.idleSince.elapsed().as_nanos() as int64
+        } else {
+            0
+        };
+        httptrace::GotConnInfo {
+            Reused: reused,
+            WasIdle: idle,
+            IdleTime: time::Duration::new(idleTime),
+        }
+    }
+
+    // Close closes the client connection immediately. In-flight requests are interrupted.
+    pub(crate) fn Close(&self) {
+        (self.cc.closer)();
+    }
+
+    pub(crate) fn ptrEq(&self, other: &ClientConn) -> bool {
+        Arc::ptr_eq(&self.cc, &other.cc)
+    }
+
+    // RoundTrip sends req as a new stream and waits for the response header. The response body streams the rest of the stream.
+    pub(crate) fn RoundTrip(&self, t: &Transport, req: &Request) -> HttpResult<Response> {
+        let deadline = req.deadline;
+        let trace = req.trace();
+        let requestedGzip = !t.DisableCompression
+            && req.Header.Get("Accept-Encoding").is_empty()
+            && req.Header.Get("Range").is_empty()
+            && req.Method != "HEAD";
+        let contentLength = req.outgoingLength();
+        let hasBody = req.Body.is_some() && contentLength != 0;
+        let fields = encodeHeaders(req, requestedGzip, contentLength)?;
+
+        let trailer = Arc::new(Mutex::new(Header::default()));
+        let (id, maxFrameSize) = {
+            let mut st = self.lock();
+            loop {
+                if st.closed || st.goAway.is_some() || st.nextStreamID >= (1 << 31) - 1 {
+                    return Err(errClientConnUnusable.into());
+                }
+                if (len!(st.streams) as u32) < st.maxConcurrentStreams {
+                    break;
+                }
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    return Err(HTTPConnectError::TimeoutError(
+                        "net/http: request canceled".to_string(),
+                    ));
+                }
+                st = self.wait(st, deadline);
+            }
+            let id = st.nextStreamID;
+            st.nextStreamID += 2;
+            let flow = st.initialWindowSize;
+            st.streams.insert(
+                id,
+                clientStream {
+                    flow,
+                    gotHead: false,
+                    head: None,
+                    body: BytesMut::new(),
+                    bodyDone: false,
+                    unacked: 0,
+                    err: None,
+                    trailer: trailer.clone(),
+                },
+            );
+            (id, st.maxFrameSize)
+        };
+
+        {
+            // 头部块必须按编码顺序发送，编码和写入都在写锁内完成。
+            let mut cw = self.cc.wmu.lock().unwrap_or_else(|e| e.into_inner());
+            let mut block = Vec::new();
+            for (name, value) in fields {
+                cw.henc.WriteField(
+                    &mut block,
+                    hpack::HeaderField {
+                        Name: name,
+                        Value: value,
+                        Sensitive: false,
+                    },
+                );
+            }
+            let mut buf = Vec::with_capacity(len!(block) + frameHeaderLen);
+            appendHeaders(&mut buf, id, !hasBody, &block, maxFrameSize);
+            if let Err(err) = cw.w.write_all(&buf).and_then(|_| cw.w.flush()) {
+                drop(cw);
+                (self.cc.closer)();
+                self.forgetStream(id);
+                let err = err.into();
+                trace.wroteRequest(Some(&err));
+                return Err(err);
+            }
+        }
+        trace.wroteHeaders();
+
+        if hasBody {
+            if let Err(err) = self.writeRequestBody(id, req, contentLength, deadline) {
+                self.resetStream(id, ErrCodeCancel);
+                trace.wroteRequest(Some(&err));
+                return Err(err);
+            }
+        }
+        trace.wroteRequest(None);
+
+        let headerTimeout = stdDuration(&t.ResponseHeaderTimeout);
+        let headerDeadline = match headerTimeout.map(|d| Instant::now() + d) {
+            Some(h) if deadline.is_none_or(|d| h < d) => Some(h),
+            _ => deadline,
+        };
+        let (status, header, endStream) = {
+            let mut st = self.lock();
+            loop {
+                let s = match st.streams.get_mut(&id) {
+                    Some(s) => s,
+                    None => return Err(errStreamClosed.into()),
+                };
+                if let Some(head) = s.head.take() {
+                    drop(st);
+                    trace.gotFirstResponseByte();
+                    break head;
+                }
+                if let Some((err, retry)) = s.err.clone() {
+                    st.streams.remove(&id);
+                    self.streamRemoved(&mut st);
+                    if retry {
+                        return Err(errClientConnUnusable.into());
+                    }
+                    return Err(HTTPConnectError::ConnectionFailure(err));
+                }
+                if headerDeadline.is_some_and(|d| Instant::now() >= d) {
+                    drop(st);
+                    self.resetStream(id, ErrCodeCancel);
+                    if deadline == headerDeadline {
+                        return Err(HTTPConnectError::TimeoutError(
+                            "net/http: request canceled".to_string(),
+                        ));
+                    }
+                    return Err(HTTPConnectError::TimeoutError(
+                        "net/http: timeout awaiting response headers".to_string(),
+                    ));
+                }
+                st = self.wait(st, headerDeadline);
+            }
+        };
+
+        let mut contentLength = -1;
+        if let [cl] = header.Values("Content-Length") {
+            if let Ok(n) = cl.parse::<int64>() {
+                contentLength = n;
+            }
+        }
+        match readTrailerKeys(&header) {
+            // trailer与Body共享，收到trailer的头部块之后填入值。
+            Ok(keys) => {
+                let mut t = lock(&trailer);
+                for k in keys.0.into_keys() {
+                    t.0.entry(k).or_default();
+                }
+            }
+            Err(err) => {
+                self.resetStream(id, ErrCodeProtocol);
+                return Err(err);
+            }
+        }
+        let mut resp = Response {
+            Status: format!("{} {}", status, StatusText(status))
+                .trim()
+                .to_string(),
+            StatusCode: status,
+            Proto: "HTTP/2.0".to_string(),
+            ProtoMajor: 2,
+            ProtoMinor: 0,
+            Header: header,
+            ContentLength: contentLength,
+            Trailer: trailer.clone(),
+            Request: req.clone(),
+            ..Default::default()
+        };
+        if endStream || req.Method == "HEAD" {
+            if endStream && resp.ContentLength < 0 {
+                resp.ContentLength = 0;
+            }
+            if !endStream {
+                self.resetStream(id, ErrCodeCancel);
+            } else {
+                self.forgetStream(id);
+            }
+        } else {
+            resp.Body = Body::New(transportResponseBody {
+                cc: self.clone(),
+                id,
+                deadline,
+                done: false,
+            })
+            .withTrailer(Some(trailer));
+        }
+        if requestedGzip {
+            decompressBody(&mut resp);
+        }
+        Ok(resp)
+    }
+
+    // writeRequestBody sends the request body as DATA frames, within the flow control windows granted by the server.
+    fn writeRequestBody(
+        &self,
+        id: u32,
+        req: &Request,
+        contentLength: int64,
+        deadline: Option<Instant>,
+    ) -> HttpResult<()> {
+        let mut body = req.Body.clone().unwrap_or_default();
+        let mut buf = vec![0u8; initialMaxFrameSize as usize];
+        let mut sent: int64 = 0;
+        loop {
+            let mut n = body.read(&mut buf)?;
+            if contentLength > 0 {
+                n = n.min((contentLength - sent) as usize);
+            }
+            sent += n as int64;
+            let endStream = n == 0 || (contentLength > 0 && sent == contentLength);
+            if n == 0 && contentLength > 0 && sent != contentLength {
+                return Err(HTTPConnectError::ConnectionFailure(format!(
+                    "http: ContentLength={} with Body length {}",
+                    contentLength, sent
+                )));
+            }
+            let mut data = &buf[..n];
+            loop {
+                let take = if data.is_empty() {
+                    0
+                } else {
+                    match self.awaitFlow(id, len!(data), deadline)? {
+                        Some(take) => take,
+                        // 服务端已经结束了这个流，不再发送剩余的body。
+                        None => return Ok(()),
+                    }
+                };
+                let mut frame = Vec::with_capacity(take + frameHeaderLen);
+                let last = take == len!(data);
+                let flags = if last && endStream {
+                    FlagDataEndStream
+                } else {
+                    0
+                };
+                appendFrame(&mut frame, FrameData, flags, id, &data[..take]);
+                self.writeFrames(&frame)?;
+                data = &data[take..];
+                if data.is_empty() {
+                    break;
+                }
+            }
+            if endStream {
+                return Ok(());
+            }
+        }
+    }
+
+    // awaitFlow waits until at least one byte may be sent on the stream and takes up to n bytes of flow control. It returns None if the stream is already finished by the server.
+    fn awaitFlow(&self, id: u32, n: usize, deadline: Option<Instant>) -> HttpResult<Option<usize>> {
+        let mut st = self.lock();
+        loop {
+            if st.closed {
+                return Err(HTTPConnectError::ConnectionFailure(st.err.clone()));
+            }
+            let connFlow = st.flow;
+            let maxFrameSize = st.maxFrameSize as i64;
+            let s = match st.streams.get_mut(&id) {
+                Some(s) => s,
+                None => return Err(errStreamClosed.into()),
+            };
+            if s.err.is_some() || s.bodyDone {
+                return Ok(None);
+            }
+            let take = (n as i64).min(connFlow).min(s.flow).min(maxFrameSize);
+            if take > 0 {
+                s.flow -= take;
+                st.flow -= take;
+                return Ok(Some(take as usize));
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(HTTPConnectError::TimeoutError(
+                    "net/http: request canceled".to_string(),
+                ));
+            }
+            st = self.wait(st, deadline);
+        }
+    }
+
+    // forgetStream drops the stream from the connection without telling the server, for streams that are already closed on both ends.
+    fn forgetStream(&self, id: u32) {
+        let mut st = self.lock();
+        if st.streams.remove(&id).is_some() {
+            self.streamRemoved(&mut st);
+        }
+    }
+
+    // resetStream abandons the stream: it is dropped and the server is told with RST_STREAM, unless the stream had already ended. Unread body data is returned to the connection flow control window.
+    fn resetStream(&self, id: u32, code: ErrCode) {
+        let mut buf = Vec::new();
+        {
+            let mut st = self.lock();
+            let s = match st.streams.remove(&id) {
+                Some(s) => s,
+                None => return,
+            };
+            if !s.bodyDone && s.err.is_none() {
+                appendRSTStream(&mut buf, id, code);
+            }
+            st.inflowUnacked += len!(s.body) as u32;
+            self.connWindowUpdate(&mut st, &mut buf, true);
+            self.streamRemoved(&mut st);
+        }
+        if !buf.is_empty() {
+            let _ = self.writeFrames(&buf);
+        }
+    }
+
+    // streamError ends the stream with err and tells the server with RST_STREAM. The stream stays in the connection, so the waiting request or body reader still gets err.
+    fn streamError(&self, id: u32, code: ErrCode, err: String) {
+        let mut buf = Vec::new();
+        {
+            let mut st = self.lock();
+            let s = match st.streams.get_mut(&id) {
+                Some(s) => s,
+                None => return,
+            };
+            if !s.bodyDone && s.err.is_none() {
+                appendRSTStream(&mut buf, id, code);
+            }
+            s.err = Some((err, false));
+            self.cc.cond.notify_all();
+        }
+        if !buf.is_empty() {
+            let _ = self.writeFrames(&buf);
+        }
+    }
+
+    fn streamRemoved(&self, st: &mut ccState) {
+        if st.streams.is_empty() {
+            st.idleSince = Instant::now();
+            if st.goAway.is_some() {
+                // 收到GOAWAY后连接不会再有新的流，最后一个流结束时关闭连接。
+                (self.cc.closer)();
+            }
+        }
+        self.cc.cond.notify_all();
+    }
+
+    // connWindowUpdate appends a connection-level WINDOW_UPDATE once enough of the window has been used up, or whenever force is set and there is something to return.
+    fn connWindowUpdate(&self, st: &mut ccState, buf: &mut Vec<u8>, force: bool) {
+        if st.inflowUnacked >= transportDefaultConnFlow / 2 || (force && st.inflowUnacked > 0) {
+            appendWindowUpdate(buf, 0, st.inflowUnacked);
+            st.inflowUnacked = 0;
+        }
+    }
+}
+
+// transportResponseBody is the Body of a response read from an HTTP/2 stream.
+struct transportResponseBody {
+    cc: ClientConn,
+    id: u32,
+    deadline: Option<Instant>,
+    done: bool,
+}
+
+impl Read for transportResponseBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done {
+            return Ok(0);
+        }
+        let cc = self.cc.clone();
+        let mut st = cc.lock();
+        loop {
+            let s = match st.streams.get_mut(&self.id) {
+                Some(s) => s,
+                None => {
+                    self.done = true;
+                    return Err(io::Error::other("http2: response body closed"));
+                }
+            };
+            if !s.body.is_empty() {
+                let n = buf.len().min(s.body.len());
+                buf[..n].copy_from_slice(&s.body[..n]);
+                s.body.advance(n);
+                s.unacked += n as u32;
+                let mut frames = Vec::new();
+                let finished = s.bodyDone && s.body.is_empty();
+                if !s.bodyDone && s.unacked >= transportDefaultStreamFlow / 2 {
+                    appendWindowUpdate(&mut frames, self.id, s.unacked);
+                    s.unacked = 0;
+                }
+                st.inflowUnacked += n as u32;
+                cc.connWindowUpdate(&mut st, &mut frames, false);
+                if finished {
+                    st.streams.remove(&self.id);
+                    cc.streamRemoved(&mut st);
+                    self.done = true;
+                }
+                drop(st);
+                if !frames.is_empty() {
+                    let _ = cc.writeFrames(&frames);
+                }
+                return Ok(n);
+            }
+            if s.bodyDone {
+                st.streams.remove(&self.id);
+                cc.streamRemoved(&mut st);
+                self.done = true;
+                return Ok(0);
+            }
+            if let Some((err, _)) = s.err.clone() {
+                st.streams.remove(&self.id);
+                cc.streamRemoved(&mut st);
+                self.done = true;
+                return Err(io::Error::other(err));
+            }
+            if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                drop(st);
+                cc.resetStream(self.id, ErrCodeCancel);
+                self.done = true;
+                return Err(io::Error::new(ErrorKind::TimedOut, errBodyReadTimeout));
+            }
+            st = cc.wait(st, self.deadline);
+        }
+    }
+}
+
+impl Drop for transportResponseBody {
+    fn drop(&mut self) {
+        if !self.done {
+            self.cc.resetStream(self.id, ErrCodeCancel);
+        }
+    }
+}
+
+// encodeHeaders returns the header fields of req in the order they are sent: the pseudo header fields first, then the regular ones with lower-case names. Connection-specific fields are dropped, see RFC 9113 section 8.2.2.
+fn encodeHeaders(
+    req: &Request,
+    addGzipHeader: bool,
+    contentLength: int64,
+) -> HttpResult<Vec<(String, String)>> {
+    let mut host = req.Host.clone();
+    if host.is_empty() {
+        host = req.URL.Host.clone();
+    }
+    let mut path = req.URL.RequestURI();
+    if path.is_empty() {
+        path = "/".to_string();
+    }
+    let method = if req.Method.is_empty() {
+        "GET".to_string()
+    } else {
+        req.Method.clone()
+    };
+    let mut fields = vec![(":authority".to_string(), host)];
+    fields.push((":method".to_string(), method.clone()));
+    if method != "CONNECT" {
+        fields.push((":path".to_string(), path));
+        fields.push((":scheme".to_string(), req.URL.Scheme.clone()));
+    }
+    let mut userAgent = defaultUserAgent.to_string();
+    for (k, vv) in &req.Header.0 {
+        let name = k.to_ascii_lowercase();
+        match name.as_str() {
+            "host" | "content-length" | "connection" | "proxy-connection" | "transfer-encoding"
+            | "upgrade" | "keep-alive" => continue,
+            "user-agent" => {
+                if let Some(v) = vv.first() {
+                    userAgent = v.clone();
+                }
+                continue;
+            }
+            _ => (),
+        }
+        for v in vv {
+            if v.contains(['\r', '\n']) {
+                return Err(HTTPConnectError::ConnectionFailure(format!(
+                    "http2: invalid header field value for {:?}",
+                    k
+                )));
+            }
+            // "te"只允许"trailers"。
+            if name == "te" && !v.eq_ignore_ascii_case("trailers") {
+                continue;
+            }
+            fields.push((name.clone(), v.clone()));
+        }
+    }
+    let sendContentLength = match contentLength {
+        n if n > 0 => true,
+        0 => matches!(method.as_str(), "POST" | "PUT" | "PATCH"),
+        _ => false,
+    };
+    if sendContentLength {
+        fields.push(("content-length".to_string(), contentLength.to_string()));
+    }
+    if addGzipHeader {
+        fields.push(("accept-encoding".to_string(), "gzip".to_string()));
+    }
+    if !userAgent.is_empty() {
+        fields.push(("user-agent".to_string(), userAgent));
+    }
+    Ok(fields)
+}
+
+// clientConnReadLoop reads and dispatches the frames the server sends on a connection.
+struct clientConnReadLoop {
+    cc: ClientConn,
+    hdec: hpack::Decoder,
+    // the header block being assembled from HEADERS and CONTINUATION frames: stream ID, fragments, END_STREAM
+    continuation: Option<(u32, Vec<u8>, bool)>,
+    maxHeaderListSize: u32, // the SETTINGS_MAX_HEADER_LIST_SIZE we advertised, also the limit of a header block
+}
+
+impl clientConnReadLoop {
+    fn run(mut self, mut r: Box<dyn Read + Send>, onClose: Box<dyn FnOnce(&ClientConn) + Send>) {
+        let reason = loop {
+            let f = match ReadFrame(&mut r, initialMaxFrameSize) {
+                Ok(f) => f,
+                Err(err) => {
+                    break format!("http2: client connection lost: {}", err);
+                }
+            };
+            if let Err(err) = self.processFrame(f) {
+                let mut buf = Vec::new();
+                appendGoAway(&mut buf, 0, err.Code);
+                let _ = self.cc.writeFrames(&buf);
+                break format!(
+                    "http2: connection error: {}: {}",
+                    errCodeName(err.Code),
+                    err.Reason
+                );
+            }
+        };
+        {
+            let mut st = self.cc.lock();
+            st.closed = true;
+            st.err = reason.clone();
+            for s in st.streams.values_mut() {
+                if !s.bodyDone && s.err.is_none() {
+                    s.err = Some((reason.clone(), false));
+                }
+            }
+            self.cc.cc.cond.notify_all();
+        }
+        (self.cc.cc.closer)();
+        onClose(&self.cc);
+    }
+
+    fn processFrame(&mut self, f: Frame) -> Result<(), connError> {
+        if let Some((id, _, _)) = &self.continuation {
+            if f.Type != FrameContinuation || f.StreamID != *id {
+                return Err(connErr(ErrCodeProtocol, "expected CONTINUATION frame"));
+            }
+        }
+        match f.Type {
+            FrameData => self.processData(f),
+            FrameHeaders => {
+                if f.StreamID == 0 {
+                    return Err(connErr(ErrCodeProtocol, "HEADERS frame with stream ID 0"));
+                }
+                let block = f.data()?.to_vec();
+                self.checkHeaderBlockSize(len!(block))?;
+                let endStream = f.has(FlagHeadersEndStream);
+                if f.has(FlagHeadersEndHeaders) {
+                    self.processHeaderBlock(f.StreamID, &block, endStream)
+                } else {
+                    self.continuation = Some((f.StreamID, block, endStream));
+                    Ok(())
+                }
+            }
+            FrameContinuation => {
+                let (id, mut block, endStream) = match self.continuation.take() {
+                    Some(c) => c,
+                    None => return Err(connErr(ErrCodeProtocol, "unexpected CONTINUATION frame")),
+                };
+                self.checkHeaderBlockSize(len!(block) + len!(f.Payload))?;
+                block.extend_from_slice(&f.Payload);
+                if f.has(FlagContinuationEndHeaders) {
+                    self.processHeaderBlock(id, &block, endStream)
+                } else {
+                    self.continuation = Some((id, block, endStream));
+                    Ok(())
+                }
+            }
+            FrameRSTStream => self.processResetStream(f),
+            FrameSettings => self.processSettings(f),
+            FramePushPromise => Err(connErr(ErrCodeProtocol, "received PUSH_PROMISE")),
+            FramePing => self.processPing(f),
+            FrameGoAway => self.processGoAway(f),
+            FrameWindowUpdate => self.processWindowUpdate(f),
+            // PRIORITY帧和未知类型的帧直接忽略。
+            _ => Ok(()),
+        }
+    }
+
+    // checkHeaderBlockSize fails the connection once a header block assembled from HEADERS and CONTINUATION frames grows past the advertised max header list size, so a peer cannot make us buffer an endless block.
+    fn checkHeaderBlockSize(&self, n: usize) -> Result<(), connError> {
+        if n > self.maxHeaderListSize as usize {
+            return Err(connErr(
+                ErrCodeEnhanceYourCalm,
+                "header block exceeds max header list size",
+            ));
+        }
+        Ok(())
+    }
+
+    fn processData(&mut self, f: Frame) -> Result<(), connError> {
+        if f.StreamID == 0 {
+            return Err(connErr(ErrCodeProtocol, "DATA frame with stream ID 0"));
+        }
+        let data = f.data()?;
+        let flowLen = len!(f.Payload) as u32;
+        let padding = flowLen - len!(data) as u32;
+        let mut frames = Vec::new();
+        {
+            let cc = &self.cc;
+            let mut st = cc.lock();
+            if f.StreamID >= st.nextStreamID {
+                return Err(connErr(ErrCodeProtocol, "DATA frame for unopened stream"));
+            }
+            match st.streams.get_mut(&f.StreamID) {
+                Some(s) => {
+                    if s.err.is_some() || s.bodyDone {
+                        // 流已经结束，数据直接丢弃，只归还连接的流量窗口。
+                        st.inflowUnacked += flowLen;
+                    } else if !s.gotHead {
+                        return Err(connErr(
+                            ErrCodeProtocol,
+                            "DATA frame before response header",
+                        ));
+                    } else if len!(s.body) + len!(data) > transportDefaultStreamFlow as usize {
+                        return Err(connErr(
+                            ErrCodeFlowControl,
+                            "stream flow control window exceeded",
+                        ));
+                    } else {
+                        s.body.extend_from_slice(data);
+                        // 填充字节不会被读取，立即计入已消费。
+                        s.unacked += padding;
+                        if f.has(FlagDataEndStream) {
+                            s.bodyDone = true;
+                        }
+                        st.inflowUnacked += padding;
+                    }
+                }
+                None => {
+                    // 已经关闭的流，数据丢弃，归还连接的流量窗口。
+                    st.inflowUnacked += flowLen;
+                }
+            }
+            cc.connWindowUpdate(&mut st, &mut frames, false);
+            cc.cc.cond.notify_all();
+        }
+        if !frames.is_empty() {
+            let _ = self.cc.writeFrames(&frames);
+        }
+        Ok(())
+    }
+
+    fn processHeaderBlock(
+        &mut self,
+        id: u32,
+        block: &[u8],
+        endStream: bool,
+    ) -> Result<(), connError> {
+        // 即使流已经关闭也必须解码，保持HPACK动态表与服务端一致。
+        let fields = self
+            .hdec
+            .DecodeFull(block)
+            .map_err(|err| connErr(ErrCodeCompression, &err.to_string()))?;
+        let mut st = self.cc.lock();
+        if id >= st.nextStreamID {
+            return Err(connErr(
+                ErrCodeProtocol,
+                "HEADERS frame for unopened stream",
+            ));
+        }
+        let s = match st.streams.get_mut(&id) {
+            Some(s) => s,
+            None => return Ok(()),
+        };
+        if s.gotHead {
+            // 响应头之后的头部块是trailer，只能出现在流的末尾。
+            if !endStream {
+                drop(st);
+                self.cc.streamError(
+                    id,
+                    ErrCodeProtocol,
+                    "http2: malformed response from server: trailer without END_STREAM".to_string(),
+                );
+                return Ok(());
+            }
+            if fields.iter().any(|f| f.IsPseudo()) {
+                drop(st);
+                self.cc.streamError(
+                    id,
+                    ErrCodeProtocol,
+                    "http2: malformed response from server: pseudo header in trailer".to_string(),
+                );
+                return Ok(());
+            }
+            // 先填好trailer再标记body结束，body读到EOF之后Body.Trailer()就能取得它们。
+            let mut trailer = s.trailer.lock().unwrap_or_else(|e| e.into_inner());
+            for f in &fields {
+                trailer.Add(&canonicalHeaderKey(&f.Name), &f.Value);
+            }
+            drop(trailer);
+            s.bodyDone = true;
+            self.cc.cc.cond.notify_all();
+            return Ok(());
+        }
+        let mut status = None;
+        let mut header = Header::default();
+        for f in fields {
+            if f.IsPseudo() {
+                if f.Name == ":status" {
+                    status = f.Value.parse::<int>().ok();
+                }
+                continue;
+            }
+            header.Add(&canonicalHeaderKey(&f.Name), &f.Value);
+        }
+        let status = match status {
+            Some(status) => status,
+            None => {
+                drop(st);
+                self.cc.streamError(
+                    id,
+                    ErrCodeProtocol,
+                    "http2: malformed response from server: missing status pseudo header"
+                        .to_string(),
+                );
+                return Ok(());
+            }
+        };
+        if (100..200).contains(&status) {
+            // 1xx信息响应，继续等待最终响应。
+            return Ok(());
+        }
+        s.gotHead = true;
+        s.head = Some((status, header, endStream));
+        if endStream {
+            s.bodyDone = true;
+        }
+        self.cc.cc.cond.notify_all();
+        Ok(())
+    }
+
+    fn processResetStream(&mut self, f: Frame) -> Result<(), connError> {
+        if len!(f.Payload) != 4 {
+            return Err(connErr(ErrCodeFrameSize, "RST_STREAM frame size"));
+        }
+        let code = u32::from_be_bytes([f.Payload[0], f.Payload[1], f.Payload[2], f.Payload[3]]);
+        let mut st = self.cc.lock();
+        if let Some(s) = st.streams.get_mut(&f.StreamID) {
+            if !s.bodyDone || code != ErrCodeNo {
+                // 被拒绝的流服务端没有处理，可以在其他连接上重试。
+                let retry = code == ErrCodeRefusedStream && !s.gotHead;
+                s.err = Some((
+                    format!(
+                        "stream error: stream ID {}; {}",
+                        f.StreamID,
+                        errCodeName(code)
+                    ),
+                    retry,
+                ));
+            }
+            self.cc.cc.cond.notify_all();
+        }
+        Ok(())
+    }
+
+    fn processSettings(&mut self, f: Frame) -> Result<(), connError> {
+        if f.StreamID != 0 {
+            return Err(connErr(ErrCodeProtocol, "SETTINGS frame with stream ID"));
+        }
+        if f.has(FlagSettingsAck) {
+            return Ok(());
+        }
+        if !f.Payload.len().is_multiple_of(6) {
+            return Err(connErr(ErrCodeFrameSize, "SETTINGS frame size"));
+        }
+        let mut sawMaxConcurrentStreams = false;
+        {
+            let mut st = self.cc.lock();
+            for s in f.Payload.chunks(6) {
+                let id = u16::from_be_bytes([s[0], s[1]]);
+                let val = u32::from_be_bytes([s[2], s[3], s[4], s[5]]);
+                match id {
+                    SettingHeaderTableSize => {
+                        let mut cw = self.cc.cc.wmu.lock().unwrap_or_else(|e| e.into_inner());
+                        cw.henc.SetMaxDynamicTableSizeLimit(val);
+                    }
+                    SettingMaxConcurrentStreams => {
+                        st.maxConcurrentStreams = val;
+                        sawMaxConcurrentStreams = true;
+                    }
+                    SettingInitialWindowSize => {
+                        if val > (1 << 31) - 1 {
+                            return Err(connErr(ErrCodeFlowControl, "invalid initial window size"));
+                        }
+                        // Adjust flow control of currently-open frames by the difference of the old initial window size and this one.
+                        let delta = val as i64 - st.initialWindowSize;
+                        for s in st.streams.values_mut() {
+                            s.flow += delta;
+                        }
+                        st.initialWindowSize = val as i64;
+                    }
+                    SettingMaxFrameSize => {
+                        if !(initialMaxFrameSize..=(1 << 24) - 1).contains(&val) {
+                            return Err(connErr(ErrCodeProtocol, "invalid max frame size"));
+                        }
+                        st.maxFrameSize = val;
+                    }
+                    _ => (),
+                }
+            }
+            if !st.seenSettings {
+                if !sawMaxConcurrentStreams {
+                    st.maxConcurrentStreams = defaultMaxConcurrentStreams;
+                }
+                st.seenSettings = true;
+            }
+            self.cc.cc.cond.notify_all();
+        }
+        let mut buf = Vec::new();
+        appendFrame(&mut buf, FrameSettings, FlagSettingsAck, 0, &[]);
+        let _ = self.cc.writeFrames(&buf);
+        Ok(())
+    }
+
+    fn processPing(&mut self, f: Frame) -> Result<(), connError> {
+        if len!(f.Payload) != 8 {
+            return Err(connErr(ErrCodeFrameSize, "PING frame size"));
+        }
+        if f.StreamID != 0 {
+            return Err(connErr(ErrCodeProtocol, "PING frame with stream ID"));
+        }
+        if !f.has(FlagPingAck) {
+            let mut buf = Vec::new();
+            appendFrame(&mut buf, FramePing, FlagPingAck, 0, &f.Payload);
+            let _ = self.cc.writeFrames(&buf);
+        }
+        Ok(())
+    }
+
+    fn processGoAway(&mut self, f: Frame) -> Result<(), connError> {
+        if len!(f.Payload) < 8 {
+            return Err(connErr(ErrCodeFrameSize, "GOAWAY frame size"));
+        }
+        let lastStreamID =
+            u32::from_be_bytes([f.Payload[0], f.Payload[1], f.Payload[2], f.Payload[3]])
+                & ((1 << 31) - 1);
+        let code = u32::from_be_bytes([f.Payload[4], f.Payload[5], f.Payload[6], f.Payload[7]]);
+        let mut st = self.cc.lock();
+        st.goAway = Some(lastStreamID);
+        for (id, s) in st.streams.iter_mut() {
+            // 编号大于lastStreamID的流没有被服务端处理，可以重试。
+            if *id > lastStreamID && s.err.is_none() {
+                s.err = Some((
+                    format!(
+                        "http2: server sent GOAWAY and closed the connection; LastStreamID={}, ErrCode={}",
+                        lastStreamID,
+                        errCodeName(code)
+                    ),
+                    true,
+                ));
+            }
+        }
+        if st.streams.is_empty() {
+            (self.cc.cc.closer)();
+        }
+        self.cc.cc.cond.notify_all();
+        Ok(())
+    }
+
+    fn processWindowUpdate(&mut self, f: Frame) -> Result<(), connError> {
+        if len!(f.Payload) != 4 {
+            return Err(connErr(ErrCodeFrameSize, "WINDOW_UPDATE frame size"));
+        }
+        let incr = u32::from_be_bytes([f.Payload[0], f.Payload[1], f.Payload[2], f.Payload[3]])
+            & ((1 << 31) - 1);
+        let mut st = self.cc.lock();
+        if f.StreamID == 0 {
+            if incr == 0 {
+                return Err(connErr(ErrCodeProtocol, "WINDOW_UPDATE increment of 0"));
+            }
+            st.flow += incr as i64;
+            if st.flow > (1 << 31) - 1 {
+                return Err(connErr(ErrCodeFlowControl, "connection window overflow"));
+            }
+        } else if let Some(s) = st.streams.get_mut(&f.StreamID) {
+            s.flow += incr as i64;
+        }
+        self.cc.cc.cond.notify_all();
+        Ok(())
+    }
+}
+
+// canonicalHeaderKey returns the canonical format of a lower-case HTTP/2 header name, "content-type" => "Content-Type".
+fn canonicalHeaderKey(s: &str) -> String {
+    let mut upper = true;
+    s.chars()
+        .map(|c| {
+            let c = if upper { c.to_ascii_uppercase() } else { c };
+            upper = c == '-';
+            c
+        })
+        .collect()
+}
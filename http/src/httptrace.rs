@@ -0,0 +1,305 @@
+//! Package httptrace provides mechanisms to trace the events within HTTP client requests.
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! httptrace包提供跟踪HTTP客户端请求内部事件的机制。
+//! </details>
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use gostd_time as time;
+
+use crate::error::HTTPConnectError;
+use crate::request::Request;
+use crate::tls;
+
+/// ClientTrace is a set of hooks to run at various stages of an outgoing HTTP request. Any particular hook may be None. Hooks may be called concurrently from different threads and some hooks may be called after a request has completed or failed.
+///
+/// ClientTrace currently traces a single HTTP request & response during a single round trip and has no hooks that span a series of redirected requests.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ClientTrace是一组在HTTP请求的各个阶段执行的钩子函数，每个钩子都可以为None。钩子可能在不同线程中并发调用，有些钩子可能在请求完成或失败之后才被调用。
+///
+/// ClientTrace只跟踪一次往返中的单个HTTP请求和响应，没有跨越一系列重定向请求的钩子。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use http::httptrace;
+/// use std::sync::Arc;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut req = http::Request::New(http::Method::Get, "http://example.com", None)?;
+///     let trace = httptrace::ClientTrace {
+///         GotConn: Some(Arc::new(|info: &httptrace::GotConnInfo| {
+///             println!("Got Conn: {:?}", info)
+///         })),
+///         DNSDone: Some(Arc::new(|info: &httptrace::DNSDoneInfo| {
+///             println!("DNS Info: {:?}", info)
+///         })),
+///         ..Default::default()
+///     };
+///     httptrace::WithClientTrace(&mut req, trace);
+///     http::Client::New().Do(&mut req)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Default, Clone)]
+pub struct ClientTrace {
+    /// GetConn is called before a connection is created or retrieved from an idle pool. The hostPort is the "host:port" of the target or proxy. GetConn is called even if there's already an idle cached connection available.
+    pub GetConn: Hook<dyn Fn(&str) + Send + Sync>,
+    /// GotConn is called after a successful connection is obtained. There is no hook for failure to obtain a connection; instead, use the error from Transport.RoundTrip.
+    pub GotConn: Hook<dyn Fn(&GotConnInfo) + Send + Sync>,
+    /// GotFirstResponseByte is called when the first byte of the response headers is available.
+    pub GotFirstResponseByte: Hook<dyn Fn() + Send + Sync>,
+    /// DNSStart is called when a DNS lookup begins. It is not called when the address is an IP literal.
+    pub DNSStart: Hook<dyn Fn(&DNSStartInfo) + Send + Sync>,
+    /// DNSDone is called when a DNS lookup ends.
+    pub DNSDone: Hook<dyn Fn(&DNSDoneInfo) + Send + Sync>,
+    /// ConnectStart is called when a new connection's dial begins. If more than one address is tried, ConnectStart may be called multiple times.
+    pub ConnectStart: Hook<ConnectStartFunc>,
+    /// ConnectDone is called when a new connection's dial completes. The provided err indicates whether the connection completed successfully. If more than one address is tried, ConnectDone may be called multiple times.
+    pub ConnectDone: Hook<ConnectDoneFunc>,
+    /// TLSHandshakeStart is called when the TLS handshake is started. When connecting to an HTTPS site via an HTTPS proxy, the handshake happens twice: once with the proxy and once with the target.
+    pub TLSHandshakeStart: Hook<dyn Fn() + Send + Sync>,
+    /// TLSHandshakeDone is called after the TLS handshake with either the successful handshake's connection state, or a non-None error on handshake failure.
+    pub TLSHandshakeDone: Hook<TLSHandshakeDoneFunc>,
+    /// WroteHeaders is called after the Transport has written all request headers.
+    pub WroteHeaders: Hook<dyn Fn() + Send + Sync>,
+    /// WroteRequest is called with the result of writing the request and any body. It may be called multiple times in the case of retried requests.
+    pub WroteRequest: Hook<dyn Fn(&WroteRequestInfo) + Send + Sync>,
+}
+
+/// Hook is an optional ClientTrace callback. A None hook is not called.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Hook是ClientTrace中的可选回调函数，为None时不调用。
+/// </details>
+pub type Hook<F> = Option<Arc<F>>;
+
+/// ConnectStartFunc is the signature of ClientTrace.ConnectStart, called with the network and address being dialed.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ConnectStartFunc是ClientTrace.ConnectStart的函数签名，参数是拨号的网络类型和地址。
+/// </details>
+pub type ConnectStartFunc = dyn Fn(&str, &str) + Send + Sync;
+
+/// ConnectDoneFunc is the signature of ClientTrace.ConnectDone, called with the network, the address and the dial error, if any.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ConnectDoneFunc是ClientTrace.ConnectDone的函数签名，参数是网络类型、地址和拨号错误。
+/// </details>
+pub type ConnectDoneFunc = dyn Fn(&str, &str, Option<&HTTPConnectError>) + Send + Sync;
+
+/// TLSHandshakeDoneFunc is the signature of ClientTrace.TLSHandshakeDone, called with the connection state and the handshake error, if any.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// TLSHandshakeDoneFunc是ClientTrace.TLSHandshakeDone的函数签名，参数是连接状态和握手错误。
+/// </details>
+pub type TLSHandshakeDoneFunc =
+    dyn Fn(&tls::ConnectionState, Option<&HTTPConnectError>) + Send + Sync;
+
+/// GotConnInfo is the argument to ClientTrace.GotConn and contains information about the obtained connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// GotConnInfo是ClientTrace.GotConn的参数，包含获得的连接的信息。
+/// </details>
+#[derive(Debug, Clone)]
+pub struct GotConnInfo {
+    /// Reused is whether this connection has been previously used for another HTTP request.
+    pub Reused: bool,
+    /// WasIdle is whether this connection was obtained from an idle pool.
+    pub WasIdle: bool,
+    /// IdleTime reports how long the connection was previously idle, if WasIdle is true.
+    pub IdleTime: time::Duration,
+}
+
+/// DNSStartInfo contains information about a DNS request.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DNSStartInfo包含一次DNS请求的信息。
+/// </details>
+#[derive(Debug, Clone, Default)]
+pub struct DNSStartInfo {
+    pub Host: String,
+}
+
+/// DNSDoneInfo contains information about the results of a DNS lookup.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DNSDoneInfo包含一次DNS查询的结果。
+/// </details>
+#[derive(Debug, Default)]
+pub struct DNSDoneInfo<'a> {
+    /// Addrs are the IPv4 and/or IPv6 addresses found in the DNS lookup.
+    pub Addrs: Vec<IpAddr>,
+    /// Err is any error that occurred during the DNS lookup.
+    pub Err: Option<&'a HTTPConnectError>,
+}
+
+/// WroteRequestInfo contains information provided to the WroteRequest hook.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// WroteRequestInfo是传给WroteRequest钩子的信息。
+/// </details>
+#[derive(Debug, Default)]
+pub struct WroteRequestInfo<'a> {
+    /// Err is any error encountered while writing the Request.
+    pub Err: Option<&'a HTTPConnectError>,
+}
+
+/// WithClientTrace attaches trace to req, so that the Transport calls its hooks while sending req. If req already has a trace, the provided trace hooks are called first, followed by the previous trace's hooks.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// WithClientTrace把trace附加到req上，Transport发送req时会调用其中的钩子。如果req已经有trace，先调用新trace的钩子，再调用原有trace的钩子。
+/// </details>
+pub fn WithClientTrace(req: &mut Request, mut trace: ClientTrace) {
+    if let Some(old) = req.trace.take() {
+        trace.compose(&old);
+    }
+    req.trace = Some(Arc::new(trace));
+}
+
+/// ContextClientTrace returns the ClientTrace attached to req, if any.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ContextClientTrace返回附加在req上的ClientTrace，没有时返回None。
+/// </details>
+pub fn ContextClientTrace(req: &Request) -> Option<&ClientTrace> {
+    req.trace.as_deref()
+}
+
+// noTrace is used for requests without a trace, so call sites don't need to check for one.
+pub(crate) static noTrace: ClientTrace = ClientTrace {
+    GetConn: None,
+    GotConn: None,
+    GotFirstResponseByte: None,
+    DNSStart: None,
+    DNSDone: None,
+    ConnectStart: None,
+    ConnectDone: None,
+    TLSHandshakeStart: None,
+    TLSHandshakeDone: None,
+    WroteHeaders: None,
+    WroteRequest: None,
+};
+
+// compose sets each hook of t to call t's hook first and then old's.
+macro_rules! compose {
+    ($t:ident, $old:ident, $($hook:ident($($arg:ident: $ty:ty),*)),* $(,)?) => {
+        $(
+            match (&$t.$hook, &$old.$hook) {
+                (Some(h), Some(o)) => {
+                    let (h, o) = (h.clone(), o.clone());
+                    $t.$hook = Some(Arc::new(move |$($arg: $ty),*| {
+                        h($($arg),*);
+                        o($($arg),*);
+                    }));
+                }
+                (None, Some(o)) => $t.$hook = Some(o.clone()),
+                _ => (),
+            }
+        )*
+    };
+}
+
+impl ClientTrace {
+    // compose modifies t such that it respects the previously-registered hooks in old.
+    fn compose(&mut self, old: &ClientTrace) {
+        let t = self;
+        compose!(t, old,
+            GetConn(hostPort: &str),
+            GotConn(info: &GotConnInfo),
+            GotFirstResponseByte(),
+            DNSStart(info: &DNSStartInfo),
+            DNSDone(info: &DNSDoneInfo),
+            ConnectStart(network: &str, addr: &str),
+            ConnectDone(network: &str, addr: &str, err: Option<&HTTPConnectError>),
+            TLSHandshakeStart(),
+            TLSHandshakeDone(state: &tls::ConnectionState, err: Option<&HTTPConnectError>),
+            WroteHeaders(),
+            WroteRequest(info: &WroteRequestInfo),
+        );
+    }
+
+    pub(crate) fn getConn(&self, hostPort: &str) {
+        if let Some(f) = &self.GetConn {
+            f(hostPort)
+        }
+    }
+
+    pub(crate) fn gotConn(&self, info: GotConnInfo) {
+        if let Some(f) = &self.GotConn {
+            f(&info)
+        }
+    }
+
+    pub(crate) fn gotFirstResponseByte(&self) {
+        if let Some(f) = &self.GotFirstResponseByte {
+            f()
+        }
+    }
+
+    pub(crate) fn dnsStart(&self, host: &str) {
+        if let Some(f) = &self.DNSStart {
+            f(&DNSStartInfo {
+                Host: host.to_string(),
+            })
+        }
+    }
+
+    pub(crate) fn dnsDone(&self, addrs: Vec<IpAddr>, err: Option<&HTTPConnectError>) {
+        if let Some(f) = &self.DNSDone {
+            f(&DNSDoneInfo {
+                Addrs: addrs,
+                Err: err,
+            })
+        }
+    }
+
+    pub(crate) fn connectStart(&self, network: &str, addr: &str) {
+        if let Some(f) = &self.ConnectStart {
+            f(network, addr)
+        }
+    }
+
+    pub(crate) fn connectDone(&self, network: &str, addr: &str, err: Option<&HTTPConnectError>) {
+        if let Some(f) = &self.ConnectDone {
+            f(network, addr, err)
+        }
+    }
+
+    pub(crate) fn tlsHandshakeStart(&self) {
+        if let Some(f) = &self.TLSHandshakeStart {
+            f()
+        }
+    }
+
+    pub(crate) fn tlsHandshakeDone(
+        &self,
+        state: &tls::ConnectionState,
+        err: Option<&HTTPConnectError>,
+    ) {
+        if let Some(f) = &self.TLSHandshakeDone {
+            f(state, err)
+        }
+    }
+
+    pub(crate) fn wroteHeaders(&self) {
+        if let Some(f) = &self.WroteHeaders {
+            f()
+        }
+    }
+
+    pub(crate) fn wroteRequest(&self, err: Option<&HTTPConnectError>) {
+        if let Some(f) = &self.WroteRequest {
+            f(&WroteRequestInfo { Err: err })
+        }
+    }
+}
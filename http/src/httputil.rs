@@ -0,0 +1,729 @@
+//! Package httputil provides HTTP utility functions, complementing the more common ones in the crate root: a ReverseProxy handler, functions to dump requests and responses in wire format for debugging, and readers and writers of the chunked transfer coding.
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! httputil包提供HTTP工具函数，补充crate根模块中常用的功能：ReverseProxy处理器、用于调试的以线路格式导出请求和响应的函数，以及chunked传输编码的读写器。
+//! </details>
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gostd_http as http;
+//! use gostd_url as url;
+//! use http::httputil;
+//!
+//! let target = url::Parse("http://127.0.0.1:9000/api").unwrap();
+//! let proxy = httputil::NewSingleHostReverseProxy(&target);
+//! http::server::ListenAndServe(":8080", proxy).unwrap();
+//! ```
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::io::{self, BufRead, Read, Write};
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gostd_builtin::*;
+use gostd_time as time;
+use gostd_url as url;
+
+use crate::{
+    body::{lock, Body},
+    client::{stdDuration, RoundTripper, TrailerPrefix, Transport},
+    error::{HTTPConnectError, HttpResult},
+    header::{CanonicalHeaderKey, Header},
+    request::Request,
+    response::Response,
+    server::{ErrAbortHandler, ErrorLogFunc, Handler, ResponseWriter},
+    transfer::{headerValuesContainsToken, transferReader, transferState},
+};
+
+/// DirectorFunc is the type of ReverseProxy.Director.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DirectorFunc是ReverseProxy.Director的类型。
+/// </details>
+pub type DirectorFunc = Arc<dyn Fn(&mut Request) + Send + Sync>;
+
+/// RewriteFunc is the type of ReverseProxy.Rewrite.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// RewriteFunc是ReverseProxy.Rewrite的类型。
+/// </details>
+pub type RewriteFunc = Arc<dyn Fn(&mut ProxyRequest<'_>) + Send + Sync>;
+
+/// ModifyResponseFunc is the type of ReverseProxy.ModifyResponse.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ModifyResponseFunc是ReverseProxy.ModifyResponse的类型。
+/// </details>
+pub type ModifyResponseFunc = Arc<dyn Fn(&mut Response) -> HttpResult<()> + Send + Sync>;
+
+/// ErrorHandlerFunc is the type of ReverseProxy.ErrorHandler.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ErrorHandlerFunc是ReverseProxy.ErrorHandler的类型。
+/// </details>
+pub type ErrorHandlerFunc =
+    Arc<dyn Fn(&mut dyn ResponseWriter, &mut Request, &HTTPConnectError) + Send + Sync>;
+
+// Hop-by-hop headers. These are removed when sent to the backend. As of RFC 7230, hop-by-hop headers are required to appear in the Connection header field.
+const hopHeaders: [&str; 9] = [
+    "Connection",
+    "Proxy-Connection", // non-standard but still sent by libcurl and rejected by e.g. google
+    "Keep-Alive",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "Te",      // canonicalized version of "TE"
+    "Trailer", // not Trailers per URL above; https://www.rfc-editor.org/errata_search.php?eid=4522
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+/// ReverseProxy is an HTTP Handler that takes an incoming request and sends it to another server, proxying the response back to the client.
+///
+/// Hop-by-hop headers, including the fields listed in the Connection header, are removed from the request and from the response. Protocol upgrades are not supported: the Upgrade header is removed like the other hop-by-hop headers.
+///
+/// The outgoing requests are sent with a clone of Transport, so that requests are proxied concurrently; clones of a Transport share their connection pool.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ReverseProxy是一个HTTP Handler，它接收传入的请求并发送给另一个服务器，再把响应代理回客户端。
+///
+/// 逐跳header(包括Connection头中列出的字段)会从请求和响应中删除。不支持协议升级：Upgrade头和其他逐跳header一样被删除。
+///
+/// 发出的请求使用Transport的克隆发送，以便并发地代理请求；Transport的克隆共享同一个连接池。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use gostd_url as url;
+/// use http::httputil::{ProxyRequest, ReverseProxy};
+/// use std::sync::Arc;
+///
+/// let target = url::Parse("http://127.0.0.1:9000").unwrap();
+/// let proxy: ReverseProxy = ReverseProxy {
+///     Rewrite: Some(Arc::new(move |r: &mut ProxyRequest<'_>| {
+///         r.SetURL(&target);
+///         r.SetXForwarded();
+///     })),
+///     ..Default::default()
+/// };
+/// http::server::ListenAndServe(":8080", proxy).unwrap();
+/// ```
+pub struct ReverseProxy<T = Transport> {
+    /// Rewrite must be a function which modifies the request into a new request to be sent using Transport. Its response is then copied back to the original client unmodified. Rewrite must not access the provided ProxyRequest or its contents after returning.
+    ///
+    /// The Forwarded, X-Forwarded-For, X-Forwarded-Host, and X-Forwarded-Proto headers are removed from the outbound request before Rewrite is called. See also the ProxyRequest.SetXForwarded method.
+    ///
+    /// Exactly one of Rewrite or Director must be set.
+    pub Rewrite: Option<RewriteFunc>,
+    /// Director is a function which modifies the request into a new request to be sent using Transport. Exactly one of Rewrite or Director must be set.
+    ///
+    /// Hop-by-hop headers are removed from the request after Director returns, which can remove headers added by Director. Use a Rewrite function instead to ensure modifications to the request are preserved.
+    ///
+    /// The client IP from the RemoteAddr of the incoming request is appended to the X-Forwarded-For header of the outgoing request after Director returns.
+    pub Director: Option<DirectorFunc>,
+    /// Transport is the RoundTripper used to perform proxy requests. The default is a Transport with default settings.
+    pub Transport: T,
+    /// FlushInterval specifies the flush interval to flush to the client while copying the response body. If zero, no periodic flushing is done. A negative value means to flush immediately after each write to the client. The FlushInterval is ignored when ReverseProxy recognizes a response as a streaming response, or if its ContentLength is -1; for such responses, writes are flushed to the client immediately.
+    ///
+    /// The interval is checked after each write, so data stays buffered while the backend sends nothing.
+    pub FlushInterval: time::Duration,
+    /// ErrorLog specifies an optional logger for errors that occur when attempting to proxy the request. If None, logging goes to standard error.
+    pub ErrorLog: Option<ErrorLogFunc>,
+    /// ModifyResponse is an optional function that modifies the Response from the backend. It is called if the backend returns a response at all, with any HTTP status code. If ModifyResponse returns an error, ErrorHandler is called with its error value.
+    pub ModifyResponse: Option<ModifyResponseFunc>,
+    /// ErrorHandler is an optional function that handles errors reaching the backend or errors from ModifyResponse. If None, the default is to log the provided error and return a 502 Status Bad Gateway response.
+    pub ErrorHandler: Option<ErrorHandlerFunc>,
+}
+
+impl<T: Default> Default for ReverseProxy<T> {
+    fn default() -> Self {
+        ReverseProxy {
+            Rewrite: None,
+            Director: None,
+            Transport: T::default(),
+            FlushInterval: time::Duration::new(0),
+            ErrorLog: None,
+            ModifyResponse: None,
+            ErrorHandler: None,
+        }
+    }
+}
+
+/// A ProxyRequest contains a request to be rewritten by a ReverseProxy.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ProxyRequest包含一个由ReverseProxy改写的请求。
+/// </details>
+pub struct ProxyRequest<'a> {
+    /// In is the request received by the proxy. The Rewrite function must not modify In.
+    pub In: &'a Request,
+    /// Out is the request which will be sent by the proxy. The Rewrite function may modify or replace this request. Hop-by-hop headers are removed from this request before Rewrite is called.
+    pub Out: Request,
+}
+
+impl ProxyRequest<'_> {
+    /// SetURL routes the outbound request to the scheme, host, and base path provided in target. If the target's path is "/base" and the incoming request was for "/dir", the target request will be for "/base/dir".
+    ///
+    /// SetURL rewrites the outbound Host header to match the target's host. To preserve the inbound request's Host header (the default behavior of NewSingleHostReverseProxy), set Out.Host to In.Host after calling SetURL.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetURL把发出的请求路由到target提供的scheme、主机和基础路径。如果target的路径是"/base"，传入的请求是"/dir"，目标请求将是"/base/dir"。
+    ///
+    /// SetURL把发出请求的Host头改写为target的主机。要保留传入请求的Host头(NewSingleHostReverseProxy的默认行为)，在调用SetURL之后把Out.Host设置为In.Host。
+    /// </details>
+    pub fn SetURL(&mut self, target: &url::URL) {
+        rewriteRequestURL(&mut self.Out, target);
+        self.Out.Host = String::new();
+    }
+
+    /// SetXForwarded sets the X-Forwarded-For, X-Forwarded-Host, and X-Forwarded-Proto headers of the outbound request.
+    ///
+    /// - The X-Forwarded-For header is set to the client IP address.
+    /// - The X-Forwarded-Host header is set to the host name requested by the client.
+    /// - The X-Forwarded-Proto header is set to "http" or "https", depending on whether the inbound request was made on a TLS-enabled connection.
+    ///
+    /// If the outbound request contains an existing X-Forwarded-For header, SetXForwarded appends the client IP address to it. To append to the inbound request's X-Forwarded-For header (the default behavior of ReverseProxy when using a Director function), copy the header from the inbound request before calling SetXForwarded.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetXForwarded设置发出请求的X-Forwarded-For、X-Forwarded-Host和X-Forwarded-Proto头。
+    ///
+    /// - X-Forwarded-For设置为客户端IP地址。
+    /// - X-Forwarded-Host设置为客户端请求的主机名。
+    /// - X-Forwarded-Proto根据传入请求是否来自TLS连接设置为"http"或"https"。
+    ///
+    /// 如果发出的请求已经有X-Forwarded-For头，SetXForwarded把客户端IP地址追加在后面。要追加到传入请求的X-Forwarded-For头之后(使用Director时ReverseProxy的默认行为)，在调用SetXForwarded之前从传入请求复制该头。
+    /// </details>
+    pub fn SetXForwarded(&mut self) {
+        match clientIP(&self.In.RemoteAddr) {
+            Some(ip) => {
+                let prior = self.Out.Header.Values("X-Forwarded-For");
+                let v = if prior.is_empty() {
+                    ip.to_string()
+                } else {
+                    format!("{}, {}", prior.join(", "), ip)
+                };
+                self.Out.Header.Set("X-Forwarded-For", &v);
+            }
+            None => self.Out.Header.Del("X-Forwarded-For"),
+        }
+        self.Out.Header.Set("X-Forwarded-Host", &self.In.Host);
+        let proto = if self.In.TLS.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        self.Out.Header.Set("X-Forwarded-Proto", proto);
+    }
+}
+
+/// NewSingleHostReverseProxy returns a new ReverseProxy that routes URLs to the scheme, host, and base path provided in target. If the target's path is "/base" and the incoming request was for "/dir", the target request will be for /base/dir.
+///
+/// NewSingleHostReverseProxy does not rewrite the Host header. To customize the ReverseProxy behavior beyond what NewSingleHostReverseProxy provides, use ReverseProxy directly with a Rewrite function. The ProxyRequest SetURL method may be used to route the outbound request.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewSingleHostReverseProxy返回一个新的ReverseProxy，把URL路由到target提供的scheme、主机和基础路径。如果target的路径是"/base"，传入的请求是"/dir"，目标请求将是/base/dir。
+///
+/// NewSingleHostReverseProxy不会改写Host头。需要更多定制时，直接使用带有Rewrite函数的ReverseProxy，可以用ProxyRequest的SetURL方法路由发出的请求。
+/// </details>
+pub fn NewSingleHostReverseProxy(target: &url::URL) -> ReverseProxy {
+    let target = target.clone();
+    ReverseProxy {
+        Director: Some(Arc::new(move |req: &mut Request| {
+            rewriteRequestURL(req, &target)
+        })),
+        ..Default::default()
+    }
+}
+
+// rewriteRequestURL routes req to the scheme and host of target, joining the paths and the queries of both.
+fn rewriteRequestURL(req: &mut Request, target: &url::URL) {
+    req.URL.Scheme = target.Scheme.clone();
+    req.URL.Host = target.Host.clone();
+    let (path, rawPath) = joinURLPath(target, &req.URL);
+    req.URL.Path = path;
+    req.URL.RawPath = rawPath;
+    if target.RawQuery.is_empty() || req.URL.RawQuery.is_empty() {
+        req.URL.RawQuery = format!("{}{}", target.RawQuery, req.URL.RawQuery);
+    } else {
+        req.URL.RawQuery = format!("{}&{}", target.RawQuery, req.URL.RawQuery);
+    }
+}
+
+fn singleJoiningSlash(a: &str, b: &str) -> String {
+    match (a.ends_with('/'), b.starts_with('/')) {
+        (true, true) => format!("{}{}", a, &b[1..]),
+        (false, false) => format!("{}/{}", a, b),
+        _ => format!("{}{}", a, b),
+    }
+}
+
+// joinURLPath joins the paths of a and b like singleJoiningSlash, keeping the escaped form of the paths if either has one.
+fn joinURLPath(a: &url::URL, b: &url::URL) -> (String, String) {
+    if a.RawPath.is_empty() && b.RawPath.is_empty() {
+        return (singleJoiningSlash(&a.Path, &b.Path), String::new());
+    }
+    // 和singleJoiningSlash相同，但同时使用EscapedPath判断是否需要添加斜杠。
+    let (aPath, bPath) = (a.EscapedPath(), b.EscapedPath());
+    match (aPath.ends_with('/'), bPath.starts_with('/')) {
+        (true, true) => (
+            format!("{}{}", a.Path, &b.Path[1.min(b.Path.len())..]),
+            format!("{}{}", aPath, &bPath[1..]),
+        ),
+        (false, false) => (
+            format!("{}/{}", a.Path, b.Path),
+            format!("{}/{}", aPath, bPath),
+        ),
+        _ => (
+            format!("{}{}", a.Path, b.Path),
+            format!("{}{}", aPath, bPath),
+        ),
+    }
+}
+
+// clientIP returns the IP address of an "IP:port" remote address.
+fn clientIP(remoteAddr: &str) -> Option<&str> {
+    let (host, port) = remoteAddr.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return None;
+    }
+    Some(host)
+}
+
+// removeHopByHopHeaders removes the hop-by-hop headers of h, including the fields listed in its Connection header (RFC 7230, section 6.1).
+fn removeHopByHopHeaders(h: &mut Header) {
+    for f in h.Values("Connection").to_vec() {
+        for sf in f.split(',').map(str::trim).filter(|sf| !sf.is_empty()) {
+            h.Del(sf);
+        }
+    }
+    for k in hopHeaders {
+        h.Del(k);
+    }
+}
+
+fn copyHeader(dst: &mut Header, src: &Header) {
+    for (k, vv) in src {
+        for v in vv {
+            dst.Add(k, v);
+        }
+    }
+}
+
+// trailerKeys returns the trailer fields announced by res, in the Trailer field or in a Trailer header.
+fn trailerKeys(res: &Response) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    let announced = res
+        .Header
+        .Values("Trailer")
+        .iter()
+        .flat_map(|v| v.split(','));
+    let declared: Vec<String> = lock(&res.Trailer).iter().map(|(k, _)| k.clone()).collect();
+    for k in declared.iter().map(String::as_str).chain(announced) {
+        let k = CanonicalHeaderKey(k.trim());
+        if !k.is_empty() && !keys.contains(&k) {
+            keys.push(k);
+        }
+    }
+    keys.sort();
+    keys
+}
+
+impl<T> ReverseProxy<T> {
+    fn logf(&self, msg: &str) {
+        match &self.ErrorLog {
+            Some(f) => f(msg),
+            None => eprintln!("{}", msg),
+        }
+    }
+
+    fn handleError(&self, rw: &mut dyn ResponseWriter, req: &mut Request, err: &HTTPConnectError) {
+        match &self.ErrorHandler {
+            Some(f) => f(rw, req, err),
+            None => {
+                self.logf(&format!("http: proxy error: {}", err));
+                rw.WriteHeader(502);
+            }
+        }
+    }
+
+    // flushInterval returns how often the body of res is flushed to the client: None never, Some(ZERO) after each write.
+    fn flushInterval(&self, res: &Response) -> Option<Duration> {
+        let ct = res.Header.Get("Content-Type");
+        let mediaType = ct.split(';').next().unwrap_or("").trim();
+        // SSE这样的流式响应和长度未知的响应需要立即发送给客户端。
+        if mediaType.eq_ignore_ascii_case("text/event-stream") || res.ContentLength == -1 {
+            return Some(Duration::ZERO);
+        }
+        if self.FlushInterval.Nanoseconds() < 0 {
+            return Some(Duration::ZERO);
+        }
+        stdDuration(&self.FlushInterval)
+    }
+
+    // copyResponse copies the body of res to rw, flushing it as flushInterval says.
+    fn copyResponse(&self, rw: &mut dyn ResponseWriter, res: &mut Response) -> HttpResult<()> {
+        let interval = self.flushInterval(res);
+        let mut buf = vec![0u8; 32 << 10];
+        let mut lastFlush = Instant::now();
+        loop {
+            let n = match res.Body.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            rw.Write(&buf[..n])?;
+            if interval.is_some_and(|d| lastFlush.elapsed() >= d) {
+                if let Some(f) = rw.Flusher() {
+                    f.Flush()?;
+                }
+                lastFlush = Instant::now();
+            }
+        }
+    }
+}
+
+impl<T: RoundTripper + Clone + Send + Sync> Handler for ReverseProxy<T> {
+    fn ServeHttp(&self, rw: &mut dyn ResponseWriter, req: &mut Request) {
+        if self.Director.is_some() == self.Rewrite.is_some() {
+            let err = "ReverseProxy must have exactly one of Director or Rewrite set".into();
+            self.handleError(rw, req, &err);
+            return;
+        }
+        let mut outreq = req.clone();
+        if req.ContentLength == 0 {
+            outreq.Body = None;
+        }
+        // 客户端请求不能设置RequestURI。
+        outreq.RequestURI = String::new();
+        if let Some(director) = &self.Director {
+            director(&mut outreq);
+        }
+        outreq.Close = false;
+        removeHopByHopHeaders(&mut outreq.Header);
+        // "Te: trailers"表示客户端能够处理trailer，这是唯一可以转发的Te取值。
+        if headerValuesContainsToken(req.Header.Values("Te"), "trailers") {
+            outreq.Header.Set("Te", "trailers");
+        }
+        if let Some(rewrite) = &self.Rewrite {
+            for k in [
+                "Forwarded",
+                "X-Forwarded-For",
+                "X-Forwarded-Host",
+                "X-Forwarded-Proto",
+            ] {
+                outreq.Header.Del(k);
+            }
+            let mut pr = ProxyRequest {
+                In: req,
+                Out: outreq,
+            };
+            rewrite(&mut pr);
+            outreq = pr.Out;
+        } else if let Some(ip) = clientIP(&req.RemoteAddr) {
+            let prior = outreq.Header.Values("X-Forwarded-For");
+            let ip = if prior.is_empty() {
+                ip.to_string()
+            } else {
+                format!("{}, {}", prior.join(", "), ip)
+            };
+            outreq.Header.Set("X-Forwarded-For", &ip);
+        }
+
+        let mut res = match self.Transport.clone().RoundTrip(&outreq) {
+            Ok(res) => res,
+            Err(err) => {
+                self.handleError(rw, req, &err);
+                return;
+            }
+        };
+        let announced = trailerKeys(&res);
+        removeHopByHopHeaders(&mut res.Header);
+        if let Some(modify) = &self.ModifyResponse {
+            if let Err(err) = modify(&mut res) {
+                res.Body.Close();
+                self.handleError(rw, req, &err);
+                return;
+            }
+        }
+        copyHeader(rw.Header(), &res.Header);
+        if !announced.is_empty() {
+            rw.Header().Set("Trailer", &announced.join(", "));
+        }
+        rw.WriteHeader(res.StatusCode as u16);
+
+        if let Err(err) = self.copyResponse(rw, &mut res) {
+            res.Body.Close();
+            self.logf(&format!(
+                "httputil: ReverseProxy read error during body copy: {}",
+                err
+            ));
+            // 响应已经开始发送，只能中断连接让客户端知道响应不完整。
+            panic::resume_unwind(Box::new(ErrAbortHandler));
+        }
+        res.Body.Close();
+
+        // trailer的值在body读到EOF之后才有。
+        let mut trailer = res.Body.Trailer();
+        for (k, vv) in &lock(&res.Trailer).clone() {
+            if trailer.Values(k).is_empty() {
+                for v in vv {
+                    trailer.Add(k, v);
+                }
+            }
+        }
+        for (k, vv) in &trailer {
+            let key = if announced.contains(k) {
+                k.clone()
+            } else {
+                format!("{}{}", TrailerPrefix, k)
+            };
+            for v in vv {
+                rw.Header().Add(&key, v);
+            }
+        }
+    }
+}
+
+/// DumpRequest returns the given request in its HTTP/1.x wire representation. It should only be used by servers to debug client requests. The returned representation is an approximation only; some details of the initial request are lost while parsing it into a Request. In particular, the order and case of header field names are lost.
+///
+/// If body is true, DumpRequest also returns the body. To do so, it consumes req.Body and then replaces it with a new Body that yields the same bytes.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DumpRequest返回请求的HTTP/1.x线路格式表示，只应该由服务器用来调试客户端请求。返回的表示只是近似的，把原始请求解析为Request时会丢失一些细节，特别是header字段名的顺序和大小写。
+///
+/// 如果body为true，DumpRequest也会返回body。为此它会读完req.Body，然后替换为一个返回相同字节的新Body。
+/// </details>
+pub fn DumpRequest(req: &mut Request, body: bool) -> HttpResult<Vec<u8>> {
+    let data = match &mut req.Body {
+        Some(b) if body => Some(drainBody(b)?),
+        _ => None,
+    };
+    let mut b = Vec::new();
+    let reqURI = if req.RequestURI.is_empty() {
+        req.URL.RequestURI()
+    } else {
+        req.RequestURI.clone()
+    };
+    let method = if req.Method.is_empty() {
+        "GET"
+    } else {
+        req.Method.as_str()
+    };
+    write!(
+        b,
+        "{} {} HTTP/{}.{}\r\n",
+        method, reqURI, req.ProtoMajor, req.ProtoMinor
+    )?;
+    let absRequestURI = reqURI.starts_with("http://") || reqURI.starts_with("https://");
+    if !absRequestURI {
+        let host = if req.Host.is_empty() {
+            &req.URL.Host
+        } else {
+            &req.Host
+        };
+        if !host.is_empty() {
+            write!(b, "Host: {}\r\n", host)?;
+        }
+    }
+    let chunked = req
+        .TransferEncoding
+        .first()
+        .is_some_and(|te| te == "chunked");
+    if !req.TransferEncoding.is_empty() {
+        write!(
+            b,
+            "Transfer-Encoding: {}\r\n",
+            req.TransferEncoding.join(",")
+        )?;
+    }
+    let exclude = ["Host", "Transfer-Encoding", "Trailer"]
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+    req.Header.WriteSubset(&mut b, &exclude)?;
+    b.extend_from_slice(b"\r\n");
+    if let Some(data) = data {
+        if chunked {
+            let mut cw = NewChunkedWriter(&mut b);
+            cw.write_all(&data)?;
+            cw.Close()?;
+            b.extend_from_slice(b"\r\n");
+        } else {
+            b.extend_from_slice(&data);
+        }
+    }
+    Ok(b)
+}
+
+/// DumpRequestOut is like DumpRequest but for outgoing client requests. It includes any headers that the standard Transport adds, such as User-Agent and Accept-Encoding.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DumpRequestOut与DumpRequest类似，但用于客户端发出的请求。它包含标准Transport添加的header，例如User-Agent和Accept-Encoding。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use http::httputil;
+///
+/// let mut req = http::Request::New(
+///     http::Method::Post,
+///     "http://example.com/upload",
+///     Some("hello".into()),
+/// )
+/// .unwrap();
+/// let dump = httputil::DumpRequestOut(&mut req, true).unwrap();
+/// let dump = String::from_utf8(dump).unwrap();
+/// assert!(dump.starts_with("POST /upload HTTP/1.1\r\nHost: example.com\r\n"));
+/// assert!(dump.contains("Content-Length: 5\r\n"));
+/// assert!(dump.ends_with("\r\n\r\nhello"));
+/// ```
+pub fn DumpRequestOut(req: &mut Request, body: bool) -> HttpResult<Vec<u8>> {
+    if let Some(b) = req.Body.as_mut().filter(|_| body) {
+        let data = drainBody(b)?;
+        if req.ContentLength <= 0 {
+            req.ContentLength = data.len() as int64;
+        }
+    }
+    let mut out = req.clone();
+    // 和Transport一样请求gzip压缩的响应，并在请求关闭连接时设置Connection头。
+    if out.Header.Get("Accept-Encoding").is_empty()
+        && out.Header.Get("Range").is_empty()
+        && out.Method != "HEAD"
+    {
+        out.Header.Set("Accept-Encoding", "gzip");
+    }
+    if out.Close {
+        out.Header.Set("Connection", "close");
+    }
+    let mut b = Vec::new();
+    if body {
+        out.Write(&mut b)?;
+    } else {
+        b.extend_from_slice(out.writeHead(false).as_bytes());
+    }
+    Ok(b)
+}
+
+/// DumpResponse is like DumpRequest but dumps a response. The response is written the way a server sends it: with a Content-Length header, or with chunked transfer encoding and its trailers when TransferEncoding says so.
+///
+/// If body is true, DumpResponse also returns the body, replacing resp.Body with a new Body that yields the same bytes.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DumpResponse与DumpRequest类似，但导出的是响应。响应按服务器发送的方式写出：带有Content-Length头，或者在TransferEncoding指定时使用chunked传输编码并写出trailer。
+///
+/// 如果body为true，DumpResponse也会返回body，并把resp.Body替换为一个返回相同字节的新Body。
+/// </details>
+pub fn DumpResponse(resp: &mut Response, body: bool) -> HttpResult<Vec<u8>> {
+    let mut b = Vec::new();
+    if !body {
+        resp.write(&mut b, false)?;
+        return Ok(b);
+    }
+    drainBody(&mut resp.Body)?;
+    let mut out = resp.clone();
+    // 读到EOF之后才能拿到trailer的值，body中的trailer也在Trailer头中声明。out与resp共享Trailer，合并到新的Header中，不修改resp。
+    let mut trailer = lock(&resp.Trailer).clone();
+    for (k, vv) in &out.Body.Trailer() {
+        if !vv.is_empty() {
+            trailer.0.insert(k.clone(), vv.clone());
+        }
+    }
+    out.Trailer = Arc::new(Mutex::new(trailer));
+    out.Write(&mut b)?;
+    Ok(b)
+}
+
+// drainBody reads all of body and replaces it with a Body holding the same bytes and trailer, returning the bytes.
+fn drainBody(body: &mut Body) -> HttpResult<Vec<u8>> {
+    let data = body.ReadAll()?;
+    let trailer = body.trailerSink();
+    *body = Body::from(data.clone()).withTrailer(trailer);
+    Ok(data.to_vec())
+}
+
+/// NewChunkedReader returns a new reader that translates the data read from r out of HTTP "chunked" format before returning it. The reader returns EOF when the final 0-length chunk is read; the trailer that follows it is consumed and discarded.
+///
+/// NewChunkedReader is not needed by normal applications. The http package automatically decodes chunking when reading response bodies.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewChunkedReader返回一个新的reader，把从r读取的HTTP "chunked"格式的数据解码后返回。读到最后一个长度为0的chunk时reader返回EOF，之后的trailer会被读取并丢弃。
+///
+/// 一般的应用不需要NewChunkedReader，http包读取响应body时会自动解码chunked编码。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use http::httputil;
+/// use std::io::Read;
+///
+/// let mut r = httputil::NewChunkedReader(&b"5\r\nhello\r\n0\r\n\r\n"[..]);
+/// let mut s = String::new();
+/// r.read_to_string(&mut s).unwrap();
+/// assert_eq!(s, "hello");
+/// ```
+pub fn NewChunkedReader<R: BufRead>(r: R) -> impl Read {
+    transferReader::new(r, transferState::Chunked(Default::default()))
+}
+
+/// ChunkedWriter is the writer returned by NewChunkedWriter.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ChunkedWriter是NewChunkedWriter返回的writer。
+/// </details>
+pub struct ChunkedWriter<W: Write> {
+    Wire: W,
+}
+
+/// NewChunkedWriter returns a new ChunkedWriter that translates writes into HTTP "chunked" format before writing them to w. Closing the returned ChunkedWriter sends the final 0-length chunk that marks the end of the stream but does not send the final CRLF that appears after trailers; trailers and the last CRLF must be written separately.
+///
+/// NewChunkedWriter is not needed by normal applications. The http package adds chunking automatically if handlers don't set a Content-Length header.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewChunkedWriter返回一个新的ChunkedWriter，把写入的数据转换为HTTP "chunked"格式后写入w。关闭返回的ChunkedWriter会发送标志流结束的长度为0的最后一个chunk，但不会发送trailer之后的最后一个CRLF；trailer和最后的CRLF必须另外写入。
+///
+/// 一般的应用不需要NewChunkedWriter，handler没有设置Content-Length头时http包会自动使用chunked编码。
+/// </details>
+pub fn NewChunkedWriter<W: Write>(w: W) -> ChunkedWriter<W> {
+    ChunkedWriter { Wire: w }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    // 每次write都作为一个chunk写出。
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // 不能发送长度为0的chunk，它表示body结束。
+        if data.is_empty() {
+            return Ok(0);
+        }
+        write!(self.Wire, "{:x}\r\n", data.len())?;
+        self.Wire.write_all(data)?;
+        self.Wire.write_all(b"\r\n")?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.Wire.flush()
+    }
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    /// Close writes the final 0-length chunk.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Close写入最后一个长度为0的chunk。
+    /// </details>
+    pub fn Close(&mut self) -> io::Result<()> {
+        self.Wire.write_all(b"0\r\n")
+    }
+}
@@ -2,18 +2,41 @@
 compile_error!("不能同时启用 tokio-runtime 和 async-std-runtime");
 #[cfg(feature = "async-http")]
 pub mod async_http;
+#[cfg(feature = "async-http")]
+pub mod async_server;
+pub mod body;
 pub mod client;
+pub mod cookiejar;
 pub mod cookies;
 pub mod error;
+pub mod fs;
 pub mod header;
+pub mod hpack;
+mod http2;
+pub mod httptest;
+pub mod httptrace;
+pub mod httputil;
 pub mod method;
+pub mod middleware;
+pub mod multipart;
+mod pattern;
+pub mod proxy;
 pub mod request;
 pub mod response;
 pub mod server;
+pub mod sniff;
+pub mod socks;
 pub mod status;
+#[cfg(test)]
+mod tests;
+pub mod tls;
+mod transfer;
+pub use body::*;
 pub use client::*;
+pub use cookies::*;
 pub use error::*;
 pub use method::*;
+pub use proxy::*;
 pub use request::*;
 pub use response::*;
 pub use status::*;
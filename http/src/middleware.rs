@@ -0,0 +1,239 @@
+//! Package middleware provides RoundTripper wrappers that add retries, logging and default headers to another RoundTripper. Wrappers compose, so a Client can use for example a RetryTransport around a LoggingTransport around a Transport.
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! middleware包提供包装其他RoundTripper的实现，用于增加重试、日志和默认header。这些包装可以组合使用，例如Client使用RetryTransport包装LoggingTransport，再包装Transport。
+//! </details>
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gostd_http as http;
+//! use http::middleware;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut header = http::header::Header::default();
+//!     header.Set("User-Agent", "gostd-example");
+//!     let rt = middleware::NewRetryTransport(middleware::NewLoggingTransport(
+//!         middleware::NewHeaderTransport(http::Transport::default(), header),
+//!         |line: &str| eprintln!("{}", line),
+//!     ));
+//!     let mut client = http::Client::NewWithTransport(rt);
+//!     let resp = client.Get("http://example.com")?;
+//!     println!("{}", resp.Status);
+//!     Ok(())
+//! }
+//! ```
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use gostd_builtin::*;
+use gostd_time as time;
+
+use crate::client::{stdDuration, RoundTripper};
+use crate::error::{HTTPConnectError, HttpResult};
+use crate::header::Header;
+use crate::request::Request;
+use crate::response::Response;
+
+/// RetryTransport is a RoundTripper that sends idempotent requests again when they fail with an I/O error or a timeout, such as a refused or reset connection, or get a 502, 503 or 504 response. Other errors, such as a malformed response or a proxy refusing the connection, are returned at once because sending the request again would fail the same way. Between attempts it waits with exponential backoff and jitter, and it never waits past the deadline of the request.
+///
+/// A request is idempotent if its method is GET, HEAD, OPTIONS, TRACE, PUT or DELETE, or if it has an Idempotency-Key or X-Idempotency-Key header. A request with a Body is only retried if it also has GetBody, so that the body can be sent again.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// RetryTransport在幂等请求因I/O错误或超时(例如连接被拒绝或被重置)失败，或收到502、503、504响应时重新发送请求。其他错误，例如响应格式错误或代理拒绝连接，会直接返回，因为重新发送也会以同样的方式失败。每次重试之前按指数退避加随机抖动等待，等待时间不会超过请求的截止时间。
+///
+/// 方法为GET、HEAD、OPTIONS、TRACE、PUT或DELETE，或者带有Idempotency-Key、X-Idempotency-Key头的请求是幂等的。带Body的请求还必须设置GetBody才会重试，以便重新发送body。
+/// </details>
+pub struct RetryTransport {
+    /// Next is the RoundTripper that sends each attempt.
+    pub Next: Box<dyn RoundTripper>,
+    /// MaxRetries is the number of times a request is sent again after the first attempt.
+    pub MaxRetries: int,
+    /// MinBackoff is the wait before the first retry. It doubles for every further retry.
+    pub MinBackoff: time::Duration,
+    /// MaxBackoff caps the wait between two attempts, including waits requested by a Retry-After header.
+    pub MaxBackoff: time::Duration,
+}
+
+/// NewRetryTransport returns a RetryTransport around next that retries up to 3 times, waiting 100ms before the first retry and at most 2s between attempts.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewRetryTransport返回包装next的RetryTransport，最多重试3次，第一次重试前等待100ms，两次尝试之间最多等待2s。
+/// </details>
+pub fn NewRetryTransport<T: RoundTripper + 'static>(next: T) -> RetryTransport {
+    RetryTransport {
+        Next: Box::new(next),
+        MaxRetries: 3,
+        MinBackoff: time::Duration::new(100 * time::Millisecond),
+        MaxBackoff: time::Duration::new(2 * time::Second),
+    }
+}
+
+impl RoundTripper for RetryTransport {
+    fn RoundTrip(&mut self, req: &Request) -> HttpResult<Response> {
+        if !isIdempotent(req) {
+            return self.Next.RoundTrip(req);
+        }
+        let mut req = req.clone();
+        let mut attempt = 0;
+        loop {
+            let r = self.Next.RoundTrip(&req);
+            if attempt >= self.MaxRetries || !shouldRetry(&r) {
+                return r;
+            }
+            let wait = self.backoff(attempt, r.as_ref().ok());
+            // 等待之后会超过请求的截止时间，直接返回这次的结果。
+            if req.deadline.is_some_and(|d| Instant::now() + wait >= d) {
+                return r;
+            }
+            if let Ok(mut resp) = r {
+                resp.Body.Close();
+            }
+            req.rewindBody()?;
+            thread::sleep(wait);
+            attempt += 1;
+        }
+    }
+}
+
+impl RetryTransport {
+    // backoff returns how long to wait before the retry that follows attempt: MinBackoff doubled for every earlier retry, capped at MaxBackoff, of which a random half is kept as jitter. A Retry-After given in seconds by resp takes precedence, capped at MaxBackoff as well.
+    fn backoff(&self, attempt: int, resp: Option<&Response>) -> std::time::Duration {
+        let max = stdDuration(&self.MaxBackoff).unwrap_or_default();
+        if let Some(resp) = resp {
+            if let Ok(secs) = resp.Header.Get("Retry-After").trim().parse::<u64>() {
+                return std::time::Duration::from_secs(secs).min(max);
+            }
+        }
+        let min = stdDuration(&self.MinBackoff).unwrap_or_default();
+        let d = min
+            .checked_mul(1 << attempt.min(30) as u32)
+            .unwrap_or(max)
+            .min(max);
+        d / 2 + d.mul_f64(random() / 2.0)
+    }
+}
+
+// isIdempotent reports whether req may be sent more than once without changing the outcome.
+fn isIdempotent(req: &Request) -> bool {
+    if req.Body.is_some() && req.GetBody.is_none() {
+        return false;
+    }
+    match req.Method.as_str() {
+        "" | "GET" | "HEAD" | "OPTIONS" | "TRACE" | "PUT" | "DELETE" => true,
+        _ => {
+            !req.Header.Get("Idempotency-Key").is_empty()
+                || !req.Header.Get("X-Idempotency-Key").is_empty()
+        }
+    }
+}
+
+// shouldRetry reports whether the result of an attempt is worth another try: an I/O error or a timeout while dialing or talking to the server, or a response saying the server or a gateway is temporarily unavailable. ConnectionFailure is not retried, it also reports errors that do not go away, such as a bad redirect or a rejected proxy handshake.
+fn shouldRetry(r: &HttpResult<Response>) -> bool {
+    match r {
+        Ok(resp) => matches!(resp.StatusCode, 502..=504),
+        Err(err) => matches!(
+            err,
+            HTTPConnectError::IoError(_) | HTTPConnectError::TimeoutError(_)
+        ),
+    }
+}
+
+// random returns a pseudo-random number in [0, 1). RandomState is seeded with fresh random keys, which is enough for jitter.
+fn random() -> f64 {
+    let h = RandomState::new().build_hasher();
+    (h.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// LoggingTransport is a RoundTripper that reports every request it sends to Logf, with the status of the response or the error, and the time it took to get the response header.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// LoggingTransport把发送的每个请求连同响应状态或错误、以及收到响应头所用的时间报告给Logf。
+/// </details>
+pub struct LoggingTransport {
+    /// Next is the RoundTripper that sends the request.
+    pub Next: Box<dyn RoundTripper>,
+    /// Logf is called with one line per request, such as "GET http://example.com/ 200 OK 12.5ms".
+    pub Logf: Arc<dyn Fn(&str) + Send + Sync>,
+}
+
+/// NewLoggingTransport returns a LoggingTransport around next that writes its lines to logf.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewLoggingTransport返回包装next的LoggingTransport，日志行写入logf。
+/// </details>
+pub fn NewLoggingTransport<T, F>(next: T, logf: F) -> LoggingTransport
+where
+    T: RoundTripper + 'static,
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    LoggingTransport {
+        Next: Box::new(next),
+        Logf: Arc::new(logf),
+    }
+}
+
+impl RoundTripper for LoggingTransport {
+    fn RoundTrip(&mut self, req: &Request) -> HttpResult<Response> {
+        let start = Instant::now();
+        let r = self.Next.RoundTrip(req);
+        let took = time::Duration::new(start.elapsed().as_nanos() as int64).String();
+        let method = if req.Method.is_empty() {
+            "GET"
+        } else {
+            req.Method.as_str()
+        };
+        let line = match &r {
+            Ok(resp) => format!("{} {} {} {}", method, req.URL.String(), resp.Status, took),
+            Err(err) => format!("{} {} error: {} {}", method, req.URL.String(), err, took),
+        };
+        (self.Logf)(line.as_str());
+        r
+    }
+}
+
+/// HeaderTransport is a RoundTripper that adds the fields of Header to every request it sends. Fields that the request already has are left unchanged.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HeaderTransport把Header中的字段添加到发送的每个请求中，请求中已有的字段保持不变。
+/// </details>
+pub struct HeaderTransport {
+    /// Next is the RoundTripper that sends the request.
+    pub Next: Box<dyn RoundTripper>,
+    /// Header holds the fields added to each request.
+    pub Header: Header,
+}
+
+/// NewHeaderTransport returns a HeaderTransport around next that adds header to every request.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewHeaderTransport返回包装next的HeaderTransport，为每个请求添加header。
+/// </details>
+pub fn NewHeaderTransport<T: RoundTripper + 'static>(next: T, header: Header) -> HeaderTransport {
+    HeaderTransport {
+        Next: Box::new(next),
+        Header: header,
+    }
+}
+
+impl RoundTripper for HeaderTransport {
+    fn RoundTrip(&mut self, req: &Request) -> HttpResult<Response> {
+        let mut req = req.clone();
+        for (key, values) in &self.Header {
+            if req.Header.Values(key).is_empty() {
+                for v in values {
+                    req.Header.Add(key, v);
+                }
+            }
+        }
+        self.Next.RoundTrip(&req)
+    }
+}
@@ -0,0 +1,783 @@
+//! Package multipart implements MIME multipart parsing, as defined in RFC 2046. The implementation is sufficient for HTTP (RFC 2388) and the multipart bodies generated by popular browsers.
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! multipart包实现了RFC 2046定义的MIME multipart解析，足以处理HTTP(RFC 2388)以及常见浏览器生成的multipart body。
+//! </details>
+//!
+//! # Example
+//!
+//! ```
+//! use gostd_http::multipart;
+//! use std::io::Write;
+//!
+//! let mut buf = Vec::new();
+//! let mut w = multipart::NewWriter(&mut buf);
+//! w.WriteField("name", "gopher").unwrap();
+//! w.CreateFormFile("upload", "hello.txt").unwrap().write_all(b"hello").unwrap();
+//! let boundary = w.Boundary().to_string();
+//! w.Close().unwrap();
+//!
+//! let form = multipart::NewReader(&buf[..], &boundary).ReadForm(1 << 20).unwrap();
+//! assert_eq!(form.Value["name"], ["gopher"]);
+//! assert_eq!(form.File["upload"][0].Filename, "hello.txt");
+//! ```
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use gostd_builtin::*;
+
+use crate::{
+    error::{HTTPConnectError, HttpResult},
+    header::Header,
+};
+
+// peekBufferSize是Reader每次从底层读取的字节数，也是preamble中一行的最大长度。
+const peekBufferSize: usize = 4096;
+
+// maxMIMEHeaderSize限制每个part的header大小。
+const maxMIMEHeaderSize: usize = 10 << 10;
+
+// maxParts限制ReadForm接受的part数量。
+const maxParts: usize = 1000;
+
+// maxValueBytes是ReadForm在maxMemory之外为非文件字段保留的内存。
+const maxValueBytes: int64 = 10 << 20;
+
+/// A Writer generates multipart messages.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Writer用于生成multipart消息。
+/// </details>
+pub struct Writer<W: Write> {
+    w: W,
+    boundary: String,
+    // partsWritten是已经开始写入的part数量。
+    partsWritten: usize,
+}
+
+/// NewWriter returns a new multipart Writer with a random boundary, writing to w.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewWriter返回一个写入w的multipart Writer，boundary是随机生成的。
+/// </details>
+pub fn NewWriter<W: Write>(w: W) -> Writer<W> {
+    Writer {
+        w,
+        boundary: randomBoundary(),
+        partsWritten: 0,
+    }
+}
+
+impl<W: Write> Writer<W> {
+    /// Boundary returns the Writer's boundary.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Boundary返回Writer的boundary。
+    /// </details>
+    pub fn Boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// SetBoundary overrides the Writer's default randomly-generated boundary separator with an explicit value.
+    ///
+    /// SetBoundary must be called before any parts are created, may only contain certain ASCII characters, and must be non-empty and at most 70 bytes long.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetBoundary用指定的值替换Writer默认随机生成的boundary。
+    ///
+    /// 必须在创建任何part之前调用SetBoundary，boundary只能包含特定的ASCII字符，长度为1到70字节。
+    /// </details>
+    pub fn SetBoundary(&mut self, boundary: &str) -> HttpResult<()> {
+        if self.partsWritten > 0 {
+            return Err(invalidInput("mime: SetBoundary called after write"));
+        }
+        if boundary.is_empty() || boundary.len() > 70 {
+            return Err(invalidInput("mime: invalid boundary length"));
+        }
+        let end = boundary.len() - 1;
+        for (i, b) in boundary.bytes().enumerate() {
+            if b.is_ascii_alphanumeric() || b"'()+_,-./:=?".contains(&b) || (b == b' ' && i != end)
+            {
+                continue;
+            }
+            return Err(invalidInput("mime: invalid boundary character"));
+        }
+        self.boundary = boundary.to_string();
+        Ok(())
+    }
+
+    /// FormDataContentType returns the Content-Type for an HTTP multipart/form-data with this Writer's Boundary.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// FormDataContentType返回使用该Writer的boundary的multipart/form-data类型的Content-Type。
+    /// </details>
+    pub fn FormDataContentType(&self) -> String {
+        let mut b = self.boundary.clone();
+        // boundary中包含tspecials时需要加引号。
+        if b.chars().any(|c| "()<>@,;:\\\"/[]?= ".contains(c)) {
+            b = format!("\"{}\"", b);
+        }
+        format!("multipart/form-data; boundary={}", b)
+    }
+
+    /// CreatePart creates a new multipart section with the provided header. The body of the part should be written to the returned writer, before the next part is created.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// CreatePart使用提供的header创建一个新的part。在创建下一个part之前，part的内容应写入返回的writer。
+    /// </details>
+    pub fn CreatePart(&mut self, header: &Header) -> HttpResult<impl Write + '_> {
+        let mut b = Vec::new();
+        if self.partsWritten > 0 {
+            write!(b, "\r\n--{}\r\n", self.boundary)?;
+        } else {
+            write!(b, "--{}\r\n", self.boundary)?;
+        }
+        let mut keys: Vec<&String> = header.iter().map(|(k, _)| k).collect();
+        keys.sort();
+        for k in keys {
+            for v in header.Values(k) {
+                write!(b, "{}: {}\r\n", k, v)?;
+            }
+        }
+        b.extend_from_slice(b"\r\n");
+        self.w.write_all(&b)?;
+        self.partsWritten += 1;
+        Ok(&mut self.w)
+    }
+
+    /// CreateFormFile is a convenience wrapper around CreatePart. It creates a new form-data header with the provided field name and file name, and Content-Type application/octet-stream.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// CreateFormFile是CreatePart的便捷封装，使用提供的字段名和文件名创建form-data类型的header，Content-Type为application/octet-stream。
+    /// </details>
+    pub fn CreateFormFile(
+        &mut self,
+        fieldname: &str,
+        filename: &str,
+    ) -> HttpResult<impl Write + '_> {
+        let mut h = Header::default();
+        h.Set(
+            "Content-Disposition",
+            &format!(
+                "form-data; name=\"{}\"; filename=\"{}\"",
+                escapeQuotes(fieldname),
+                escapeQuotes(filename)
+            ),
+        );
+        h.Set("Content-Type", "application/octet-stream");
+        self.CreatePart(&h)
+    }
+
+    /// CreateFormField calls CreatePart with a header using the given field name.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// CreateFormField使用给定的字段名构造header并调用CreatePart。
+    /// </details>
+    pub fn CreateFormField(&mut self, fieldname: &str) -> HttpResult<impl Write + '_> {
+        let mut h = Header::default();
+        h.Set(
+            "Content-Disposition",
+            &format!("form-data; name=\"{}\"", escapeQuotes(fieldname)),
+        );
+        self.CreatePart(&h)
+    }
+
+    /// WriteField calls CreateFormField and then writes the given value.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// WriteField调用CreateFormField并写入给定的值。
+    /// </details>
+    pub fn WriteField(&mut self, fieldname: &str, value: &str) -> HttpResult<()> {
+        self.CreateFormField(fieldname)?
+            .write_all(value.as_bytes())?;
+        Ok(())
+    }
+
+    /// Close finishes the multipart message and writes the trailing boundary end line to the output.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Close结束multipart消息，并写入结尾的boundary行。
+    /// </details>
+    pub fn Close(&mut self) -> HttpResult<()> {
+        if self.partsWritten > 0 {
+            write!(self.w, "\r\n--{}--\r\n", self.boundary)?;
+        } else {
+            write!(self.w, "--{}--\r\n", self.boundary)?;
+        }
+        self.w.flush()?;
+        Ok(())
+    }
+}
+
+fn invalidInput(msg: &'static str) -> HTTPConnectError {
+    io::Error::new(io::ErrorKind::InvalidInput, msg).into()
+}
+
+fn escapeQuotes(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// randomBoundary返回60个十六进制字符的随机boundary。RandomState每次创建时使用新的随机key。
+fn randomBoundary() -> String {
+    let mut s = String::with_capacity(64);
+    for i in 0..4 {
+        let mut h = RandomState::new().build_hasher();
+        h.write_usize(i);
+        s.push_str(&format!("{:016x}", h.finish()));
+    }
+    s.truncate(60);
+    s
+}
+
+/// Reader is an iterator over parts in a MIME multipart body. Reader's underlying parser consumes its input as needed. Seeking isn't supported.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Reader用于遍历MIME multipart body中的各个part。Reader按需读取底层输入，不支持Seek。
+/// </details>
+pub struct Reader<R: Read> {
+    r: R,
+    // buf是已经从r读取但还没有消费的数据。
+    buf: Vec<u8>,
+    eof: bool,
+    // nlDashBoundary是"\r\n--boundary"，dashBoundary是"--boundary"。
+    nlDashBoundary: Vec<u8>,
+    dashBoundary: Vec<u8>,
+    partsRead: usize,
+    // partEOF表示当前part的内容已经读完，buf以nlDashBoundary开头。
+    partEOF: bool,
+    done: bool,
+}
+
+/// NewReader creates a new multipart Reader reading from r using the given MIME boundary.
+///
+/// The boundary is usually obtained from the "boundary" parameter of the message's "Content-Type" header.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewReader创建一个使用给定MIME boundary从r读取的multipart Reader。
+///
+/// boundary通常从消息"Content-Type"头的"boundary"参数中获取。
+/// </details>
+pub fn NewReader<R: Read>(r: R, boundary: &str) -> Reader<R> {
+    Reader {
+        r,
+        buf: Vec::new(),
+        eof: false,
+        nlDashBoundary: format!("\r\n--{}", boundary).into_bytes(),
+        dashBoundary: format!("--{}", boundary).into_bytes(),
+        partsRead: 0,
+        partEOF: false,
+        done: false,
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// NextPart returns the next part in the multipart, or None once there are no more parts. Any unread data of the previous part is discarded.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// NextPart返回multipart中的下一个part，没有更多part时返回None。上一个part中未读取的数据会被丢弃。
+    /// </details>
+    pub fn NextPart(&mut self) -> HttpResult<Option<Part<'_, R>>> {
+        if self.done {
+            return Ok(None);
+        }
+        if self.partsRead > 0 {
+            let mut sink = [0u8; peekBufferSize];
+            while self.readPart(&mut sink)? > 0 {}
+            self.buf.drain(..self.nlDashBoundary.len());
+            let line = self.readLine()?.ok_or_else(unexpectedEOF)?;
+            if line.starts_with(b"--") {
+                self.done = true;
+                return Ok(None);
+            }
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Err(HTTPConnectError::ProtocolError(format!(
+                    "multipart: expecting a new Part; got line {:?}",
+                    String::from_utf8_lossy(&line)
+                )));
+            }
+        } else {
+            // 跳过第一个boundary之前的preamble。
+            loop {
+                let line = self.readLine()?.ok_or_else(unexpectedEOF)?;
+                let line = trimRight(&line);
+                if line == &self.dashBoundary[..] {
+                    break;
+                }
+                if line.len() == self.dashBoundary.len() + 2
+                    && line.starts_with(&self.dashBoundary)
+                    && line.ends_with(b"--")
+                {
+                    self.done = true;
+                    return Ok(None);
+                }
+            }
+        }
+        let header = self.readHeader()?;
+        self.partsRead += 1;
+        self.partEOF = false;
+        Ok(Some(Part {
+            Header: header,
+            mr: self,
+        }))
+    }
+
+    /// ReadForm parses an entire multipart message whose parts have a Content-Disposition of "form-data". It stores up to maxMemory bytes of the file parts in memory and the remainder on disk in temporary files. Non-file parts are stored in memory, up to maxMemory plus 10 MB.
+    ///
+    /// ErrMessageTooLarge is returned if the non-file parts cannot be stored in memory. The caller should call Form::RemoveAll once the files are no longer needed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ReadForm解析Content-Disposition为"form-data"的整个multipart消息。文件part最多maxMemory字节保存在内存中，其余保存在磁盘上的临时文件中。非文件part保存在内存中，最多maxMemory加10MB。
+    ///
+    /// 非文件part无法保存在内存中时返回ErrMessageTooLarge。调用者不再需要这些文件时应调用Form::RemoveAll。
+    /// </details>
+    pub fn ReadForm(&mut self, maxMemory: int64) -> HttpResult<Form> {
+        let mut form = Form::default();
+        let r = self.readForm(maxMemory.max(0), &mut form);
+        if r.is_err() {
+            form.RemoveAll();
+        }
+        r.map(|_| form)
+    }
+
+    fn readForm(&mut self, maxMemory: int64, form: &mut Form) -> HttpResult<()> {
+        let mut maxFileMemoryBytes = maxMemory;
+        let mut maxMemoryBytes = maxMemory.saturating_add(maxValueBytes);
+        let mut parts = 0;
+        while let Some(mut p) = self.NextPart()? {
+            parts += 1;
+            if parts > maxParts {
+                return Err(HTTPConnectError::ErrMessageTooLarge);
+            }
+            let name = p.FormName();
+            if name.is_empty() {
+                continue;
+            }
+            let filename = p.FileName();
+
+            let mut b = Vec::new();
+            if filename.is_empty() {
+                // 非文件字段，保存在内存中。
+                (&mut p)
+                    .take(maxMemoryBytes as u64 + 1)
+                    .read_to_end(&mut b)?;
+                maxMemoryBytes -= b.len() as int64;
+                if maxMemoryBytes < 0 {
+                    return Err(HTTPConnectError::ErrMessageTooLarge);
+                }
+                form.Value
+                    .entry(name)
+                    .or_default()
+                    .push(String::from_utf8_lossy(&b).into_owned());
+                continue;
+            }
+
+            let mut fh = FileHeader {
+                Filename: filename,
+                Header: p.Header.clone(),
+                Size: 0,
+                content: Bytes::new(),
+                tmpfile: None,
+            };
+            (&mut p)
+                .take(maxFileMemoryBytes as u64 + 1)
+                .read_to_end(&mut b)?;
+            if b.len() as int64 > maxFileMemoryBytes {
+                // 超过内存限制，写入临时文件。
+                let (mut f, path) = createTemp()?;
+                fh.tmpfile = Some(path);
+                form.File.entry(name.clone()).or_default().push(fh.clone());
+                f.write_all(&b)?;
+                let n = io::copy(&mut p, &mut f)?;
+                fh.Size = b.len() as int64 + n as int64;
+                form.File.get_mut(&name).unwrap().last_mut().unwrap().Size = fh.Size;
+            } else {
+                fh.Size = b.len() as int64;
+                maxFileMemoryBytes -= fh.Size;
+                maxMemoryBytes -= fh.Size;
+                fh.content = Bytes::from(b);
+                form.File.entry(name).or_default().push(fh);
+            }
+        }
+        Ok(())
+    }
+
+    // readPart读取当前part的内容，遇到分隔符时返回0。
+    fn readPart(&mut self, p: &mut [u8]) -> io::Result<usize> {
+        if self.partEOF || p.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let dlen = self.nlDashBoundary.len();
+            let mut from = 0;
+            let mut atDelimiter = false;
+            let safe = loop {
+                match find(&self.buf[from..], &self.nlDashBoundary) {
+                    Some(i) => {
+                        let at = from + i;
+                        match matchAfterPrefix(&self.buf[at + dlen..], self.eof) {
+                            Some(true) => {
+                                atDelimiter = true;
+                                break at;
+                            }
+                            // 数据中出现了boundary的前缀，但不是分隔符。
+                            Some(false) => from = at + 1,
+                            None => break at,
+                        }
+                    }
+                    None if self.eof => break self.buf.len(),
+                    None => break from.max(self.buf.len().saturating_sub(dlen - 1)),
+                }
+            };
+            if safe > 0 {
+                let n = safe.min(p.len());
+                p[..n].copy_from_slice(&self.buf[..n]);
+                self.buf.drain(..n);
+                return Ok(n);
+            }
+            if atDelimiter {
+                self.partEOF = true;
+                return Ok(0);
+            }
+            if self.eof {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "multipart: NextPart: EOF",
+                ));
+            }
+            self.fill()?;
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; peekBufferSize];
+        loop {
+            match self.r.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+            return Ok(());
+        }
+    }
+
+    // readLine读取包括"\n"在内的一行，读到EOF时返回剩余的数据，没有数据时返回None。
+    fn readLine(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut from = 0;
+        loop {
+            if let Some(i) = self.buf[from..].iter().position(|&c| c == b'\n') {
+                return Ok(Some(self.buf.drain(..from + i + 1).collect()));
+            }
+            from = self.buf.len();
+            if self.eof {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(std::mem::take(&mut self.buf)));
+            }
+            if from > peekBufferSize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "multipart: NextPart: line too long",
+                ));
+            }
+            self.fill()?;
+        }
+    }
+
+    fn readHeader(&mut self) -> HttpResult<Header> {
+        let mut h = Header::default();
+        let mut size = 0;
+        loop {
+            let line = self.readLine()?.ok_or_else(unexpectedEOF)?;
+            size += line.len();
+            if size > maxMIMEHeaderSize {
+                return Err(HTTPConnectError::ErrMessageTooLarge);
+            }
+            let line = String::from_utf8_lossy(trimRight(&line)).into_owned();
+            if line.is_empty() {
+                return Ok(h);
+            }
+            match line.find(':') {
+                Some(i) => h.Add(line[..i].trim(), line[i + 1..].trim()),
+                None => {
+                    return Err(HTTPConnectError::ProtocolError(format!(
+                        "malformed MIME header line: {}",
+                        line
+                    )))
+                }
+            }
+        }
+    }
+}
+
+fn unexpectedEOF() -> HTTPConnectError {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "multipart: NextPart: EOF").into()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn trimRight(b: &[u8]) -> &[u8] {
+    let n = b
+        .iter()
+        .rposition(|c| !c.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    &b[..n]
+}
+
+// matchAfterPrefix检查"\r\n--boundary"之后的数据，判断它是否是真正的分隔符。后面必须是"--"，或者是可选的空白加换行。数据不足以判断时返回None。
+fn matchAfterPrefix(rest: &[u8], eof: bool) -> Option<bool> {
+    let trimmed = &rest[rest
+        .iter()
+        .position(|&c| c != b' ' && c != b'\t')
+        .unwrap_or(rest.len())..];
+    if rest.starts_with(b"--") || trimmed.starts_with(b"\r\n") {
+        return Some(true);
+    }
+    if eof {
+        return Some(rest.is_empty());
+    }
+    if b"--".starts_with(rest) || trimmed.is_empty() || trimmed == b"\r" {
+        return None;
+    }
+    Some(false)
+}
+
+/// A Part represents a single part in a multipart body. Reading from it returns the body of the part.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Part表示multipart body中的一个part，从中读取得到part的内容。
+/// </details>
+pub struct Part<'a, R: Read> {
+    /// The headers of the body, if any, with the keys canonicalized.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// part的header，key已经规范化。
+    /// </details>
+    pub Header: Header,
+    mr: &'a mut Reader<R>,
+}
+
+impl<R: Read> Part<'_, R> {
+    /// FormName returns the name parameter if the Part's Content-Disposition is of type form-data. Otherwise it returns the empty string.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Part的Content-Disposition为form-data类型时，FormName返回name参数，否则返回空字符串。
+    /// </details>
+    pub fn FormName(&self) -> String {
+        match parseMediaType(&self.Header.Get("Content-Disposition")) {
+            Ok((d, params)) if d == "form-data" => params.get("name").cloned().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    /// FileName returns the filename parameter of the Part's Content-Disposition header. If not empty, the filename is passed through Path::file_name (which is platform dependent) before being returned.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// FileName返回Part的Content-Disposition头的filename参数。不为空时，返回之前会经过Path::file_name处理(与平台相关)。
+    /// </details>
+    pub fn FileName(&self) -> String {
+        let filename = match parseMediaType(&self.Header.Get("Content-Disposition")) {
+            Ok((_, params)) => params.get("filename").cloned().unwrap_or_default(),
+            Err(_) => return String::new(),
+        };
+        if filename.is_empty() {
+            return filename;
+        }
+        Path::new(&filename)
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+impl<R: Read> Read for Part<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.mr.readPart(buf)
+    }
+}
+
+/// Form is a parsed multipart form. Its File parts are stored either in memory or on disk, and are accessible via the FileHeader's Open method. Its Value parts are stored as strings. Both are keyed by field name.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Form是解析后的multipart表单。文件part保存在内存或磁盘上，可以通过FileHeader的Open方法访问；值part保存为字符串。两者都以字段名为key。
+/// </details>
+#[derive(Default, Debug, Clone)]
+pub struct Form {
+    pub Value: HashMap<String, Vec<String>>,
+    pub File: HashMap<String, Vec<FileHeader>>,
+}
+
+impl Form {
+    /// RemoveAll removes any temporary files associated with a Form.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// RemoveAll删除Form关联的所有临时文件。
+    /// </details>
+    pub fn RemoveAll(&self) -> HttpResult<()> {
+        let mut err = None;
+        for fh in self.File.values().flatten() {
+            if let Some(path) = &fh.tmpfile {
+                match fs::remove_file(path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound && err.is_none() => err = Some(e),
+                    _ => {}
+                }
+            }
+        }
+        err.map_or(Ok(()), |e| Err(e.into()))
+    }
+}
+
+/// A FileHeader describes a file part of a multipart request.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// FileHeader描述multipart请求中的一个文件part。
+/// </details>
+#[derive(Debug, Clone)]
+pub struct FileHeader {
+    pub Filename: String,
+    pub Header: Header,
+    pub Size: int64,
+    content: Bytes,
+    tmpfile: Option<PathBuf>,
+}
+
+impl FileHeader {
+    /// Open opens and returns the FileHeader's associated File.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Open打开并返回FileHeader关联的File。
+    /// </details>
+    pub fn Open(&self) -> HttpResult<File> {
+        match &self.tmpfile {
+            Some(path) => Ok(File(fileInner::Disk(fs::File::open(path)?))),
+            None => Ok(File(fileInner::Memory(Cursor::new(self.content.clone())))),
+        }
+    }
+}
+
+/// File is the content of a file part of a multipart message. It is held either in memory or in a temporary file on disk, and supports Read and Seek.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// File是multipart消息中文件part的内容，保存在内存或磁盘上的临时文件中，支持Read和Seek。
+/// </details>
+#[derive(Debug)]
+pub struct File(fileInner);
+
+#[derive(Debug)]
+enum fileInner {
+    Memory(Cursor<Bytes>),
+    Disk(fs::File),
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            fileInner::Memory(c) => c.read(buf),
+            fileInner::Disk(f) => f.read(buf),
+        }
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match &mut self.0 {
+            fileInner::Memory(c) => c.seek(pos),
+            fileInner::Disk(f) => f.seek(pos),
+        }
+    }
+}
+
+// createTemp在系统临时目录中创建一个名为"multipart-*"的新文件。
+fn createTemp() -> io::Result<(fs::File, PathBuf)> {
+    let dir = std::env::temp_dir();
+    loop {
+        let path = dir.join(format!("multipart-{}", &randomBoundary()[..20]));
+        match fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(f) => return Ok((f, path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// parseMediaType解析Content-Type、Content-Disposition这类"type; key=value"格式的值，返回小写的类型和参数。参数key转换为小写，带引号的值会去掉引号和转义。
+pub(crate) fn parseMediaType(v: &str) -> HttpResult<(String, HashMap<String, String>)> {
+    let invalid = || HTTPConnectError::ProtocolError("mime: invalid media parameter".to_string());
+    let (mediatype, mut rest) = match v.find(';') {
+        Some(i) => (&v[..i], &v[i..]),
+        None => (v, ""),
+    };
+    let mediatype = mediatype.trim().to_ascii_lowercase();
+    if mediatype.is_empty() {
+        return Err(HTTPConnectError::ProtocolError(
+            "mime: no media type".to_string(),
+        ));
+    }
+    let mut params = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        rest = match rest.strip_prefix(';') {
+            Some(r) => r.trim_start(),
+            None => return Err(invalid()),
+        };
+        if rest.is_empty() {
+            // 允许结尾多余的分号。
+            break;
+        }
+        let eq = rest.find('=').ok_or_else(invalid)?;
+        let key = rest[..eq].trim().to_ascii_lowercase();
+        if key.is_empty() || key.contains(';') {
+            return Err(invalid());
+        }
+        rest = rest[eq + 1..].trim_start();
+        let mut value = String::new();
+        if let Some(r) = rest.strip_prefix('"') {
+            let mut chars = r.char_indices();
+            let mut end = None;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        end = Some(i + 1);
+                        break;
+                    }
+                    '\\' => {
+                        if let Some((_, c)) = chars.next() {
+                            value.push(c);
+                        }
+                    }
+                    c => value.push(c),
+                }
+            }
+            rest = &r[end.ok_or_else(invalid)?..];
+        } else {
+            let end = rest
+                .find(|c: char| c == ';' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            value = rest[..end].to_string();
+            rest = &rest[end..];
+        }
+        params.entry(key).or_insert(value);
+    }
+    Ok((mediatype, params))
+}
@@ -0,0 +1,505 @@
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// Patterns for ServeMux, with the syntax and precedence rules of Go 1.22: "[METHOD ][HOST]/[PATH]".
+// A path segment is either a literal, a wildcard "{name}" matching one segment, a wildcard "{name...}" matching the rest of the path, or "{$}" matching only the end of the path after a trailing slash. A pattern ending in a slash matches every path with that prefix.
+
+use std::collections::HashSet;
+
+use crate::header::validHeaderFieldByte;
+use crate::request::Request;
+
+// pattern is a parsed ServeMux pattern.
+#[derive(Debug, Clone)]
+pub(crate) struct pattern {
+    pub(crate) str: String,
+    pub(crate) method: String,
+    pub(crate) host: String,
+    segments: Vec<segment>,
+}
+
+// segment is one path segment of a pattern. A literal "{$}" is stored as the segment "/", and a trailing slash as an anonymous multi wildcard.
+#[derive(Debug, Clone)]
+struct segment {
+    s: String,
+    wild: bool,
+    multi: bool,
+}
+
+// relationship describes how the sets of requests matched by two patterns relate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum relationship {
+    equivalent,
+    moreGeneral,
+    moreSpecific,
+    disjoint,
+    overlaps,
+}
+
+impl relationship {
+    fn inverse(self) -> relationship {
+        match self {
+            relationship::moreGeneral => relationship::moreSpecific,
+            relationship::moreSpecific => relationship::moreGeneral,
+            r => r,
+        }
+    }
+
+    fn combine(self, r2: relationship) -> relationship {
+        use relationship::*;
+        match self {
+            equivalent => r2,
+            disjoint => disjoint,
+            overlaps if r2 == disjoint => disjoint,
+            overlaps => overlaps,
+            moreGeneral | moreSpecific => {
+                if r2 == equivalent {
+                    self
+                } else if r2 == self.inverse() {
+                    overlaps
+                } else {
+                    r2
+                }
+            }
+        }
+    }
+}
+
+// parsePattern parses a pattern string, returning a description of the problem if it is not valid.
+pub(crate) fn parsePattern(s: &str) -> Result<pattern, String> {
+    if s.is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    let mut p = pattern {
+        str: s.to_string(),
+        method: String::new(),
+        host: String::new(),
+        segments: Vec::new(),
+    };
+    let mut rest = s;
+    if let Some(i) = s.find([' ', '\t']) {
+        p.method = s[..i].to_string();
+        rest = s[i..].trim_start_matches([' ', '\t']);
+        if !p.method.bytes().all(validHeaderFieldByte) {
+            return Err(format!("invalid method {:?}", p.method));
+        }
+    }
+    let i = match rest.find('/') {
+        Some(i) => i,
+        None => return Err("host/path missing /".to_string()),
+    };
+    p.host = rest[..i].to_string();
+    rest = &rest[i..];
+    if p.host.contains('{') {
+        return Err("host contains '{' (missing initial '/'?)".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    while !rest.is_empty() {
+        // 去掉开头的'/'。
+        rest = &rest[1..];
+        if rest.is_empty() {
+            // 以'/'结尾，匹配所有以该前缀开头的路径。
+            p.segments.push(segment {
+                s: String::new(),
+                wild: true,
+                multi: true,
+            });
+            break;
+        }
+        let i = rest.find('/').unwrap_or(rest.len());
+        let seg = &rest[..i];
+        rest = &rest[i..];
+        if !seg.contains('{') {
+            p.segments.push(segment {
+                s: seg.to_string(),
+                wild: false,
+                multi: false,
+            });
+            continue;
+        }
+        if !seg.starts_with('{') || !seg.ends_with('}') {
+            return Err(r#"bad wildcard segment (must be "{name}" or "{name...}")"#.to_string());
+        }
+        let name = &seg[1..seg.len() - 1];
+        if name == "$" {
+            if !rest.is_empty() {
+                return Err("{$} not at end".to_string());
+            }
+            p.segments.push(segment {
+                s: "/".to_string(),
+                wild: false,
+                multi: false,
+            });
+            break;
+        }
+        let (name, multi) = match name.strip_suffix("...") {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        if multi && !rest.is_empty() {
+            return Err("{...} wildcard not at end".to_string());
+        }
+        if name.is_empty() {
+            return Err("empty wildcard".to_string());
+        }
+        if !isValidWildcardName(name) {
+            return Err(format!("bad wildcard name {:?}", name));
+        }
+        if !seen.insert(name) {
+            return Err(format!("duplicate wildcard name {:?}", name));
+        }
+        p.segments.push(segment {
+            s: name.to_string(),
+            wild: true,
+            multi,
+        });
+    }
+    Ok(p)
+}
+
+// isValidWildcardName reports whether s is a Go identifier.
+fn isValidWildcardName(s: &str) -> bool {
+    s.chars()
+        .enumerate()
+        .all(|(i, c)| c == '_' || c.is_alphabetic() || (i > 0 && c.is_numeric()))
+}
+
+// firstSegment splits path, which starts with '/', into its first segment and the rest. A path of just "/" is the segment "/".
+fn firstSegment(path: &str) -> (&str, &str) {
+    if path == "/" {
+        return ("/", "");
+    }
+    let path = &path[1..];
+    let i = path.find('/').unwrap_or(path.len());
+    (&path[..i], &path[i..])
+}
+
+impl pattern {
+    fn lastSegmentIsMulti(&self) -> bool {
+        self.segments.last().is_some_and(|s| s.multi)
+    }
+
+    // matchesMethod reports whether the pattern accepts method. A GET pattern also accepts HEAD.
+    pub(crate) fn matchesMethod(&self, method: &str) -> bool {
+        self.method.is_empty()
+            || self.method == method
+            || (self.method == "GET" && method == "HEAD")
+    }
+
+    // matchPath matches path against the segments of the pattern and returns the values of its named wildcards.
+    pub(crate) fn matchPath(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let mut values = Vec::new();
+        let mut rest = path;
+        for seg in &self.segments {
+            if rest.is_empty() {
+                return None;
+            }
+            if seg.multi {
+                if !seg.s.is_empty() {
+                    values.push((seg.s.clone(), rest[1..].to_string()));
+                }
+                return Some(values);
+            }
+            let (s, r) = firstSegment(rest);
+            if seg.wild {
+                // 通配符不能匹配结尾的'/'。
+                if s == "/" {
+                    return None;
+                }
+                values.push((seg.s.clone(), s.to_string()));
+            } else if seg.s != s {
+                return None;
+            }
+            rest = r;
+        }
+        if rest.is_empty() {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    // exactMatch reports whether the pattern matches path without its trailing multi wildcard swallowing anything, so that "/a/" is an exact match for "/a/" but not for "/a/b".
+    pub(crate) fn exactMatch(&self, path: &str) -> bool {
+        if !self.lastSegmentIsMulti() {
+            return true;
+        }
+        if !path.ends_with('/') {
+            return false;
+        }
+        self.segments.len() == path.matches('/').count()
+    }
+
+    // conflictsWith reports whether the two patterns match some request in common without one of them being more specific than the other.
+    pub(crate) fn conflictsWith(&self, p2: &pattern) -> bool {
+        if self.host != p2.host {
+            // 一个有host一个没有时，有host的优先；两个host不同时不会匹配相同的请求。
+            return false;
+        }
+        matches!(
+            self.comparePathsAndMethods(p2),
+            relationship::equivalent | relationship::overlaps
+        )
+    }
+
+    pub(crate) fn comparePathsAndMethods(&self, p2: &pattern) -> relationship {
+        let mrel = self.compareMethods(p2);
+        if mrel == relationship::disjoint {
+            return mrel;
+        }
+        mrel.combine(self.comparePaths(p2))
+    }
+
+    fn compareMethods(&self, p2: &pattern) -> relationship {
+        if self.method == p2.method {
+            relationship::equivalent
+        } else if self.method.is_empty() {
+            relationship::moreGeneral
+        } else if p2.method.is_empty() {
+            relationship::moreSpecific
+        } else if self.method == "GET" && p2.method == "HEAD" {
+            relationship::moreGeneral
+        } else if self.method == "HEAD" && p2.method == "GET" {
+            relationship::moreSpecific
+        } else {
+            relationship::disjoint
+        }
+    }
+
+    fn comparePaths(&self, p2: &pattern) -> relationship {
+        if self.segments.len() != p2.segments.len()
+            && !self.lastSegmentIsMulti()
+            && !p2.lastSegmentIsMulti()
+        {
+            return relationship::disjoint;
+        }
+        let mut rel = relationship::equivalent;
+        for (s1, s2) in self.segments.iter().zip(&p2.segments) {
+            rel = rel.combine(compareSegments(s1, s2));
+            if rel == relationship::disjoint {
+                return rel;
+            }
+        }
+        let (n1, n2) = (self.segments.len(), p2.segments.len());
+        if n1 == n2 {
+            rel
+        } else if n1 < n2 && self.lastSegmentIsMulti() {
+            rel.combine(relationship::moreGeneral)
+        } else if n2 < n1 && p2.lastSegmentIsMulti() {
+            rel.combine(relationship::moreSpecific)
+        } else {
+            relationship::disjoint
+        }
+    }
+}
+
+fn compareSegments(x: &segment, y: &segment) -> relationship {
+    if x.multi && y.multi {
+        return relationship::equivalent;
+    }
+    if x.multi {
+        return relationship::moreGeneral;
+    }
+    if y.multi {
+        return relationship::moreSpecific;
+    }
+    if x.wild && y.wild {
+        return relationship::equivalent;
+    }
+    if x.wild {
+        if y.s == "/" {
+            return relationship::disjoint;
+        }
+        return relationship::moreGeneral;
+    }
+    if y.wild {
+        if x.s == "/" {
+            return relationship::disjoint;
+        }
+        return relationship::moreSpecific;
+    }
+    if x.s == y.s {
+        relationship::equivalent
+    } else {
+        relationship::disjoint
+    }
+}
+
+// router holds the registered patterns of a ServeMux together with their handlers. It is generic over the handler type so that the same routing serves sync and async handlers.
+pub(crate) struct router<H> {
+    entries: Vec<(pattern, H)>,
+}
+
+impl<H> Default for router<H> {
+    fn default() -> Self {
+        router {
+            entries: Vec::new(),
+        }
+    }
+}
+
+// route is the outcome of looking up a request in a router.
+pub(crate) enum route<'a, H> {
+    // found is the most specific matching handler, its pattern and the values of its wildcards.
+    found(&'a H, &'a str, Vec<(String, String)>),
+    // redirect is the path the request should be redirected to, with a trailing slash or cleaned.
+    redirect(String),
+    // methodNotAllowed lists the methods the path would be accepted with.
+    methodNotAllowed(Vec<String>),
+    notFound,
+}
+
+// patternMatch is a matched pattern with its handler and the values of its wildcards.
+type patternMatch<'a, H> = (&'a pattern, &'a H, Vec<(String, String)>);
+
+impl<H> router<H> {
+    // register adds h under pattern s. It returns an error if s is not valid or conflicts with a registered pattern.
+    pub(crate) fn register(&mut self, s: &str, h: H) -> Result<(), String> {
+        let p = parsePattern(s).map_err(|err| format!("parsing {:?}: {}", s, err))?;
+        for (p2, _) in &self.entries {
+            if p.conflictsWith(p2) {
+                return Err(format!(
+                    "pattern {:?} conflicts with pattern {:?}",
+                    p.str, p2.str
+                ));
+            }
+        }
+        self.entries.push((p, h));
+        Ok(())
+    }
+
+    // findRequest looks up the handler for r the way a ServeMux does: by the host without its port, or the host of the URL for a CONNECT request, and with GET for an empty method.
+    pub(crate) fn findRequest(&self, r: &Request) -> route<'_, H> {
+        // CONNECT请求的路径不做规范化。
+        let host = if r.Method == "CONNECT" {
+            r.URL.Host.as_str()
+        } else {
+            stripHostPort(&r.Host)
+        };
+        let method = if r.Method.is_empty() {
+            "GET"
+        } else {
+            r.Method.as_str()
+        };
+        self.find(method, host, &r.URL.Path, &r.URL.RawQuery)
+    }
+
+    // find looks up the handler for a request. host has no port. path is the path of the URL and rawQuery its query, which is kept on redirects. CONNECT requests are not redirected to a cleaned path.
+    pub(crate) fn find(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        rawQuery: &str,
+    ) -> route<'_, H> {
+        let withQuery = |p: String| {
+            if rawQuery.is_empty() {
+                p
+            } else {
+                format!("{}?{}", p, rawQuery)
+            }
+        };
+        let clean = if method == "CONNECT" {
+            path.to_string()
+        } else {
+            cleanPath(path)
+        };
+        let m = self.matchRequest(method, host, &clean);
+        // 没有精确匹配时，如果加上'/'之后有精确匹配，重定向到带'/'的路径。
+        if !m.as_ref().is_some_and(|(p, _, _)| p.exactMatch(&clean)) && !clean.ends_with('/') {
+            let slash = format!("{}/", clean);
+            if self
+                .matchRequest(method, host, &slash)
+                .is_some_and(|(p, _, _)| p.exactMatch(&slash))
+            {
+                return route::redirect(withQuery(slash));
+            }
+        }
+        if clean != path {
+            return route::redirect(withQuery(clean));
+        }
+        if let Some((p, h, values)) = m {
+            return route::found(h, p.str.as_str(), values);
+        }
+        let mut allowed: Vec<String> = Vec::new();
+        for (p, _) in self
+            .entries
+            .iter()
+            .filter(|(p, _)| p.host.is_empty() || p.host == host)
+        {
+            if p.matchPath(path).is_some() && !allowed.contains(&p.method) {
+                allowed.push(p.method.clone());
+            }
+        }
+        if allowed.is_empty() {
+            return route::notFound;
+        }
+        if allowed.iter().any(|m| m == "GET") && !allowed.iter().any(|m| m == "HEAD") {
+            allowed.push("HEAD".to_string());
+        }
+        allowed.sort();
+        route::methodNotAllowed(allowed)
+    }
+
+    // matchRequest returns the most specific pattern matching the request, preferring patterns with a host. Since registered patterns never conflict, among the matching patterns one is more specific than all the others.
+    fn matchRequest(&self, method: &str, host: &str, path: &str) -> Option<patternMatch<'_, H>> {
+        for host in [host, ""] {
+            let mut best: Option<patternMatch<'_, H>> = None;
+            for (p, h) in self.entries.iter().filter(|(p, _)| p.host == host) {
+                if !p.matchesMethod(method) {
+                    continue;
+                }
+                if let Some(values) = p.matchPath(path) {
+                    match &best {
+                        Some((b, _, _))
+                            if p.comparePathsAndMethods(b) != relationship::moreSpecific => {}
+                        _ => best = Some((p, h, values)),
+                    }
+                }
+            }
+            if best.is_some() || host.is_empty() {
+                return best;
+            }
+        }
+        None
+    }
+}
+
+// stripHostPort returns h without any trailing ":<port>" and without the brackets of an IPv6 literal.
+fn stripHostPort(h: &str) -> &str {
+    if let Some(rest) = h.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    match h.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') && port.bytes().all(|b| b.is_ascii_digit()) => {
+            host
+        }
+        _ => h,
+    }
+}
+
+// cleanPath returns the canonical path for p, eliminating . and .. elements and repeated slashes. A trailing slash is kept.
+pub(crate) fn cleanPath(p: &str) -> String {
+    if p.is_empty() {
+        return "/".to_string();
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    for seg in p.split('/') {
+        match seg {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            seg => parts.push(seg),
+        }
+    }
+    let mut np = format!("/{}", parts.join("/"));
+    if p.ends_with('/') && np != "/" {
+        np.push('/');
+    }
+    np
+}
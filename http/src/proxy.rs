@@ -0,0 +1,286 @@
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::env;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use gostd_strings as strings;
+use gostd_url as url;
+
+use crate::error::{HTTPConnectError, HttpResult};
+use crate::request::Request;
+
+/// ProxyFunc returns the proxy to use for a given request. Ok(None) means the request is sent directly.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ProxyFunc返回给定请求应使用的代理，返回Ok(None)表示不使用代理直接发送请求。
+/// </details>
+pub type ProxyFunc = Arc<dyn Fn(&Request) -> HttpResult<Option<url::URL>> + Send + Sync>;
+
+/// ProxyFromEnvironment returns the URL of the proxy to use for a given request, as indicated by the environment variables HTTP_PROXY, HTTPS_PROXY and NO_PROXY (or the lowercase versions thereof). Requests use the proxy from the environment variable matching their scheme, unless excluded by NO_PROXY.
+///
+/// The environment values may be either a complete URL or a "host[:port]", in which case the "http" scheme is assumed. An error is returned if the value is a different form.
+///
+/// Ok(None) is returned if no proxy is defined in the environment, or a proxy should not be used for the given request, as defined by NO_PROXY.
+///
+/// As a special case, if req.URL.Host is "localhost" (with or without a port number) or a loopback address, then Ok(None) is returned.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ProxyFromEnvironment使用环境变量HTTP_PROXY、HTTPS_PROXY和NO_PROXY(或其小写版本)返回给定请求应使用的代理URL。请求根据自身的协议选择对应的环境变量，被NO_PROXY排除的除外。
+///
+/// 环境变量的值可以是完整的URL，也可以是"host[:port]"，后者默认使用"http"协议。其他形式会返回错误。
+///
+/// 如果环境中没有定义代理，或者根据NO_PROXY该请求不应使用代理，返回Ok(None)。
+///
+/// 特殊情况：如果req.URL.Host是"localhost"(可以带端口)或回环地址，返回Ok(None)。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use std::sync::Arc;
+///
+/// let mut tr = http::Transport::default();
+/// tr.Proxy = Some(Arc::new(http::ProxyFromEnvironment));
+/// ```
+pub fn ProxyFromEnvironment(req: &Request) -> HttpResult<Option<url::URL>> {
+    proxyConfig::FromEnvironment().proxyForURL(&req.URL)
+}
+
+/// ProxyURL returns a proxy function (for use in a Transport) that always returns the same URL.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ProxyURL返回一个代理函数(用于Transport)，该函数总是返回同一个URL。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use gostd_url as url;
+///
+/// let mut tr = http::Transport::default();
+/// tr.Proxy = Some(http::ProxyURL(url::Parse("http://127.0.0.1:8080").unwrap()));
+/// ```
+pub fn ProxyURL(fixedURL: url::URL) -> ProxyFunc {
+    Arc::new(move |_: &Request| Ok(Some(fixedURL.clone())))
+}
+
+// proxyConfig holds configuration for HTTP proxy settings, mirroring golang.org/x/net/http/httpproxy.Config.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct proxyConfig {
+    // HTTPProxy represents the value of the HTTP_PROXY or http_proxy environment variable. It will be used as the proxy URL for HTTP requests unless overridden by NoProxy.
+    pub(crate) HTTPProxy: String,
+    // HTTPSProxy represents the HTTPS_PROXY or https_proxy environment variable. It will be used as the proxy URL for HTTPS requests unless overridden by NoProxy.
+    pub(crate) HTTPSProxy: String,
+    // NoProxy represents the NO_PROXY or no_proxy environment variable. It specifies a string that contains comma-separated values specifying hosts that should be excluded from proxying.
+    pub(crate) NoProxy: String,
+    // CGI holds whether the current process is running as a CGI handler (FromEnvironment infers this from the presence of a REQUEST_METHOD environment variable). When this is set, HTTPProxy is ignored, see golang.org/s/cgihttpproxy.
+    pub(crate) CGI: bool,
+}
+
+impl proxyConfig {
+    pub(crate) fn FromEnvironment() -> proxyConfig {
+        proxyConfig {
+            HTTPProxy: getEnvAny(&["HTTP_PROXY", "http_proxy"]),
+            HTTPSProxy: getEnvAny(&["HTTPS_PROXY", "https_proxy"]),
+            NoProxy: getEnvAny(&["NO_PROXY", "no_proxy"]),
+            CGI: env::var_os("REQUEST_METHOD").is_some(),
+        }
+    }
+
+    pub(crate) fn proxyForURL(&self, reqURL: &url::URL) -> HttpResult<Option<url::URL>> {
+        let proxy = match reqURL.Scheme.as_str() {
+            "https" => self.HTTPSProxy.as_str(),
+            "http" if !self.CGI => self.HTTPProxy.as_str(),
+            _ => "",
+        };
+        if proxy.is_empty() {
+            return Ok(None);
+        }
+        if !self.useProxy(canonicalProxyAddr(reqURL).as_str()) {
+            return Ok(None);
+        }
+        parseProxy(proxy).map(Some)
+    }
+
+    // useProxy reports whether requests to addr should use a proxy, according to the NO_PROXY or no_proxy environment variable. addr is always a canonicalAddr with a host and port.
+    fn useProxy(&self, addr: &str) -> bool {
+        if addr.is_empty() {
+            return true;
+        }
+        let (host, port) = splitHostPort(addr);
+        let host = host.to_ascii_lowercase();
+        if host == "localhost" {
+            return false;
+        }
+        let ip = host.parse::<IpAddr>().ok();
+        if let Some(ip) = ip {
+            if ip.is_loopback() {
+                return false;
+            }
+        }
+        for p in self.NoProxy.split(',') {
+            let p = p.trim().to_ascii_lowercase();
+            if p.is_empty() {
+                continue;
+            }
+            if p == "*" {
+                return false;
+            }
+            if let Some((network, bits)) = parseCIDR(&p) {
+                if let Some(ip) = ip {
+                    if cidrContains(network, bits, ip) {
+                        return false;
+                    }
+                }
+                continue;
+            }
+            let (mut phost, pport) = if p.parse::<IpAddr>().is_ok() {
+                (p.as_str(), "")
+            } else {
+                splitHostPort(&p)
+            };
+            if let Ok(pip) = phost
+                .trim_matches(|c| c == '[' || c == ']')
+                .parse::<IpAddr>()
+            {
+                if Some(pip) == ip && (pport.is_empty() || pport == port) {
+                    return false;
+                }
+                continue;
+            }
+            if phost.is_empty() {
+                continue;
+            }
+            // "*.example.com" and ".example.com" only match subdomains, while "example.com" also matches the domain itself.
+            if let Some(stripped) = phost.strip_prefix('*') {
+                phost = stripped;
+            }
+            let matchHost = !phost.starts_with('.');
+            let suffix = if matchHost {
+                format!(".{}", phost)
+            } else {
+                phost.to_string()
+            };
+            if (pport.is_empty() || pport == port)
+                && (host.ends_with(suffix.as_str()) || (matchHost && host == suffix[1..]))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn getEnvAny(names: &[&str]) -> String {
+    for n in names {
+        if let Ok(val) = env::var(n) {
+            if !val.is_empty() {
+                return val;
+            }
+        }
+    }
+    "".to_string()
+}
+
+fn parseProxy(proxy: &str) -> HttpResult<url::URL> {
+    if proxy.is_empty() {
+        return Err("invalid proxy address".into());
+    }
+    match url::Parse(proxy) {
+        Ok(proxyURL) if !proxyURL.Scheme.is_empty() && !proxyURL.Host.is_empty() => {
+            match proxyURL.Scheme.as_str() {
+                "http" | "https" | "socks5" => Ok(proxyURL),
+                _ => Err(HTTPConnectError::ConnectionFailure(format!(
+                    "invalid proxy address {:?}: unsupported scheme {:?}",
+                    proxy, proxyURL.Scheme
+                ))),
+            }
+        }
+        // proxy was bogus. Try prepending "http://" to it and see if that parses correctly.
+        _ => match url::Parse(format!("http://{}", proxy).as_str()) {
+            Ok(proxyURL) if !proxyURL.Host.is_empty() => Ok(proxyURL),
+            _ => Err(HTTPConnectError::ConnectionFailure(format!(
+                "invalid proxy address {:?}",
+                proxy
+            ))),
+        },
+    }
+}
+
+// canonicalProxyAddr returns url.Host but always with a ":port" suffix, like canonicalAddr in the transport.
+fn canonicalProxyAddr(u: &url::URL) -> String {
+    let port = match u.Port().as_str() {
+        "" => match u.Scheme.as_str() {
+            "https" => "443".to_string(),
+            "socks5" => "1080".to_string(),
+            _ => "80".to_string(),
+        },
+        p => p.to_string(),
+    };
+    format!("{}:{}", u.Hostname(), port)
+}
+
+// splitHostPort splits "host:port", "[ipv6]:port" or a bare host into host and port.
+fn splitHostPort(hostport: &str) -> (&str, &str) {
+    if let Some(rest) = hostport.strip_prefix('[') {
+        if let Some(end) = rest.find(']') {
+            let port = rest[end + 1..].strip_prefix(':').unwrap_or("");
+            return (&rest[..end], port);
+        }
+    }
+    match hostport.rfind(':') {
+        Some(i) if !hostport[..i].contains(':') => (&hostport[..i], &hostport[i + 1..]),
+        _ => (hostport, ""),
+    }
+}
+
+fn parseCIDR(s: &str) -> Option<(IpAddr, u32)> {
+    let (ip, bits) = s.split_once('/')?;
+    let ip = ip.parse::<IpAddr>().ok()?;
+    let bits = bits.parse::<u32>().ok()?;
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    if bits > max {
+        return None;
+    }
+    Some((ip, bits))
+}
+
+fn cidrContains(network: IpAddr, bits: u32, ip: IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(n), IpAddr::V4(i)) => {
+            let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+            u32::from(n) & mask == u32::from(i) & mask
+        }
+        (IpAddr::V6(n), IpAddr::V6(i)) => {
+            let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+            u128::from(n) & mask == u128::from(i) & mask
+        }
+        _ => false,
+    }
+}
+
+// splitProxyUserinfo separates the "user:password@" part of a proxy URL's host, which gostd_url keeps inside URL.Host, and returns the decoded credentials together with the URL stripped of them.
+pub(crate) fn splitProxyUserinfo(proxyURL: &url::URL) -> (Option<(String, String)>, url::URL) {
+    let mut u = proxyURL.clone();
+    let i = strings::LastIndex(u.Host.as_str(), "@");
+    if i < 0 {
+        return (None, u);
+    }
+    let userinfo = u.Host[..i as usize].to_string();
+    u.Host = u.Host[i as usize + 1..].to_string();
+    let (user, password, _) = strings::Cut(userinfo.as_str(), ":");
+    let unescape = |s: &str| url::PathUnescape(s).unwrap_or_else(|_| s.to_string());
+    (Some((unescape(user), unescape(password))), u)
+}
+
+// basicAuth returns the base64 encoded "username:password" used by the Basic authentication scheme. See RFC 7617.
+pub(crate) fn basicAuth(username: &str, password: &str) -> String {
+    STANDARD.encode(format!("{}:{}", username, password))
+}
@@ -4,41 +4,198 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
 use bytes::Bytes;
 use gostd_builtin::*;
 use gostd_io::*;
 use gostd_strings as strings;
 use gostd_url as url;
 
-use crate::{error::HttpResult, header::Header, method::Method};
+use crate::{
+    body::Body,
+    client::{fixPragmaCacheControl, ParseHTTPVersion},
+    cookies::{readCookies, sanitizeCookieName, sanitizeCookieValue, Cookie},
+    error::{HTTPConnectError, HttpResult},
+    header::{validHeaderFieldByte, CanonicalHeaderKey, Header},
+    httptrace::{noTrace, ClientTrace},
+    method::Method,
+    multipart,
+    server::DefaultMaxHeaderBytes,
+    tls, transfer,
+};
+
+/// GetBodyFunc returns a new copy of a request body. It is used to send the body again when a request is redirected or retried.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// GetBodyFunc返回请求body的一个新副本，用于请求重定向或重试时再次发送body。
+/// </details>
+pub type GetBodyFunc = Arc<dyn Fn() -> HttpResult<Body> + Send + Sync>;
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone)]
 pub struct Request {
     pub Method: String,
     pub URL: url::URL,
-    Proto: String,
-    ProtoMajor: int,
-    ProtoMinor: int,
+    /// The protocol version for incoming server requests, such as "HTTP/1.0". For client requests these fields are ignored; requests are always sent as HTTP/1.1 or HTTP/2.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 服务端收到的请求的协议版本，例如"HTTP/1.0"。客户端请求忽略这些字段，总是以HTTP/1.1或HTTP/2发送。
+    /// </details>
+    pub Proto: String,
+    pub ProtoMajor: int,
+    pub ProtoMinor: int,
     pub Header: Header,
-    pub Body: Option<Bytes>,
-    // GetBody func() (io.ReadCloser, error)
+    /// Body is the request's body. None means the request has no body. For client requests a body of unknown length (ContentLength 0 or -1 with a reader backed Body) is sent with chunked transfer encoding.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Body是请求的主体，None表示没有body。客户端请求中长度未知的body(ContentLength为0或-1且Body基于Reader)使用chunked传输编码发送。
+    /// </details>
+    pub Body: Option<Body>,
+    /// GetBody defines an optional func to return a new copy of Body. It is used for client requests when a redirect requires reading the body more than once. Use of GetBody still requires setting Body.
+    ///
+    /// For server requests, it is unused.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// GetBody是一个可选的函数，返回Body的新副本。当客户端请求的重定向需要多次读取body时使用。使用GetBody时仍然需要设置Body。
+    ///
+    /// 服务端请求不使用该字段。
+    /// </details>
+    pub GetBody: Option<GetBodyFunc>,
     pub ContentLength: int64,
-    TransferEncoding: Vec<String>,
+    /// TransferEncoding lists the transfer encodings from outermost to innermost. For server requests it is ["chunked"] when the body was sent with chunked transfer encoding, and empty otherwise.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// TransferEncoding按从外到内的顺序列出传输编码。对于服务端请求，body使用chunked传输编码时为["chunked"]，否则为空。
+    /// </details>
+    pub TransferEncoding: Vec<String>,
     pub Close: bool,
     pub Host: String,
-    Form: url::Values,
-    PostForm: url::Values,
-    // MultipartForm:*multipart.Form,
-    Trailer: Header,
-    RemoteAddr: String,
-    RequestURI: String,
+    /// Form contains the parsed form data, including both the URL field's query parameters and the PATCH, POST, or PUT form data. This field is only available after ParseForm is called. The HTTP client ignores Form and uses Body instead.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Form包含解析后的表单数据，包括URL中的查询参数和PATCH、POST或PUT请求的表单数据。只有调用ParseForm之后该字段才有值。HTTP客户端忽略Form，使用Body。
+    /// </details>
+    pub Form: url::Values,
+    /// PostForm contains the parsed form data from PATCH, POST or PUT body parameters. This field is only available after ParseForm is called. The HTTP client ignores PostForm and uses Body instead.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// PostForm包含从PATCH、POST或PUT请求body中解析的表单数据。只有调用ParseForm之后该字段才有值。HTTP客户端忽略PostForm，使用Body。
+    /// </details>
+    pub PostForm: url::Values,
+    /// MultipartForm is the parsed multipart form, including file uploads. This field is only available after ParseMultipartForm is called. The HTTP client ignores MultipartForm and uses Body instead.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// MultipartForm是解析后的multipart表单，包括上传的文件。只有调用ParseMultipartForm之后该字段才有值。HTTP客户端忽略MultipartForm，使用Body。
+    /// </details>
+    pub MultipartForm: Option<multipart::Form>,
+    /// Trailer specifies additional headers that are sent after the request body. For server requests it initially contains only the keys announced by the Trailer header, with no values; the values are returned by Body.Trailer() once the body has been read to EOF.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Trailer指定在请求body之后发送的附加header。对于服务端请求，最初只包含Trailer头声明的key，没有值；body读到EOF之后，由Body.Trailer()返回这些值。
+    /// </details>
+    pub Trailer: Header,
+    /// RemoteAddr allows HTTP servers and other software to record the network address that sent the request, usually for logging. ReadRequest leaves it empty; the Server sets it to the "IP:port" of the client.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// RemoteAddr记录发送请求的网络地址，通常用于日志。ReadRequest不设置该字段，由Server设置为客户端的"IP:port"。
+    /// </details>
+    pub RemoteAddr: String,
+    /// RequestURI is the unmodified request-target of the Request-Line (RFC 7230, Section 3.1.1) as sent by the client to a server. It is an error to set this field in a client request.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// RequestURI是客户端发送给服务端的请求行中未经修改的request-target(RFC 7230 3.1.1节)。客户端请求中不应设置该字段。
+    /// </details>
+    pub RequestURI: String,
     pub isTLS: bool,
-    // TLS *tls.ConnectionState,
+    /// TLS allows HTTP servers and other software to record information about the TLS connection on which the request was received. The Server sets it for connections accepted by ServeTLS and ListenAndServeTLS, and leaves it None otherwise. It is ignored by the HTTP client.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// TLS记录接收请求的TLS连接的信息。对于ServeTLS和ListenAndServeTLS接受的连接，Server会设置该字段，其他情况为None。HTTP客户端忽略该字段。
+    /// </details>
+    pub TLS: Option<tls::ConnectionState>,
     // Cancel <-chan struct{}
     // ctx context.Context
+    // deadline是Client.Timeout换算出的截止时间，作用相当于Go中ctx的deadline，由Transport在各个阶段检查。
+    pub(crate) deadline: Option<Instant>,
+    // trace是httptrace.WithClientTrace附加的钩子，作用相当于Go中保存在ctx里的ClientTrace。
+    pub(crate) trace: Option<Arc<ClientTrace>>,
+    // pathValues保存ServeMux匹配到的路径通配符的值。
+    pathValues: HashMap<String, String>,
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("Method", &self.Method)
+            .field("URL", &self.URL)
+            .field("Proto", &self.Proto)
+            .field("ProtoMajor", &self.ProtoMajor)
+            .field("ProtoMinor", &self.ProtoMinor)
+            .field("Header", &self.Header)
+            .field("Body", &self.Body)
+            .field("GetBody", &self.GetBody.as_ref().map(|_| "func"))
+            .field("ContentLength", &self.ContentLength)
+            .field("TransferEncoding", &self.TransferEncoding)
+            .field("Close", &self.Close)
+            .field("Host", &self.Host)
+            .field("Form", &self.Form)
+            .field("PostForm", &self.PostForm)
+            .field("MultipartForm", &self.MultipartForm)
+            .field("Trailer", &self.Trailer)
+            .field("RemoteAddr", &self.RemoteAddr)
+            .field("RequestURI", &self.RequestURI)
+            .field("isTLS", &self.isTLS)
+            .field("TLS", &self.TLS)
+            .finish()
+    }
 }
+
 impl Request {
     pub fn New(method: Method, url: &str, body: Option<Bytes>) -> HttpResult<Request> {
+        match body {
+            Some(buf) => Request::NewWithBody(method, url, buf),
+            None => Request::newRequest(method, url),
+        }
+    }
+
+    /// NewWithBody returns a new Request sending body, which may be built from bytes or from any reader with Body::New.
+    ///
+    /// For a body held in memory ContentLength is set to its exact length and GetBody is set so that the body can be sent again on redirects. A reader backed body has an unknown length and is sent with chunked transfer encoding unless ContentLength is set by the caller.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// NewWithBody返回一个发送body的新请求，body可以由字节构造，也可以通过Body::New由任意Reader构造。
+    ///
+    /// 对于内存中的body，ContentLength会被设置为其实际长度，并设置GetBody以便重定向时再次发送body。基于Reader的body长度未知，除非调用者设置ContentLength，否则使用chunked传输编码发送。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use gostd_http as http;
+    ///
+    /// let f = std::fs::File::open("upload.bin").unwrap();
+    /// let mut req =
+    ///     http::Request::NewWithBody(http::Method::Put, "http://127.0.0.1:8080/upload", http::Body::New(f))
+    ///         .unwrap();
+    /// let resp = http::Client::New().Do(&mut req).unwrap();
+    /// ```
+    pub fn NewWithBody(method: Method, url: &str, body: impl Into<Body>) -> HttpResult<Request> {
+        let mut req = Request::newRequest(method, url)?;
+        let body = body.into();
+        if let Some(n) = body.knownLen() {
+            req.ContentLength = n as int64;
+            let snapshot = body.clone();
+            req.GetBody = Some(Arc::new(move || Ok(snapshot.clone())));
+        }
+        req.Body = Some(body);
+        Ok(req)
+    }
+
+    fn newRequest(method: Method, url: &str) -> HttpResult<Request> {
         let mut u = url::Parse(url)?;
 
         u.Host = removeEmptyPort(u.Host.as_str()).to_string();
@@ -54,63 +211,479 @@ impl Request {
             Close: false,
             Form: url::Values::default(),
             PostForm: url::Values::default(),
+            MultipartForm: None,
             Trailer: Header::default(),
             RemoteAddr: "".to_string(),
             RequestURI: "".to_string(),
             Body: None,
+            GetBody: None,
             Host: u.Host.to_owned(),
             isTLS: false,
+            TLS: None,
+            deadline: None,
+            trace: None,
+            pathValues: HashMap::new(),
         };
-        if let Some(buf) = body {
-            req.ContentLength = len!(buf) as i64;
-
-            req.Body = Some(buf);
-        }
         if strings::HasPrefix(url, "https://") {
             req.isTLS = true
         }
         Ok(req)
     }
 
-    pub fn Write(&self) -> HttpResult<Vec<u8>> {
-        self.write(false)
+    /// Write writes an HTTP/1.1 request, which is the header and body, in wire format to w. The body is copied to w as it is read, using chunked transfer encoding when its length is unknown.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Write以HTTP/1.1线路格式将请求的header和body写入w。body边读边写入w，长度未知时使用chunked传输编码。
+    /// </details>
+    pub fn Write(&self, w: &mut dyn Write) -> HttpResult<()> {
+        self.write(w, false)
+    }
+
+    // write writes the request in wire format. If usingProxy is true, the request line uses the absolute-form URI expected by HTTP proxies.
+    pub(crate) fn write(&self, w: &mut dyn Write, usingProxy: bool) -> HttpResult<()> {
+        let mut bw = BufWriter::new(w);
+        bw.write_all(self.writeHead(usingProxy).as_bytes())?;
+        self.trace().wroteHeaders();
+        if let Some(body) = &self.Body {
+            transfer::writeBody(&mut bw, &mut body.clone(), self.outgoingLength())?;
+        }
+        bw.flush()?;
+        Ok(())
+    }
+
+    // writeBody writes only the body of the request. It is used once the server has answered a request sent with "Expect: 100-continue".
+    pub(crate) fn writeBody(&self, w: &mut dyn Write) -> HttpResult<()> {
+        let mut bw = BufWriter::new(w);
+        if let Some(body) = &self.Body {
+            transfer::writeBody(&mut bw, &mut body.clone(), self.outgoingLength())?;
+        }
+        bw.flush()?;
+        Ok(())
+    }
+
+    /// AddCookie adds a cookie to the request. Per RFC 6265 section 5.4, AddCookie does not attach more than one Cookie header field. That means all cookies, if any, are written into the same line, separated by semicolon. AddCookie only sanitizes c's name and value, and does not sanitize a Cookie header already present in the request.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// AddCookie向请求添加一个cookie。按照RFC 6265第5.4节，AddCookie不会添加多个Cookie头字段，所有cookie都写在同一行，用分号分隔。AddCookie只清理c的名字和值，不会处理请求中已有的Cookie头。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    ///
+    /// let mut req = http::Request::default();
+    /// let mut c = http::Cookie::default();
+    /// c.Name = "a".to_string();
+    /// c.Value = "1".to_string();
+    /// req.AddCookie(&c);
+    /// c.Name = "b".to_string();
+    /// c.Value = "2".to_string();
+    /// req.AddCookie(&c);
+    /// assert_eq!(req.Header.Get("Cookie"), "a=1; b=2");
+    /// ```
+    pub fn AddCookie(&mut self, c: &Cookie) {
+        let s = format!(
+            "{}={}",
+            sanitizeCookieName(&c.Name),
+            sanitizeCookieValue(&c.Value)
+        );
+        let cookie = self.Header.Get("Cookie");
+        if cookie.is_empty() {
+            self.Header.Set("Cookie", s.as_str());
+        } else {
+            self.Header
+                .Set("Cookie", format!("{}; {}", cookie, s).as_str());
+        }
     }
 
-    fn write(&self, usingProxy: bool) -> HttpResult<Vec<u8>> {
+    /// Cookies parses and returns the HTTP cookies sent with the request.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Cookies解析并返回请求中携带的cookie。
+    /// </details>
+    pub fn Cookies(&self) -> Vec<Cookie> {
+        readCookies(&self.Header, "")
+    }
+
+    /// Cookie returns the named cookie provided in the request or ErrNoCookie if not found. If multiple cookies match the given name, only one cookie will be returned.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Cookie返回请求中指定名字的cookie，找不到时返回ErrNoCookie。如果有多个同名cookie，只返回其中一个。
+    /// </details>
+    pub fn Cookie(&self, name: &str) -> HttpResult<Cookie> {
+        if name.is_empty() {
+            return Err(HTTPConnectError::ErrNoCookie);
+        }
+        readCookies(&self.Header, name)
+            .into_iter()
+            .next()
+            .ok_or(HTTPConnectError::ErrNoCookie)
+    }
+
+    /// PathValue returns the value for the named path wildcard in the ServeMux pattern that matched the request. It returns the empty string if the request was not matched against a pattern or there is no such wildcard in the pattern.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// PathValue返回与请求匹配的ServeMux模式中指定名字的路径通配符的值。如果请求没有经过模式匹配，或者模式中没有该通配符，返回空字符串。
+    /// </details>
+    pub fn PathValue(&self, name: &str) -> String {
+        self.pathValues.get(name).cloned().unwrap_or_default()
+    }
+
+    /// SetPathValue sets name to value, so that subsequent calls to PathValue(name) return value.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// SetPathValue把name设置为value，之后调用PathValue(name)会返回value。
+    /// </details>
+    pub fn SetPathValue(&mut self, name: &str, value: &str) {
+        self.pathValues.insert(name.to_string(), value.to_string());
+    }
+
+    /// ParseForm populates Form and PostForm.
+    ///
+    /// For all requests, ParseForm parses the raw query from the URL and updates Form. For POST, PUT, and PATCH requests, it also reads the request body, parses it as a form and puts the results into both PostForm and Form. Request body parameters take precedence over URL query string values in Form. Only bodies with Content-Type application/x-www-form-urlencoded are parsed, and they are limited to 10 MB.
+    ///
+    /// ParseMultipartForm calls ParseForm automatically. ParseForm is idempotent. Handlers of the async server must read the body with ReadAllAsync and put it back with Body::from before calling it.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ParseForm填充Form和PostForm。
+    ///
+    /// 对于所有请求，ParseForm解析URL中的查询字符串并更新Form。对于POST、PUT和PATCH请求，还会读取请求body，将其解析为表单，并把结果同时放入PostForm和Form。在Form中，请求body中的参数优先于URL查询字符串中的值。只解析Content-Type为application/x-www-form-urlencoded的body，大小限制为10MB。
+    ///
+    /// ParseMultipartForm会自动调用ParseForm。ParseForm是幂等的。异步服务器的handler需要先用ReadAllAsync读取body，再用Body::from放回，然后再调用该方法。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    ///
+    /// let mut req = http::Request::New(
+    ///     http::Method::Post,
+    ///     "http://example.com/?q=query",
+    ///     Some("q=body&z=post".into()),
+    /// )
+    /// .unwrap();
+    /// req.Header.Set("Content-Type", "application/x-www-form-urlencoded");
+    /// req.ParseForm().unwrap();
+    /// assert_eq!(req.FormValue("q"), "body");
+    /// assert_eq!(req.PostFormValue("z"), "post");
+    /// ```
+    pub fn ParseForm(&mut self) -> HttpResult<()> {
+        let mut err = None;
+        if isEmptyValues(&self.PostForm) && matches!(self.Method.as_str(), "POST" | "PUT" | "PATCH")
+        {
+            match self.parsePostForm() {
+                Ok(v) => self.PostForm = v,
+                Err(e) => err = Some(e),
+            }
+        }
+        if isEmptyValues(&self.Form) {
+            // body中的参数排在查询字符串之前，FormValue优先返回body中的值。
+            let mut raw = self.PostForm.Encode();
+            if !self.URL.RawQuery.is_empty() {
+                if !raw.is_empty() {
+                    raw.push('&');
+                }
+                raw.push_str(&self.URL.RawQuery);
+            }
+            match url::ParseQuery(&raw) {
+                Ok(v) => self.Form = v,
+                Err(e) => {
+                    self.Form = self.PostForm.clone();
+                    err.get_or_insert(e.into());
+                }
+            }
+        }
+        err.map_or(Ok(()), Err)
+    }
+
+    fn parsePostForm(&mut self) -> HttpResult<url::Values> {
+        let mut ct = self.Header.Get("Content-Type");
+        // RFC 7231 3.1.1.5: 缺少Content-Type时按application/octet-stream处理。
+        if ct.is_empty() {
+            ct = "application/octet-stream".to_string();
+        }
+        let (ct, _) = multipart::parseMediaType(&ct)?;
+        let body = match self.Body.as_mut() {
+            Some(body) if ct == "application/x-www-form-urlencoded" => body,
+            _ => return Ok(url::Values::default()),
+        };
+        let mut b = Vec::new();
+        body.take(maxFormSize as u64 + 1).read_to_end(&mut b)?;
+        if b.len() as int64 > maxFormSize {
+            return Err(HTTPConnectError::ErrMessageTooLarge);
+        }
+        Ok(url::ParseQuery(&String::from_utf8_lossy(&b))?)
+    }
+
+    /// ParseMultipartForm parses a request body as multipart/form-data. The whole request body is parsed and up to a total of maxMemory bytes of its file parts are stored in memory, with the remainder stored on disk in temporary files. ParseMultipartForm calls ParseForm if necessary. If ParseForm returns an error, ParseMultipartForm returns it but also continues parsing the request body. After one call to ParseMultipartForm, subsequent calls have no effect.
+    ///
+    /// The server removes the temporary files once the handler returns.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ParseMultipartForm将请求body解析为multipart/form-data。整个请求body都会被解析，其中文件part最多共maxMemory字节保存在内存中，其余保存在磁盘上的临时文件中。必要时ParseMultipartForm会调用ParseForm，ParseForm返回错误时，ParseMultipartForm返回该错误，但仍会继续解析请求body。调用一次ParseMultipartForm之后，再次调用不会产生任何效果。
+    ///
+    /// handler返回后，服务器会删除这些临时文件。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    /// use http::multipart;
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut w = multipart::NewWriter(&mut buf);
+    /// w.WriteField("title", "gopher").unwrap();
+    /// let ct = w.FormDataContentType();
+    /// w.Close().unwrap();
+    ///
+    /// let mut req = http::Request::New(http::Method::Post, "http://example.com/upload", Some(buf.into())).unwrap();
+    /// req.Header.Set("Content-Type", &ct);
+    /// req.ParseMultipartForm(32 << 20).unwrap();
+    /// assert_eq!(req.FormValue("title"), "gopher");
+    /// ```
+    pub fn ParseMultipartForm(&mut self, maxMemory: int64) -> HttpResult<()> {
+        if self.MultipartForm.is_some() {
+            return Ok(());
+        }
+        let mut parseFormErr = None;
+        if isEmptyValues(&self.Form) {
+            parseFormErr = self.ParseForm().err();
+        }
+        let boundary = self.multipartBoundary()?;
+        let f = match self.Body.as_mut() {
+            Some(body) => multipart::NewReader(body, &boundary).ReadForm(maxMemory)?,
+            None => multipart::NewReader(io::empty(), &boundary).ReadForm(maxMemory)?,
+        };
+        for (k, vs) in &f.Value {
+            for v in vs {
+                self.Form.Add(k, v);
+                // 同时更新PostForm。
+                self.PostForm.Add(k, v);
+            }
+        }
+        self.MultipartForm = Some(f);
+        parseFormErr.map_or(Ok(()), Err)
+    }
+
+    // multipartBoundary返回multipart/form-data请求的boundary。
+    fn multipartBoundary(&self) -> HttpResult<String> {
+        let v = self.Header.Get("Content-Type");
+        if v.is_empty() {
+            return Err(HTTPConnectError::ErrNotMultipart);
+        }
+        match multipart::parseMediaType(&v) {
+            Ok((d, params)) if d == "multipart/form-data" => params
+                .get("boundary")
+                .cloned()
+                .ok_or(HTTPConnectError::ErrMissingBoundary),
+            _ => Err(HTTPConnectError::ErrNotMultipart),
+        }
+    }
+
+    /// FormValue returns the first value for the named component of the query. Body parameters of POST, PUT and PATCH requests take precedence over URL query string values. FormValue calls ParseMultipartForm and ParseForm if necessary and ignores any errors returned by these functions. If key is not present, FormValue returns the empty string. To access multiple values of the same key, call ParseForm and then inspect Form directly.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// FormValue返回查询中指定名字的第一个值。POST、PUT和PATCH请求body中的参数优先于URL查询字符串中的值。必要时FormValue会调用ParseMultipartForm和ParseForm，并忽略它们返回的错误。key不存在时返回空字符串。要访问同一个key的多个值，请调用ParseForm后直接检查Form。
+    /// </details>
+    pub fn FormValue(&mut self, key: &str) -> String {
+        if isEmptyValues(&self.Form) {
+            let _ = self.ParseMultipartForm(defaultMaxMemory);
+        }
+        self.Form.Get(key)
+    }
+
+    /// PostFormValue returns the first value for the named component of the POST, PUT, or PATCH request body. URL query parameters are ignored. PostFormValue calls ParseMultipartForm and ParseForm if necessary and ignores any errors returned by these functions. If key is not present, PostFormValue returns the empty string.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// PostFormValue返回POST、PUT或PATCH请求body中指定名字的第一个值，忽略URL查询参数。必要时PostFormValue会调用ParseMultipartForm和ParseForm，并忽略它们返回的错误。key不存在时返回空字符串。
+    /// </details>
+    pub fn PostFormValue(&mut self, key: &str) -> String {
+        if isEmptyValues(&self.PostForm) {
+            let _ = self.ParseMultipartForm(defaultMaxMemory);
+        }
+        self.PostForm.Get(key)
+    }
+
+    /// FormFile returns the first file for the provided form key. FormFile calls ParseMultipartForm and ParseForm if necessary.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// FormFile返回表单中指定key的第一个文件。必要时FormFile会调用ParseMultipartForm和ParseForm。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use gostd_http as http;
+    /// use std::io::Read;
+    ///
+    /// fn upload(w: &mut dyn http::server::ResponseWriter, r: &mut http::Request) {
+    ///     let (mut file, header) = match r.FormFile("upload") {
+    ///         Ok(f) => f,
+    ///         Err(err) => return http::server::Error(w, &err.to_string(), 400),
+    ///     };
+    ///     let mut content = Vec::new();
+    ///     file.read_to_end(&mut content).unwrap();
+    ///     println!("{}: {} bytes", header.Filename, header.Size);
+    /// }
+    /// ```
+    pub fn FormFile(&mut self, key: &str) -> HttpResult<(multipart::File, multipart::FileHeader)> {
+        if self.MultipartForm.is_none() {
+            self.ParseMultipartForm(defaultMaxMemory)?;
+        }
+        let fh = self
+            .MultipartForm
+            .as_ref()
+            .and_then(|f| f.File.get(key))
+            .and_then(|fhs| fhs.first())
+            .ok_or(HTTPConnectError::ErrMissingFile)?;
+        Ok((fh.Open()?, fh.clone()))
+    }
+
+    /// ProtoAtLeast reports whether the HTTP protocol used in the request is at least major.minor.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ProtoAtLeast报告请求使用的HTTP协议版本是否至少为major.minor。
+    /// </details>
+    pub fn ProtoAtLeast(&self, major: int, minor: int) -> bool {
+        self.ProtoMajor > major || self.ProtoMajor == major && self.ProtoMinor >= minor
+    }
+
+    // expectsContinue reports whether the request carries an "Expect: 100-continue" header.
+    pub(crate) fn expectsContinue(&self) -> bool {
+        transfer::headerValuesContainsToken(self.Header.Values("Expect"), "100-continue")
+    }
+
+    // writeHead returns the request line and header block, including the framing headers for the body.
+    pub(crate) fn writeHead(&self, usingProxy: bool) -> String {
         let mut buf = strings::Builder::new();
-        let host = self.Host.clone();
-        let ruri = self.URL.RequestURI();
-        let userAgent = "rust-http-client/1.1";
+        let mut host = self.Host.clone();
+        if host.is_empty() {
+            host = self.URL.Host.clone();
+        }
+        let mut ruri = self.URL.RequestURI();
+        if usingProxy && !self.URL.Scheme.is_empty() && self.URL.Opaque.is_empty() {
+            ruri = format!("{}://{}{}", self.URL.Scheme, host, ruri);
+        }
+        let mut userAgent = self.Header.Get("User-Agent");
+        if userAgent.is_empty() {
+            userAgent = defaultUserAgent.to_string();
+        }
         buf.WriteString(format!("{} {} HTTP/1.1\r\n", self.Method.as_str(), ruri).as_str());
         buf.WriteString(format!("Host: {}\r\n", host).as_str());
         buf.WriteString(format!("User-Agent: {}\r\n", userAgent).as_str());
         buf.WriteString(self.writeHeader().as_str());
         buf.WriteString("\r\n");
-        if let Some(body) = &self.Body {
-            buf.Write(body.to_vec())?;
-            buf.WriteString("\r\n");
-        }
-        Ok(buf.Bytes())
+        buf.String()
     }
 
     fn writeHeader(&self) -> String {
         let mut buf = strings::Builder::new();
-        for (k, v) in &self.Header.0 {
-            if len!(v) > 1 {
-                let value = strings::Join(v.iter().map(|x| x.as_str()).collect(), ",");
-                buf.WriteString(format!("{}: {}\r\n", k.as_str(), value.as_str()).as_str());
-            } else {
-                buf.WriteString(format!("{}: {}\r\n", k.as_str(), v[0].as_str()).as_str());
+        // 传输相关的header由Body决定，忽略用户设置的值。多个值各写一行，Set-Cookie等字段不能用逗号合并。
+        let exclude = reqWriteExcludeHeader
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let mut head = Vec::new();
+        self.Header
+            .WriteSubset(&mut head, &exclude)
+            .expect("writing to a Vec cannot fail");
+        buf.WriteString(String::from_utf8_lossy(&head).as_ref());
+        match self.outgoingLength() {
+            -1 => {
+                buf.WriteString("Transfer-Encoding: chunked\r\n");
+            }
+            0 if !matches!(self.Method.as_str(), "POST" | "PUT" | "PATCH") => (),
+            n => {
+                buf.WriteString(format!("Content-Length: {}\r\n", n).as_str());
             }
-        }
-        if self.ContentLength > 0 {
-            buf.WriteString(format!("Content-Length: {}\r\n", self.ContentLength).as_str());
         }
         buf.String()
     }
+
+    // outgoingLength reports the Content-Length of this outgoing (Client) request. It maps 0 into -1 (unknown) when the Body is a reader of unknown length.
+    pub(crate) fn outgoingLength(&self) -> int64 {
+        match &self.Body {
+            None => 0,
+            Some(body) if self.ContentLength > 0 => self.ContentLength,
+            Some(body) => match body.knownLen() {
+                Some(n) => n as int64,
+                None => -1,
+            },
+        }
+    }
+
+    // rewindBody replaces an already consumed Body with a fresh copy from GetBody, so the request can be sent again.
+    pub(crate) fn rewindBody(&mut self) -> HttpResult<()> {
+        if self.Body.is_none() {
+            return Ok(());
+        }
+        match &self.GetBody {
+            Some(getBody) => {
+                self.Body = Some(getBody()?);
+                Ok(())
+            }
+            None => Err("http: cannot rewind body after connection loss".into()),
+        }
+    }
+
+    // trace returns the ClientTrace attached with httptrace.WithClientTrace, or one without hooks.
+    pub(crate) fn trace(&self) -> &ClientTrace {
+        self.trace.as_deref().unwrap_or(&noTrace)
+    }
+
+    // requiresHTTP1 reports whether this request requires being sent on an HTTP/1 connection, like a websocket upgrade.
+    pub(crate) fn requiresHTTP1(&self) -> bool {
+        self.Header
+            .Get("Connection")
+            .split(',')
+            .any(|t| t.trim().eq_ignore_ascii_case("upgrade"))
+            && self.Header.Get("Upgrade").eq_ignore_ascii_case("websocket")
+    }
+
+    // isReplayable reports whether the request may safely be sent again on a new connection after a failure on a reused one.
+    pub(crate) fn isReplayable(&self) -> bool {
+        if self.Body.is_some() && self.GetBody.is_none() {
+            return false;
+        }
+        match self.Method.as_str() {
+            "" | "GET" | "HEAD" | "OPTIONS" | "TRACE" => true,
+            // The Idempotency-Key, while non-standard, is widely used to
+            // mean a POST or other request is idempotent. See
+            // https://golang.org/issue/19943#issuecomment-421092421
+            _ => {
+                !self.Header.Get("Idempotency-Key").is_empty()
+                    || !self.Header.Get("X-Idempotency-Key").is_empty()
+            }
+        }
+    }
 }
 
-fn hasPort(s: &str) -> bool {
+const defaultUserAgent: &str = "rust-http-client/1.1";
+
+// defaultMaxMemory是FormValue等方法调用ParseMultipartForm时使用的内存限制(32MB)。
+const defaultMaxMemory: int64 = 32 << 20;
+
+// isEmptyValues报告v是否不包含任何key，相当于Go中检查Form是否为nil。
+fn isEmptyValues(v: &url::Values) -> bool {
+    *v == url::Values::default()
+}
+
+// maxFormSize限制ParseForm读取的application/x-www-form-urlencoded body的大小(10MB)。
+const maxFormSize: int64 = 10 << 20;
+
+// Headers that Request.Write handles itself and should be skipped.
+const reqWriteExcludeHeader: [&str; 5] = [
+    "Host",
+    "User-Agent",
+    "Content-Length",
+    "Transfer-Encoding",
+    "Trailer",
+];
+
+pub(crate) fn hasPort(s: &str) -> bool {
     strings::LastIndex(s, ":") > strings::LastIndex(s, "]")
 }
 
@@ -122,3 +695,270 @@ fn removeEmptyPort(host: &str) -> &str {
     }
     host
 }
+
+/// ReadRequest reads and parses an incoming request from b. It handles the origin ("/path?q"), absolute ("http://host/path"), authority ("host:port", CONNECT only) and asterisk ("*", OPTIONS only) forms of the request target. The request line and header together may not exceed DefaultMaxHeaderBytes.
+///
+/// The returned Body streams the rest of the message from b, decoding chunked transfer encoding if needed; trailer values are available from Body.Trailer() once it has been read to EOF. ReadRequest does not answer "Expect: 100-continue": the header is left in Header and the caller must send "HTTP/1.1 100 Continue" itself before reading Body.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ReadRequest从b读取并解析一个传入的请求。支持request target的origin("/path?q")、absolute("http://host/path")、authority("host:port"，仅限CONNECT)和asterisk("*"，仅限OPTIONS)形式。请求行和header加起来不能超过DefaultMaxHeaderBytes。
+///
+/// 返回的Body从b上流式读取剩余的消息内容，需要时解码chunked传输编码；body读到EOF之后，可以通过Body.Trailer()获取trailer的值。ReadRequest不会回应"Expect: 100-continue"：该头保留在Header中，调用者必须在读取Body之前自己发送"HTTP/1.1 100 Continue"。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use std::io::Cursor;
+///
+/// let raw = "POST /upload?x=1 HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\n\r\nhello";
+/// let mut req = http::request::ReadRequest(Cursor::new(raw)).unwrap();
+/// assert_eq!(req.Method, "POST");
+/// assert_eq!(req.RequestURI, "/upload?x=1");
+/// assert_eq!(req.Host, "example.com");
+/// assert_eq!(req.Body.as_mut().unwrap().ReadAll().unwrap(), "hello");
+/// ```
+pub fn ReadRequest<R: BufRead + Send + 'static>(mut b: R) -> HttpResult<Request> {
+    let (mut req, state) = readRequest(&mut b, DefaultMaxHeaderBytes)?;
+    if !state.isDone() {
+        let trailer = state.trailer();
+        req.Body = Some(Body::New(transfer::transferReader::new(b, state)).withTrailer(trailer));
+    }
+    Ok(req)
+}
+
+// readRequest reads the request line and header of a request, leaving its body on b. The request line and header together may take at most maxHeaderBytes bytes. It returns the framing of the body still to be read.
+pub(crate) fn readRequest(
+    b: &mut impl BufRead,
+    maxHeaderBytes: int,
+) -> HttpResult<(Request, transfer::transferState)> {
+    let mut remaining = maxHeaderBytes.max(0) as u64;
+    // RFC 7230 3.5: 请求行之前的空行应当忽略。
+    let mut line = String::new();
+    while line.is_empty() {
+        line = readLine(b, &mut remaining)?;
+    }
+
+    let mut req = Request::default();
+    let parts: Vec<&str> = line.split(' ').collect();
+    if parts.len() != 3 {
+        return Err(HTTPConnectError::ProtocolError(format!(
+            "malformed HTTP request {:?}",
+            line
+        )));
+    }
+    let (method, target, proto) = (parts[0], parts[1], parts[2]);
+    if method.is_empty() || !method.bytes().all(validHeaderFieldByte) {
+        return Err(HTTPConnectError::ProtocolError(format!(
+            "invalid method {:?}",
+            method
+        )));
+    }
+    let (major, minor, ok) = ParseHTTPVersion(proto);
+    if !ok {
+        return Err(HTTPConnectError::ProtocolError(format!(
+            "malformed HTTP version {:?}",
+            proto
+        )));
+    }
+    req.Method = method.to_string();
+    req.RequestURI = target.to_string();
+    req.Proto = proto.to_string();
+    req.ProtoMajor = major;
+    req.ProtoMinor = minor;
+    req.URL = parseRequestTarget(method, target)?;
+
+    req.Header = readMIMEHeader(b, &mut remaining)?;
+    // RFC 7230 5.4: absolute形式的request target优先于Host头。
+    req.Host = if req.URL.Host.is_empty() {
+        req.Header.Get("Host")
+    } else {
+        req.URL.Host.clone()
+    };
+    req.Header.Del("Host");
+    fixPragmaCacheControl(&mut req.Header);
+    req.Close = transfer::shouldClose(req.ProtoMajor, req.ProtoMinor, &req.Header);
+
+    let state = readTransfer(&mut req)?;
+    Ok((req, state))
+}
+
+// readLine reads one line from b and returns it without the line ending. Its length is charged to remaining; ErrTooLarge is returned once that runs out.
+fn readLine(b: &mut impl BufRead, remaining: &mut u64) -> HttpResult<String> {
+    let mut line = Vec::new();
+    let n = b
+        .by_ref()
+        .take(*remaining + 1)
+        .read_until(b'\n', &mut line)? as u64;
+    if n > *remaining {
+        return Err(HTTPConnectError::ErrTooLarge);
+    }
+    *remaining -= n;
+    if line.pop() != Some(b'\n') {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+// parseRequestTarget parses the request target of a request line into a URL, following the four forms of RFC 7230 5.3.
+fn parseRequestTarget(method: &str, target: &str) -> HttpResult<url::URL> {
+    let invalid =
+        || HTTPConnectError::ProtocolError(format!("invalid URI for request {:?}", target));
+    if method == "CONNECT" && !target.starts_with('/') {
+        // authority形式: "host:port"，只有Host。
+        if target.contains(['/', '?', '#', '@']) {
+            return Err(invalid());
+        }
+        let u = url::Parse(format!("http://{}", target).as_str()).map_err(|_| invalid())?;
+        if u.Host.is_empty() {
+            return Err(invalid());
+        }
+        return Ok(url::URL {
+            Host: u.Host,
+            ..Default::default()
+        });
+    }
+    if target == "*" {
+        return Ok(url::URL {
+            Path: "*".to_string(),
+            ..Default::default()
+        });
+    }
+    if target.starts_with('/') {
+        // origin形式: 加上一个占位的scheme和host再解析，保证"//x"这样的路径不会被当成host。
+        let u = url::Parse(format!("http://h{}", target).as_str()).map_err(|_| invalid())?;
+        return Ok(url::URL {
+            Path: u.Path,
+            RawPath: u.RawPath,
+            RawQuery: u.RawQuery,
+            ForceQuery: u.ForceQuery,
+            ..Default::default()
+        });
+    }
+    let u = url::Parse(target).map_err(|_| invalid())?;
+    if u.Scheme.is_empty() || u.Host.is_empty() {
+        return Err(invalid());
+    }
+    Ok(u)
+}
+
+// readMIMEHeader reads header lines up to the empty line that ends the header. Continuation lines (obs-fold) are joined to the previous value with a single space.
+fn readMIMEHeader(b: &mut impl BufRead, remaining: &mut u64) -> HttpResult<Header> {
+    let mut header = Header::default();
+    let mut last: Option<String> = None;
+    loop {
+        let line = readLine(b, remaining)?;
+        if line.is_empty() {
+            return Ok(header);
+        }
+        if line.starts_with([' ', '\t']) {
+            let prev = last
+                .as_ref()
+                .and_then(|key| header.0.get_mut(key))
+                .and_then(|values| values.last_mut());
+            match prev {
+                Some(v) => {
+                    v.push(' ');
+                    v.push_str(line.trim_matches([' ', '\t']));
+                    continue;
+                }
+                None => {
+                    return Err(HTTPConnectError::ProtocolError(format!(
+                        "malformed MIME header initial line: {:?}",
+                        line
+                    )))
+                }
+            }
+        }
+        // RFC 7230 3.2.4: 字段名和冒号之间不允许有空白。
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) if !key.is_empty() && key.bytes().all(validHeaderFieldByte) => {
+                (key, value)
+            }
+            _ => {
+                return Err(HTTPConnectError::ProtocolError(format!(
+                    "malformed MIME header line: {:?}",
+                    line
+                )))
+            }
+        };
+        let key = CanonicalHeaderKey(key);
+        header.Add(&key, value.trim_matches([' ', '\t']));
+        last = Some(key);
+    }
+}
+
+// readTransfer works out how the body of req is framed, from Transfer-Encoding or Content-Length, and sets TransferEncoding, ContentLength and Trailer accordingly.
+fn readTransfer(req: &mut Request) -> HttpResult<transfer::transferState> {
+    let te = req.Header.Values("Transfer-Encoding").to_vec();
+    // HTTP/1.0 没有Transfer-Encoding，忽略这个头。
+    if !te.is_empty() && (req.ProtoMajor, req.ProtoMinor) >= (1, 1) {
+        if te.len() != 1 || !te[0].trim().eq_ignore_ascii_case("chunked") {
+            return Err(HTTPConnectError::ProtocolError(format!(
+                "unsupported transfer encoding: {:?}",
+                te.join(",")
+            )));
+        }
+        req.Header.Del("Transfer-Encoding");
+        // RFC 7230 3.3.3: 同时有chunked和Content-Length时，忽略Content-Length。
+        req.Header.Del("Content-Length");
+        req.TransferEncoding = vec!["chunked".to_string()];
+        req.ContentLength = -1;
+        req.Trailer = readTrailerKeys(&req.Header)?;
+        req.Header.Del("Trailer");
+        let trailer = Arc::new(Mutex::new(Header::default()));
+        return Ok(transfer::transferState::chunkedWithTrailer(trailer));
+    }
+    req.Header.Del("Transfer-Encoding");
+
+    let cl = req.Header.Values("Content-Length");
+    if cl.iter().any(|v| v.trim() != cl[0].trim()) {
+        return Err(HTTPConnectError::ProtocolError(format!(
+            "message cannot contain multiple Content-Length headers; got {:?}",
+            cl
+        )));
+    }
+    req.ContentLength = match cl.first() {
+        None => 0,
+        Some(v) => match transfer::parseContentLength(v)? {
+            Some(n) => n as int64,
+            None => {
+                return Err(HTTPConnectError::ProtocolError(format!(
+                    "bad Content-Length {:?}",
+                    v
+                )))
+            }
+        },
+    };
+    if req.ContentLength == 0 {
+        return Ok(transfer::transferState::Done);
+    }
+    Ok(transfer::transferState::ContentLength(
+        req.ContentLength as u64,
+    ))
+}
+
+// readTrailerKeys returns the fields announced by the Trailer header, with no values. Fields that frame the message may not be sent as trailers.
+pub(crate) fn readTrailerKeys(header: &Header) -> HttpResult<Header> {
+    let mut trailer = Header::default();
+    for v in header.Values("Trailer") {
+        for key in v.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+            let key = CanonicalHeaderKey(key);
+            if matches!(
+                key.as_str(),
+                "Transfer-Encoding" | "Trailer" | "Content-Length"
+            ) {
+                return Err(HTTPConnectError::ProtocolError(format!(
+                    "bad trailer key {:?}",
+                    key
+                )));
+            }
+            trailer.0.entry(key).or_default();
+        }
+    }
+    Ok(trailer)
+}
@@ -3,10 +3,21 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
-use bytes::BytesMut;
+use std::collections::HashSet;
+use std::io::{self, Cursor, Read, Write};
+use std::sync::{Arc, Mutex};
+
 use gostd_builtin::*;
+use gostd_url as url;
 
-use crate::{header::Header, request::Request};
+use crate::{
+    body::{lock, Body},
+    error::{HTTPConnectError, HttpResult},
+    header::Header,
+    request::Request,
+    status::StatusText,
+    transfer::{bodyAllowedForStatus, headerValuesContainsToken},
+};
 
 #[derive(Default, Debug, Clone)]
 pub struct Response {
@@ -18,10 +29,204 @@ pub struct Response {
     pub Header: Header,
     pub ContentLength: int64,
     pub TransferEncoding: Vec<String>,
-    pub Body: Option<BytesMut>,
+    pub Body: Body,
     pub Close: bool,
     pub Uncompressed: bool,
-    pub Trailer: Header,
+    /// Trailer maps trailer keys to values in the same format as Header. For a response read by the client it is shared with Body: it initially contains the keys announced by the Trailer header, with no values, and the values are added once Body has been read to EOF. Response::Write sends the keys of Trailer in the Trailer header and, after a chunked body, their values.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Trailer以与Header相同的格式保存trailer的key和值。对于客户端读取的响应，它与Body共享：最初只包含Trailer头声明的key，没有值，Body读到EOF之后填入这些值。Response::Write在Trailer头中发送Trailer的key，并在chunked body之后发送它们的值。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    /// use std::io::{Cursor, Read};
+    ///
+    /// let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: X-Sum\r\n\r\n2\r\nhi\r\n0\r\nX-Sum: 42\r\n\r\n";
+    /// let mut resp = http::ReadResponse(Cursor::new(raw), &http::Request::default()).unwrap();
+    /// assert_eq!(resp.Trailer.lock().unwrap().Get("X-Sum"), "");
+    /// let mut body = String::new();
+    /// resp.Body.read_to_string(&mut body).unwrap();
+    /// assert_eq!(resp.Trailer.lock().unwrap().Get("X-Sum"), "42");
+    /// ```
+    pub Trailer: Arc<Mutex<Header>>,
     pub Request: Request,
     pub(crate) request: Request,
 }
+
+impl Response {
+    /// ProtoAtLeast reports whether the HTTP protocol used in the response is at least major.minor.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ProtoAtLeast报告响应使用的HTTP协议版本是否至少为major.minor。
+    /// </details>
+    pub fn ProtoAtLeast(&self, major: int, minor: int) -> bool {
+        self.ProtoMajor > major || self.ProtoMajor == major && self.ProtoMinor >= minor
+    }
+
+    /// Location returns the URL of the response's "Location" header, if present. Relative redirects are resolved relative to the URL of the Response's Request. ErrNoLocation is returned if no Location header is present.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Location返回响应"Location"头中的URL。相对地址以响应对应的请求的URL为基准进行解析。没有Location头时返回ErrNoLocation。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use gostd_http as http;
+    ///
+    /// let mut client = http::Client::New();
+    /// client.CheckRedirect = Some(std::sync::Arc::new(|_, _| Err(http::HTTPConnectError::ErrUseLastResponse)));
+    /// let resp = client.Get("http://example.com/old").unwrap();
+    /// println!("moved to {}", resp.Location().unwrap().String());
+    /// ```
+    pub fn Location(&self) -> HttpResult<url::URL> {
+        let lv = self.Header.Get("Location");
+        if lv.is_empty() {
+            return Err(HTTPConnectError::ErrNoLocation);
+        }
+        let base = &self.Request.URL;
+        if base.Scheme.is_empty() && base.Host.is_empty() && base.Path.is_empty() {
+            return Ok(url::Parse(&lv)?);
+        }
+        Ok(base.Parse(&lv)?)
+    }
+
+    /// Write writes the response in HTTP/1.x wire format: the status line, the header, and the body framed by Content-Length or chunked transfer encoding, followed by the trailer for a chunked body. The body is read to EOF.
+    ///
+    /// A body of unknown length that is not chunked is written until EOF and the response is marked with "Connection: close". No body is written for a response to a HEAD request or for a status code that does not permit one.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Write以HTTP/1.x线路格式写入响应：状态行、header，以及按Content-Length或chunked传输编码分帧的body，chunked body之后还会写入trailer。body会被读到EOF。
+    ///
+    /// 长度未知且不是chunked的body会一直写到EOF，响应带上"Connection: close"。HEAD请求的响应以及状态码不允许body的响应不写入body。
+    /// </details>
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gostd_http as http;
+    ///
+    /// let mut resp = http::Response::default();
+    /// resp.StatusCode = 200;
+    /// resp.ContentLength = 5;
+    /// resp.Body = "hello".into();
+    /// let mut b = Vec::new();
+    /// resp.Write(&mut b).unwrap();
+    /// assert_eq!(b, b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    /// ```
+    pub fn Write(&mut self, w: &mut dyn io::Write) -> HttpResult<()> {
+        self.write(w, true)
+    }
+
+    // write writes the response in wire format. If withBody is false only the status line and the header are written and the body is left unread.
+    pub(crate) fn write(&mut self, w: &mut dyn io::Write, withBody: bool) -> HttpResult<()> {
+        let (major, minor) = if self.ProtoMajor == 0 {
+            (1, 1)
+        } else {
+            (self.ProtoMajor, self.ProtoMinor)
+        };
+        let code = self.StatusCode;
+        let prefix = format!("{} ", code);
+        let text = match self.Status.strip_prefix(&prefix) {
+            Some(text) => text.to_string(),
+            None if !self.Status.is_empty() && self.Status != code.to_string() => {
+                self.Status.clone()
+            }
+            None => match StatusText(code) {
+                "" => format!("status code {}", code),
+                text => text.to_string(),
+            },
+        };
+
+        // ContentLength为0但body不为空时，按长度未知处理。
+        let mut contentLength = self.ContentLength;
+        let mut probe = Vec::new();
+        if withBody && contentLength == 0 && !self.Body.isNoBody() {
+            (&mut self.Body).take(1).read_to_end(&mut probe)?;
+            if !probe.is_empty() {
+                contentLength = -1;
+            }
+        }
+        let chunked = self
+            .TransferEncoding
+            .iter()
+            .any(|te| te.eq_ignore_ascii_case("chunked"));
+        let close = self.Close
+            || contentLength == -1 && !chunked && (major, minor) >= (1, 1) && !self.Uncompressed;
+
+        let mut b = Vec::new();
+        write!(b, "HTTP/{}.{} {:03} {}\r\n", major, minor, code, text)?;
+        let exclude: HashSet<String> = respExcludeHeader.iter().map(|k| k.to_string()).collect();
+        self.Header.WriteSubset(&mut b, &exclude)?;
+        if close && !headerValuesContainsToken(self.Header.Values("Connection"), "close") {
+            b.extend_from_slice(b"Connection: close\r\n");
+        }
+        if chunked {
+            b.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+            let mut keys: Vec<String> =
+                lock(&self.Trailer).iter().map(|(k, _)| k.clone()).collect();
+            if !keys.is_empty() {
+                keys.sort();
+                write!(b, "Trailer: {}\r\n", keys.join(","))?;
+            }
+        } else if contentLength > 0 || contentLength == 0 && bodyAllowedForStatus(code) {
+            write!(b, "Content-Length: {}\r\n", contentLength)?;
+        }
+        b.extend_from_slice(b"\r\n");
+        w.write_all(&b)?;
+
+        if !withBody || !bodyAllowedForStatus(code) || self.Request.Method == "HEAD" {
+            return Ok(());
+        }
+        let mut body = Cursor::new(probe).chain(&mut self.Body);
+        if chunked {
+            let mut buf = vec![0u8; 32 << 10];
+            loop {
+                let n = body.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                write!(w, "{:x}\r\n", n)?;
+                w.write_all(&buf[..n])?;
+                w.write_all(b"\r\n")?;
+            }
+            w.write_all(b"0\r\n")?;
+            // body读到EOF之后才能拿到Body.Trailer()的值，没有设置值的key用它补上。
+            let bodyTrailer = self.Body.Trailer();
+            let mut trailer = Header::default();
+            for (k, vv) in lock(&self.Trailer).0.iter_mut() {
+                if vv.is_empty() {
+                    *vv = bodyTrailer.Values(k).to_vec();
+                }
+                for v in vv.iter() {
+                    trailer.Add(k, v);
+                }
+            }
+            trailer.Write(w)?;
+            w.write_all(b"\r\n")?;
+        } else if contentLength > 0 {
+            let n = io::copy(&mut (&mut body).take(contentLength as u64), w)?;
+            let extra = io::copy(&mut body, &mut io::sink())?;
+            if n + extra != contentLength as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "http: ContentLength={} with Body length {}",
+                        contentLength,
+                        n + extra
+                    ),
+                )
+                .into());
+            }
+        } else {
+            io::copy(&mut body, w)?;
+        }
+        Ok(())
+    }
+}
+
+// Headers that Response.Write handles itself and should be skipped.
+const respExcludeHeader: [&str; 3] = ["Content-Length", "Transfer-Encoding", "Trailer"];
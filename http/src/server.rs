@@ -5,38 +5,1973 @@
 #![allow(non_camel_case_types)]
 
 use std::{
-    io::{self, BufReader, BufWriter},
-    net::TcpStream,
-    sync::{mpsc::Receiver, Arc},
+    any::Any,
+    collections::{HashMap, VecDeque},
+    fmt,
+    io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use crate::{error::HttpResult, header::Header, request::Request};
-trait Handler {
-    fn ServeHttp(&self, response_writer: &mut impl ResponseWriter, request: &Request);
+use gostd_builtin::*;
+use gostd_time as time;
+use gostd_url as url;
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+use crate::{
+    body::{lock, Body},
+    client::{stdDuration, TimeFormat, TrailerPrefix},
+    error::{HTTPConnectError, HttpResult},
+    header::{CanonicalHeaderKey, Header},
+    pattern::{cleanPath, route, router},
+    request::{readRequest, Request},
+    status::StatusText,
+    tls,
+    transfer::{bodyAllowedForStatus, headerValuesContainsToken, transferState},
+};
+
+/// DefaultMaxHeaderBytes is the maximum permitted size of the headers in an HTTP request.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DefaultMaxHeaderBytes是HTTP请求header允许的最大字节数。
+/// </details>
+pub const DefaultMaxHeaderBytes: int = 1 << 20;
+
+// bufferBeforeChunkingSize是发送响应头之前缓存的body字节数。handler返回时body没有超过这个大小，响应使用Content-Length而不是chunked编码。
+const bufferBeforeChunkingSize: usize = 2048;
+
+// maxPostHandlerReadBytes是handler没有读完请求body时，为了复用连接最多丢弃的字节数，超过时关闭连接。
+pub(crate) const maxPostHandlerReadBytes: u64 = 256 << 10;
+
+// workerIdleTimeout是线程池中空闲的线程退出之前等待新连接的时间。
+const workerIdleTimeout: Duration = Duration::from_secs(60);
+
+// Shutdown轮询空闲连接的间隔，从shutdownPollIntervalMin开始每次加倍，最大为shutdownPollIntervalMax。
+pub(crate) const shutdownPollIntervalMin: Duration = Duration::from_millis(1);
+const shutdownPollIntervalMax: Duration = Duration::from_millis(500);
+
+// newConnIdleTimeout是处于StateNew的连接在Shutdown时被当作空闲连接关闭之前的时间，避免客户端刚建立的连接上的请求被中断。
+const newConnIdleTimeout: Duration = Duration::from_secs(5);
+
+/// A Handler responds to an HTTP request.
+///
+/// ServeHttp should write reply headers and data to the ResponseWriter and then return. Returning signals that the request is finished; the ResponseWriter and the request Body must not be used after ServeHttp returns. If ServeHttp panics, the server logs the panic and closes the connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Handler响应一个HTTP请求。
+///
+/// ServeHttp应该把响应头和数据写入ResponseWriter然后返回。返回表示请求已经处理完成，ServeHttp返回之后不能再使用ResponseWriter和请求的Body。如果ServeHttp发生panic，服务器记录panic信息并关闭连接。
+/// </details>
+pub trait Handler: Send + Sync {
+    fn ServeHttp(&self, w: &mut dyn ResponseWriter, r: &mut Request);
+}
+
+impl<H: Handler + ?Sized> Handler for Arc<H> {
+    fn ServeHttp(&self, w: &mut dyn ResponseWriter, r: &mut Request) {
+        (**self).ServeHttp(w, r)
+    }
+}
+
+impl<H: Handler + ?Sized> Handler for Box<H> {
+    fn ServeHttp(&self, w: &mut dyn ResponseWriter, r: &mut Request) {
+        (**self).ServeHttp(w, r)
+    }
 }
 
-trait ResponseWriter {
-    // Returns the header map that will be sent by WriteHeader.
-    fn Header(&self) -> &Header;
+/// The HandlerFunc type is an adapter to allow the use of ordinary functions and closures as HTTP handlers. HandlerFunc(f) is a Handler that calls f.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HandlerFunc是一个适配器，允许把普通函数和闭包用作HTTP处理器。HandlerFunc(f)是调用f的Handler。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use http::server::{HandlerFunc, ResponseWriter};
+///
+/// let hello = HandlerFunc(|w: &mut dyn ResponseWriter, r: &mut http::Request| {
+///     w.Write(format!("hello from {}", r.URL.Path).as_bytes()).unwrap();
+/// });
+/// http::server::ListenAndServe("127.0.0.1:8080", hello).unwrap();
+/// ```
+pub struct HandlerFunc<F>(pub F);
 
-    // Writes the data to the connection as part of an HTTP reply.
+impl<F> Handler for HandlerFunc<F>
+where
+    F: Fn(&mut dyn ResponseWriter, &mut Request) + Send + Sync,
+{
+    fn ServeHttp(&self, w: &mut dyn ResponseWriter, r: &mut Request) {
+        (self.0)(w, r)
+    }
+}
+
+/// A ResponseWriter interface is used by an HTTP handler to construct an HTTP response.
+///
+/// The optional capabilities of Go's ResponseWriter, which are discovered there with a type assertion, are reached through the Flusher, Hijacker and CloseNotifier methods; they return None when the writer does not support them.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// HTTP处理器使用ResponseWriter接口构造HTTP响应。
+///
+/// Go中通过类型断言获得的ResponseWriter可选能力，这里通过Flusher、Hijacker和CloseNotifier方法获取；writer不支持时返回None。
+/// </details>
+pub trait ResponseWriter {
+    /// Header returns the header map that will be sent by WriteHeader. Changing the header after a call to WriteHeader (or Write) has no effect, except for trailers declared with the Trailer header or set with TrailerPrefix.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Header返回WriteHeader将要发送的header。调用WriteHeader(或Write)之后修改header没有效果，Trailer头声明的字段和TrailerPrefix开头的字段除外。
+    /// </details>
+    fn Header(&mut self) -> &mut Header;
+
+    /// Write writes the data to the connection as part of an HTTP reply. If WriteHeader has not yet been called, Write calls WriteHeader(200) before writing the data. Small responses get a Content-Length header; larger ones, when the handler did not set Content-Length, are sent with chunked transfer encoding.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Write把数据作为HTTP响应的一部分写入连接。如果还没有调用WriteHeader，Write会先调用WriteHeader(200)。较小的响应会带上Content-Length头；handler没有设置Content-Length的较大响应使用chunked传输编码发送。
+    /// </details>
     fn Write(&mut self, buf: &[u8]) -> HttpResult<usize>;
 
-    // Sends an HTTP response header with the provided status code.
+    /// WriteHeader sends an HTTP response header with the provided status code. Informational 1xx codes other than 101 are sent immediately and may be followed by the final status code.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// WriteHeader使用指定的状态码发送HTTP响应头。101以外的1xx信息状态码会立即发送，之后还可以发送最终的状态码。
+    /// </details>
     fn WriteHeader(&mut self, status_code: u16);
+
+    /// Flusher returns the writer as a Flusher if it can send buffered data to the client before the handler returns.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 如果writer能够在handler返回之前把缓存的数据发送给客户端，Flusher返回对应的Flusher。
+    /// </details>
+    fn Flusher(&mut self) -> Option<&mut dyn Flusher> {
+        None
+    }
+
+    /// Hijacker returns the writer as a Hijacker if the handler can take over its connection.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 如果handler可以接管writer的连接，Hijacker返回对应的Hijacker。
+    /// </details>
+    fn Hijacker(&mut self) -> Option<&mut dyn Hijacker> {
+        None
+    }
+
+    /// CloseNotifier returns the writer as a CloseNotifier if it can report that the client went away.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// 如果writer能够报告客户端断开连接，CloseNotifier返回对应的CloseNotifier。
+    /// </details>
+    fn CloseNotifier(&mut self) -> Option<&mut dyn CloseNotifier> {
+        None
+    }
 }
 
-trait Flusher {
-    // Flush sends any buffered data to the client.
+// 让handler可以使用write!宏写入响应。
+impl io::Write for dyn ResponseWriter + '_ {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.Write(buf).map_err(io::Error::other)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.Flusher() {
+            Some(f) => f.Flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The Flusher interface is implemented by ResponseWriters that allow an HTTP handler to flush buffered data to the client.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// 允许HTTP处理器把缓存的数据发送给客户端的ResponseWriter实现Flusher接口。
+/// </details>
+pub trait Flusher {
+    /// Flush sends any buffered data to the client.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Flush把所有缓存的数据发送给客户端。
+    /// </details>
     fn Flush(&mut self) -> io::Result<()>;
 }
 
-trait Hijacker {
-    // Hijack lets the caller take over the connection.
-    fn Hijack(self) -> io::Result<(TcpStream, BufReader<TcpStream>, BufWriter<TcpStream>)>;
+/// Conn is a connection taken over from the server by Hijack.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Conn是通过Hijack从服务器接管的连接。
+/// </details>
+pub trait Conn: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Conn for T {}
+
+/// The Hijacker interface is implemented by ResponseWriters that allow an HTTP handler to take over the connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// 允许HTTP处理器接管连接的ResponseWriter实现Hijacker接口。
+/// </details>
+pub trait Hijacker {
+    /// Hijack lets the caller take over the connection. After a call to Hijack the HTTP server library will not do anything else with the connection; it becomes the caller's responsibility to manage and close it. Data the client already sent after the request header is returned first by reads from the connection.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Hijack让调用者接管连接。调用Hijack之后HTTP服务器不会再对该连接做任何操作，由调用者负责管理和关闭连接。客户端在请求头之后已经发送的数据会在读取连接时首先返回。
+    /// </details>
+    fn Hijack(&mut self) -> HttpResult<Box<dyn Conn>>;
 }
 
-trait CloseNotifier {
-    // CloseNotify returns a channel that receives at most a single value (true) when the client connection has gone away.
+/// The CloseNotifier interface is implemented by ResponseWriters which allow detecting when the underlying connection has gone away.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// 可以检测底层连接断开的ResponseWriter实现CloseNotifier接口。
+/// </details>
+pub trait CloseNotifier {
+    /// CloseNotify returns a channel that receives at most a single value (true) when the client connection has gone away.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// CloseNotify返回一个channel，客户端连接断开时最多收到一个值(true)。
+    /// </details>
     fn CloseNotify(&self) -> Receiver<bool>;
 }
+
+/// ErrAbortHandler is a sentinel panic value to abort a handler. While any panic from ServeHttp aborts the response to the client, panicking with ErrAbortHandler also suppresses logging of the panic to the server's error log. Raise it with std::panic::resume_unwind, which does not run the panic hook either.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ErrAbortHandler是用于中断handler的panic值。ServeHttp中的任何panic都会中断发给客户端的响应，使用ErrAbortHandler发生panic时服务器还不会把它记录到错误日志。用std::panic::resume_unwind抛出它，这样也不会调用panic钩子。
+/// </details>
+#[derive(Debug, Clone, Copy)]
+pub struct ErrAbortHandler;
+
+/// Error replies to the request with the specified error message and HTTP code. It does not otherwise end the request; the caller should ensure no further writes are done to w. The error message should be plain text.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Error使用指定的错误信息和HTTP状态码回复请求。它不会结束请求，调用者应该确保之后不再写入w。错误信息应该是纯文本。
+/// </details>
+pub fn Error(w: &mut dyn ResponseWriter, error: &str, code: u16) {
+    errorHeader(w.Header());
+    w.WriteHeader(code);
+    let _ = w.Write(format!("{}\n", error).as_bytes());
+}
+
+// errorHeader prepares h for the plain text body of an error reply.
+pub(crate) fn errorHeader(h: &mut Header) {
+    h.Del("Content-Length");
+    h.Set("Content-Type", "text/plain; charset=utf-8");
+    h.Set("X-Content-Type-Options", "nosniff");
+}
+
+/// NotFound replies to the request with an HTTP 404 not found error.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NotFound使用HTTP 404 not found错误回复请求。
+/// </details>
+pub fn NotFound(w: &mut dyn ResponseWriter, r: &mut Request) {
+    Error(w, "404 page not found", 404)
+}
+
+/// NotFoundHandler returns a simple request handler that replies to each request with a "404 page not found" reply.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NotFoundHandler返回一个简单的处理器，对每个请求回复"404 page not found"。
+/// </details>
+pub fn NotFoundHandler() -> impl Handler {
+    HandlerFunc(NotFound)
+}
+
+/// Redirect replies to the request with a redirect to url, which may be a path relative to the request path. The provided code should be in the 3xx range and is usually 301, 302 or 303. If the Content-Type header has not been set, Redirect sets it to "text/html; charset=utf-8" and writes a small HTML body for GET requests.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Redirect回复一个重定向到url的响应，url可以是相对于请求路径的路径。code应该是3xx范围内的状态码，通常是301、302或303。如果没有设置Content-Type头，Redirect把它设置为"text/html; charset=utf-8"，并为GET请求写入一个简短的HTML body。
+/// </details>
+pub fn Redirect(w: &mut dyn ResponseWriter, r: &Request, url: &str, code: u16) {
+    let body = redirectHeader(w.Header(), r, url);
+    w.WriteHeader(code);
+    if let Some(body) = body {
+        let _ = w.Write(redirectBody(&body, code).as_bytes());
+    }
+}
+
+// redirectHeader sets the Location of a redirect to url, resolved against the path of r, and the Content-Type of its body. It returns the location if a body should be written, that is for GET requests when the handler set no Content-Type.
+pub(crate) fn redirectHeader(h: &mut Header, r: &Request, url: &str) -> Option<String> {
+    let mut url = url.to_string();
+    if let Ok(u) = url::Parse(&url) {
+        // 没有scheme和host的url是相对于请求路径的。
+        if u.Scheme.is_empty() && u.Host.is_empty() {
+            let oldpath = if r.URL.Path.is_empty() {
+                "/"
+            } else {
+                r.URL.Path.as_str()
+            };
+            if !url.starts_with('/') {
+                let olddir = &oldpath[..oldpath.rfind('/').map_or(0, |i| i + 1)];
+                url = format!("{}{}", olddir, url);
+            }
+            let (path, query) = match url.find('?') {
+                Some(i) => url.split_at(i),
+                None => (url.as_str(), ""),
+            };
+            url = format!("{}{}", cleanPath(path), query);
+        }
+    }
+    let hadCT = !h.Values("Content-Type").is_empty();
+    h.Set("Location", &url);
+    if !hadCT && (r.Method == "GET" || r.Method == "HEAD") {
+        h.Set("Content-Type", "text/html; charset=utf-8");
+    }
+    if !hadCT && r.Method == "GET" {
+        Some(url)
+    } else {
+        None
+    }
+}
+
+// redirectBody returns the short HTML body of a redirect to url.
+pub(crate) fn redirectBody(url: &str, code: u16) -> String {
+    format!(
+        "<a href=\"{}\">{}</a>.\n\n",
+        htmlEscape(url),
+        StatusText(code as int)
+    )
+}
+
+/// RedirectHandler returns a request handler that redirects each request it receives to the given url using the given status code.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// RedirectHandler返回一个处理器，使用指定的状态码把收到的每个请求重定向到url。
+/// </details>
+pub fn RedirectHandler(url: &str, code: u16) -> impl Handler {
+    let url = url.to_string();
+    HandlerFunc(move |w: &mut dyn ResponseWriter, r: &mut Request| Redirect(w, r, &url, code))
+}
+
+pub(crate) fn htmlEscape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&#34;")
+        .replace('\'', "&#39;")
+}
+
+/// A Server defines parameters for running an HTTP server.
+///
+/// Each connection is served on a thread from a pool that grows as connections arrive and shrinks when threads stay idle. Requests on a connection are served one after the other; the connection is kept alive between them unless the client or the handler asks to close it.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Server定义运行HTTP服务器的参数。
+///
+/// 每个连接在线程池的一个线程上处理，线程池随连接的到来增长，线程空闲时收缩。同一个连接上的请求依次处理，除非客户端或handler要求关闭，请求之间保持连接。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use http::server::{ResponseWriter, ServeMux, Server};
+///
+/// let mut mux = ServeMux::default();
+/// mux.HandleFunc("GET /hello/{name}", |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+///     w.Write(format!("hello, {}\n", r.PathValue("name")).as_bytes()).unwrap();
+/// });
+/// let srv = Server::New("127.0.0.1:8080", mux);
+/// srv.ListenAndServe().unwrap();
+/// ```
+///
+/// A Server can be shared with other threads to shut it down gracefully while it serves:
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use gostd_time as time;
+/// use http::server::{ResponseWriter, Server};
+/// use http::HTTPConnectError;
+/// use std::sync::Arc;
+///
+/// let srv = Arc::new(Server::New(
+///     ":8080",
+///     http::server::HandlerFunc(|w: &mut dyn ResponseWriter, _r: &mut http::Request| {
+///         w.Write(b"ok").unwrap();
+///     }),
+/// ));
+/// srv.RegisterOnShutdown(|| println!("shutting down"));
+/// let s = srv.clone();
+/// let serving = std::thread::spawn(move || s.ListenAndServe());
+///
+/// // ... on SIGTERM:
+/// let mut deadline = time::Now();
+/// srv.Shutdown(deadline.Add(&time::Duration::new(10 * time::Second)))
+///     .unwrap();
+/// assert!(matches!(
+///     serving.join().unwrap(),
+///     Err(HTTPConnectError::ErrServerClosed)
+/// ));
+/// ```
+pub struct Server {
+    /// Addr optionally specifies the TCP address for the server to listen on, in the form "host:port". A missing host, as in ":8080", listens on all interfaces; if Addr is empty, ":80" is used, or ":443" by ListenAndServeTLS.
+    pub Addr: String,
+    /// Handler to invoke for every request.
+    pub Handler: Arc<dyn Handler>,
+    /// ErrorLog specifies an optional logger for errors accepting connections, unexpected behavior from handlers and handler panics. If None, logging goes to standard error.
+    pub ErrorLog: Option<ErrorLogFunc>,
+    /// ReadTimeout is the maximum duration for reading the entire request, including the body. It is a deadline for the whole request rather than a limit on each read: it starts when the connection is accepted for the first request and when the first byte arrives for the following ones, and a client that is still sending once it passes has its connection closed. A zero or negative value means there will be no timeout.
+    pub ReadTimeout: time::Duration,
+    /// WriteTimeout is the maximum duration a single write of the response to the connection may block. A zero or negative value means there will be no timeout.
+    pub WriteTimeout: time::Duration,
+    /// IdleTimeout is the maximum amount of time to wait for the next request when keep-alives are enabled. If IdleTimeout is zero, the value of ReadTimeout is used. If both are zero, there is no timeout.
+    pub IdleTimeout: time::Duration,
+    /// MaxHeaderBytes controls the maximum number of bytes the server will read parsing the request line and header fields. It does not limit the size of the request body. If zero, DefaultMaxHeaderBytes is used.
+    pub MaxHeaderBytes: int,
+    /// ConnState specifies an optional callback function that is called when a client connection changes state. See the ConnState type for details.
+    pub ConnState: Option<ConnStateFunc>,
+    /// TLSConfig optionally provides a TLS configuration for use by ServeTLS and ListenAndServeTLS. Its Certificates, ClientAuth, ClientCAs and NextProtos are used; see tls::Config.
+    pub TLSConfig: Option<tls::Config>,
+    state: Arc<serverState>,
+}
+
+impl Server {
+    /// New returns a Server listening on addr and serving every request with handler.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// New返回一个监听addr，并用handler处理每个请求的Server。
+    /// </details>
+    pub fn New(addr: &str, handler: impl Handler + 'static) -> Server {
+        Server {
+            Addr: addr.to_string(),
+            Handler: Arc::new(handler),
+            ErrorLog: None,
+            ReadTimeout: time::Duration::new(0),
+            WriteTimeout: time::Duration::new(0),
+            IdleTimeout: time::Duration::new(0),
+            MaxHeaderBytes: 0,
+            ConnState: None,
+            TLSConfig: None,
+            state: Arc::new(serverState::default()),
+        }
+    }
+
+    /// ListenAndServe listens on the TCP network address Addr and then calls Serve to handle requests on incoming connections.
+    ///
+    /// ListenAndServe always returns a non-nil error. After Shutdown or Close, the returned error is ErrServerClosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ListenAndServe监听TCP网络地址Addr，然后调用Serve处理传入连接上的请求。
+    ///
+    /// ListenAndServe总是返回错误。调用Shutdown或Close之后，返回的错误是ErrServerClosed。
+    /// </details>
+    pub fn ListenAndServe(&self) -> HttpResult<()> {
+        if self.state.shuttingDown() {
+            return Err(HTTPConnectError::ErrServerClosed);
+        }
+        let l = TcpListener::bind(listenAddr(&self.Addr, "80"))?;
+        self.Serve(l)
+    }
+
+    /// ListenAndServeTLS listens on the TCP network address Addr and then calls ServeTLS to handle requests on incoming TLS connections.
+    ///
+    /// Filenames containing a certificate and matching private key for the server must be provided if neither the Server's TLSConfig.Certificates is populated. If the certificate is signed by a certificate authority, the certFile should be the concatenation of the server's certificate, any intermediates, and the CA's certificate.
+    ///
+    /// ListenAndServeTLS always returns a non-nil error. After Shutdown or Close, the returned error is ErrServerClosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ListenAndServeTLS监听TCP网络地址Addr，然后调用ServeTLS处理传入的TLS连接上的请求。
+    ///
+    /// 如果Server的TLSConfig.Certificates为空，必须提供包含服务器证书和对应私钥的文件名。如果证书是由证书颁发机构签发的，certFile应该依次包含服务器证书、所有中间证书和CA证书。
+    ///
+    /// ListenAndServeTLS总是返回错误。调用Shutdown或Close之后，返回的错误是ErrServerClosed。
+    /// </details>
+    pub fn ListenAndServeTLS(&self, certFile: &str, keyFile: &str) -> HttpResult<()> {
+        if self.state.shuttingDown() {
+            return Err(HTTPConnectError::ErrServerClosed);
+        }
+        let l = TcpListener::bind(listenAddr(&self.Addr, "443"))?;
+        self.ServeTLS(l, certFile, keyFile)
+    }
+
+    /// Serve accepts incoming connections on the Listener l, handing each to a thread of the pool that reads requests and calls Handler to reply to them.
+    ///
+    /// Serve always returns a non-nil error. After Shutdown or Close, the returned error is ErrServerClosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Serve在监听器l上接受传入的连接，把每个连接交给线程池中的一个线程读取请求，并调用Handler回复。
+    ///
+    /// Serve总是返回错误。调用Shutdown或Close之后，返回的错误是ErrServerClosed。
+    /// </details>
+    pub fn Serve(&self, l: TcpListener) -> HttpResult<()> {
+        self.serve(l, None)
+    }
+
+    /// ServeTLS accepts incoming connections on the Listener l, performing the TLS handshake on each and then serving its requests like Serve. The Request.TLS field of every request holds the state of its connection.
+    ///
+    /// The certificate is taken from certFile and keyFile when they are not empty, replacing the certificates of TLSConfig; otherwise TLSConfig.Certificates must contain at least one certificate. The server negotiates "http/1.1" through ALPN in addition to TLSConfig.NextProtos, and picks a certificate from the name the client sends through SNI.
+    ///
+    /// ServeTLS always returns a non-nil error. After Shutdown or Close, the returned error is ErrServerClosed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// ServeTLS在监听器l上接受传入的连接，在每个连接上完成TLS握手后，像Serve一样处理其上的请求。每个请求的Request.TLS字段保存其连接的状态。
+    ///
+    /// certFile和keyFile不为空时从中读取证书，替换TLSConfig中的证书；否则TLSConfig.Certificates中必须至少有一个证书。除了TLSConfig.NextProtos，服务器还会通过ALPN协商"http/1.1"，并根据客户端通过SNI发送的名称选择证书。
+    ///
+    /// ServeTLS总是返回错误。调用Shutdown或Close之后，返回的错误是ErrServerClosed。
+    /// </details>
+    pub fn ServeTLS(&self, l: TcpListener, certFile: &str, keyFile: &str) -> HttpResult<()> {
+        let mut config = self.TLSConfig.clone().unwrap_or_default();
+        if config.Certificates.is_empty() || !certFile.is_empty() || !keyFile.is_empty() {
+            config.Certificates = vec![tls::LoadX509KeyPair(certFile, keyFile)?];
+        }
+        let config = tls::serverConfig(&config)?;
+        self.serve(l, Some(config))
+    }
+
+    fn serve(&self, l: TcpListener, tlsConfig: Option<Arc<ServerConfig>>) -> HttpResult<()> {
+        let addr = l.local_addr()?;
+        self.state.addListener(addr);
+        let r = self.acceptLoop(&l, tlsConfig);
+        self.state.removeListener(addr);
+        r
+    }
+
+    fn acceptLoop(&self, l: &TcpListener, tlsConfig: Option<Arc<ServerConfig>>) -> HttpResult<()> {
+        let srv = Arc::new(connServer {
+            handler: self.Handler.clone(),
+            errorLog: self.ErrorLog.clone(),
+            readTimeout: stdDuration(&self.ReadTimeout),
+            writeTimeout: stdDuration(&self.WriteTimeout),
+            idleTimeout: stdDuration(&self.IdleTimeout),
+            maxHeaderBytes: if self.MaxHeaderBytes > 0 {
+                self.MaxHeaderBytes
+            } else {
+                DefaultMaxHeaderBytes
+            },
+            connState: self.ConnState.clone(),
+            tlsConfig,
+            state: self.state.clone(),
+        });
+        let pool = workerPool::new();
+        let mut tempDelay = Duration::ZERO;
+        loop {
+            if self.state.shuttingDown() {
+                return Err(HTTPConnectError::ErrServerClosed);
+            }
+            let rwc = match l.accept() {
+                Ok((rwc, _)) => rwc,
+                Err(_) if self.state.shuttingDown() => {
+                    return Err(HTTPConnectError::ErrServerClosed);
+                }
+                Err(err) => {
+                    // 接受连接的错误(例如文件描述符用尽)通常是暂时的，等待一段时间后重试。
+                    tempDelay =
+                        (tempDelay * 2).clamp(Duration::from_millis(5), Duration::from_secs(1));
+                    srv.logf(&format!(
+                        "http: Accept error: {}; retrying in {:?}",
+                        err, tempDelay
+                    ));
+                    thread::sleep(tempDelay);
+                    continue;
+                }
+            };
+            tempDelay = Duration::ZERO;
+            // Shutdown为了唤醒accept建立的连接在这里被丢弃。
+            if self.state.shuttingDown() {
+                return Err(HTTPConnectError::ErrServerClosed);
+            }
+            let c = match conn::new(&srv, rwc) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+            if !srv.setState(&c, ConnState::StateNew) {
+                continue;
+            }
+            let srv = srv.clone();
+            pool.execute(move || serveConn(&srv, c));
+        }
+    }
+
+    /// Shutdown gracefully shuts down the server without interrupting any active connections. Shutdown works by first closing all open listeners, then closing all idle connections, and then waiting indefinitely for connections to return to idle and then shut down. If the deadline passes before the shutdown is complete, Shutdown returns HTTPConnectError::TimeoutError.
+    ///
+    /// When Shutdown is called, Serve and ListenAndServe immediately return ErrServerClosed. Make sure the program doesn't exit and waits instead for Shutdown to return.
+    ///
+    /// Shutdown does not attempt to close nor wait for hijacked connections. The functions registered with RegisterOnShutdown are called on their own threads, and can be used to notify such long-lived connections of the shutdown.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Shutdown在不中断活动连接的情况下平滑地关闭服务器。Shutdown首先关闭所有打开的监听器，然后关闭所有空闲连接，再一直等待其余连接回到空闲状态后关闭它们。如果在关闭完成之前超过了deadline，Shutdown返回HTTPConnectError::TimeoutError。
+    ///
+    /// 调用Shutdown时，Serve和ListenAndServe立即返回ErrServerClosed。要确保程序不会直接退出，而是等待Shutdown返回。
+    ///
+    /// Shutdown不会关闭也不会等待被劫持的连接。通过RegisterOnShutdown注册的函数在各自的线程中调用，可以用来通知这类长连接服务器正在关闭。
+    /// </details>
+    pub fn Shutdown(&self, deadline: time::Time) -> HttpResult<()> {
+        self.state.startShutdown();
+        let deadline = shutdownDeadline(&deadline);
+        let mut interval = shutdownPollIntervalMin;
+        while let Some(wait) = self.state.pollShutdown(deadline, &mut interval)? {
+            thread::sleep(wait);
+        }
+        Ok(())
+    }
+
+    /// Close immediately closes all active listeners and all connections in state StateNew, StateActive, or StateIdle. For a graceful shutdown, use Shutdown.
+    ///
+    /// Close does not attempt to close (and does not even know about) any hijacked connections.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Close立即关闭所有活动的监听器以及所有处于StateNew、StateActive或StateIdle状态的连接。要平滑地关闭服务器，使用Shutdown。
+    ///
+    /// Close不会尝试关闭(也不知道)任何被劫持的连接。
+    /// </details>
+    pub fn Close(&self) -> HttpResult<()> {
+        self.state.close();
+        Ok(())
+    }
+
+    /// RegisterOnShutdown registers a function to call on Shutdown. This can be used to gracefully shutdown connections that have undergone a protocol upgrade or that have been hijacked. This function should start protocol-specific graceful shutdown, but should not wait for shutdown to complete.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// RegisterOnShutdown注册一个在Shutdown时调用的函数，可以用来平滑地关闭经过协议升级或被劫持的连接。该函数应该开始特定协议的平滑关闭，但不应该等待关闭完成。
+    /// </details>
+    pub fn RegisterOnShutdown(&self, f: impl Fn() + Send + Sync + 'static) {
+        self.state.registerOnShutdown(Arc::new(f));
+    }
+}
+
+/// A ConnState represents the state of a client connection to a server. It's used by the optional Server.ConnState hook.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ConnState表示客户端到服务器的连接的状态，用于可选的Server.ConnState回调。
+/// </details>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnState {
+    /// StateNew represents a new connection that is expected to send a request immediately. Connections begin at this state and then transition to either StateActive or StateClosed.
+    StateNew,
+    /// StateActive represents a connection that has read 1 or more bytes of a request. The Server.ConnState hook for StateActive fires before the request has entered a handler and doesn't fire again until the request has been handled. After the request is handled, the state transitions to StateClosed, StateHijacked, or StateIdle.
+    StateActive,
+    /// StateIdle represents a connection that has finished handling a request and is in the keep-alive state, waiting for a new request. Connections transition from StateIdle to either StateActive or StateClosed.
+    StateIdle,
+    /// StateHijacked represents a hijacked connection. This is a terminal state. It does not transition to StateClosed.
+    StateHijacked,
+    /// StateClosed represents a closed connection. This is a terminal state.
+    StateClosed,
+}
+
+impl fmt::Display for ConnState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConnState::StateNew => "new",
+            ConnState::StateActive => "active",
+            ConnState::StateIdle => "idle",
+            ConnState::StateHijacked => "hijacked",
+            ConnState::StateClosed => "closed",
+        })
+    }
+}
+
+/// ConnStateFunc is the type of the Server.ConnState hook. It is called with the connection and its new state.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ConnStateFunc是Server.ConnState回调的类型，调用时传入连接及其新状态。
+/// </details>
+pub type ConnStateFunc = Arc<dyn Fn(&TcpStream, ConnState) + Send + Sync>;
+
+// serverState is the state shared by a Server, its Serve loops and its connections, through which Shutdown and Close reach them. The async server tracks its connections the same way.
+#[derive(Default)]
+pub(crate) struct serverState {
+    inShutdown: AtomicBool,
+    // 正在Serve的监听地址，Shutdown通过连接它们唤醒accept。
+    listeners: Mutex<Vec<SocketAddr>>,
+    // 除被劫持的连接以外的所有连接，按conn.id索引。
+    conns: Mutex<HashMap<u64, trackedConn>>,
+    nextConnId: AtomicU64,
+    onShutdown: Mutex<Vec<Arc<dyn Fn() + Send + Sync>>>,
+}
+
+struct trackedConn {
+    rwc: TcpStream,
+    state: ConnState,
+    since: Instant,
+}
+
+impl serverState {
+    pub(crate) fn shuttingDown(&self) -> bool {
+        self.inShutdown.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn addListener(&self, addr: SocketAddr) {
+        lock(&self.listeners).push(addr);
+    }
+
+    pub(crate) fn removeListener(&self, addr: SocketAddr) {
+        let mut listeners = lock(&self.listeners);
+        if let Some(i) = listeners.iter().position(|a| *a == addr) {
+            listeners.remove(i);
+        }
+    }
+
+    pub(crate) fn newConnId(&self) -> u64 {
+        self.nextConnId.fetch_add(1, Ordering::SeqCst)
+    }
+
+    // trackConn records the new state of the connection id, whose TCP stream is rwc. It reports false if Shutdown or Close has already closed the connection, in which case it must not be used any more.
+    pub(crate) fn trackConn(&self, id: u64, rwc: &TcpStream, state: ConnState) -> bool {
+        let mut conns = lock(&self.conns);
+        match state {
+            ConnState::StateNew => match rwc.try_clone() {
+                Ok(rwc) => {
+                    conns.insert(
+                        id,
+                        trackedConn {
+                            rwc,
+                            state,
+                            since: Instant::now(),
+                        },
+                    );
+                    true
+                }
+                Err(_) => false,
+            },
+            ConnState::StateHijacked | ConnState::StateClosed => {
+                conns.remove(&id);
+                true
+            }
+            _ => match conns.get_mut(&id) {
+                Some(t) => {
+                    t.state = state;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    pub(crate) fn registerOnShutdown(&self, f: Arc<dyn Fn() + Send + Sync>) {
+        lock(&self.onShutdown).push(f);
+    }
+
+    // startShutdown stops the Serve loops and calls the functions registered with RegisterOnShutdown, each on its own thread.
+    pub(crate) fn startShutdown(&self) {
+        self.shutdown();
+        for f in lock(&self.onShutdown).iter() {
+            let f = f.clone();
+            thread::spawn(move || f());
+        }
+    }
+
+    // pollShutdown closes the idle connections for Shutdown. It returns None once the server is quiescent, and otherwise how long to wait before polling again, doubling interval each time.
+    pub(crate) fn pollShutdown(
+        &self,
+        deadline: Option<Instant>,
+        interval: &mut Duration,
+    ) -> HttpResult<Option<Duration>> {
+        if self.closeIdleConns() {
+            return Ok(None);
+        }
+        let now = Instant::now();
+        match deadline {
+            Some(d) if now < d => {
+                let wait = (*interval).min(d - now);
+                *interval = (*interval * 2).min(shutdownPollIntervalMax);
+                Ok(Some(wait))
+            }
+            _ => Err(HTTPConnectError::TimeoutError(
+                "http: Server.Shutdown deadline exceeded".to_string(),
+            )),
+        }
+    }
+
+    // close stops the Serve loops and closes all connections that are not hijacked.
+    pub(crate) fn close(&self) {
+        self.shutdown();
+        for (_, c) in lock(&self.conns).drain() {
+            let _ = c.rwc.shutdown(Shutdown::Both);
+        }
+    }
+
+    // shutdown stops the Serve loops. A TcpListener cannot be closed from another thread, so each one blocked in accept is woken up by connecting to it.
+    fn shutdown(&self) {
+        self.inShutdown.store(true, Ordering::SeqCst);
+        for addr in lock(&self.listeners).iter() {
+            let mut addr = *addr;
+            if addr.ip().is_unspecified() {
+                addr.set_ip(if addr.is_ipv4() {
+                    Ipv4Addr::LOCALHOST.into()
+                } else {
+                    Ipv6Addr::LOCALHOST.into()
+                });
+            }
+            let _ = TcpStream::connect_timeout(&addr, Duration::from_secs(1));
+        }
+    }
+
+    // closeIdleConns closes all idle connections and reports whether the server is quiescent.
+    fn closeIdleConns(&self) -> bool {
+        let mut quiescent = true;
+        lock(&self.conns).retain(|_, c| {
+            let idle = c.state == ConnState::StateIdle
+                || (c.state == ConnState::StateNew && c.since.elapsed() >= newConnIdleTimeout);
+            if !idle {
+                quiescent = false;
+                return true;
+            }
+            let _ = c.rwc.shutdown(Shutdown::Both);
+            false
+        });
+        quiescent
+    }
+}
+
+// shutdownDeadline returns the instant at which Shutdown gives up, or None if deadline has already passed.
+pub(crate) fn shutdownDeadline(deadline: &time::Time) -> Option<Instant> {
+    // 用UnixNano计算剩余时间，time::Until对带单调时钟读数的Time总是返回0。
+    let remaining = time::Duration::new(deadline.UnixNano() - time::Now().UnixNano());
+    stdDuration(&remaining).map(|d| Instant::now() + d)
+}
+
+/// ListenAndServe listens on the TCP network address addr and then serves requests on incoming connections with handler.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ListenAndServe监听TCP网络地址addr，然后使用handler处理传入连接上的请求。
+/// </details>
+pub fn ListenAndServe(addr: &str, handler: impl Handler + 'static) -> HttpResult<()> {
+    Server::New(addr, handler).ListenAndServe()
+}
+
+/// Serve accepts incoming HTTP connections on the listener l and serves their requests with handler.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Serve在监听器l上接受传入的HTTP连接，并使用handler处理请求。
+/// </details>
+pub fn Serve(l: TcpListener, handler: impl Handler + 'static) -> HttpResult<()> {
+    Server::New("", handler).Serve(l)
+}
+
+/// ListenAndServeTLS acts identically to ListenAndServe, except that it expects HTTPS connections. Additionally, files containing a certificate and matching private key for the server must be provided.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ListenAndServeTLS与ListenAndServe相同，只是它接受HTTPS连接。此外，必须提供包含服务器证书和对应私钥的文件。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use http::server::ResponseWriter;
+///
+/// let handler = http::server::HandlerFunc(|w: &mut dyn ResponseWriter, r: &mut http::Request| {
+///     let proto = r.TLS.as_ref().map(|s| s.NegotiatedProtocol.clone()).unwrap_or_default();
+///     w.Write(format!("This is an example server over {}.\n", proto).as_bytes()).unwrap();
+/// });
+/// // cert.pem and key.pem hold the certificate chain of the server and its private key in PEM format.
+/// http::server::ListenAndServeTLS(":8443", "cert.pem", "key.pem", handler).unwrap();
+/// ```
+pub fn ListenAndServeTLS(
+    addr: &str,
+    certFile: &str,
+    keyFile: &str,
+    handler: impl Handler + 'static,
+) -> HttpResult<()> {
+    Server::New(addr, handler).ListenAndServeTLS(certFile, keyFile)
+}
+
+/// ServeTLS accepts incoming HTTPS connections on the listener l and serves their requests with handler. Files containing a certificate and matching private key for the server must be provided.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ServeTLS在监听器l上接受传入的HTTPS连接，并使用handler处理请求。必须提供包含服务器证书和对应私钥的文件。
+/// </details>
+pub fn ServeTLS(
+    l: TcpListener,
+    handler: impl Handler + 'static,
+    certFile: &str,
+    keyFile: &str,
+) -> HttpResult<()> {
+    Server::New("", handler).ServeTLS(l, certFile, keyFile)
+}
+
+// listenAddr turns a Go style address such as ":8080" into one TcpListener can bind. An empty address listens on defaultPort.
+pub(crate) fn listenAddr(addr: &str, defaultPort: &str) -> String {
+    if addr.is_empty() {
+        format!("0.0.0.0:{}", defaultPort)
+    } else if addr.starts_with(':') {
+        format!("0.0.0.0{}", addr)
+    } else {
+        addr.to_string()
+    }
+}
+
+/// ErrorLogFunc receives the messages a Server logs.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ErrorLogFunc接收Server记录的日志信息。
+/// </details>
+pub type ErrorLogFunc = Arc<dyn Fn(&str) + Send + Sync>;
+
+// connServer is the part of a Server its connections need, shared with the threads that serve them.
+struct connServer {
+    handler: Arc<dyn Handler>,
+    errorLog: Option<ErrorLogFunc>,
+    readTimeout: Option<Duration>,
+    writeTimeout: Option<Duration>,
+    idleTimeout: Option<Duration>,
+    maxHeaderBytes: int,
+    connState: Option<ConnStateFunc>,
+    tlsConfig: Option<Arc<ServerConfig>>,
+    state: Arc<serverState>,
+}
+
+impl connServer {
+    fn logf(&self, msg: &str) {
+        match &self.errorLog {
+            Some(f) => f(msg),
+            None => eprintln!("{}", msg),
+        }
+    }
+
+    // setState records the new state of c and calls the ConnState hook. It reports false if Shutdown or Close has already closed c, in which case the connection must not be used any more.
+    fn setState(&self, c: &conn, state: ConnState) -> bool {
+        let tracked = self.state.trackConn(c.id, &c.rwc, state);
+        if tracked {
+            if let Some(f) = &self.connState {
+                f(&c.rwc, state);
+            }
+        }
+        tracked
+    }
+}
+
+// connIO is the byte stream of a connection: the TCP stream itself, or the TLS session on top of it. The reading and the writing half of a TLS connection share the session.
+#[derive(Clone)]
+enum connIO {
+    plain(Arc<TcpStream>),
+    tls(Arc<Mutex<StreamOwned<ServerConnection, TcpStream>>>),
+}
+
+impl Read for connIO {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            connIO::plain(s) => (&**s).read(buf),
+            connIO::tls(s) => lock(s).read(buf),
+        }
+    }
+}
+
+impl Write for connIO {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            connIO::plain(s) => (&**s).write(buf),
+            connIO::tls(s) => lock(s).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            connIO::plain(s) => (&**s).flush(),
+            connIO::tls(s) => lock(s).flush(),
+        }
+    }
+}
+
+// deadlineReader reads from a connection until its read deadline, if any, has passed. Before each read the socket timeout is set to the time left, so that a client trickling in bytes cannot keep a request open past the deadline.
+struct deadlineReader {
+    io: connIO,
+    rwc: TcpStream,
+    deadline: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Read for deadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = *lock(&self.deadline);
+        armReadDeadline(&self.rwc, deadline)?;
+        self.io.read(buf)
+    }
+}
+
+// armReadDeadline sets the read timeout of rwc to the time left until deadline. It fails with TimedOut once the deadline has passed.
+fn armReadDeadline(rwc: &TcpStream, deadline: Option<Instant>) -> io::Result<()> {
+    let Some(deadline) = deadline else {
+        return Ok(());
+    };
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if !left.is_zero() => rwc.set_read_timeout(Some(left)),
+        _ => Err(io::Error::new(io::ErrorKind::TimedOut, "i/o timeout")),
+    }
+}
+
+// conn is the server side of an HTTP connection. Reads and writes go through br and bw, which the request Body and the ResponseWriter share. rwc is the underlying TCP stream, used for timeouts and to close the connection.
+struct conn {
+    id: u64,
+    rwc: TcpStream,
+    remoteAddr: String,
+    io: connIO,
+    // tlsState是TLS握手完成后连接的状态，普通连接为None。
+    tlsState: Option<tls::ConnectionState>,
+    // readDeadline是br读取连接的期限，由deadlineReader在每次读取前检查。
+    readDeadline: Arc<Mutex<Option<Instant>>>,
+    br: Arc<Mutex<BufReader<deadlineReader>>>,
+    bw: Arc<Mutex<BufWriter<connIO>>>,
+}
+
+impl conn {
+    fn new(srv: &connServer, rwc: TcpStream) -> io::Result<conn> {
+        rwc.set_write_timeout(srv.writeTimeout)?;
+        let io = match &srv.tlsConfig {
+            Some(config) => {
+                let session = ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+                connIO::tls(Arc::new(Mutex::new(StreamOwned::new(
+                    session,
+                    rwc.try_clone()?,
+                ))))
+            }
+            None => connIO::plain(Arc::new(rwc.try_clone()?)),
+        };
+        let readDeadline = Arc::new(Mutex::new(None));
+        let r = deadlineReader {
+            io: io.clone(),
+            rwc: rwc.try_clone()?,
+            deadline: readDeadline.clone(),
+        };
+        Ok(conn {
+            id: srv.state.newConnId(),
+            remoteAddr: rwc.peer_addr().map(|a| a.to_string()).unwrap_or_default(),
+            readDeadline,
+            br: Arc::new(Mutex::new(BufReader::new(r))),
+            bw: Arc::new(Mutex::new(BufWriter::new(io.clone()))),
+            io,
+            tlsState: None,
+            rwc,
+        })
+    }
+
+    // setReadDeadline sets the deadline for reading from the connection. None clears it.
+    fn setReadDeadline(&self, deadline: Option<Instant>) {
+        *lock(&self.readDeadline) = deadline;
+        if deadline.is_none() {
+            let _ = self.rwc.set_read_timeout(None);
+        }
+    }
+
+    // handshake runs the TLS handshake of a TLS connection and records its state. A client speaking plain HTTP to the TLS port gets a 400 response.
+    fn handshake(&mut self) -> io::Result<()> {
+        let s = match &self.io {
+            connIO::tls(s) => s.clone(),
+            connIO::plain(_) => return Ok(()),
+        };
+        let mut hdr = [0u8; 5];
+        let n = self.rwc.peek(&mut hdr)?;
+        if tlsRecordHeaderLooksLikeHTTP(&hdr[..n]) {
+            let _ = (&self.rwc).write_all(
+                b"HTTP/1.0 400 Bad Request\r\n\r\nClient sent an HTTP request to an HTTPS server.\n",
+            );
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "client sent an HTTP request to an HTTPS server",
+            ));
+        }
+        let deadline = *lock(&self.readDeadline);
+        let mut s = lock(&s);
+        let StreamOwned { conn, sock } = &mut *s;
+        while conn.is_handshaking() {
+            armReadDeadline(sock, deadline)?;
+            conn.complete_io(sock)?;
+        }
+        self.tlsState = Some(tls::connectionState(
+            conn,
+            conn.server_name().unwrap_or_default(),
+        ));
+        Ok(())
+    }
+
+    // writeError replies to a request that could not be served with a plain text error, after which the connection is closed.
+    fn writeError(&self, code: u16, msg: &str) {
+        let mut bw = lock(&self.bw);
+        let _ = bw.write_all(errorReply(code, msg).as_bytes());
+        let _ = bw.flush();
+    }
+
+    fn close(&self) {
+        let _ = lock(&self.bw).flush();
+        // 握手完成之后才发送close_notify，握手中的flush会等待对方的数据。
+        if let connIO::tls(s) = &self.io {
+            let mut s = lock(s);
+            if !s.conn.is_handshaking() {
+                s.conn.send_close_notify();
+                let _ = s.flush();
+            }
+        }
+        let _ = self.rwc.shutdown(Shutdown::Both);
+    }
+}
+
+// tlsRecordHeaderLooksLikeHTTP reports whether the first bytes received on a TLS connection look like an HTTP request.
+fn tlsRecordHeaderLooksLikeHTTP(hdr: &[u8]) -> bool {
+    matches!(hdr, b"GET /" | b"HEAD " | b"POST " | b"PUT /" | b"OPTIO")
+}
+
+// serveConn reads requests from rwc and serves them one after the other until the connection is closed or hijacked.
+fn serveConn(srv: &connServer, mut c: conn) {
+    // TLS握手和第一个请求共用从连接建立开始计算的ReadTimeout期限。
+    let start = Instant::now();
+    c.setReadDeadline(srv.readTimeout.map(|d| start + d));
+    if let Err(err) = c.handshake() {
+        if err.kind() != io::ErrorKind::UnexpectedEof {
+            srv.logf(&format!(
+                "http: TLS handshake error from {}: {}",
+                c.remoteAddr, err
+            ));
+        }
+        c.close();
+        srv.setState(&c, ConnState::StateClosed);
+        return;
+    }
+    let mut idle = false;
+    loop {
+        // 等待下一个请求，对方关闭连接或超时时结束。第一个请求受ReadTimeout限制，之后受IdleTimeout限制。
+        if idle {
+            let wait = srv.idleTimeout.or(srv.readTimeout);
+            c.setReadDeadline(wait.map(|d| Instant::now() + d));
+        }
+        match lock(&c.br).fill_buf() {
+            Ok(buf) if !buf.is_empty() => {}
+            _ => break,
+        }
+        if !srv.setState(&c, ConnState::StateActive) {
+            break;
+        }
+        // ReadTimeout是整个请求(包括body)的期限，之后的请求从第一个字节到达时开始计算。
+        if idle {
+            c.setReadDeadline(srv.readTimeout.map(|d| Instant::now() + d));
+        }
+        let r = readRequest(&mut *lock(&c.br), srv.maxHeaderBytes);
+        let (mut req, state) = match r {
+            Ok(r) => r,
+            Err(err) => {
+                if let Some((code, msg)) = requestError(&err) {
+                    c.writeError(code, &msg);
+                }
+                break;
+            }
+        };
+        if let Some((code, msg)) = checkRequest(&req) {
+            c.writeError(code, msg);
+            break;
+        }
+        req.RemoteAddr = c.remoteAddr.clone();
+        req.TLS = c.tlsState.clone();
+        let (keepAlive, hijacked) = serveRequest(srv, &c, req, state);
+        if hijacked {
+            return;
+        }
+        if !keepAlive || srv.state.shuttingDown() || !srv.setState(&c, ConnState::StateIdle) {
+            break;
+        }
+        idle = true;
+    }
+    c.close();
+    srv.setState(&c, ConnState::StateClosed);
+}
+
+// requestError returns the status code and message to reply with when reading a request failed with err. It returns None when the connection is just closed, as when the client went away.
+pub(crate) fn requestError(err: &HTTPConnectError) -> Option<(u16, String)> {
+    match err {
+        HTTPConnectError::ErrTooLarge => Some((431, String::new())),
+        HTTPConnectError::ProtocolError(msg)
+            if msg.starts_with("unsupported transfer encoding") =>
+        {
+            Some((501, "unsupported transfer encoding".to_string()))
+        }
+        HTTPConnectError::ProtocolError(msg) => Some((400, msg.clone())),
+        _ => None,
+    }
+}
+
+// checkRequest returns the status code and message to reply with when the server cannot serve req.
+pub(crate) fn checkRequest(req: &Request) -> Option<(u16, &'static str)> {
+    if req.ProtoMajor != 1 {
+        return Some((505, "unsupported protocol version"));
+    }
+    if req.ProtoAtLeast(1, 1) && req.Host.is_empty() && req.Method != "CONNECT" {
+        return Some((400, "missing required Host header"));
+    }
+    if !req.Header.Get("Expect").is_empty() && !req.expectsContinue() {
+        return Some((417, ""));
+    }
+    None
+}
+
+// errorReply returns the plain text error response to a request that could not be served, after which the connection is closed.
+pub(crate) fn errorReply(code: u16, msg: &str) -> String {
+    let status = format!("{} {}", code, StatusText(code as int));
+    let body = if msg.is_empty() {
+        status.clone()
+    } else {
+        format!("{}: {}", status, msg)
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nConnection: close\r\n\r\n{}",
+        status, body
+    )
+}
+
+// panicMessage returns the message a handler panicked with.
+pub(crate) fn panicMessage(err: &(dyn Any + Send)) -> String {
+    err.downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| err.downcast_ref::<String>().cloned())
+        .unwrap_or_default()
+}
+
+// serveRequest calls the handler for req and finishes its response. It reports whether the connection can be used for another request and whether the handler hijacked it.
+fn serveRequest(
+    srv: &connServer,
+    c: &conn,
+    mut req: Request,
+    state: transferState,
+) -> (bool, bool) {
+    let mut body = None;
+    if !state.isDone() {
+        // 客户端等待"100 Continue"时，handler第一次读取body之前发送。
+        let cont = if req.expectsContinue() && req.ProtoAtLeast(1, 1) {
+            Some(Arc::new(continueWriter {
+                bw: c.bw.clone(),
+                canWrite: AtomicBool::new(true),
+            }))
+        } else {
+            None
+        };
+        let trailer = state.trailer();
+        let b = connBody {
+            br: c.br.clone(),
+            state: Arc::new(Mutex::new(state)),
+            cont,
+        };
+        req.Body = Some(Body::New(b.clone()).withTrailer(trailer));
+        body = Some(b);
+    }
+    req.Header.Del("Expect");
+
+    let mut w = response::new(srv, c, &req, body);
+    let handler = srv.handler.clone();
+    let r = panic::catch_unwind(AssertUnwindSafe(|| handler.ServeHttp(&mut w, &mut req)));
+    if let Some(form) = req.MultipartForm.take() {
+        // 删除ParseMultipartForm创建的临时文件。
+        let _ = form.RemoveAll();
+    }
+    if let Err(err) = r {
+        if !err.is::<ErrAbortHandler>() {
+            srv.logf(&format!(
+                "http: panic serving {}: {}",
+                c.remoteAddr,
+                panicMessage(&*err)
+            ));
+        }
+        return (false, w.st.hijacked);
+    }
+    if w.st.hijacked {
+        return (false, true);
+    }
+    w.finishRequest();
+    (!w.st.closeAfterReply, false)
+}
+
+// continueWriter sends "100 Continue" the first time the body of a request with "Expect: 100-continue" is read, unless the response has already started.
+struct continueWriter {
+    bw: Arc<Mutex<BufWriter<connIO>>>,
+    canWrite: AtomicBool,
+}
+
+// connBody reads the body of a request from the connection.
+#[derive(Clone)]
+struct connBody {
+    br: Arc<Mutex<BufReader<deadlineReader>>>,
+    state: Arc<Mutex<transferState>>,
+    cont: Option<Arc<continueWriter>>,
+}
+
+impl Read for connBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(cont) = &self.cont {
+            if cont.canWrite.swap(false, Ordering::SeqCst) {
+                let mut bw = lock(&cont.bw);
+                bw.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+                bw.flush()?;
+            }
+        }
+        let mut br = lock(&self.br);
+        lock(&self.state).read(&mut *br, buf)
+    }
+}
+
+// hijackedConn is a connection taken over by Hijack. Reads return the data the server had already buffered before reading from the connection again.
+struct hijackedConn {
+    buffered: Cursor<Vec<u8>>,
+    io: connIO,
+}
+
+impl Read for hijackedConn {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.buffered.read(buf)?;
+        if n > 0 || buf.is_empty() {
+            return Ok(n);
+        }
+        self.io.read(buf)
+    }
+}
+
+impl Write for hijackedConn {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.io.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+// responseState is the part of a response that does not depend on how it reaches the client: the header set by the handler, the status, the framing chosen for the body and whether the connection is kept alive afterwards. The sync and the async server wrap it with the I/O of their connections.
+//
+// The body written by the handler is buffered until it outgrows bufferBeforeChunkingSize, the handler flushes, or the handler returns. Only then is the header committed, so that a handler that returns after writing a small body gets a Content-Length instead of a chunked response.
+pub(crate) struct responseState {
+    // 请求中与生成响应有关的信息。
+    method: String,
+    protoAtLeast11: bool,
+    wantsClose: bool,
+
+    pub(crate) handlerHeader: Header,
+    // sentHeader是调用WriteHeader时handlerHeader的快照。
+    sentHeader: Header,
+    pub(crate) wroteHeader: bool,
+    status: u16,
+    // contentLength是Content-Length头的值，没有时为-1。
+    contentLength: int64,
+    written: int64,
+    buf: Vec<u8>,
+    pub(crate) committed: bool,
+    chunking: bool,
+    trailers: Vec<String>,
+    pub(crate) closeAfterReply: bool,
+    pub(crate) handlerDone: bool,
+    pub(crate) hijacked: bool,
+}
+
+impl responseState {
+    pub(crate) fn new(req: &Request) -> responseState {
+        responseState {
+            method: req.Method.clone(),
+            protoAtLeast11: req.ProtoAtLeast(1, 1),
+            wantsClose: req.Close,
+            handlerHeader: Header::default(),
+            sentHeader: Header::default(),
+            wroteHeader: false,
+            status: 0,
+            contentLength: -1,
+            written: 0,
+            buf: Vec::new(),
+            committed: false,
+            chunking: false,
+            trailers: Vec::new(),
+            closeAfterReply: false,
+            handlerDone: false,
+            hijacked: false,
+        }
+    }
+
+    fn bodyAllowed(&self) -> bool {
+        bodyAllowedForStatus(self.status as int)
+    }
+
+    // writeHeader records the status code of the response, reporting misuse through logf. For an informational 1xx code other than 101 it returns the response to send right away instead.
+    pub(crate) fn writeHeader(&mut self, code: u16, logf: impl Fn(&str)) -> Option<Vec<u8>> {
+        if self.hijacked {
+            logf("http: response.WriteHeader on hijacked connection");
+            return None;
+        }
+        if self.wroteHeader {
+            logf("http: superfluous response.WriteHeader call");
+            return None;
+        }
+        if !(100..=999).contains(&code) {
+            panic!("invalid WriteHeader code {}", code);
+        }
+        if (100..=199).contains(&code) && code != 101 {
+            let mut b = Vec::new();
+            let _ = writeStatusLine(&mut b, code);
+            let _ = self.handlerHeader.Write(&mut b);
+            b.extend_from_slice(b"\r\n");
+            return Some(b);
+        }
+        self.wroteHeader = true;
+        self.status = code;
+        self.sentHeader = self.handlerHeader.Clone();
+        let cl = self.sentHeader.Get("Content-Length");
+        if !cl.is_empty() {
+            match cl.trim().parse::<int64>() {
+                Ok(n) if n >= 0 => self.contentLength = n,
+                _ => {
+                    logf(&format!("http: invalid Content-Length of {:?}", cl));
+                    self.sentHeader.Del("Content-Length");
+                }
+            }
+        }
+        None
+    }
+
+    // write accounts for data written by the handler once the header was written. Before the header is committed the data is buffered and None is returned; after that it returns the bytes to send for it.
+    pub(crate) fn write(&mut self, data: &[u8]) -> HttpResult<Option<Vec<u8>>> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        if !self.bodyAllowed() {
+            return Err(HTTPConnectError::ErrBodyNotAllowed);
+        }
+        self.written += data.len() as int64;
+        if self.contentLength != -1 && self.written > self.contentLength {
+            return Err(HTTPConnectError::ErrContentLength);
+        }
+        if self.committed {
+            return Ok(Some(self.frame(data)));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(None)
+    }
+
+    // needsCommit reports whether the buffered body has outgrown bufferBeforeChunkingSize.
+    pub(crate) fn needsCommit(&self) -> bool {
+        !self.committed && self.buf.len() > bufferBeforeChunkingSize
+    }
+
+    // startCommit marks the header as committed and decides from the request and the header whether the connection is closed after the response. It returns false if the header was already committed. The caller discards the rest of the request body before calling commitHeader, since that can also close the connection.
+    pub(crate) fn startCommit(&mut self, shuttingDown: bool) -> bool {
+        if self.committed {
+            return false;
+        }
+        self.committed = true;
+        if self.wantsClose
+            || headerValuesContainsToken(self.sentHeader.Values("Connection"), "close")
+            || shuttingDown
+        {
+            self.closeAfterReply = true;
+        }
+        true
+    }
+
+    // commitHeader returns the status line and header of the response followed by the buffered body. After that the body is sent as the handler writes it.
+    pub(crate) fn commitHeader(&mut self) -> Vec<u8> {
+        let mut h = std::mem::take(&mut self.sentHeader);
+        let isHEAD = self.method == "HEAD";
+        let bodyAllowed = self.bodyAllowed();
+
+        for v in h.Values("Trailer") {
+            for k in v.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                self.trailers.push(CanonicalHeaderKey(k));
+            }
+        }
+        // handler已经返回并且body全部在缓存中，可以直接设置Content-Length。
+        if self.handlerDone
+            && bodyAllowed
+            && self.contentLength == -1
+            && self.trailers.is_empty()
+            && (!isHEAD || !self.buf.is_empty())
+        {
+            self.contentLength = self.buf.len() as int64;
+            h.Set("Content-Length", &self.buf.len().to_string());
+        }
+        h.Del("Transfer-Encoding");
+        if !bodyAllowed {
+            h.Del("Content-Length");
+            if self.status == 304 {
+                h.Del("Content-Type");
+            }
+        } else if self.contentLength == -1 && !isHEAD {
+            if self.protoAtLeast11 {
+                self.chunking = true;
+                h.Set("Transfer-Encoding", "chunked");
+            } else {
+                // HTTP/1.0没有chunked编码，body一直到连接关闭为止。
+                self.closeAfterReply = true;
+            }
+        }
+        if self.closeAfterReply {
+            h.Set("Connection", "close");
+        } else if !self.protoAtLeast11 {
+            h.Set("Connection", "keep-alive");
+        }
+        if h.Values("Date").is_empty() {
+            h.Set("Date", &time::Now().UTC().Format(TimeFormat));
+        }
+
+        let mut b = Vec::new();
+        let _ = writeStatusLine(&mut b, self.status);
+        let _ = h.Write(&mut b);
+        b.extend_from_slice(b"\r\n");
+        let buf = std::mem::take(&mut self.buf);
+        b.extend(self.frame(&buf));
+        b
+    }
+
+    // frame returns data the way it is sent in the body: as a chunk when chunking, and not at all when the response has no body.
+    fn frame(&self, data: &[u8]) -> Vec<u8> {
+        if data.is_empty() || self.method == "HEAD" || !self.bodyAllowed() {
+            return Vec::new();
+        }
+        if !self.chunking {
+            return data.to_vec();
+        }
+        let mut b = format!("{:x}\r\n", data.len()).into_bytes();
+        b.extend_from_slice(data);
+        b.extend_from_slice(b"\r\n");
+        b
+    }
+
+    // finish returns what ends a committed response: the last chunk and the trailers of a chunked body.
+    pub(crate) fn finish(&self) -> Vec<u8> {
+        if !self.chunking {
+            return Vec::new();
+        }
+        let mut trailer = Header::default();
+        for k in &self.trailers {
+            for v in self.handlerHeader.Values(k) {
+                trailer.Add(k, v);
+            }
+        }
+        for (k, values) in &self.handlerHeader {
+            if let Some(k) = k.strip_prefix(TrailerPrefix) {
+                for v in values {
+                    trailer.Add(k, v);
+                }
+            }
+        }
+        let mut b = b"0\r\n".to_vec();
+        let _ = trailer.Write(&mut b);
+        b.extend_from_slice(b"\r\n");
+        b
+    }
+
+    // checkLength closes the connection after a body shorter than its Content-Length, since the client keeps waiting for the rest of it.
+    pub(crate) fn checkLength(&mut self) {
+        if self.method != "HEAD"
+            && self.bodyAllowed()
+            && self.contentLength != -1
+            && self.written != self.contentLength
+        {
+            self.closeAfterReply = true;
+        }
+    }
+}
+
+// response is the ResponseWriter handed to the handler of a request.
+struct response<'a> {
+    srv: &'a connServer,
+    conn: &'a conn,
+    body: Option<connBody>,
+    st: responseState,
+}
+
+impl<'a> response<'a> {
+    fn new(
+        srv: &'a connServer,
+        conn: &'a conn,
+        req: &Request,
+        body: Option<connBody>,
+    ) -> response<'a> {
+        response {
+            srv,
+            conn,
+            body,
+            st: responseState::new(req),
+        }
+    }
+
+    // stopContinue keeps the request body from sending "100 Continue" once the final response has started.
+    fn stopContinue(&self) {
+        if let Some(cont) = self.body.as_ref().and_then(|b| b.cont.as_ref()) {
+            cont.canWrite.store(false, Ordering::SeqCst);
+        }
+    }
+
+    // discardRequestBody reads what the handler left of the request body, so that the next request can be read from the connection. Bodies that are too large, or that the client is still waiting to be asked for, close the connection instead.
+    fn discardRequestBody(&mut self) {
+        let body = match &self.body {
+            Some(b) if !self.st.closeAfterReply => b.clone(),
+            _ => return,
+        };
+        if lock(&body.state).isDone() {
+            return;
+        }
+        // 客户端在等待100 Continue，body可能不会发送。
+        if body.cont.is_some() {
+            self.st.closeAfterReply = true;
+            return;
+        }
+        let r = io::copy(
+            &mut body.clone().take(maxPostHandlerReadBytes + 1),
+            &mut io::sink(),
+        );
+        if r.is_err() || !lock(&body.state).isDone() {
+            self.st.closeAfterReply = true;
+        }
+    }
+
+    // writeRaw writes b to the connection's buffer.
+    fn writeRaw(&self, b: &[u8]) -> HttpResult<()> {
+        if !b.is_empty() {
+            lock(&self.conn.bw).write_all(b)?;
+        }
+        Ok(())
+    }
+
+    // commit writes the status line and header, then the buffered body. After that the body is written straight to the connection.
+    fn commit(&mut self) -> HttpResult<()> {
+        if !self.st.startCommit(self.srv.state.shuttingDown()) {
+            return Ok(());
+        }
+        self.discardRequestBody();
+        let b = self.st.commitHeader();
+        self.writeRaw(&b)
+    }
+
+    // finishRequest completes the response once the handler has returned: it commits the header if the handler never flushed, ends a chunked body with its trailers and flushes the connection.
+    fn finishRequest(&mut self) {
+        self.st.handlerDone = true;
+        if !self.st.wroteHeader {
+            self.WriteHeader(200);
+        }
+        if self.finish().is_err() {
+            self.st.closeAfterReply = true;
+        }
+        self.st.checkLength();
+    }
+
+    fn finish(&mut self) -> HttpResult<()> {
+        self.commit()?;
+        self.writeRaw(&self.st.finish())?;
+        lock(&self.conn.bw).flush()?;
+        Ok(())
+    }
+}
+
+pub(crate) fn writeStatusLine(w: &mut impl Write, code: u16) -> io::Result<()> {
+    let text = StatusText(code as int);
+    if text.is_empty() {
+        write!(w, "HTTP/1.1 {:03} status code {}\r\n", code, code)
+    } else {
+        write!(w, "HTTP/1.1 {:03} {}\r\n", code, text)
+    }
+}
+
+impl ResponseWriter for response<'_> {
+    fn Header(&mut self) -> &mut Header {
+        &mut self.st.handlerHeader
+    }
+
+    fn Write(&mut self, data: &[u8]) -> HttpResult<usize> {
+        if self.st.hijacked {
+            return Err(HTTPConnectError::ErrHijacked);
+        }
+        if !self.st.wroteHeader {
+            self.WriteHeader(200);
+        }
+        let r = match self.st.write(data)? {
+            Some(b) => self.writeRaw(&b),
+            None if self.st.needsCommit() => self.commit(),
+            None => Ok(()),
+        };
+        if let Err(err) = r {
+            self.st.closeAfterReply = true;
+            return Err(err);
+        }
+        Ok(data.len())
+    }
+
+    fn WriteHeader(&mut self, code: u16) {
+        let srv = self.srv;
+        let informational = self.st.writeHeader(code, |msg| srv.logf(msg));
+        if code == 100 || self.st.wroteHeader {
+            self.stopContinue();
+        }
+        if let Some(b) = informational {
+            if self.writeRaw(&b).is_err() || lock(&self.conn.bw).flush().is_err() {
+                self.st.closeAfterReply = true;
+            }
+        }
+    }
+
+    fn Flusher(&mut self) -> Option<&mut dyn Flusher> {
+        Some(self)
+    }
+
+    fn Hijacker(&mut self) -> Option<&mut dyn Hijacker> {
+        Some(self)
+    }
+
+    fn CloseNotifier(&mut self) -> Option<&mut dyn CloseNotifier> {
+        Some(self)
+    }
+}
+
+impl Flusher for response<'_> {
+    fn Flush(&mut self) -> io::Result<()> {
+        if self.st.hijacked {
+            return Ok(());
+        }
+        if !self.st.wroteHeader {
+            self.WriteHeader(200);
+        }
+        if let Err(err) = self.commit() {
+            self.st.closeAfterReply = true;
+            return Err(io::Error::other(err));
+        }
+        lock(&self.conn.bw).flush()
+    }
+}
+
+impl Hijacker for response<'_> {
+    fn Hijack(&mut self) -> HttpResult<Box<dyn Conn>> {
+        if self.st.hijacked {
+            return Err(HTTPConnectError::ErrHijacked);
+        }
+        if self.st.wroteHeader {
+            self.commit()?;
+        }
+        self.st.hijacked = true;
+        self.stopContinue();
+        self.srv.setState(self.conn, ConnState::StateHijacked);
+        lock(&self.conn.bw).flush()?;
+        // 接管连接之后由调用者负责超时，清除请求的读取期限。
+        self.conn.setReadDeadline(None);
+        let mut br = lock(&self.conn.br);
+        let buffered = br.buffer().to_vec();
+        br.consume(buffered.len());
+        Ok(Box::new(hijackedConn {
+            buffered: Cursor::new(buffered),
+            io: self.conn.io.clone(),
+        }))
+    }
+}
+
+impl CloseNotifier for response<'_> {
+    fn CloseNotify(&self) -> Receiver<bool> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(rwc) = self.conn.rwc.try_clone() {
+            thread::spawn(move || {
+                let mut b = [0u8; 1];
+                // peek不会消费数据，连接上的下一个请求仍然可以正常读取。
+                loop {
+                    match rwc.peek(&mut b) {
+                        Ok(0) => break,
+                        Ok(_) => return,
+                        Err(err)
+                            if matches!(
+                                err.kind(),
+                                io::ErrorKind::WouldBlock
+                                    | io::ErrorKind::TimedOut
+                                    | io::ErrorKind::Interrupted
+                            ) => {}
+                        Err(_) => break,
+                    }
+                }
+                let _ = tx.send(true);
+            });
+        }
+        rx
+    }
+}
+
+// job is a unit of work run by the workerPool.
+type job = Box<dyn FnOnce() + Send>;
+
+// workerPool runs connections on reusable threads. A connection is handed to an idle thread if there is one and to a new thread otherwise, so a slow connection never holds up the others. Threads that stay idle for workerIdleTimeout exit.
+struct workerPool {
+    shared: Arc<(Mutex<poolState>, Condvar)>,
+}
+
+struct poolState {
+    idle: usize,
+    queue: VecDeque<job>,
+}
+
+impl workerPool {
+    fn new() -> workerPool {
+        workerPool {
+            shared: Arc::new((
+                Mutex::new(poolState {
+                    idle: 0,
+                    queue: VecDeque::new(),
+                }),
+                Condvar::new(),
+            )),
+        }
+    }
+
+    fn execute(&self, f: impl FnOnce() + Send + 'static) {
+        let (m, cv) = &*self.shared;
+        {
+            let mut s = lock(m);
+            // 排队的任务不超过空闲线程数，每个任务都有线程马上处理。
+            if s.idle > s.queue.len() {
+                s.queue.push_back(Box::new(f));
+                cv.notify_one();
+                return;
+            }
+        }
+        let shared = self.shared.clone();
+        thread::spawn(move || {
+            f();
+            worker(&shared);
+        });
+    }
+}
+
+fn worker(shared: &(Mutex<poolState>, Condvar)) {
+    let (m, cv) = shared;
+    let mut s = lock(m);
+    loop {
+        s.idle += 1;
+        loop {
+            if let Some(f) = s.queue.pop_front() {
+                s.idle -= 1;
+                drop(s);
+                f();
+                s = lock(m);
+                break;
+            }
+            let (g, timeout) = cv
+                .wait_timeout(s, workerIdleTimeout)
+                .unwrap_or_else(|e| e.into_inner());
+            s = g;
+            if timeout.timed_out() && s.queue.is_empty() {
+                s.idle -= 1;
+                return;
+            }
+        }
+    }
+}
+
+/// ServeMux is an HTTP request multiplexer. It matches the URL of each incoming request against a list of registered patterns and calls the handler for the pattern that most closely matches the URL.
+///
+/// Patterns follow Go 1.22 and have the form "[METHOD ][HOST]/[PATH]":
+///
+/// - "/index.html" matches the path "/index.html" for any host and method.
+/// - "GET /static/" matches a GET or HEAD request whose path begins with "/static/".
+/// - "example.com/" matches any request to the host "example.com".
+/// - "example.com/{$}" matches requests with host "example.com" and path "/".
+/// - "/b/{bucket}/o/{objectname...}" matches paths whose first segment is "b" and whose third segment is "o". Request::PathValue("bucket") returns the second segment and PathValue("objectname") the rest of the path.
+///
+/// If two or more patterns match a request, the most specific pattern takes precedence: a pattern P1 is more specific than P2 if P1 matches a strict subset of P2's requests. A pattern with a host takes precedence over one without. If neither of two patterns is more specific and they match some request in common, they conflict, and registering the second one panics.
+///
+/// A request for a path without a trailing slash is redirected to the path with one if only the latter is registered, for example "/images" to "/images/". Paths containing . or .. elements or repeated slashes are redirected to an equivalent, cleaner path. A path that matches a pattern registered for other methods gets 405 Method Not Allowed with an Allow header, and anything else 404 Not Found.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ServeMux是HTTP请求多路复用器。它把每个传入请求的URL与注册的模式列表进行匹配，然后调用与URL最匹配的模式对应的处理器。
+///
+/// 模式与Go 1.22相同，格式为"[METHOD ][HOST]/[PATH]"：
+///
+/// - "/index.html"匹配任意host和方法的路径"/index.html"。
+/// - "GET /static/"匹配路径以"/static/"开头的GET或HEAD请求。
+/// - "example.com/"匹配发往host "example.com"的所有请求。
+/// - "example.com/{$}"匹配host为"example.com"并且路径为"/"的请求。
+/// - "/b/{bucket}/o/{objectname...}"匹配第一段为"b"、第三段为"o"的路径。Request::PathValue("bucket")返回第二段，PathValue("objectname")返回路径的剩余部分。
+///
+/// 如果多个模式匹配同一个请求，最具体的模式优先：如果P1匹配的请求是P2匹配的请求的真子集，P1比P2更具体。带host的模式优先于不带host的模式。如果两个模式都不比对方更具体，又会匹配某些相同的请求，则它们冲突，注册第二个模式时panic。
+///
+/// 如果只注册了带'/'结尾的路径，不带'/'的请求会被重定向到带'/'的路径，例如"/images"重定向到"/images/"。包含.或..或者重复'/'的路径会被重定向到等价的规范路径。路径匹配其他方法注册的模式时返回405 Method Not Allowed并带有Allow头，其他情况返回404 Not Found。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http as http;
+/// use http::server::{ResponseWriter, ServeMux};
+///
+/// let mut mux = ServeMux::default();
+/// mux.HandleFunc("GET /items/{id}", |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+///     w.Write(r.PathValue("id").as_bytes()).unwrap();
+/// });
+/// mux.HandleFunc("/items/", |w: &mut dyn ResponseWriter, r: &mut http::Request| {});
+///
+/// let req = http::Request::New(http::Method::Get, "http://example.com/items/42", None).unwrap();
+/// assert_eq!(mux.Handler(&req).1, "GET /items/{id}");
+/// let req = http::Request::New(http::Method::Get, "http://example.com/items/42/parts", None).unwrap();
+/// assert_eq!(mux.Handler(&req).1, "/items/");
+/// ```
+#[derive(Default)]
+pub struct ServeMux {
+    router: router<Arc<dyn Handler>>,
+}
+
+/// NewServeMux allocates and returns a new ServeMux.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewServeMux创建并返回一个新的ServeMux。
+/// </details>
+pub fn NewServeMux() -> ServeMux {
+    ServeMux::default()
+}
+
+impl ServeMux {
+    /// Handle registers the handler for the given pattern. It panics if the pattern is invalid or conflicts with a pattern that is already registered.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Handle为指定的模式注册处理器。如果模式无效或者与已注册的模式冲突，Handle会panic。
+    /// </details>
+    pub fn Handle(&mut self, pattern: &str, handler: impl Handler + 'static) {
+        if let Err(err) = self.router.register(pattern, Arc::new(handler)) {
+            panic!("http: {}", err);
+        }
+    }
+
+    /// HandleFunc registers the handler function for the given pattern. It panics like Handle.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// HandleFunc为指定的模式注册处理函数，与Handle一样会panic。
+    /// </details>
+    pub fn HandleFunc<F>(&mut self, pattern: &str, handler: F)
+    where
+        F: Fn(&mut dyn ResponseWriter, &mut Request) + Send + Sync + 'static,
+    {
+        self.Handle(pattern, HandlerFunc(handler))
+    }
+
+    /// Handler returns the handler to use for the given request, consulting r.Method, r.Host and r.URL.Path, together with the pattern that matched it. It always returns a handler: for requests that match no pattern it is one that redirects, answers 405 Method Not Allowed or 404 Not Found, and the pattern is empty.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Handler根据r.Method、r.Host和r.URL.Path返回请求使用的处理器，以及匹配的模式。它总会返回一个处理器：请求不匹配任何模式时，返回的处理器进行重定向、回复405 Method Not Allowed或404 Not Found，模式为空。
+    /// </details>
+    pub fn Handler(&self, r: &Request) -> (Arc<dyn Handler>, String) {
+        let (h, pattern, _) = self.findHandler(r);
+        (h, pattern)
+    }
+
+    fn findHandler(&self, r: &Request) -> (Arc<dyn Handler>, String, Vec<(String, String)>) {
+        match self.router.findRequest(r) {
+            route::found(h, pattern, values) => (h.clone(), pattern.to_string(), values),
+            route::redirect(url) => (
+                Arc::new(RedirectHandler(&url, 301)),
+                String::new(),
+                Vec::new(),
+            ),
+            route::methodNotAllowed(allow) => {
+                let allow = allow.join(", ");
+                let h = HandlerFunc(move |w: &mut dyn ResponseWriter, r: &mut Request| {
+                    w.Header().Set("Allow", &allow);
+                    Error(w, StatusText(405), 405);
+                });
+                (Arc::new(h), String::new(), Vec::new())
+            }
+            route::notFound => (Arc::new(NotFoundHandler()), String::new(), Vec::new()),
+        }
+    }
+}
+
+impl Handler for ServeMux {
+    fn ServeHttp(&self, w: &mut dyn ResponseWriter, r: &mut Request) {
+        if r.RequestURI == "*" {
+            if r.ProtoAtLeast(1, 1) {
+                w.Header().Set("Connection", "close");
+            }
+            w.WriteHeader(400);
+            return;
+        }
+        let (h, _, values) = self.findHandler(r);
+        for (name, value) in values {
+            r.SetPathValue(&name, &value);
+        }
+        h.ServeHttp(w, r)
+    }
+}
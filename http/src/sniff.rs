@@ -0,0 +1,145 @@
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// sniffLen is the maximum number of bytes DetectContentType looks at.
+pub(crate) const sniffLen: usize = 512;
+
+/// DetectContentType implements the algorithm described at <https://mimesniff.spec.whatwg.org/> to determine the Content-Type of the given data. It considers at most the first 512 bytes of data. DetectContentType always returns a valid MIME type: if it cannot determine a more specific one, it returns "application/octet-stream".
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// DetectContentType实现了<https://mimesniff.spec.whatwg.org/>描述的算法，用于判断给定数据的Content-Type。最多只检查数据的前512个字节。DetectContentType总是返回一个有效的MIME类型：无法确定更具体的类型时，返回"application/octet-stream"。
+/// </details>
+///
+/// # Example
+///
+/// ```
+/// use gostd_http::sniff;
+///
+/// assert_eq!(sniff::DetectContentType(b"<!DOCTYPE html><p>hi"), "text/html; charset=utf-8");
+/// assert_eq!(sniff::DetectContentType(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"), "image/png");
+/// assert_eq!(sniff::DetectContentType(b"plain words"), "text/plain; charset=utf-8");
+/// ```
+pub fn DetectContentType(data: &[u8]) -> &'static str {
+    let data = &data[..data.len().min(sniffLen)];
+
+    // 跳过开头的空白字符，HTML和XML的签名可以出现在空白之后。
+    let firstNonWS = data.iter().position(|&b| !isWS(b)).unwrap_or(data.len());
+    let text = &data[firstNonWS..];
+    for sig in htmlSigs {
+        if htmlMatch(text, sig.as_bytes()) {
+            return "text/html; charset=utf-8";
+        }
+    }
+    if text.starts_with(b"<?xml") {
+        return "text/xml; charset=utf-8";
+    }
+
+    for (prefix, ct) in exactSigs {
+        if data.starts_with(prefix) {
+            return ct;
+        }
+    }
+    if data.len() >= 14 && data.starts_with(b"RIFF") && &data[8..14] == b"WEBPVP" {
+        return "image/webp";
+    }
+    if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WAVE" {
+        return "audio/wave";
+    }
+    if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"AVI " {
+        return "video/avi";
+    }
+    if isMP4(data) {
+        return "video/mp4";
+    }
+
+    // 没有二进制控制字符的数据按文本处理。
+    if !data.iter().any(|&b| isBinary(b)) {
+        return "text/plain; charset=utf-8";
+    }
+    "application/octet-stream"
+}
+
+// htmlSigs are the HTML signatures, matched case-insensitively after leading whitespace and followed by a space or '>'.
+const htmlSigs: [&str; 17] = [
+    "<!DOCTYPE HTML",
+    "<HTML",
+    "<HEAD",
+    "<SCRIPT",
+    "<IFRAME",
+    "<H1",
+    "<DIV",
+    "<FONT",
+    "<TABLE",
+    "<A",
+    "<STYLE",
+    "<TITLE",
+    "<B",
+    "<BODY",
+    "<BR",
+    "<P",
+    "<!--",
+];
+
+// exactSigs are byte prefixes compared against the data in order; the first match wins.
+const exactSigs: [(&[u8], &str); 24] = [
+    (b"%PDF-", "application/pdf"),
+    (b"%!PS-Adobe-", "application/postscript"),
+    (b"\xFE\xFF", "text/plain; charset=utf-16be"),
+    (b"\xFF\xFE", "text/plain; charset=utf-16le"),
+    (b"\xEF\xBB\xBF", "text/plain; charset=utf-8"),
+    (b"\x00\x00\x01\x00", "image/x-icon"),
+    (b"\x00\x00\x02\x00", "image/x-icon"),
+    (b"BM", "image/bmp"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"\x89PNG\x0D\x0A\x1A\x0A", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"OggS\x00", "application/ogg"),
+    (b"MThd\x00\x00\x00\x06", "audio/midi"),
+    (b"ID3", "audio/mpeg"),
+    (b"\x1A\x45\xDF\xA3", "video/webm"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"\x1F\x8B\x08", "application/x-gzip"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"Rar!\x1A\x07\x00", "application/x-rar-compressed"),
+    (b"Rar!\x1A\x07\x01\x00", "application/x-rar-compressed"),
+    (b"\x00\x61\x73\x6D", "application/wasm"),
+    (b"\x00\x01\x00\x00", "font/ttf"),
+];
+
+// htmlMatch reports whether data starts with sig, ignoring ASCII case, followed by a tag-terminating byte.
+fn htmlMatch(data: &[u8], sig: &[u8]) -> bool {
+    if data.len() < sig.len() + 1 || !data[..sig.len()].eq_ignore_ascii_case(sig) {
+        return false;
+    }
+    // 签名之后必须是标签结束字符。
+    matches!(data[sig.len()], b' ' | b'>')
+}
+
+// isMP4 matches an ISO base media file with an "mp4" brand in its ftyp box.
+fn isMP4(data: &[u8]) -> bool {
+    if data.len() < 12 {
+        return false;
+    }
+    let boxSize = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    if data.len() < boxSize || !boxSize.is_multiple_of(4) || &data[4..8] != b"ftyp" {
+        return false;
+    }
+    (8..boxSize).step_by(4).any(|st| {
+        // 跳过minor_version。
+        st != 12 && data.len() >= st + 3 && &data[st..st + 3] == b"mp4"
+    })
+}
+
+fn isWS(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+// isBinary reports whether b is a binary data byte, which never appears in text.
+fn isBinary(b: u8) -> bool {
+    b <= 0x08 || b == 0x0B || (0x0E..=0x1A).contains(&b) || (0x1C..=0x1F).contains(&b)
+}
@@ -0,0 +1,310 @@
+//! Package socks provides a SOCKS version 5 client implementation.
+//!
+//! SOCKS protocol version 5 is defined in RFC 1928. Username/Password authentication for SOCKS version 5 is defined in RFC 1929.
+//!
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! socks包实现了SOCKS5协议的客户端。
+//!
+//! SOCKS5协议定义在RFC 1928，用户名/密码认证定义在RFC 1929。
+//! </details>
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+use gostd_builtin::*;
+
+use crate::error::{HTTPConnectError, HttpResult};
+
+#[cfg(feature = "async-std-runtime")]
+use async_std::io::{
+    Read as AsyncRead, ReadExt as AsyncReadExt, Write as AsyncWrite, WriteExt as AsyncWriteExt,
+};
+#[cfg(feature = "tokio-runtime")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Version5 is the SOCKS protocol version.
+pub const Version5: byte = 0x05;
+
+/// An AuthMethod represents a SOCKS authentication method.
+pub type AuthMethod = byte;
+
+/// no authentication required
+pub const AuthMethodNotRequired: AuthMethod = 0x00;
+/// use username/password
+pub const AuthMethodUsernamePassword: AuthMethod = 0x02;
+/// no acceptable authentication methods
+pub const AuthMethodNoAcceptableMethods: AuthMethod = 0xff;
+
+const CmdConnect: byte = 0x01;
+
+const AddrTypeIPv4: byte = 0x01;
+const AddrTypeFQDN: byte = 0x03;
+const AddrTypeIPv6: byte = 0x04;
+
+const authUsernamePasswordVersion: byte = 0x01;
+const authStatusSucceeded: byte = 0x00;
+
+/// Auth contains authentication parameters that specific Dialers may require.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Auth包含某些Dialer需要的认证参数。
+/// </details>
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd)]
+pub struct Auth {
+    pub User: String,
+    pub Password: String,
+}
+
+/// A Dialer holds SOCKS-specific options.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Dialer保存SOCKS相关的选项。
+/// </details>
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd)]
+pub struct Dialer {
+    proxyAddress: String, // proxy server address
+    auth: Option<Auth>,
+}
+
+/// SOCKS5 returns a Dialer that makes SOCKSv5 connections to the given address with an optional username and password. Host names are resolved by the proxy server.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// SOCKS5返回一个通过指定地址的SOCKS5代理建立连接的Dialer，可选用户名和密码认证。主机名由代理服务器解析。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http::socks;
+///
+/// let d = socks::SOCKS5("127.0.0.1:1080", None);
+/// let conn = d.Dial("tcp", "example.com:80").unwrap();
+/// ```
+pub fn SOCKS5(address: &str, auth: Option<Auth>) -> Dialer {
+    Dialer {
+        proxyAddress: address.to_string(),
+        auth,
+    }
+}
+
+impl Dialer {
+    /// Dial connects to the provided address on the provided network through the proxy.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// Dial通过代理连接到指定网络上的指定地址。
+    /// </details>
+    pub fn Dial(&self, network: &str, address: &str) -> HttpResult<TcpStream> {
+        if network != "tcp" {
+            return Err(HTTPConnectError::ConnectionFailure(format!(
+                "socks connect {} {}->{}: network not implemented",
+                network, self.proxyAddress, address
+            )));
+        }
+        let mut conn = TcpStream::connect(self.proxyAddress.as_str())?;
+        self.DialWithConn(&mut conn, address)?;
+        Ok(conn)
+    }
+
+    /// DialWithConn performs the SOCKS handshake on conn, an established connection to the proxy server, asking it to connect to address. On success conn carries the tunnelled stream.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// DialWithConn在已经连接到代理服务器的conn上进行SOCKS握手，请求代理连接address。成功后conn即为到目标地址的隧道。
+    /// </details>
+    pub fn DialWithConn(&self, conn: &mut (impl Read + Write), address: &str) -> HttpResult<()> {
+        conn.write_all(&self.greeting())?;
+        let mut reply = [0u8; 2];
+        conn.read_exact(&mut reply)?;
+        if self.chooseMethod(reply)? == AuthMethodUsernamePassword {
+            conn.write_all(&self.authRequest()?)?;
+            conn.read_exact(&mut reply)?;
+            checkAuthReply(reply)?;
+        }
+        conn.write_all(&connectRequest(address)?)?;
+        let mut head = [0u8; 4];
+        conn.read_exact(&mut head)?;
+        checkReply(head)?;
+        // 域名类型的地址需要先读一个长度字节
+        let mut l = [0u8; 1];
+        if head[3] == AddrTypeFQDN {
+            conn.read_exact(&mut l)?;
+        }
+        let mut rest = vec![0u8; replyAddrLen(head[3], l[0])?];
+        conn.read_exact(&mut rest)?;
+        Ok(())
+    }
+
+    /// DialWithConnAsync is the async counterpart of DialWithConn.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// DialWithConnAsync是DialWithConn的异步版本。
+    /// </details>
+    #[cfg(feature = "async-http")]
+    pub async fn DialWithConnAsync<C>(&self, conn: &mut C, address: &str) -> HttpResult<()>
+    where
+        C: AsyncRead + AsyncWrite + Unpin,
+    {
+        conn.write_all(&self.greeting()).await?;
+        let mut reply = [0u8; 2];
+        conn.read_exact(&mut reply).await?;
+        if self.chooseMethod(reply)? == AuthMethodUsernamePassword {
+            conn.write_all(&self.authRequest()?).await?;
+            conn.read_exact(&mut reply).await?;
+            checkAuthReply(reply)?;
+        }
+        conn.write_all(&connectRequest(address)?).await?;
+        let mut head = [0u8; 4];
+        conn.read_exact(&mut head).await?;
+        checkReply(head)?;
+        // 域名类型的地址需要先读一个长度字节
+        let mut l = [0u8; 1];
+        if head[3] == AddrTypeFQDN {
+            conn.read_exact(&mut l).await?;
+        }
+        let mut rest = vec![0u8; replyAddrLen(head[3], l[0])?];
+        conn.read_exact(&mut rest).await?;
+        Ok(())
+    }
+
+    fn greeting(&self) -> Vec<byte> {
+        if self.auth.is_some() {
+            return vec![
+                Version5,
+                2,
+                AuthMethodNotRequired,
+                AuthMethodUsernamePassword,
+            ];
+        }
+        vec![Version5, 1, AuthMethodNotRequired]
+    }
+
+    fn chooseMethod(&self, reply: [byte; 2]) -> HttpResult<AuthMethod> {
+        if reply[0] != Version5 {
+            return Err(HTTPConnectError::ConnectionFailure(format!(
+                "socks: unexpected protocol version {}",
+                reply[0]
+            )));
+        }
+        match reply[1] {
+            AuthMethodNotRequired => Ok(AuthMethodNotRequired),
+            AuthMethodUsernamePassword if self.auth.is_some() => Ok(AuthMethodUsernamePassword),
+            AuthMethodNoAcceptableMethods => {
+                Err("socks: no acceptable authentication methods".into())
+            }
+            m => Err(HTTPConnectError::ConnectionFailure(format!(
+                "socks: unsupported authentication method {}",
+                m
+            ))),
+        }
+    }
+
+    fn authRequest(&self) -> HttpResult<Vec<byte>> {
+        let auth = self.auth.clone().unwrap_or_default();
+        if len!(auth.User) < 1
+            || len!(auth.User) > 255
+            || len!(auth.Password) < 1
+            || len!(auth.Password) > 255
+        {
+            return Err("socks: invalid username/password".into());
+        }
+        let mut b = vec![authUsernamePasswordVersion, len!(auth.User) as byte];
+        b.extend_from_slice(auth.User.as_bytes());
+        b.push(len!(auth.Password) as byte);
+        b.extend_from_slice(auth.Password.as_bytes());
+        Ok(b)
+    }
+}
+
+fn checkAuthReply(reply: [byte; 2]) -> HttpResult<()> {
+    if reply[0] != authUsernamePasswordVersion {
+        return Err("socks: invalid username/password version".into());
+    }
+    if reply[1] != authStatusSucceeded {
+        return Err("socks: username/password authentication failed".into());
+    }
+    Ok(())
+}
+
+// connectRequest builds the CONNECT command for address. Host names are sent as is so that the proxy resolves them.
+fn connectRequest(address: &str) -> HttpResult<Vec<byte>> {
+    let (host, port) = splitHostPort(address)?;
+    let mut b = vec![Version5, CmdConnect, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            b.push(AddrTypeIPv4);
+            b.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            b.push(AddrTypeIPv6);
+            b.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if len!(host) > 255 {
+                return Err("socks: FQDN too long".into());
+            }
+            b.push(AddrTypeFQDN);
+            b.push(len!(host) as byte);
+            b.extend_from_slice(host.as_bytes());
+        }
+    }
+    b.extend_from_slice(&port.to_be_bytes());
+    Ok(b)
+}
+
+// checkReply validates the fixed part of the CONNECT reply.
+fn checkReply(head: [byte; 4]) -> HttpResult<()> {
+    if head[0] != Version5 {
+        return Err(HTTPConnectError::ConnectionFailure(format!(
+            "socks: unexpected protocol version {}",
+            head[0]
+        )));
+    }
+    if head[1] != 0 {
+        return Err(HTTPConnectError::ConnectionFailure(format!(
+            "socks: {}",
+            replyString(head[1])
+        )));
+    }
+    Ok(())
+}
+
+// replyAddrLen returns how many bytes of bound address and port follow the fixed part of the CONNECT reply. fqdnLen is the length prefix read for FQDN addresses.
+fn replyAddrLen(addrType: byte, fqdnLen: byte) -> HttpResult<usize> {
+    match addrType {
+        AddrTypeIPv4 => Ok(4 + 2),
+        AddrTypeIPv6 => Ok(16 + 2),
+        AddrTypeFQDN => Ok(fqdnLen as usize + 2),
+        t => Err(HTTPConnectError::ConnectionFailure(format!(
+            "socks: unknown address type {}",
+            t
+        ))),
+    }
+}
+
+fn replyString(code: byte) -> String {
+    match code {
+        0x00 => "succeeded".to_string(),
+        0x01 => "general SOCKS server failure".to_string(),
+        0x02 => "connection not allowed by ruleset".to_string(),
+        0x03 => "network unreachable".to_string(),
+        0x04 => "host unreachable".to_string(),
+        0x05 => "connection refused".to_string(),
+        0x06 => "TTL expired".to_string(),
+        0x07 => "command not supported".to_string(),
+        0x08 => "address type not supported".to_string(),
+        _ => format!("unknown code: {}", code),
+    }
+}
+
+fn splitHostPort(address: &str) -> HttpResult<(&str, u16)> {
+    let i = address.rfind(':').ok_or_else(|| {
+        HTTPConnectError::ConnectionFailure(format!("socks: missing port in address {}", address))
+    })?;
+    let host = address[..i].trim_start_matches('[').trim_end_matches(']');
+    let port = address[i + 1..].parse::<u16>()?;
+    Ok((host, port))
+}
@@ -0,0 +1,3452 @@
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use crate as http;
+use crate::proxy::proxyConfig;
+use gostd_url as url;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// serve starts a minimal HTTP/1.1 server on 127.0.0.1 that answers every request with "ok" and keeps connections alive. It returns the listening address and the number of accepted connections.
+fn serve() -> (String, Arc<AtomicUsize>) {
+    serveFunc(|_| b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_vec())
+}
+
+// serveFunc is like serve, but the raw response for each request is produced by f from the request line and header lines.
+fn serveFunc<F>(f: F) -> (String, Arc<AtomicUsize>)
+where
+    F: Fn(&[String]) -> Vec<u8> + Send + Sync + 'static,
+{
+    serveFuncBody(move |head, _| f(head))
+}
+
+// serveFuncBody is like serveFunc, but f also receives the request body.
+fn serveFuncBody<F>(f: F) -> (String, Arc<AtomicUsize>)
+where
+    F: Fn(&[String], &[u8]) -> Vec<u8> + Send + Sync + 'static,
+{
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    let f = Arc::new(f);
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let conn = conn.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            let f = f.clone();
+            thread::spawn(move || serveConn(conn, &*f));
+        }
+    });
+    (addr, accepted)
+}
+
+// ConnHandler produces the raw response for a request from its request line, header lines and body.
+type ConnHandler = dyn Fn(&[String], &[u8]) -> Vec<u8>;
+
+fn serveConn(conn: TcpStream, f: &ConnHandler) {
+    let mut w = conn.try_clone().unwrap();
+    let mut r = BufReader::new(conn);
+    loop {
+        let mut head = Vec::new();
+        let mut contentLength = 0;
+        let mut close = false;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if r.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let l = line.trim_end().to_ascii_lowercase();
+            if l.is_empty() {
+                break;
+            }
+            if let Some(v) = l.strip_prefix("content-length:") {
+                contentLength = v.trim().parse().unwrap();
+            }
+            if l == "connection: close" {
+                close = true;
+            }
+            head.push(line.trim_end().to_string());
+        }
+        let mut body = vec![0; contentLength];
+        r.read_exact(&mut body).unwrap();
+        w.write_all(&f(&head, &body)).unwrap();
+        if close {
+            return;
+        }
+    }
+}
+
+#[test]
+fn Test_TransportReusesIdleConn() {
+    let (addr, accepted) = serve();
+    let mut tr = http::Transport::default();
+    for _ in 0..3 {
+        let req =
+            http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+        let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+        assert_eq!(resp.StatusCode, 200);
+        assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), b"ok".to_vec());
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn Test_TransportDisableKeepAlives() {
+    let (addr, accepted) = serve();
+    let mut tr = http::Transport::default();
+    tr.DisableKeepAlives = true;
+    for _ in 0..3 {
+        let req =
+            http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+        let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+        resp.Body.ReadAll().unwrap();
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn Test_TransportIdleConnTimeout() {
+    let (addr, accepted) = serve();
+    let mut tr = http::Transport::default();
+    tr.IdleConnTimeout = gostd_time::Duration::new(50 * gostd_time::Millisecond);
+    let req = http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    resp.Body.ReadAll().unwrap();
+    thread::sleep(std::time::Duration::from_millis(100));
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    resp.Body.ReadAll().unwrap();
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn Test_TransportHTTPProxy() {
+    let (addr, _) = serveFunc(|head| {
+        let auth = head
+            .iter()
+            .find(|l| l.starts_with("Proxy-Authorization:"))
+            .cloned()
+            .unwrap_or_default();
+        let body = format!("{}|{}", head[0], auth);
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .into_bytes()
+    });
+    let mut tr = http::Transport::default();
+    let proxyURL = url::Parse(&format!("http://user:p%40ss@{}", addr)).unwrap();
+    tr.Proxy = Some(http::ProxyURL(proxyURL));
+    let req = http::Request::New(http::Method::Get, "http://example.invalid/a?b=c", None).unwrap();
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert_eq!(
+        String::from_utf8(resp.Body.ReadAll().unwrap().to_vec()).unwrap(),
+        "GET http://example.invalid/a?b=c HTTP/1.1|Proxy-Authorization: Basic dXNlcjpwQHNz"
+    );
+}
+
+#[test]
+fn Test_TransportProxyConnectRefused() {
+    let (addr, _) = serveFunc(|head| {
+        assert_eq!(head[0], "CONNECT example.invalid:443 HTTP/1.1");
+        b"HTTP/1.1 407 Proxy Authentication Required\r\nContent-Length: 0\r\n\r\n".to_vec()
+    });
+    let mut tr = http::Transport::default();
+    tr.Proxy = Some(http::ProxyURL(
+        url::Parse(&format!("http://{}", addr)).unwrap(),
+    ));
+    let req = http::Request::New(http::Method::Get, "https://example.invalid/", None).unwrap();
+    let err = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap_err();
+    assert!(err.to_string().contains("407"), "{}", err);
+}
+
+#[test]
+fn Test_ProxyForURL() {
+    let cfg = proxyConfig {
+        HTTPProxy: "proxy.local:3128".to_string(),
+        HTTPSProxy: "https://secure.proxy:443".to_string(),
+        NoProxy: "example.com, .internal,10.0.0.0/8, 192.168.1.1, other.org:8080".to_string(),
+        CGI: false,
+    };
+    let proxyFor = |u: &str| {
+        cfg.proxyForURL(&url::Parse(u).unwrap())
+            .unwrap()
+            .map(|p| format!("{}://{}", p.Scheme, p.Host))
+    };
+    assert_eq!(
+        proxyFor("http://golang.org/"),
+        Some("http://proxy.local:3128".to_string())
+    );
+    assert_eq!(
+        proxyFor("https://golang.org/"),
+        Some("https://secure.proxy:443".to_string())
+    );
+    assert_eq!(proxyFor("http://localhost:8080/"), None);
+    assert_eq!(proxyFor("http://127.0.0.1/"), None);
+    assert_eq!(proxyFor("http://example.com/"), None);
+    assert_eq!(proxyFor("http://www.example.com/"), None);
+    assert_eq!(
+        proxyFor("http://notexample.com/"),
+        Some("http://proxy.local:3128".to_string())
+    );
+    assert_eq!(proxyFor("http://a.internal/"), None);
+    assert_eq!(
+        proxyFor("http://internal/"),
+        Some("http://proxy.local:3128".to_string())
+    );
+    assert_eq!(proxyFor("http://10.1.2.3/"), None);
+    assert_eq!(proxyFor("http://192.168.1.1/"), None);
+    assert_eq!(
+        proxyFor("http://192.168.1.2/"),
+        Some("http://proxy.local:3128".to_string())
+    );
+    assert_eq!(proxyFor("http://other.org:8080/"), None);
+    assert_eq!(
+        proxyFor("http://other.org/"),
+        Some("http://proxy.local:3128".to_string())
+    );
+
+    let cgi = proxyConfig {
+        CGI: true,
+        ..cfg.clone()
+    };
+    assert!(cgi
+        .proxyForURL(&url::Parse("http://golang.org/").unwrap())
+        .unwrap()
+        .is_none());
+}
+
+// serveSOCKS5 starts a small SOCKS5 server that requires the given credentials, resolves every FQDN to 127.0.0.1 and relays the tunnelled bytes. It returns the listening address and the last requested destination.
+fn serveSOCKS5(
+    user: &'static str,
+    password: &'static str,
+) -> (String, Arc<std::sync::Mutex<String>>) {
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    let dest = Arc::new(std::sync::Mutex::new(String::new()));
+    let lastDest = dest.clone();
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let mut c = conn.unwrap();
+            let dest = dest.clone();
+            thread::spawn(move || {
+                let mut b = [0u8; 2];
+                c.read_exact(&mut b).unwrap();
+                let mut methods = vec![0u8; b[1] as usize];
+                c.read_exact(&mut methods).unwrap();
+                assert!(methods.contains(&0x02));
+                c.write_all(&[0x05, 0x02]).unwrap();
+                c.read_exact(&mut b).unwrap();
+                let mut u = vec![0u8; b[1] as usize];
+                c.read_exact(&mut u).unwrap();
+                let mut l = [0u8; 1];
+                c.read_exact(&mut l).unwrap();
+                let mut p = vec![0u8; l[0] as usize];
+                c.read_exact(&mut p).unwrap();
+                if u != user.as_bytes() || p != password.as_bytes() {
+                    c.write_all(&[0x01, 0x01]).unwrap();
+                    return;
+                }
+                c.write_all(&[0x01, 0x00]).unwrap();
+                let mut head = [0u8; 4];
+                c.read_exact(&mut head).unwrap();
+                assert_eq!(head[3], 0x03, "expected a FQDN to be resolved remotely");
+                c.read_exact(&mut l).unwrap();
+                let mut host = vec![0u8; l[0] as usize];
+                c.read_exact(&mut host).unwrap();
+                let mut port = [0u8; 2];
+                c.read_exact(&mut port).unwrap();
+                let port = u16::from_be_bytes(port);
+                *dest.lock().unwrap() = format!("{}:{}", String::from_utf8(host).unwrap(), port);
+                let target = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                c.write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                    .unwrap();
+                let (mut cr, mut tw) = (c.try_clone().unwrap(), target.try_clone().unwrap());
+                thread::spawn(move || std::io::copy(&mut cr, &mut tw));
+                let (mut tr, mut cw) = (target, c);
+                let _ = std::io::copy(&mut tr, &mut cw);
+            });
+        }
+    });
+    (addr, lastDest)
+}
+
+#[test]
+fn Test_TransportSOCKS5Proxy() {
+    let (addr, _) = serve();
+    let port = addr.rsplit(':').next().unwrap();
+    let (proxyAddr, dest) = serveSOCKS5("user", "secret");
+    let mut tr = http::Transport::default();
+    let proxyURL = url::Parse(&format!("socks5://user:secret@{}", proxyAddr)).unwrap();
+    tr.Proxy = Some(http::ProxyURL(proxyURL));
+    let target = format!("http://backend.invalid:{}/", port);
+    let req = http::Request::New(http::Method::Get, &target, None).unwrap();
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), b"ok".to_vec());
+    assert_eq!(*dest.lock().unwrap(), format!("backend.invalid:{}", port));
+
+    let mut tr = http::Transport::default();
+    let proxyURL = url::Parse(&format!("socks5://user:wrong@{}", proxyAddr)).unwrap();
+    tr.Proxy = Some(http::ProxyURL(proxyURL));
+    let err = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap_err();
+    assert!(err.to_string().contains("authentication failed"), "{}", err);
+}
+
+#[cfg(feature = "async-http")]
+fn blockOn<F: std::future::Future>(f: F) -> F::Output {
+    #[cfg(feature = "tokio-runtime")]
+    return tokio::runtime::Runtime::new().unwrap().block_on(f);
+    #[cfg(feature = "async-std-runtime")]
+    return async_std::task::block_on(f);
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncTransportSOCKS5Proxy() {
+    let (addr, _) = serve();
+    let port = addr.rsplit(':').next().unwrap();
+    let (proxyAddr, dest) = serveSOCKS5("user", "secret");
+    let mut tr = http::async_http::Transport::default();
+    tr.proxy = Some(url::Parse(&format!("socks5://user:secret@{}", proxyAddr)).unwrap());
+    let mut client = http::async_http::AsyncClient::NewWithTransport(tr);
+    let target = format!("http://backend.invalid:{}/", port);
+    let mut resp = blockOn(client.Get(&target)).unwrap();
+    assert_eq!(
+        blockOn(resp.Body.ReadAllAsync()).unwrap().to_vec(),
+        b"ok".to_vec()
+    );
+    assert_eq!(*dest.lock().unwrap(), format!("backend.invalid:{}", port));
+}
+
+// serveGzip answers with a gzip encoded body when the request accepts it, and with a deflate encoded one when asked for it explicitly.
+fn serveGzip() -> String {
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    let (addr, _) = serveFunc(|head| {
+        let accept = head
+            .iter()
+            .find_map(|l| l.strip_prefix("Accept-Encoding: "))
+            .unwrap_or("");
+        let (encoding, body) = match accept {
+            "gzip" => {
+                let mut e = GzEncoder::new(Vec::new(), Compression::default());
+                e.write_all(b"hello, gzip").unwrap();
+                ("gzip", e.finish().unwrap())
+            }
+            "deflate" => {
+                let mut e = ZlibEncoder::new(Vec::new(), Compression::default());
+                e.write_all(b"hello, deflate").unwrap();
+                ("deflate", e.finish().unwrap())
+            }
+            _ => ("identity", b"hello, identity".to_vec()),
+        };
+        let mut resp = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n",
+            encoding,
+            body.len()
+        )
+        .into_bytes();
+        resp.extend_from_slice(&body);
+        resp
+    });
+    addr
+}
+
+#[test]
+fn Test_TransportGzipResponse() {
+    let addr = serveGzip();
+    let mut tr = http::Transport::default();
+    let req = http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert!(resp.Uncompressed);
+    assert_eq!(resp.ContentLength, -1);
+    assert_eq!(resp.Header.Get("Content-Encoding"), "");
+    assert_eq!(resp.Header.Get("Content-Length"), "");
+    assert_eq!(
+        resp.Body.ReadAll().unwrap().to_vec(),
+        b"hello, gzip".to_vec()
+    );
+
+    // A caller asking for an encoding itself gets the raw body back.
+    let mut req = req.clone();
+    req.Header.Set("Accept-Encoding", "deflate");
+    let resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert!(!resp.Uncompressed);
+    assert_eq!(resp.Header.Get("Content-Encoding"), "deflate");
+
+    let mut tr = http::Transport::default();
+    tr.DisableCompression = true;
+    let req = http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert!(!resp.Uncompressed);
+    assert_eq!(
+        resp.Body.ReadAll().unwrap().to_vec(),
+        b"hello, identity".to_vec()
+    );
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncClientGzipResponse() {
+    let addr = serveGzip();
+    let mut resp = blockOn(http::async_http::Get(&format!("http://{}/", addr))).unwrap();
+    assert!(resp.Uncompressed);
+    assert_eq!(resp.Header.Get("Content-Encoding"), "");
+    assert_eq!(
+        blockOn(resp.Body.ReadAllAsync()).unwrap().to_vec(),
+        b"hello, gzip".to_vec()
+    );
+}
+
+// serveChunked answers every request with a chunked body of n chunks of "0123456789".
+fn serveChunked(n: usize) -> (String, Arc<AtomicUsize>) {
+    serveFunc(move |_| {
+        let mut resp = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        for _ in 0..n {
+            resp.extend_from_slice(b"a;ext=1\r\n0123456789\r\n");
+        }
+        resp.extend_from_slice(b"0\r\nX-Trailer: done\r\n\r\n");
+        resp
+    })
+}
+
+#[test]
+fn Test_TransportStreamsChunkedBody() {
+    let (addr, accepted) = serveChunked(1000);
+    let mut tr = http::Transport::default();
+    let req = http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    for _ in 0..2 {
+        let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+        assert_eq!(resp.ContentLength, -1);
+        assert_eq!(resp.TransferEncoding, vec!["chunked".to_string()]);
+        let mut total = 0;
+        let mut buf = [0u8; 7];
+        loop {
+            let n = resp.Body.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            total += n;
+        }
+        assert_eq!(total, 10000);
+    }
+    // 读到EOF的连接回到连接池被复用。
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn Test_TransportClosesUnreadBody() {
+    let (addr, accepted) = serveChunked(10);
+    let mut tr = http::Transport::default();
+    let req = http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    for _ in 0..2 {
+        let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+        let mut buf = [0u8; 5];
+        resp.Body.read_exact(&mut buf).unwrap();
+        resp.Body.Close();
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn Test_ReadResponseStreamsBody() {
+    let raw = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloHTTP/1.1 204 No Content\r\n\r\n";
+    let req = http::Request::default();
+    let mut resp = http::ReadResponse(BufReader::new(std::io::Cursor::new(raw)), &req).unwrap();
+    assert_eq!(resp.ContentLength, 5);
+    let mut b = vec![0u8; 8];
+    assert_eq!(
+        gostd_io::Reader::Read(&mut resp.Body, b.clone()).unwrap(),
+        5
+    );
+    let err = gostd_io::Reader::Read(&mut resp.Body, b).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncClientStreamsChunkedBody() {
+    let (addr, _) = serveChunked(100);
+    let mut resp = blockOn(http::async_http::Get(&format!("http://{}/", addr))).unwrap();
+    assert_eq!(resp.ContentLength, -1);
+    assert_eq!(blockOn(resp.Body.ReadAllAsync()).unwrap().len(), 1000);
+}
+
+#[test]
+fn Test_RequestWriteBody() {
+    let req = http::Request::New(
+        http::Method::Post,
+        "http://example.com/a",
+        Some("hello".into()),
+    )
+    .unwrap();
+    let mut w = Vec::new();
+    req.Write(&mut w).unwrap();
+    let out = String::from_utf8(w).unwrap();
+    assert!(out.starts_with("POST /a HTTP/1.1\r\n"));
+    assert!(out.contains("Content-Length: 5\r\n"));
+    assert!(out.ends_with("\r\n\r\nhello"));
+
+    let body = http::Body::New(std::io::Cursor::new(b"streamed body".to_vec()));
+    let req = http::Request::NewWithBody(http::Method::Put, "http://example.com/", body).unwrap();
+    assert!(req.GetBody.is_none());
+    let mut w = Vec::new();
+    req.Write(&mut w).unwrap();
+    let out = String::from_utf8(w).unwrap();
+    assert!(out.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(!out.contains("Content-Length"));
+    assert!(out.ends_with("\r\n\r\nd\r\nstreamed body\r\n0\r\n\r\n"));
+}
+
+#[test]
+fn Test_TransportChunkedUpload() {
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (conn, _) = ln.accept().unwrap();
+        let mut r = BufReader::new(conn.try_clone().unwrap());
+        let mut head = Vec::new();
+        loop {
+            let mut line = String::new();
+            r.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line);
+        }
+        assert!(head.contains(&"Transfer-Encoding: chunked\r\n".to_string()));
+        let mut body = Vec::new();
+        crate::transfer::transferReader::new(
+            &mut r,
+            crate::transfer::transferState::Chunked(Default::default()),
+        )
+        .read_to_end(&mut body)
+        .unwrap();
+        let mut w = conn;
+        write!(
+            w,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .unwrap();
+        w.write_all(&body).unwrap();
+    });
+    // 100KB的数据分多个chunk上传。
+    let data: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+    let body = http::Body::New(std::io::Cursor::new(data.clone()));
+    let mut req =
+        http::Request::NewWithBody(http::Method::Post, &format!("http://{}/", addr), body).unwrap();
+    let mut resp = http::Client::New().Do(&mut req).unwrap();
+    assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), data);
+}
+
+#[test]
+fn Test_ClientRedirectLimit() {
+    let (addr, _) = serveFunc(|_| {
+        b"HTTP/1.1 302 Found\r\nLocation: /loop\r\nContent-Length: 0\r\n\r\n".to_vec()
+    });
+    let err = http::Client::New()
+        .Get(&format!("http://{}/", addr))
+        .unwrap_err();
+    assert!(err.to_string().contains("stopped after 10 redirects"));
+
+    let mut client = http::Client::New();
+    let via = Arc::new(AtomicUsize::new(0));
+    let n = via.clone();
+    client.CheckRedirect = Some(Arc::new(move |req, v| {
+        n.store(v.len(), Ordering::SeqCst);
+        assert_eq!(req.URL.Path, "/loop");
+        if v.len() == 3 {
+            return Err(http::HTTPConnectError::ErrUseLastResponse);
+        }
+        Ok(())
+    }));
+    let resp = client.Get(&format!("http://{}/", addr)).unwrap();
+    assert_eq!(resp.StatusCode, 302);
+    assert_eq!(via.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn Test_ClientRedirect307() {
+    let (addr, _) = serveFuncBody(|head, body| {
+        if head[0].starts_with("POST /start") {
+            // 跨主机重定向：127.0.0.1 -> localhost
+            let port = head
+                .iter()
+                .find_map(|l| l.strip_prefix("Host: 127.0.0.1:"))
+                .unwrap()
+                .to_string();
+            return format!(
+                "HTTP/1.1 307 Temporary Redirect\r\nLocation: http://localhost:{}/dst\r\nContent-Length: 0\r\n\r\n",
+                port
+            )
+            .into_bytes();
+        }
+        let auth = head.iter().any(|l| l.starts_with("Authorization:"));
+        let out = format!("{}|{}|{}", head[0], String::from_utf8_lossy(body), auth);
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            out.len(),
+            out
+        )
+        .into_bytes()
+    });
+    let mut req = http::Request::New(
+        http::Method::Post,
+        &format!("http://{}/start", addr),
+        Some("hello".into()),
+    )
+    .unwrap();
+    req.Header.Set("Authorization", "Bearer secret");
+    let mut resp = http::Client::New().Do(&mut req).unwrap();
+    assert_eq!(
+        String::from_utf8(resp.Body.ReadAll().unwrap().to_vec()).unwrap(),
+        "POST /dst HTTP/1.1|hello|false"
+    );
+}
+
+#[test]
+fn Test_ClientRedirectSensitiveHeaders() {
+    // 127.0.0.1 -> localhost -> 127.0.0.1，每一跳记录收到的Authorization和Cookie。
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let s = seen.clone();
+    let (addr, _) = serveFunc(move |head| {
+        let get = |k: &str| {
+            head.iter()
+                .find_map(|l| l.strip_prefix(k))
+                .unwrap_or("")
+                .to_string()
+        };
+        let host = get("Host: ");
+        let port = host.rsplit(':').next().unwrap().to_string();
+        let path = head[0].split(' ').nth(1).unwrap().to_string();
+        s.lock().unwrap().push(format!(
+            "{} {}|{}",
+            path,
+            get("Authorization: "),
+            get("Cookie: ")
+        ));
+        let next = match path.as_str() {
+            "/a" => format!("http://localhost:{}/b", port),
+            "/b" => format!("http://127.0.0.1:{}/c", port),
+            "/login" => "/home".to_string(),
+            _ => return b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec(),
+        };
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: {}\r\nSet-Cookie: session=abc; Path=/\r\nContent-Length: 0\r\n\r\n",
+            next
+        )
+        .into_bytes()
+    });
+    let mut req =
+        http::Request::New(http::Method::Get, &format!("http://{}/a", addr), None).unwrap();
+    req.Header.Set("Authorization", "Bearer secret");
+    req.Header.Set("Cookie", "theme=dark");
+    http::Client::New().Do(&mut req).unwrap();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        ["/a Bearer secret|theme=dark", "/b |", "/c |"]
+    );
+
+    // 有Jar时不转发调用者设置的Cookie头，由Jar按目标地址重新生成。
+    seen.lock().unwrap().clear();
+    let mut client = http::Client::New();
+    client.Jar = Some(Arc::new(http::cookiejar::New(None).unwrap()));
+    let mut req =
+        http::Request::New(http::Method::Get, &format!("http://{}/login", addr), None).unwrap();
+    req.Header.Set("Cookie", "session=old");
+    client.Do(&mut req).unwrap();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        ["/login |session=old", "/home |session=abc"]
+    );
+}
+
+// serveStall answers every request with the header and the first bytes of a 10 byte body, then stalls without sending the rest.
+fn serveStall() -> String {
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let mut conn = conn.unwrap();
+            thread::spawn(move || {
+                let mut r = BufReader::new(conn.try_clone().unwrap());
+                let mut line = String::new();
+                while r.read_line(&mut line).unwrap_or(0) > 2 {
+                    line.clear();
+                }
+                conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nabc")
+                    .unwrap();
+                thread::sleep(std::time::Duration::from_secs(2));
+            });
+        }
+    });
+    addr
+}
+
+#[test]
+fn Test_ClientTimeout() {
+    let (addr, _) = serveFunc(|_| {
+        thread::sleep(std::time::Duration::from_secs(1));
+        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_vec()
+    });
+    let mut client = http::Client::New();
+    client.Timeout = gostd_time::Duration::new(100 * gostd_time::Millisecond);
+    let start = std::time::Instant::now();
+    let err = client.Get(&format!("http://{}/", addr)).unwrap_err();
+    assert!(err.Timeout(), "{}", err);
+    assert!(
+        err.to_string().contains("Client.Timeout exceeded"),
+        "{}",
+        err
+    );
+    assert!(start.elapsed() < std::time::Duration::from_millis(900));
+
+    // 响应头返回后，计时继续作用于body的读取。
+    let addr = serveStall();
+    let mut resp = client.Get(&format!("http://{}/", addr)).unwrap();
+    let err = resp.Body.ReadAll().unwrap_err();
+    assert!(
+        matches!(err, http::HTTPConnectError::TimeoutError(_)),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn Test_TransportResponseHeaderTimeout() {
+    let (addr, _) = serveFunc(|head| {
+        if head[0].starts_with("GET /slow") {
+            thread::sleep(std::time::Duration::from_secs(1));
+        }
+        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_vec()
+    });
+    let mut tr = http::Transport::default();
+    tr.ResponseHeaderTimeout = gostd_time::Duration::new(100 * gostd_time::Millisecond);
+    let req =
+        http::Request::New(http::Method::Get, &format!("http://{}/slow", addr), None).unwrap();
+    let err = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap_err();
+    assert!(err.Timeout(), "{}", err);
+    assert_eq!(
+        err.to_string(),
+        "net/http: timeout awaiting response headers"
+    );
+
+    let req = http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), b"ok".to_vec());
+}
+
+#[test]
+fn Test_TransportExpectContinue() {
+    // /continue同意接收body，/reject不读取body直接拒绝。
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let mut conn = conn.unwrap();
+            let mut r = BufReader::new(conn.try_clone().unwrap());
+            let mut head = Vec::new();
+            let mut line = String::new();
+            while r.read_line(&mut line).unwrap_or(0) > 2 {
+                head.push(line.trim_end().to_string());
+                line.clear();
+            }
+            if head[0].starts_with("POST /reject") {
+                conn.write_all(b"HTTP/1.1 417 Expectation Failed\r\nContent-Length: 0\r\n\r\n")
+                    .unwrap();
+                continue;
+            }
+            conn.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+            let mut body = [0u8; 5];
+            r.read_exact(&mut body).unwrap();
+            conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n")
+                .unwrap();
+            conn.write_all(&body).unwrap();
+        }
+    });
+    let mut tr = http::Transport::default();
+    tr.ExpectContinueTimeout = gostd_time::Duration::new(10 * gostd_time::Second);
+    let start = std::time::Instant::now();
+    for (path, status) in [("/continue", 200), ("/reject", 417)] {
+        let mut req = http::Request::New(
+            http::Method::Post,
+            &format!("http://{}{}", addr, path),
+            Some("hello".into()),
+        )
+        .unwrap();
+        req.Header.Set("Expect", "100-continue");
+        let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+        assert_eq!(resp.StatusCode, status);
+        if status == 200 {
+            assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), b"hello".to_vec());
+        }
+    }
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncClientTimeout() {
+    let addr = serveStall();
+    let mut client = http::async_http::AsyncClient::New();
+    client.timeout = gostd_time::Duration::new(100 * gostd_time::Millisecond);
+    blockOn(async {
+        let mut resp = client.Get(&format!("http://{}/", addr)).await.unwrap();
+        let err = resp.Body.ReadAllAsync().await.unwrap_err();
+        assert!(err.Timeout(), "{}", err);
+    });
+}
+
+// psl treats the last label as the public suffix, plus "co.uk".
+struct psl;
+
+impl http::cookiejar::PublicSuffixList for psl {
+    fn PublicSuffix(&self, domain: &str) -> String {
+        if domain.ends_with("co.uk") {
+            return "co.uk".to_string();
+        }
+        domain.rsplit('.').next().unwrap().to_string()
+    }
+
+    fn String(&self) -> String {
+        "test".to_string()
+    }
+}
+
+fn jarCookies(jar: &dyn http::CookieJar, u: &str) -> String {
+    jar.Cookies(&url::Parse(u).unwrap())
+        .iter()
+        .map(|c| format!("{}={}", c.Name, c.Value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn Test_CookieJar() {
+    use http::CookieJar;
+    let opts = http::cookiejar::Options {
+        PublicSuffixList: Some(Arc::new(psl)),
+    };
+    let jar = http::cookiejar::New(Some(&opts)).unwrap();
+    let mut resp = http::Response::default();
+    resp.Header.0.insert(
+        "Set-Cookie".to_string(),
+        vec![
+            "host=1".to_string(),
+            "domain=2; Domain=.Example.com; Path=/".to_string(),
+            "secure=3; Secure; Path=/".to_string(),
+            "deep=4; Path=/foo/bar".to_string(),
+            "other=5; Domain=other.com".to_string(),
+            "suffix=6; Domain=com".to_string(),
+            "persistent=7; Max-Age=3600; Path=/".to_string(),
+            "expired=8; Expires=Wed, 21 Oct 2015 07:28:00 GMT".to_string(),
+        ],
+    );
+    let u = url::Parse("http://www.example.com:8080/foo/x").unwrap();
+    jar.SetCookies(&u, resp.Cookies());
+
+    // 按路径长度从长到短排序，相同长度按创建顺序。
+    assert_eq!(
+        jarCookies(&jar, "http://www.example.com/foo/bar/baz"),
+        "deep=4 host=1 domain=2 persistent=7"
+    );
+    assert_eq!(
+        jarCookies(&jar, "https://www.example.com/foo"),
+        "host=1 domain=2 secure=3 persistent=7"
+    );
+    assert_eq!(jarCookies(&jar, "http://sub.example.com/foo"), "domain=2");
+    assert_eq!(jarCookies(&jar, "http://other.com/"), "");
+    assert_eq!(jarCookies(&jar, "ftp://www.example.com/foo"), "");
+
+    // Max-Age<=0的cookie删除已有的同名cookie。
+    let mut resp = http::Response::default();
+    resp.Header.0.insert(
+        "Set-Cookie".to_string(),
+        vec!["domain=x; Domain=example.com; Path=/; Max-Age=0".to_string()],
+    );
+    jar.SetCookies(&u, resp.Cookies());
+    assert_eq!(jarCookies(&jar, "http://sub.example.com/"), "");
+    assert_eq!(
+        jarCookies(&jar, "http://www.example.com/foo"),
+        "host=1 persistent=7"
+    );
+
+    // 公共后缀与主机名相同时作为host-only cookie。
+    let u = url::Parse("http://www.co.uk/").unwrap();
+    let mut resp = http::Response::default();
+    resp.Header.0.insert(
+        "Set-Cookie".to_string(),
+        vec!["a=1; Domain=co.uk".to_string()],
+    );
+    jar.SetCookies(&u, resp.Cookies());
+    assert_eq!(jarCookies(&jar, "http://www.co.uk/"), "");
+    let u = url::Parse("http://co.uk/").unwrap();
+    jar.SetCookies(&u, resp.Cookies());
+    assert_eq!(jarCookies(&jar, "http://co.uk/"), "a=1");
+    assert_eq!(jarCookies(&jar, "http://www.co.uk/"), "");
+}
+
+#[test]
+fn Test_ClientJar() {
+    let (addr, _) = serveFunc(|head| {
+        if head[0].starts_with("GET /login") {
+            return b"HTTP/1.1 302 Found\r\nLocation: /home\r\nSet-Cookie: session=abc; Path=/; HttpOnly\r\nContent-Length: 0\r\n\r\n".to_vec();
+        }
+        let cookie = head
+            .iter()
+            .find_map(|l| l.strip_prefix("Cookie: "))
+            .unwrap_or("");
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            cookie.len(),
+            cookie
+        )
+        .into_bytes()
+    });
+    let mut client = http::Client::New();
+    let jar: Arc<dyn http::CookieJar> = Arc::new(http::cookiejar::New(None).unwrap());
+    client.Jar = Some(jar.clone());
+    let mut resp = client.Get(&format!("http://{}/login", addr)).unwrap();
+    assert_eq!(
+        resp.Body.ReadAll().unwrap().to_vec(),
+        b"session=abc".to_vec()
+    );
+
+    let mut req =
+        http::Request::New(http::Method::Get, &format!("http://{}/home", addr), None).unwrap();
+    req.Header.Set("Cookie", "theme=dark");
+    let mut resp = client.Do(&mut req).unwrap();
+    assert_eq!(
+        resp.Body.ReadAll().unwrap().to_vec(),
+        b"theme=dark; session=abc".to_vec()
+    );
+    assert_eq!(req.Header.Get("Cookie"), "theme=dark");
+    assert_eq!(
+        jarCookies(&*jar, &format!("http://{}/", addr)),
+        "session=abc"
+    );
+}
+
+#[test]
+fn Test_CookieString() {
+    let mut c = http::Cookie {
+        Name: "cookie-1".to_string(),
+        Value: "v$1".to_string(),
+        Path: "/path".to_string(),
+        Domain: ".example.com".to_string(),
+        MaxAge: 3600,
+        HttpOnly: true,
+        Secure: true,
+        SameSite: http::SameSite::SameSiteLaxMode,
+        ..Default::default()
+    };
+    assert_eq!(
+        c.String(),
+        "cookie-1=v$1; Path=/path; Domain=example.com; Max-Age=3600; HttpOnly; Secure; SameSite=Lax"
+    );
+    assert!(c.Valid().is_ok());
+
+    c = http::Cookie {
+        Name: "special".to_string(),
+        Value: "a z;\"b\"".to_string(),
+        Domain: "bad domain".to_string(),
+        MaxAge: -1,
+        ..Default::default()
+    };
+    assert_eq!(c.String(), "special=\"a zb\"; Max-Age=0");
+    assert!(c.Valid().is_err());
+
+    c = http::Cookie {
+        Name: "bad name".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(c.String(), "");
+}
+
+#[test]
+fn Test_RequestCookies() {
+    let mut req = http::Request::default();
+    for (name, value) in [("a", "1"), ("b", "two words"), ("a", "3")] {
+        req.AddCookie(&http::Cookie {
+            Name: name.to_string(),
+            Value: value.to_string(),
+            ..Default::default()
+        });
+    }
+    assert_eq!(req.Header.Get("Cookie"), "a=1; b=\"two words\"; a=3");
+    let cookies: Vec<String> = req
+        .Cookies()
+        .iter()
+        .map(|c| format!("{}={}", c.Name, c.Value))
+        .collect();
+    assert_eq!(cookies, ["a=1", "b=two words", "a=3"]);
+    assert_eq!(req.Cookie("a").unwrap().Value, "1");
+    assert!(matches!(
+        req.Cookie("missing"),
+        Err(http::HTTPConnectError::ErrNoCookie)
+    ));
+}
+
+// recorder is a minimal ResponseWriter that keeps everything in memory.
+#[derive(Default)]
+struct recorder {
+    header: http::header::Header,
+    body: Vec<u8>,
+    code: u16,
+}
+
+impl http::server::ResponseWriter for recorder {
+    fn Header(&mut self) -> &mut http::header::Header {
+        &mut self.header
+    }
+
+    fn Write(&mut self, buf: &[u8]) -> http::HttpResult<usize> {
+        self.body.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn WriteHeader(&mut self, status_code: u16) {
+        self.code = status_code;
+    }
+}
+
+#[test]
+fn Test_SetCookie() {
+    let mut w = recorder::default();
+    http::SetCookie(
+        &mut w,
+        &http::Cookie {
+            Name: "id".to_string(),
+            Value: "42".to_string(),
+            Path: "/".to_string(),
+            ..Default::default()
+        },
+    );
+    http::SetCookie(
+        &mut w,
+        &http::Cookie {
+            Name: "".to_string(),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        w.header.0.get("Set-Cookie").cloned().unwrap_or_default(),
+        ["id=42; Path=/"]
+    );
+}
+
+// serveTLS starts an HTTPS server on 127.0.0.1 with the certificate in testdata/server.pem that requires a client certificate issued by testdata/ca.pem, and answers every request with "ok".
+fn serveTLS() -> String {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    let mut roots = rustls::RootCertStore::empty();
+    for c in CertificateDer::pem_slice_iter(include_bytes!("../testdata/ca.pem")) {
+        roots.add(c.unwrap()).unwrap();
+    }
+    let verifier = rustls::server::WebPkiClientVerifier::builder(Arc::new(roots))
+        .build()
+        .unwrap();
+    let chain = CertificateDer::pem_slice_iter(include_bytes!("../testdata/server.pem"))
+        .map(|c| c.unwrap())
+        .collect();
+    let key = PrivateKeyDer::from_pem_slice(include_bytes!("../testdata/server.key")).unwrap();
+    let config = Arc::new(
+        rustls::ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(chain, key)
+            .unwrap(),
+    );
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let config = config.clone();
+            thread::spawn(move || {
+                let tlsConn = rustls::ServerConnection::new(config).unwrap();
+                let mut stream = rustls::StreamOwned::new(tlsConn, conn.unwrap());
+                let mut line = String::new();
+                let mut r = BufReader::new(&mut stream);
+                while r.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+                    line.clear();
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                );
+                let _ = stream.flush();
+            });
+        }
+    });
+    addr
+}
+
+#[test]
+fn Test_TransportTLSClientConfig() {
+    use http::tls;
+    use http::RoundTripper;
+
+    let addr = serveTLS();
+    let mut roots = tls::NewCertPool();
+    assert!(roots.AppendCertsFromPEM(include_bytes!("../testdata/ca.pem")));
+    let cert = tls::X509KeyPair(
+        include_bytes!("../testdata/client.pem"),
+        include_bytes!("../testdata/client.key"),
+    )
+    .unwrap();
+    let roundTrip = |cfg: tls::Config| {
+        let mut tr = http::Transport::default();
+        tr.TLSClientConfig = Some(cfg);
+        let req = http::Request::New(http::Method::Get, &format!("https://{}/", addr), None)?;
+        let mut resp = tr.RoundTrip(&req)?;
+        let mut body = String::new();
+        resp.Body.read_to_string(&mut body)?;
+        Ok::<_, http::HTTPConnectError>(body)
+    };
+
+    // 通过ServerName覆盖SNI，用私有CA校验服务器证书，并提供客户端证书。
+    let cfg = tls::Config {
+        RootCAs: Some(roots.clone()),
+        Certificates: vec![cert.clone()],
+        ServerName: "localhost".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(roundTrip(cfg.clone()).unwrap(), "ok");
+
+    // 服务器证书不是由默认根证书签发的。
+    assert!(roundTrip(tls::Config {
+        RootCAs: None,
+        ..cfg.clone()
+    })
+    .is_err());
+    // 服务器要求客户端证书。
+    assert!(roundTrip(tls::Config {
+        Certificates: vec![],
+        ..cfg.clone()
+    })
+    .is_err());
+    // 证书和主机名都不校验。
+    assert_eq!(
+        roundTrip(tls::Config {
+            RootCAs: None,
+            ServerName: "".to_string(),
+            InsecureSkipVerify: true,
+            ..cfg
+        })
+        .unwrap(),
+        "ok"
+    );
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncTransportTLSClientConfig() {
+    use http::tls;
+
+    let addr = serveTLS();
+    let mut roots = tls::NewCertPool();
+    roots.AppendCertsFromPEM(include_bytes!("../testdata/ca.pem"));
+    let mut tr = http::async_http::Transport::default();
+    tr.tls_client_config = Some(tls::Config {
+        RootCAs: Some(roots),
+        Certificates: vec![tls::X509KeyPair(
+            include_bytes!("../testdata/client.pem"),
+            include_bytes!("../testdata/client.key"),
+        )
+        .unwrap()],
+        ServerName: "localhost".to_string(),
+        ..Default::default()
+    });
+    let mut client = http::async_http::AsyncClient::NewWithTransport(tr);
+    blockOn(async {
+        let mut resp = client.Get(&format!("https://{}/", addr)).await.unwrap();
+        assert_eq!(resp.Body.ReadAllAsync().await.unwrap(), "ok".as_bytes());
+    });
+}
+
+#[test]
+fn Test_HPACK() {
+    use http::hpack;
+
+    let field = |name: &str, value: &str| hpack::HeaderField {
+        Name: name.to_string(),
+        Value: value.to_string(),
+        Sensitive: false,
+    };
+    let hex = |b: &[u8]| b.iter().map(|c| format!("{:02x}", c)).collect::<String>();
+    // RFC 7541 C.4: 使用Huffman编码的连续请求，第二个请求复用动态表。
+    let reqs = vec![
+        (
+            vec![
+                field(":method", "GET"),
+                field(":scheme", "http"),
+                field(":path", "/"),
+                field(":authority", "www.example.com"),
+            ],
+            "828684418cf1e3c2e5f23a6ba0ab90f4ff",
+        ),
+        (
+            vec![
+                field(":method", "GET"),
+                field(":scheme", "http"),
+                field(":path", "/"),
+                field(":authority", "www.example.com"),
+                field("cache-control", "no-cache"),
+            ],
+            "828684be5886a8eb10649cbf",
+        ),
+    ];
+    let mut enc = hpack::NewEncoder();
+    let mut dec = hpack::NewDecoder(4096);
+    for (fields, want) in reqs {
+        let mut buf = Vec::new();
+        for f in fields.clone() {
+            enc.WriteField(&mut buf, f);
+        }
+        assert_eq!(hex(&buf), want);
+        assert_eq!(dec.DecodeFull(&buf).unwrap(), fields);
+    }
+    assert_eq!(
+        hpack::HuffmanDecodeToString(&[
+            0x9d, 0x29, 0xad, 0x17, 0x18, 0x63, 0xc7, 0x8f, 0x0b, 0x97, 0xc8, 0xe9, 0xae, 0x82,
+            0xae, 0x43, 0xd3
+        ])
+        .unwrap(),
+        "https://www.example.com"
+    );
+    // 动态表只有两项，索引64不存在。
+    assert!(dec.DecodeFull(&[0xc0]).is_err());
+}
+
+// serveH2Conn answers the HTTP/2 requests on conn with "<method> <path> <body length>". A request for "/wait" is only answered once a request for "/go" arrives on the same connection.
+fn serveH2Conn(mut conn: impl Read + Write) {
+    use http::hpack;
+    use http::http2::*;
+
+    let mut preface = [0u8; 24];
+    if conn.read_exact(&mut preface).is_err() || preface != ClientPreface {
+        return;
+    }
+    let mut buf = Vec::new();
+    appendSettings(&mut buf, &[(SettingMaxConcurrentStreams, 100)]);
+    if conn.write_all(&buf).is_err() {
+        return;
+    }
+    let mut dec = hpack::NewDecoder(4096);
+    let mut enc = hpack::NewEncoder();
+    let mut streams: std::collections::HashMap<u32, (String, String, usize)> = Default::default();
+    let mut waiting = Vec::new();
+    while let Ok(f) = ReadFrame(&mut conn, initialMaxFrameSize) {
+        let mut done = None;
+        match f.Type {
+            FrameSettings if f.Flags & FlagSettingsAck == 0 => {
+                let mut buf = Vec::new();
+                appendFrame(&mut buf, FrameSettings, FlagSettingsAck, 0, &[]);
+                let _ = conn.write_all(&buf);
+            }
+            FrameHeaders => {
+                let mut method = String::new();
+                let mut path = String::new();
+                for hf in dec.DecodeFull(&f.Payload).unwrap() {
+                    match hf.Name.as_str() {
+                        ":method" => method = hf.Value,
+                        ":path" => path = hf.Value,
+                        _ => (),
+                    }
+                }
+                streams.insert(f.StreamID, (method, path, 0));
+                if f.Flags & FlagHeadersEndStream != 0 {
+                    done = Some(f.StreamID);
+                }
+            }
+            FrameData => {
+                if let Some(s) = streams.get_mut(&f.StreamID) {
+                    s.2 += f.Payload.len();
+                }
+                if f.Flags & FlagDataEndStream != 0 {
+                    done = Some(f.StreamID);
+                }
+            }
+            _ => (),
+        }
+        let id = match done {
+            Some(id) => id,
+            None => continue,
+        };
+        let path = streams[&id].1.clone();
+        let mut ready = vec![id];
+        if path == "/wait" {
+            waiting.push(id);
+            continue;
+        } else if path == "/go" {
+            ready.append(&mut waiting);
+        }
+        for id in ready {
+            let (method, path, n) = streams.remove(&id).unwrap();
+            let body = format!("{} {} {}", method, path, n);
+            let mut block = Vec::new();
+            for (name, value) in [
+                (":status", "200".to_string()),
+                ("content-type", "text/plain".to_string()),
+                ("content-length", body.len().to_string()),
+            ] {
+                enc.WriteField(
+                    &mut block,
+                    hpack::HeaderField {
+                        Name: name.to_string(),
+                        Value: value,
+                        Sensitive: false,
+                    },
+                );
+            }
+            let mut buf = Vec::new();
+            appendHeaders(&mut buf, id, false, &block, initialMaxFrameSize);
+            appendFrame(&mut buf, FrameData, FlagDataEndStream, id, body.as_bytes());
+            if conn.write_all(&buf).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// serveH2C starts an unencrypted HTTP/2 server on 127.0.0.1 that handles connections with serveH2Conn. It returns the listening address and the number of accepted connections.
+fn serveH2C() -> (String, Arc<AtomicUsize>) {
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let conn = conn.unwrap();
+            thread::spawn(move || serveH2Conn(conn));
+        }
+    });
+    (addr, accepted)
+}
+
+#[test]
+fn Test_TransportH2C() {
+    use http::RoundTripper;
+
+    let (addr, accepted) = serveH2C();
+    let mut p = http::Protocols::default();
+    p.SetUnencryptedHTTP2(true);
+    let mut tr = http::Transport::default();
+    tr.Protocols = Some(p);
+    let base = format!("http://{}", addr);
+    let get = move |tr: &mut http::Transport, path: &str| {
+        let url = format!("{}{}", base, path);
+        let req = http::Request::New(http::Method::Get, &url, None).unwrap();
+        let mut resp = tr.RoundTrip(&req).unwrap();
+        let mut body = String::new();
+        resp.Body.read_to_string(&mut body).unwrap();
+        (resp, body)
+    };
+
+    let (resp, body) = get(&mut tr, "/hello?a=1");
+    assert_eq!(resp.ProtoMajor, 2);
+    assert_eq!(resp.Proto, "HTTP/2.0");
+    assert_eq!(resp.Status, "200 OK");
+    assert_eq!(resp.ContentLength, 16);
+    assert_eq!(resp.Header.Get("Content-Type"), "text/plain");
+    assert_eq!(body, "GET /hello?a=1 0");
+
+    let url = format!("http://{}/upload", addr);
+    let req = http::Request::New(http::Method::Post, &url, Some("hello".into())).unwrap();
+    let mut resp = tr.RoundTrip(&req).unwrap();
+    let mut body = String::new();
+    resp.Body.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "POST /upload 5");
+
+    // "/wait"的响应要等到同一连接上的"/go"请求到达才返回，两个请求必须在同一个连接上并发。
+    let mut tr2 = tr.clone();
+    let get2 = get.clone();
+    let waiter = thread::spawn(move || get2(&mut tr2, "/wait").1);
+    thread::sleep(std::time::Duration::from_millis(200));
+    assert_eq!(get(&mut tr, "/go").1, "GET /go 0");
+    assert_eq!(waiter.join().unwrap(), "GET /wait 0");
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+// serveH2Reply starts an unencrypted HTTP/2 server on 127.0.0.1 that writes the frames returned by reply for every request it gets, once its END_STREAM arrives. It returns the listening address and the number of requests received.
+fn serveH2Reply<F>(reply: F) -> (String, Arc<AtomicUsize>)
+where
+    F: Fn(u32, &mut http::hpack::Encoder) -> Vec<u8> + Send + Sync + 'static,
+{
+    use http::hpack;
+    use http::http2::*;
+
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let reply = Arc::new(reply);
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let mut conn = conn.unwrap();
+            let counter = counter.clone();
+            let reply = reply.clone();
+            thread::spawn(move || {
+                let mut preface = [0u8; 24];
+                if conn.read_exact(&mut preface).is_err() || preface != ClientPreface {
+                    return;
+                }
+                let mut buf = Vec::new();
+                appendSettings(&mut buf, &[(SettingMaxConcurrentStreams, 100)]);
+                if conn.write_all(&buf).is_err() {
+                    return;
+                }
+                let mut enc = hpack::NewEncoder();
+                while let Ok(f) = ReadFrame(&mut conn, initialMaxFrameSize) {
+                    let endStream = match f.Type {
+                        FrameSettings if f.Flags & FlagSettingsAck == 0 => {
+                            let mut buf = Vec::new();
+                            appendFrame(&mut buf, FrameSettings, FlagSettingsAck, 0, &[]);
+                            let _ = conn.write_all(&buf);
+                            false
+                        }
+                        FrameHeaders => {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            f.Flags & FlagHeadersEndStream != 0
+                        }
+                        FrameData => f.Flags & FlagDataEndStream != 0,
+                        _ => false,
+                    };
+                    if endStream && conn.write_all(&reply(f.StreamID, &mut enc)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    (addr, requests)
+}
+
+// h2Client returns a Transport that speaks unencrypted HTTP/2 to http URLs.
+fn h2Client() -> http::Transport {
+    let mut p = http::Protocols::default();
+    p.SetUnencryptedHTTP2(true);
+    let mut tr = http::Transport::default();
+    tr.Protocols = Some(p);
+    tr
+}
+
+#[test]
+fn Test_TransportH2MissingStatus() {
+    use http::hpack;
+    use http::http2::*;
+    use http::RoundTripper;
+
+    let (addr, requests) = serveH2Reply(|id, enc| {
+        let mut block = Vec::new();
+        enc.WriteField(
+            &mut block,
+            hpack::HeaderField {
+                Name: "content-type".to_string(),
+                Value: "text/plain".to_string(),
+                Sensitive: false,
+            },
+        );
+        let mut buf = Vec::new();
+        appendHeaders(&mut buf, id, true, &block, initialMaxFrameSize);
+        buf
+    });
+    let mut tr = h2Client();
+    let url = format!("http://{}/upload", addr);
+    let req = http::Request::New(http::Method::Post, &url, Some("hello".into())).unwrap();
+    match tr.RoundTrip(&req) {
+        Err(http::HTTPConnectError::ConnectionFailure(msg)) => {
+            assert!(msg.contains("missing status"), "{}", msg)
+        }
+        r => panic!("got {:?}", r.map(|resp| resp.Status)),
+    }
+    // 格式错误的响应不能当作未处理的请求重试。
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn Test_TransportH2HeaderBlockLimit() {
+    use http::hpack;
+    use http::http2::*;
+    use http::RoundTripper;
+
+    let (addr, _) = serveH2Reply(|id, enc| {
+        let mut block = Vec::new();
+        enc.WriteField(
+            &mut block,
+            hpack::HeaderField {
+                Name: ":status".to_string(),
+                Value: "200".to_string(),
+                Sensitive: false,
+            },
+        );
+        let mut buf = Vec::new();
+        appendFrame(&mut buf, FrameHeaders, 0, id, &block);
+        // 没有END_HEADERS的CONTINUATION帧，头部块永远不会结束。
+        for _ in 0..64 {
+            appendFrame(&mut buf, FrameContinuation, 0, id, &[0u8; 512]);
+        }
+        buf
+    });
+    let mut tr = h2Client();
+    tr.MaxResponseHeaderBytes = 4096;
+    tr.ResponseHeaderTimeout = gostd_time::Duration::new(2 * gostd_time::Second);
+    let url = format!("http://{}/", addr);
+    let req = http::Request::New(http::Method::Get, &url, None).unwrap();
+    match tr.RoundTrip(&req) {
+        Err(http::HTTPConnectError::ConnectionFailure(msg)) => {
+            assert!(msg.contains("ENHANCE_YOUR_CALM"), "{}", msg)
+        }
+        r => panic!("got {:?}", r.map(|resp| resp.Status)),
+    }
+}
+
+#[test]
+fn Test_TransportH2Trailer() {
+    use http::hpack;
+    use http::http2::*;
+    use http::RoundTripper;
+
+    let (addr, _) = serveH2Reply(|id, enc| {
+        let mut field = |block: &mut Vec<u8>, name: &str, value: &str| {
+            enc.WriteField(
+                block,
+                hpack::HeaderField {
+                    Name: name.to_string(),
+                    Value: value.to_string(),
+                    Sensitive: false,
+                },
+            )
+        };
+        let mut head = Vec::new();
+        field(&mut head, ":status", "200");
+        field(&mut head, "trailer", "X-Checksum");
+        let mut trailer = Vec::new();
+        field(&mut trailer, "x-checksum", "abc");
+        let mut buf = Vec::new();
+        appendHeaders(&mut buf, id, false, &head, initialMaxFrameSize);
+        appendFrame(&mut buf, FrameData, 0, id, b"hello");
+        appendHeaders(&mut buf, id, true, &trailer, initialMaxFrameSize);
+        buf
+    });
+    let mut tr = h2Client();
+    let url = format!("http://{}/", addr);
+    let req = http::Request::New(http::Method::Get, &url, None).unwrap();
+    let mut resp = tr.RoundTrip(&req).unwrap();
+    assert!(resp.Trailer.lock().unwrap().0.contains_key("X-Checksum"));
+    let mut body = String::new();
+    resp.Body.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "hello");
+    assert_eq!(resp.Body.Trailer().Get("X-Checksum"), "abc");
+    assert_eq!(resp.Trailer.lock().unwrap().Get("X-Checksum"), "abc");
+}
+
+#[test]
+fn Test_TransportHTTP2OverTLS() {
+    use http::tls;
+    use http::RoundTripper;
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    let chain = CertificateDer::pem_slice_iter(include_bytes!("../testdata/server.pem"))
+        .map(|c| c.unwrap())
+        .collect();
+    let key = PrivateKeyDer::from_pem_slice(include_bytes!("../testdata/server.key")).unwrap();
+    let mut config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(chain, key)
+        .unwrap();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let config = Arc::new(config);
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = ln.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for conn in ln.incoming() {
+            let config = config.clone();
+            thread::spawn(move || {
+                let tlsConn = rustls::ServerConnection::new(config).unwrap();
+                serveH2Conn(rustls::StreamOwned::new(tlsConn, conn.unwrap()));
+            });
+        }
+    });
+
+    let mut roots = tls::NewCertPool();
+    roots.AppendCertsFromPEM(include_bytes!("../testdata/ca.pem"));
+    let mut tr = http::Transport::default();
+    tr.TLSClientConfig = Some(tls::Config {
+        RootCAs: Some(roots),
+        ServerName: "localhost".to_string(),
+        ..Default::default()
+    });
+    // 自定义TLSClientConfig时默认只使用HTTP/1，需要ForceAttemptHTTP2开启ALPN协商h2。
+    tr.ForceAttemptHTTP2 = true;
+    for _ in 0..2 {
+        let url = format!("https://{}/tls", addr);
+        let req = http::Request::New(http::Method::Get, &url, None).unwrap();
+        let mut resp = tr.RoundTrip(&req).unwrap();
+        assert_eq!(resp.ProtoMajor, 2);
+        let mut body = String::new();
+        resp.Body.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "GET /tls 0");
+    }
+}
+
+// recordTrace returns a ClientTrace that appends the name of every hook it sees to the returned log.
+fn recordTrace() -> (
+    http::httptrace::ClientTrace,
+    Arc<std::sync::Mutex<Vec<String>>>,
+) {
+    use http::httptrace::*;
+    let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let l = log.clone();
+    let push = move |s: String| {
+        let mut l = l.lock().unwrap();
+        // 同一主机解析出多个地址时会重复拨号，只记录一次。
+        if l.last() != Some(&s) {
+            l.push(s);
+        }
+    };
+    let p = Arc::new(push);
+    macro_rules! hook {
+        ($($arg:ident),* => $e:expr) => {{
+            let p = p.clone();
+            Some(Arc::new(move |$($arg),*| p($e)))
+        }};
+    }
+    let trace = ClientTrace {
+        GetConn: hook!(hostPort => "GetConn".to_string()),
+        GotConn: hook!(info => format!("GotConn reused={}", info.Reused)),
+        GotFirstResponseByte: hook!( => "GotFirstResponseByte".to_string()),
+        DNSStart: hook!(info => format!("DNSStart {}", info.Host)),
+        DNSDone: hook!(info => format!("DNSDone {}", info.Err.is_none())),
+        ConnectStart: hook!(network, addr => format!("ConnectStart {}", network)),
+        ConnectDone: hook!(network, addr, err => format!("ConnectDone {}", network)),
+        WroteHeaders: hook!( => "WroteHeaders".to_string()),
+        WroteRequest: hook!(info => format!("WroteRequest {}", info.Err.is_none())),
+        ..Default::default()
+    };
+    (trace, log)
+}
+
+#[test]
+fn Test_ClientTrace() {
+    let (addr, _) = serve();
+    let port = addr.rsplit(':').next().unwrap().to_string();
+    let mut tr = http::Transport::default();
+    let mut logs = Vec::new();
+    for _ in 0..2 {
+        let url = format!("http://localhost:{}/", port);
+        let mut req = http::Request::New(http::Method::Get, &url, None).unwrap();
+        let (trace, log) = recordTrace();
+        http::httptrace::WithClientTrace(&mut req, trace);
+        let mut resp = http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+        assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), b"ok".to_vec());
+        logs.push(log.lock().unwrap().clone());
+    }
+    assert_eq!(
+        logs[0],
+        vec![
+            "GetConn",
+            "DNSStart localhost",
+            "DNSDone true",
+            "ConnectStart tcp",
+            "ConnectDone tcp",
+            "GotConn reused=false",
+            "WroteHeaders",
+            "WroteRequest true",
+            "GotFirstResponseByte",
+        ]
+    );
+    assert_eq!(
+        logs[1],
+        vec![
+            "GetConn",
+            "GotConn reused=true",
+            "WroteHeaders",
+            "WroteRequest true",
+            "GotFirstResponseByte",
+        ]
+    );
+
+    // WithClientTrace在已有trace时先调用新的钩子，再调用原来的钩子。
+    let mut req =
+        http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    let (first, firstLog) = recordTrace();
+    let (second, secondLog) = recordTrace();
+    http::httptrace::WithClientTrace(&mut req, first);
+    http::httptrace::WithClientTrace(&mut req, second);
+    assert!(http::httptrace::ContextClientTrace(&req).is_some());
+    http::RoundTripper::RoundTrip(&mut tr, &req).unwrap();
+    assert_eq!(*firstLog.lock().unwrap(), *secondLog.lock().unwrap());
+    assert!(firstLog
+        .lock()
+        .unwrap()
+        .contains(&"GotFirstResponseByte".to_string()));
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncClientTrace() {
+    use http::async_http;
+    let (addr, _) = serve();
+    let mut req =
+        http::Request::New(http::Method::Get, &format!("http://{}/", addr), None).unwrap();
+    let (trace, log) = recordTrace();
+    http::httptrace::WithClientTrace(&mut req, trace);
+    let mut resp = blockOn(async_http::AsyncClient::New().Do(&mut req)).unwrap();
+    assert_eq!(resp.StatusCode, 200);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "GetConn",
+            "ConnectStart tcp",
+            "ConnectDone tcp",
+            "GotConn reused=false",
+            "WroteHeaders",
+            "WroteRequest true",
+            "GotFirstResponseByte",
+        ]
+    );
+}
+
+#[test]
+fn Test_ClientUsesTransport() {
+    use http::middleware;
+    let (addr, accepted) = serveFunc(|head| {
+        let ua = head
+            .iter()
+            .find(|l| l.starts_with("User-Agent:"))
+            .cloned()
+            .unwrap_or_default();
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            ua.len(),
+            ua
+        )
+        .into_bytes()
+    });
+    let lines = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let l = lines.clone();
+    let mut header = http::header::Header::default();
+    header.Set("User-Agent", "middleware-test");
+    let mut c = http::Client::NewWithTransport(middleware::NewLoggingTransport(
+        middleware::NewHeaderTransport(http::Transport::default(), header),
+        move |line: &str| l.lock().unwrap().push(line.to_string()),
+    ));
+    let url = format!("http://{}/", addr);
+    for _ in 0..2 {
+        let mut resp = c.Get(&url).unwrap();
+        assert_eq!(
+            resp.Body.ReadAll().unwrap().to_vec(),
+            b"User-Agent: middleware-test".to_vec()
+        );
+    }
+    // 请求中已有的字段不被覆盖。
+    let mut req = http::Request::New(http::Method::Get, &url, None).unwrap();
+    req.Header.Set("User-Agent", "own");
+    let mut resp = c.Do(&mut req).unwrap();
+    assert_eq!(
+        resp.Body.ReadAll().unwrap().to_vec(),
+        b"User-Agent: own".to_vec()
+    );
+    // Client使用同一个Transport，连接被复用。
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(&format!("GET {} 200 OK ", url)));
+}
+
+#[test]
+fn Test_RetryTransport() {
+    use http::middleware;
+    let hits = Arc::new(AtomicUsize::new(0));
+    let h = hits.clone();
+    let (addr, _) = serveFunc(move |head| {
+        if h.fetch_add(1, Ordering::SeqCst) < 2 || head[0].contains("/busy") {
+            return b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\n\r\nbusy".to_vec();
+        }
+        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_vec()
+    });
+    let mut rt = middleware::NewRetryTransport(http::Transport::default());
+    rt.MinBackoff = gostd_time::Duration::new(gostd_time::Millisecond);
+    let mut c = http::Client::NewWithTransport(rt);
+    let url = format!("http://{}/", addr);
+    let mut resp = c.Get(&url).unwrap();
+    assert_eq!(resp.StatusCode, 200);
+    assert_eq!(resp.Body.ReadAll().unwrap().to_vec(), b"ok".to_vec());
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    // POST不是幂等的，不重试。
+    let resp = c
+        .Post(&format!("{}busy", url), "text/plain", Some("x".into()))
+        .unwrap();
+    assert_eq!(resp.StatusCode, 503);
+    assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+    // 重试次数用完后返回最后一次的响应。
+    let mut rt = middleware::NewRetryTransport(http::Transport::default());
+    rt.MinBackoff = gostd_time::Duration::new(gostd_time::Millisecond);
+    rt.MaxRetries = 1;
+    let mut c = http::Client::NewWithTransport(rt);
+    let resp = c.Get(&format!("{}busy", url)).unwrap();
+    assert_eq!(resp.StatusCode, 503);
+    assert_eq!(hits.load(Ordering::SeqCst), 6);
+
+    // 连接错误也会重试。
+    let ln = TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed = ln.local_addr().unwrap().to_string();
+    drop(ln);
+    let tries = Arc::new(AtomicUsize::new(0));
+    let t = tries.clone();
+    let mut rt = middleware::NewRetryTransport(middleware::NewLoggingTransport(
+        http::Transport::default(),
+        move |_| {
+            t.fetch_add(1, Ordering::SeqCst);
+        },
+    ));
+    rt.MinBackoff = gostd_time::Duration::new(gostd_time::Millisecond);
+    let mut c = http::Client::NewWithTransport(rt);
+    assert!(c.Get(&format!("http://{}/", closed)).is_err());
+    assert_eq!(tries.load(Ordering::SeqCst), 4);
+
+    // 格式错误的响应重试也不会成功，直接返回错误。
+    let hits = Arc::new(AtomicUsize::new(0));
+    let h = hits.clone();
+    let (addr, _) = serveFunc(move |_| {
+        h.fetch_add(1, Ordering::SeqCst);
+        b"FOO/9 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec()
+    });
+    let mut rt = middleware::NewRetryTransport(http::Transport::default());
+    rt.MinBackoff = gostd_time::Duration::new(gostd_time::Millisecond);
+    let mut c = http::Client::NewWithTransport(rt);
+    match c.Get(&format!("http://{}/", addr)) {
+        Err(http::HTTPConnectError::ConnectionFailure(msg)) => {
+            assert_eq!(msg, "malformed HTTP version")
+        }
+        r => panic!("got {:?}", r.map(|resp| resp.Status)),
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn Test_Header() {
+    use http::header::{CanonicalHeaderKey, Header};
+    use std::collections::HashSet;
+
+    assert_eq!(CanonicalHeaderKey("accept-encoding"), "Accept-Encoding");
+    assert_eq!(CanonicalHeaderKey("WWW-AUTHENTICATE"), "Www-Authenticate");
+    assert_eq!(CanonicalHeaderKey("foo bar"), "foo bar");
+
+    let mut h = Header::default();
+    h.Add("content-type", "text/plain");
+    assert_eq!(h.Get("Content-Type"), "text/plain");
+    assert_eq!(h.Get("CONTENT-TYPE"), "text/plain");
+    h.Add("X-Multi", "a");
+    h.Add("x-multi", "b");
+    assert_eq!(h.Values("X-MULTI"), ["a", "b"]);
+    assert!(h.Values("Missing").is_empty());
+    assert_eq!(h.Get("Missing"), "");
+
+    let c = h.Clone();
+    h.Del("x-multi");
+    assert!(h.Values("X-Multi").is_empty());
+    assert_eq!(c.Values("X-Multi"), ["a", "b"]);
+    assert_eq!(c.Len(), 2);
+    let mut keys: Vec<&String> = c.iter().map(|(k, _)| k).collect();
+    keys.sort();
+    assert_eq!(keys, ["Content-Type", "X-Multi"]);
+
+    let mut h = Header::default();
+    h.Add("Zeta", "z");
+    h.Add("Set-Cookie", "a=1");
+    h.Add("Set-Cookie", "b=2");
+    h.Add("Alpha", "x\r\nInjected: yes ");
+    let mut buf = Vec::new();
+    h.Write(&mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "Alpha: x  Injected: yes\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nZeta: z\r\n"
+    );
+    let mut buf = Vec::new();
+    let exclude: HashSet<String> = ["Alpha".to_string(), "Zeta".to_string()].into();
+    h.WriteSubset(&mut buf, &exclude).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "Set-Cookie: a=1\r\nSet-Cookie: b=2\r\n"
+    );
+
+    // 请求中同一字段的多个值分行写出，不再用逗号合并。
+    let mut req = http::Request::New(http::Method::Get, "http://example.com/", None).unwrap();
+    req.Header.Add("x-token", "1");
+    req.Header.Add("X-Token", "2");
+    req.Header.Set("content-length", "99");
+    let mut w = Vec::new();
+    req.Write(&mut w).unwrap();
+    let out = String::from_utf8(w).unwrap();
+    assert!(out.contains("X-Token: 1\r\nX-Token: 2\r\n"));
+    assert!(!out.contains("Content-Length"));
+}
+
+#[test]
+fn Test_ReadRequest() {
+    use http::error::HTTPConnectError;
+    use http::request::ReadRequest;
+    use std::io::Cursor;
+
+    let read = |raw: &str| ReadRequest(Cursor::new(raw.to_string()));
+
+    let req = read("GET /a//b?x=1 HTTP/1.1\r\nHost: example.com\r\nX-Long: one\r\n  two\r\n\r\n")
+        .unwrap();
+    assert_eq!(req.Method, "GET");
+    assert_eq!(req.RequestURI, "/a//b?x=1");
+    assert_eq!(req.URL.Path, "/a//b");
+    assert_eq!(req.URL.RawQuery, "x=1");
+    assert_eq!(req.Host, "example.com");
+    assert_eq!(
+        (req.Proto.as_str(), req.ProtoMajor, req.ProtoMinor),
+        ("HTTP/1.1", 1, 1)
+    );
+    assert_eq!(req.Header.Get("X-Long"), "one two");
+    assert!(req.Header.Values("Host").is_empty());
+    assert!(req.Body.is_none());
+
+    let req = read("GET http://proxy.test:8080/p HTTP/1.0\r\nHost: other\r\n\r\n").unwrap();
+    assert_eq!(req.URL.Scheme, "http");
+    assert_eq!(req.Host, "proxy.test:8080");
+    assert!(req.Close);
+
+    let req = read("CONNECT example.com:443 HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(req.URL.Host, "example.com:443");
+    assert_eq!(req.URL.Scheme, "");
+    let req = read("OPTIONS * HTTP/1.1\r\nHost: h\r\n\r\n").unwrap();
+    assert_eq!(req.URL.Path, "*");
+
+    let mut req = read("POST /up HTTP/1.1\r\nHost: h\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\nhelloEXTRA").unwrap();
+    assert_eq!(req.ContentLength, 5);
+    assert_eq!(req.Header.Get("Expect"), "100-continue");
+    assert_eq!(req.Body.as_mut().unwrap().ReadAll().unwrap(), "hello");
+
+    let mut req = read(
+        "POST /c HTTP/1.1\r\nHost: h\r\nTransfer-Encoding: chunked\r\nTrailer: x-sum\r\n\r\n\
+         3\r\nabc\r\n2\r\nde\r\n0\r\nX-Sum: 42\r\n\r\n",
+    )
+    .unwrap();
+    assert_eq!(req.TransferEncoding, ["chunked"]);
+    assert_eq!(req.ContentLength, -1);
+    assert!(req.Trailer.0.contains_key("X-Sum"));
+    let body = req.Body.as_mut().unwrap();
+    assert_eq!(body.ReadAll().unwrap(), "abcde");
+    assert_eq!(body.Trailer().Get("X-Sum"), "42");
+
+    let big = format!("GET / HTTP/1.1\r\nX-Big: {}\r\n\r\n", "a".repeat(1 << 20));
+    assert!(matches!(read(&big), Err(HTTPConnectError::ErrTooLarge)));
+    assert!(read("GET / HTTP/1.1\r\nBad Key: v\r\n\r\n").is_err());
+    assert!(read("GET /\r\n\r\n").is_err());
+    assert!(read("GET relative HTTP/1.1\r\n\r\n").is_err());
+    assert!(read("POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n").is_err());
+    assert!(read("POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\n").is_err());
+
+    // Content-Length只能是十进制数字，"+5"这类值会让前端代理和服务器对body边界的判断不一致。
+    for cl in ["+5", "-5", "0x5", "5 5", ""] {
+        let raw = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\nhello", cl);
+        let err = read(&raw).unwrap_err();
+        assert!(err.to_string().contains("bad Content-Length"), "{}", err);
+    }
+    let raw = "HTTP/1.1 200 OK\r\nContent-Length: +5\r\n\r\nhello";
+    let err = http::ReadResponse(BufReader::new(Cursor::new(raw)), &http::Request::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("bad Content-Length"), "{}", err);
+}
+
+// startServer serves handler with a Server on a free port of 127.0.0.1 and returns the address. Lines logged by the server are appended to the returned log.
+fn startServer(
+    handler: impl http::server::Handler + 'static,
+) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap().to_string();
+    let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut srv = http::server::Server::New("", handler);
+    let l2 = log.clone();
+    srv.ErrorLog = Some(Arc::new(move |line: &str| {
+        l2.lock().unwrap().push(line.to_string())
+    }));
+    thread::spawn(move || srv.Serve(l));
+    (addr, log)
+}
+
+#[test]
+fn Test_Server() {
+    use http::server::{ResponseWriter, ServeMux};
+
+    let mut mux = ServeMux::default();
+    mux.HandleFunc(
+        "GET /hello/{name}",
+        |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            write!(w, "hello, {}", r.PathValue("name")).unwrap();
+        },
+    );
+    mux.HandleFunc(
+        "/stream",
+        |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            for _ in 0..3 {
+                w.Write(&[b'x'; 1500]).unwrap();
+                w.Flusher().unwrap().Flush().unwrap();
+            }
+        },
+    );
+    mux.HandleFunc(
+        "POST /echo",
+        |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            let body = r.Body.as_mut().unwrap().ReadAll().unwrap();
+            w.Header().Set("Trailer", "X-Len");
+            w.Write(&body).unwrap();
+            w.Header().Set("X-Len", &body.len().to_string());
+        },
+    );
+    mux.HandleFunc(
+        "/addr",
+        |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            w.Write(r.RemoteAddr.as_bytes()).unwrap();
+        },
+    );
+    mux.HandleFunc(
+        "/panic",
+        |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            panic!("boom");
+        },
+    );
+    let (addr, log) = startServer(mux);
+    let base = format!("http://{}", addr);
+    let mut client = http::Client::New();
+
+    let mut resp = client.Get(&format!("{}/hello/gopher", base)).unwrap();
+    assert_eq!(resp.StatusCode, 200);
+    assert_eq!(resp.ContentLength, 13);
+    assert!(!resp.Header.Get("Date").is_empty());
+    assert_eq!(resp.Body.ReadAll().unwrap(), "hello, gopher");
+
+    let mut resp = client.Get(&format!("{}/stream", base)).unwrap();
+    assert_eq!(resp.TransferEncoding, ["chunked"]);
+    assert_eq!(resp.Body.ReadAll().unwrap().len(), 4500);
+
+    let first = client
+        .Get(&format!("{}/addr", base))
+        .unwrap()
+        .Body
+        .ReadAll()
+        .unwrap();
+    let second = client
+        .Get(&format!("{}/addr", base))
+        .unwrap()
+        .Body
+        .ReadAll()
+        .unwrap();
+    assert_eq!(first, second, "keep-alive connection was not reused");
+
+    let mut resp = client
+        .Post(
+            &format!("{}/echo", base),
+            "text/plain",
+            Some(bytes::Bytes::from("ping")),
+        )
+        .unwrap();
+    assert_eq!(resp.Body.ReadAll().unwrap(), "ping");
+
+    let mut req =
+        http::Request::New(http::Method::Delete, &format!("{}/hello/x", base), None).unwrap();
+    let resp = client.Do(&mut req).unwrap();
+    assert_eq!(resp.StatusCode, 405);
+    assert_eq!(resp.Header.Get("Allow"), "GET, HEAD");
+    assert_eq!(
+        client.Get(&format!("{}/nope", base)).unwrap().StatusCode,
+        404
+    );
+
+    assert!(client.Get(&format!("{}/panic", base)).is_err());
+    assert!(log
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|l| l.contains("panic serving") && l.contains("boom")));
+
+    // 100-continue: 服务器在handler读取body时才回复100 Continue。
+    let mut conn = TcpStream::connect(&addr).unwrap();
+    conn.write_all(
+        b"POST /echo HTTP/1.1\r\nHost: x\r\nContent-Length: 4\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n",
+    )
+    .unwrap();
+    let mut br = BufReader::new(conn.try_clone().unwrap());
+    let mut line = String::new();
+    br.read_line(&mut line).unwrap();
+    assert_eq!(line, "HTTP/1.1 100 Continue\r\n");
+    br.read_line(&mut line).unwrap();
+    conn.write_all(b"pong").unwrap();
+    let mut out = String::new();
+    br.read_to_string(&mut out).unwrap();
+    assert!(out.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(out.ends_with("\r\n\r\n4\r\npong\r\n0\r\nX-Len: 4\r\n\r\n"));
+
+    // HTTP/1.0没有chunked编码，两个请求之后服务器关闭连接。
+    let mut conn = TcpStream::connect(&addr).unwrap();
+    conn.write_all(
+        b"GET /hello/a HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /stream HTTP/1.0\r\n\r\n",
+    )
+    .unwrap();
+    let mut out = String::new();
+    conn.read_to_string(&mut out).unwrap();
+    assert!(out.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(out.contains("Connection: keep-alive\r\n"));
+    assert!(out.contains("Connection: close\r\n"));
+    assert!(!out.contains("Transfer-Encoding"));
+    assert!(out.ends_with(&"x".repeat(4500)));
+
+    let mut conn = TcpStream::connect(&addr).unwrap();
+    conn.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let mut out = String::new();
+    conn.read_to_string(&mut out).unwrap();
+    assert!(out.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[test]
+fn Test_ServeMux() {
+    use http::server::{ResponseWriter, ServeMux};
+
+    let nop = |w: &mut dyn ResponseWriter, r: &mut http::Request| {};
+    let mut mux = ServeMux::default();
+    for p in [
+        "/",
+        "/a/",
+        "/a/{x}",
+        "GET /a/b",
+        "example.com/a/b",
+        "/c/{rest...}",
+        "/d/{$}",
+        "PUT /only",
+    ] {
+        mux.HandleFunc(p, nop);
+    }
+    let pattern = |method: http::Method, url: &str| {
+        let req = http::Request::New(method, url, None).unwrap();
+        mux.Handler(&req).1
+    };
+    assert_eq!(pattern(http::Method::Get, "http://h/x"), "/");
+    assert_eq!(pattern(http::Method::Get, "http://h/a/b"), "GET /a/b");
+    assert_eq!(pattern(http::Method::Head, "http://h/a/b"), "GET /a/b");
+    assert_eq!(pattern(http::Method::Post, "http://h/a/b"), "/a/{x}");
+    assert_eq!(pattern(http::Method::Get, "http://h/a/b/c"), "/a/");
+    assert_eq!(
+        pattern(http::Method::Get, "http://example.com:8080/a/b"),
+        "example.com/a/b"
+    );
+    assert_eq!(pattern(http::Method::Get, "http://h/c/1/2"), "/c/{rest...}");
+    assert_eq!(pattern(http::Method::Get, "http://h/d/"), "/d/{$}");
+    assert_eq!(pattern(http::Method::Get, "http://h/d/x"), "/");
+    // 重定向到"/a/"和规范路径时没有匹配的模式。
+    assert_eq!(pattern(http::Method::Get, "http://h/a"), "");
+    assert_eq!(pattern(http::Method::Get, "http://h/a/../x"), "");
+    assert_eq!(pattern(http::Method::Get, "http://h/only"), "/");
+
+    for (p1, p2) in [
+        ("/x/{a}", "/{b}/y"),
+        ("/same", "/same"),
+        ("GET /g/{a}", "/g/b"),
+    ] {
+        let r = std::panic::catch_unwind(|| {
+            let mut mux = ServeMux::default();
+            mux.HandleFunc(p1, nop);
+            mux.HandleFunc(p2, nop);
+        });
+        assert!(r.is_err(), "{} and {} should conflict", p1, p2);
+    }
+    assert!(std::panic::catch_unwind(|| ServeMux::default().HandleFunc("/{x...}/a", nop)).is_err());
+}
+
+// readRawResponse reads one response with a Content-Length body from r and returns its head and body.
+fn readRawResponse(r: &mut impl BufRead) -> std::io::Result<(String, String)> {
+    let mut head = String::new();
+    let mut n = 0;
+    loop {
+        let mut line = String::new();
+        if r.read_line(&mut line)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        if line == "\r\n" {
+            break;
+        }
+        if let Some(v) = line.strip_prefix("Content-Length: ") {
+            n = v.trim().parse().unwrap();
+        }
+        head.push_str(&line);
+    }
+    let mut body = vec![0; n];
+    r.read_exact(&mut body)?;
+    Ok((head, String::from_utf8(body).unwrap()))
+}
+
+#[test]
+fn Test_ServerShutdown() {
+    use http::server::{ConnState, HandlerFunc, ResponseWriter, Server};
+    use std::time::{Duration, Instant};
+
+    let handler = HandlerFunc(|w: &mut dyn ResponseWriter, r: &mut http::Request| {
+        if r.URL.Path == "/slow" {
+            thread::sleep(Duration::from_millis(300));
+        }
+        w.Write(b"done").unwrap();
+    });
+    let states = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut srv = Server::New("", handler);
+    let s2 = states.clone();
+    srv.ConnState = Some(Arc::new(move |c: &TcpStream, st: ConnState| {
+        s2.lock().unwrap().push((c.peer_addr().unwrap().port(), st))
+    }));
+    let hooked = Arc::new(AtomicUsize::new(0));
+    let h2 = hooked.clone();
+    srv.RegisterOnShutdown(move || {
+        h2.fetch_add(1, Ordering::SeqCst);
+    });
+    let srv = Arc::new(srv);
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap();
+    let s = srv.clone();
+    let serving = thread::spawn(move || s.Serve(l));
+
+    // 一个空闲的keep-alive连接和一个正在处理请求的连接。
+    let idle = TcpStream::connect(addr).unwrap();
+    let mut idleR = BufReader::new(idle.try_clone().unwrap());
+    (&idle)
+        .write_all(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    let (head, body) = readRawResponse(&mut idleR).unwrap();
+    assert!(!head.contains("Connection: close"), "{}", head);
+    assert_eq!(body, "done");
+    let busy = TcpStream::connect(addr).unwrap();
+    (&busy)
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    let start = Instant::now();
+    let mut deadline = gostd_time::Now();
+    srv.Shutdown(deadline.Add(&gostd_time::Duration::new(5 * gostd_time::Second)))
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!(matches!(
+        serving.join().unwrap(),
+        Err(http::HTTPConnectError::ErrServerClosed)
+    ));
+    assert!(matches!(
+        srv.ListenAndServe(),
+        Err(http::HTTPConnectError::ErrServerClosed)
+    ));
+    // 处理中的请求完整返回并关闭连接，空闲连接被直接关闭。
+    let (head, body) = readRawResponse(&mut BufReader::new(&busy)).unwrap();
+    assert!(head.contains("Connection: close"), "{}", head);
+    assert_eq!(body, "done");
+    assert_eq!(idleR.read(&mut [0; 1]).unwrap_or(0), 0);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(hooked.load(Ordering::SeqCst), 1);
+    let port = idle.local_addr().unwrap().port();
+    let idleStates: Vec<ConnState> = states
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(p, _)| *p == port)
+        .map(|(_, st)| *st)
+        .collect();
+    assert_eq!(
+        idleStates,
+        [
+            ConnState::StateNew,
+            ConnState::StateActive,
+            ConnState::StateIdle,
+            ConnState::StateClosed
+        ]
+    );
+
+    // Shutdown超过deadline时返回错误，Close立即关闭活动连接。IdleTimeout关闭空闲连接。
+    let mut srv = Server::New(
+        "",
+        HandlerFunc(|w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            if r.URL.Path == "/slow" {
+                thread::sleep(Duration::from_secs(2));
+            }
+            w.Write(b"done").unwrap();
+        }),
+    );
+    srv.IdleTimeout = gostd_time::Duration::new(100 * gostd_time::Millisecond);
+    let srv = Arc::new(srv);
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap();
+    let s = srv.clone();
+    let serving = thread::spawn(move || s.Serve(l));
+
+    let idle = TcpStream::connect(addr).unwrap();
+    let mut idleR = BufReader::new(idle.try_clone().unwrap());
+    (&idle)
+        .write_all(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    readRawResponse(&mut idleR).unwrap();
+    let start = Instant::now();
+    assert_eq!(idleR.read(&mut [0; 1]).unwrap(), 0);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    let busy = TcpStream::connect(addr).unwrap();
+    (&busy)
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let mut deadline = gostd_time::Now();
+    let r = srv.Shutdown(deadline.Add(&gostd_time::Duration::new(50 * gostd_time::Millisecond)));
+    assert!(matches!(r, Err(http::HTTPConnectError::TimeoutError(_))));
+    let start = Instant::now();
+    srv.Close().unwrap();
+    assert_eq!((&busy).read(&mut [0; 1]).unwrap_or(0), 0);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(serving.join().unwrap().is_err());
+}
+
+#[test]
+fn Test_ServerTLS() {
+    use http::server::{HandlerFunc, ResponseWriter, Server};
+    use http::tls;
+    use http::RoundTripper;
+
+    let pair = |cert: &[u8], key: &[u8]| tls::X509KeyPair(cert, key).unwrap();
+    let mut clientCAs = tls::NewCertPool();
+    clientCAs.AppendCertsFromPEM(include_bytes!("../testdata/ca.pem"));
+    let mut srv = Server::New(
+        "",
+        HandlerFunc(|w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            let st = r.TLS.as_ref().unwrap();
+            write!(
+                w,
+                "{} {} {} {}",
+                st.ServerName,
+                st.NegotiatedProtocol,
+                st.PeerCertificates.len(),
+                st.HandshakeComplete
+            )
+            .unwrap();
+        }),
+    );
+    srv.TLSConfig = Some(tls::Config {
+        Certificates: vec![
+            pair(
+                include_bytes!("../testdata/server.pem"),
+                include_bytes!("../testdata/server.key"),
+            ),
+            pair(
+                include_bytes!("../testdata/other.pem"),
+                include_bytes!("../testdata/other.key"),
+            ),
+        ],
+        ClientAuth: tls::ClientAuthType::VerifyClientCertIfGiven,
+        ClientCAs: Some(clientCAs),
+        ..Default::default()
+    });
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap().to_string();
+    thread::spawn(move || srv.ServeTLS(l, "", ""));
+
+    let get = |cfg: tls::Config| {
+        let mut tr = http::Transport::default();
+        tr.TLSClientConfig = Some(cfg);
+        let req = http::Request::New(http::Method::Get, &format!("https://{}/", addr), None)?;
+        let mut resp = tr.RoundTrip(&req)?;
+        let mut body = String::new();
+        resp.Body.read_to_string(&mut body)?;
+        Ok::<_, http::HTTPConnectError>(body)
+    };
+    let mut roots = tls::NewCertPool();
+    roots.AppendCertsFromPEM(include_bytes!("../testdata/ca.pem"));
+    let clientCert = pair(
+        include_bytes!("../testdata/client.pem"),
+        include_bytes!("../testdata/client.key"),
+    );
+    let cfg = tls::Config {
+        RootCAs: Some(roots),
+        ServerName: "localhost".to_string(),
+        NextProtos: vec!["http/1.1".to_string()],
+        Certificates: vec![clientCert],
+        ..Default::default()
+    };
+    assert_eq!(get(cfg.clone()).unwrap(), "localhost http/1.1 1 true");
+    // 客户端证书是可选的，但提供的证书必须由ClientCAs签发。
+    assert_eq!(
+        get(tls::Config {
+            Certificates: vec![],
+            ..cfg.clone()
+        })
+        .unwrap(),
+        "localhost http/1.1 0 true"
+    );
+    assert!(get(tls::Config {
+        Certificates: vec![pair(
+            include_bytes!("../testdata/other.pem"),
+            include_bytes!("../testdata/other.key"),
+        )],
+        ..cfg.clone()
+    })
+    .is_err());
+    // 按SNI选择证书，通配符名称也能匹配。
+    let mut otherRoots = tls::NewCertPool();
+    otherRoots.AppendCertsFromPEM(include_bytes!("../testdata/other.pem"));
+    assert_eq!(
+        get(tls::Config {
+            RootCAs: Some(otherRoots),
+            ServerName: "www.other.test".to_string(),
+            Certificates: vec![],
+            ..cfg
+        })
+        .unwrap(),
+        "www.other.test http/1.1 0 true"
+    );
+
+    // 向HTTPS端口发送普通HTTP请求。
+    let mut c = TcpStream::connect(&addr).unwrap();
+    c.write_all(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+    let mut resp = String::new();
+    c.read_to_string(&mut resp).unwrap();
+    assert!(resp.starts_with("HTTP/1.0 400 Bad Request"), "{}", resp);
+
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    assert!(Server::New(
+        "",
+        HandlerFunc(|_: &mut dyn ResponseWriter, _: &mut http::Request| {})
+    )
+    .ServeTLS(l, "testdata/missing.pem", "testdata/missing.key")
+    .is_err());
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncServer() {
+    use http::async_server::{ServeMux, Server};
+    use std::time::Duration;
+
+    let mut mux = ServeMux::default();
+    mux.HandleFunc("GET /hello/{name}", |w, r| {
+        Box::pin(async move {
+            let msg = format!("hello, {}", r.PathValue("name"));
+            w.Write(msg.as_bytes()).await.unwrap();
+        })
+    });
+    mux.HandleFunc("POST /echo", |w, r| {
+        Box::pin(async move {
+            let body = r.Body.as_mut().unwrap().ReadAllAsync().await.unwrap();
+            w.Header().Set("X-Len", &body.len().to_string());
+            w.Write(&body).await.unwrap();
+        })
+    });
+    mux.HandleFunc("/big", |w, _| {
+        Box::pin(async move {
+            w.Write(&[b'x'; 3000]).await.unwrap();
+        })
+    });
+    mux.HandleFunc("/slow", |w, _| {
+        Box::pin(async move {
+            http::async_http::sleep(Duration::from_millis(300)).await;
+            w.Write(b"done").await.unwrap();
+        })
+    });
+    mux.HandleFunc("/panic", |_, _| Box::pin(async { panic!("boom") }));
+    let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut srv = Server::New("", mux);
+    let l2 = log.clone();
+    srv.ErrorLog = Some(Arc::new(move |line: &str| {
+        l2.lock().unwrap().push(line.to_string())
+    }));
+    let srv = Arc::new(srv);
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap();
+    let s = srv.clone();
+    // Serve在Shutdown时立即返回，运行时要保持到连接处理完为止。
+    let serving = thread::spawn(move || {
+        blockOn(async move {
+            let r = s.Serve(l).await;
+            let mut deadline = gostd_time::Now();
+            let _ = s
+                .Shutdown(deadline.Add(&gostd_time::Duration::new(5 * gostd_time::Second)))
+                .await;
+            r
+        })
+    });
+
+    // 同一个连接上的多个请求。
+    let c = TcpStream::connect(addr).unwrap();
+    let mut r = BufReader::new(c.try_clone().unwrap());
+    (&c).write_all(b"GET /hello/gopher HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    let (head, body) = readRawResponse(&mut r).unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+    assert_eq!(body, "hello, gopher");
+    (&c).write_all(b"POST /echo HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello")
+        .unwrap();
+    let (head, body) = readRawResponse(&mut r).unwrap();
+    assert!(head.contains("X-Len: 5\r\n"), "{}", head);
+    assert_eq!(body, "hello");
+    (&c).write_all(b"DELETE /hello/x HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    let (head, _) = readRawResponse(&mut r).unwrap();
+    assert!(head.starts_with("HTTP/1.1 405"), "{}", head);
+    assert!(head.contains("Allow: GET, HEAD\r\n"), "{}", head);
+    (&c).write_all(b"GET /big HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut rest = String::new();
+    r.read_to_string(&mut rest).unwrap();
+    assert!(rest.contains("Transfer-Encoding: chunked\r\n"), "{}", rest);
+    assert!(rest.ends_with("0\r\n\r\n"), "{}", rest);
+
+    // handler的panic只关闭它所在的连接。
+    let mut c = TcpStream::connect(addr).unwrap();
+    c.write_all(b"GET /panic HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    assert_eq!(c.read(&mut [0; 1]).unwrap_or(0), 0);
+    assert!(log.lock().unwrap()[0].contains("boom"));
+
+    // Shutdown等待处理中的请求，关闭空闲连接。
+    let idle = TcpStream::connect(addr).unwrap();
+    let mut idleR = BufReader::new(idle.try_clone().unwrap());
+    (&idle)
+        .write_all(b"GET /hello/a HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    readRawResponse(&mut idleR).unwrap();
+    let busy = TcpStream::connect(addr).unwrap();
+    (&busy)
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let mut deadline = gostd_time::Now();
+    blockOn(srv.Shutdown(deadline.Add(&gostd_time::Duration::new(5 * gostd_time::Second))))
+        .unwrap();
+    assert!(matches!(
+        serving.join().unwrap(),
+        Err(http::HTTPConnectError::ErrServerClosed)
+    ));
+    let (head, body) = readRawResponse(&mut BufReader::new(&busy)).unwrap();
+    assert!(head.contains("Connection: close"), "{}", head);
+    assert_eq!(body, "done");
+    assert_eq!(idleR.read(&mut [0; 1]).unwrap_or(0), 0);
+}
+
+#[test]
+fn Test_HttptestServer() {
+    use http::httptest;
+    use http::server::{HandlerFunc, ResponseWriter};
+
+    let handler = |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+        let tls = r.TLS.as_ref().map(|st| st.HandshakeComplete);
+        write!(w, "{} {} {:?}", r.Method, r.URL.Path, tls).unwrap();
+    };
+    let ts = httptest::NewServer(HandlerFunc(handler));
+    assert!(ts.URL().starts_with("http://127.0.0.1:"), "{}", ts.URL());
+    assert!(ts.Certificate().is_none());
+    let mut resp = ts.Client().Get(&format!("{}/a", ts.URL())).unwrap();
+    assert_eq!(resp.Body.ReadAll().unwrap(), "GET /a None");
+
+    // TLS服务器的Client信任其自签名证书，其他客户端不信任。
+    let tts = httptest::NewTLSServer(HandlerFunc(handler));
+    assert!(tts.URL().starts_with("https://127.0.0.1:"), "{}", tts.URL());
+    let mut resp = tts.Client().Get(&format!("{}/b", tts.URL())).unwrap();
+    assert_eq!(resp.Body.ReadAll().unwrap(), "GET /b Some(true)");
+    assert!(http::Client::New()
+        .Get(&format!("{}/b", tts.URL()))
+        .is_err());
+
+    let url = ts.URL();
+    ts.Close();
+    ts.Close();
+    assert!(http::Client::New().Get(&url).is_err());
+}
+
+#[test]
+fn Test_ResponseRecorder() {
+    use http::httptest;
+    use http::server::{Handler, HandlerFunc, ResponseWriter};
+
+    let handler = HandlerFunc(|w: &mut dyn ResponseWriter, r: &mut http::Request| {
+        let body = r.Body.as_mut().unwrap().ReadAll().unwrap();
+        w.Header().Set("Trailer", "X-Sum");
+        w.Header().Set("Content-Type", "text/plain");
+        w.WriteHeader(100);
+        w.WriteHeader(202);
+        w.Header().Set("Content-Type", "ignored");
+        write!(
+            w,
+            "{} {} {} {} {}",
+            r.Host,
+            r.RemoteAddr,
+            r.ContentLength,
+            r.TLS.is_some(),
+            String::from_utf8_lossy(&body)
+        )
+        .unwrap();
+        w.Flusher().unwrap().Flush().unwrap();
+        w.WriteHeader(500);
+        w.Header().Set("X-Sum", "5");
+        w.Header().Set("Trailer:X-Late", "1");
+    });
+
+    let mut req = httptest::NewRequest("POST", "/upload?x=1", Some("hello".into()));
+    assert_eq!(req.Proto, "HTTP/1.1");
+    assert_eq!(req.RequestURI, "/upload?x=1");
+    let mut w = httptest::NewRecorder();
+    handler.ServeHttp(&mut w, &mut req);
+    assert_eq!(w.Code, 202);
+    assert!(w.Flushed);
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "ignored");
+    let mut resp = w.Result();
+    assert_eq!(resp.Status, "202 Accepted");
+    assert_eq!(resp.Header.Get("Content-Type"), "text/plain");
+    assert_eq!(resp.ContentLength, -1);
+    assert_eq!(resp.Trailer.lock().unwrap().Get("X-Sum"), "5");
+    assert_eq!(resp.Trailer.lock().unwrap().Get("X-Late"), "1");
+    assert_eq!(
+        resp.Body.ReadAll().unwrap(),
+        "example.com 1.2.3.4:1234 5 false hello"
+    );
+
+    let mut req = httptest::NewRequest("", "https://go.dev/", None);
+    assert_eq!(req.Method, "GET");
+    assert_eq!(req.Host, "go.dev");
+    assert!(req.TLS.is_some());
+    let mut w = httptest::NewRecorder();
+    HandlerFunc(|_: &mut dyn ResponseWriter, _: &mut http::Request| {}).ServeHttp(&mut w, &mut req);
+    assert_eq!(w.Result().StatusCode, 200);
+}
+
+#[test]
+fn Test_ReverseProxy() {
+    use http::httptest;
+    use http::httputil::{self, ProxyRequest, ReverseProxy};
+    use http::server::{HandlerFunc, ResponseWriter};
+    use std::sync::Mutex;
+
+    let backend = httptest::NewServer(HandlerFunc(
+        |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            let body = r.Body.as_mut().map(|b| b.ReadAll().unwrap());
+            w.Header().Set("Connection", "X-Hop");
+            w.Header().Set("X-Hop", "1");
+            w.Header().Set("Trailer", "X-Sum");
+            write!(
+                w,
+                "{} {}?{} host={} xff={} xfh={} xfp={} drop={:?} te={:?} body={:?}",
+                r.Method,
+                r.URL.Path,
+                r.URL.RawQuery,
+                r.Host,
+                r.Header.Get("X-Forwarded-For"),
+                r.Header.Get("X-Forwarded-Host"),
+                r.Header.Get("X-Forwarded-Proto"),
+                r.Header.Get("X-Drop"),
+                r.Header.Get("Te"),
+                body.map(|b| String::from_utf8_lossy(&b).into_owned()),
+            )
+            .unwrap();
+            w.Header().Set("X-Sum", "42");
+        },
+    ));
+    let target = url::Parse(&format!("{}/base?a=1", backend.URL())).unwrap();
+    let front = httptest::NewServer(httputil::NewSingleHostReverseProxy(&target));
+
+    let mut req = http::Request::New(
+        http::Method::Post,
+        &format!("{}/x?b=2", front.URL()),
+        Some("hi".into()),
+    )
+    .unwrap();
+    req.Header.Set("X-Forwarded-For", "10.0.0.1");
+    req.Header.Set("Connection", "X-Drop");
+    req.Header.Set("X-Drop", "1");
+    req.Header.Set("Te", "trailers");
+    let mut resp = front.Client().Do(&mut req).unwrap();
+    assert_eq!(resp.StatusCode, 200);
+    assert_eq!(resp.Header.Get("X-Hop"), "");
+    assert!(resp.Trailer.lock().unwrap().0.contains_key("X-Sum"));
+    let body = resp.Body.ReadAll().unwrap();
+    assert_eq!(resp.Body.Trailer().Get("X-Sum"), "42");
+    assert_eq!(resp.Trailer.lock().unwrap().Get("X-Sum"), "42");
+    // NewSingleHostReverseProxy保留传入请求的Host。
+    let host = front.URL().trim_start_matches("http://").to_string();
+    assert_eq!(
+        String::from_utf8_lossy(&body),
+        format!(
+            "POST /base/x?a=1&b=2 host={} xff=10.0.0.1, 127.0.0.1 xfh= xfp= drop=\"\" te=\"trailers\" body=Some(\"hi\")",
+            host
+        )
+    );
+
+    // Rewrite: SetURL改写Host，SetXForwarded设置X-Forwarded-*。
+    let rewriteTarget = target.clone();
+    let proxy: ReverseProxy = ReverseProxy {
+        Rewrite: Some(Arc::new(move |r: &mut ProxyRequest<'_>| {
+            r.SetURL(&rewriteTarget);
+            r.SetXForwarded();
+        })),
+        ..Default::default()
+    };
+    let rewrite = httptest::NewServer(proxy);
+    let mut resp = rewrite
+        .Client()
+        .Get(&format!("{}/y", rewrite.URL()))
+        .unwrap();
+    let backendHost = backend.URL().trim_start_matches("http://").to_string();
+    let rewriteHost = rewrite.URL().trim_start_matches("http://").to_string();
+    assert_eq!(
+        String::from_utf8_lossy(&resp.Body.ReadAll().unwrap()),
+        format!(
+            "GET /base/y?a=1 host={} xff=127.0.0.1 xfh={} xfp=http drop=\"\" te=\"\" body=None",
+            backendHost, rewriteHost
+        )
+    );
+
+    // 后端不可用时返回502并记录错误；ModifyResponse的错误也交给ErrorHandler。
+    let logs = Arc::new(Mutex::new(Vec::<String>::new()));
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dead = url::Parse(&format!("http://{}", l.local_addr().unwrap())).unwrap();
+    drop(l);
+    let mut proxy = httputil::NewSingleHostReverseProxy(&dead);
+    let logsW = logs.clone();
+    proxy.ErrorLog = Some(Arc::new(move |msg: &str| {
+        logsW.lock().unwrap().push(msg.to_string())
+    }));
+    let down = httptest::NewServer(proxy);
+    let resp = down.Client().Get(&down.URL()).unwrap();
+    assert_eq!(resp.StatusCode, 502);
+    assert!(logs.lock().unwrap()[0].starts_with("http: proxy error: "));
+
+    let mut proxy = httputil::NewSingleHostReverseProxy(&target);
+    proxy.ModifyResponse = Some(Arc::new(|resp: &mut http::Response| Err("rejected".into())));
+    proxy.ErrorHandler = Some(Arc::new(
+        |w: &mut dyn ResponseWriter, _: &mut http::Request, err: &http::HTTPConnectError| {
+            w.WriteHeader(503);
+            write!(w, "{}", err).unwrap();
+        },
+    ));
+    let modify = httptest::NewServer(proxy);
+    let mut resp = modify.Client().Get(&modify.URL()).unwrap();
+    assert_eq!(resp.StatusCode, 503);
+    assert!(String::from_utf8_lossy(&resp.Body.ReadAll().unwrap()).contains("rejected"));
+}
+
+#[test]
+fn Test_DumpRequestResponse() {
+    use http::httputil;
+
+    let raw = "POST /upload?x=1 HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\nX-A: 1\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\nX-T: 9\r\n\r\n";
+    let mut req = http::request::ReadRequest(std::io::Cursor::new(raw)).unwrap();
+    let dump = httputil::DumpRequest(&mut req, true).unwrap();
+    assert_eq!(
+        String::from_utf8(dump).unwrap(),
+        "POST /upload?x=1 HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\nX-A: 1\r\n\r\n5\r\nabcde\r\n0\r\n\r\n"
+    );
+    // body被替换成相同内容的副本，trailer也保留下来。
+    let body = req.Body.as_mut().unwrap();
+    assert_eq!(body.ReadAll().unwrap(), "abcde");
+    assert_eq!(body.Trailer().Get("X-T"), "9");
+
+    let mut req = http::Request::New(http::Method::Get, "http://example.com/a?b", None).unwrap();
+    req.Header.Set("User-Agent", "dump");
+    let dump = httputil::DumpRequestOut(&mut req, false).unwrap();
+    assert_eq!(
+        String::from_utf8(dump).unwrap(),
+        "GET /a?b HTTP/1.1\r\nHost: example.com\r\nUser-Agent: dump\r\nAccept-Encoding: gzip\r\n\r\n"
+    );
+
+    let mut resp = http::Response {
+        StatusCode: 200,
+        Status: "200 OK".to_string(),
+        ProtoMajor: 1,
+        ProtoMinor: 1,
+        ContentLength: -1,
+        TransferEncoding: vec!["chunked".to_string()],
+        Body: "hello".into(),
+        ..Default::default()
+    };
+    resp.Header.Set("Content-Type", "text/plain");
+    resp.Trailer.lock().unwrap().Set("X-Sum", "5");
+    let dump = httputil::DumpResponse(&mut resp, true).unwrap();
+    assert_eq!(
+        String::from_utf8(dump).unwrap(),
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\nTrailer: X-Sum\r\n\r\n5\r\nhello\r\n0\r\nX-Sum: 5\r\n\r\n"
+    );
+    assert_eq!(resp.Body.ReadAll().unwrap(), "hello");
+
+    let mut resp = http::Response {
+        StatusCode: 404,
+        ProtoMajor: 1,
+        ProtoMinor: 0,
+        ContentLength: 3,
+        Body: "nop".into(),
+        ..Default::default()
+    };
+    let dump = httputil::DumpResponse(&mut resp, false).unwrap();
+    assert_eq!(
+        String::from_utf8(dump).unwrap(),
+        "HTTP/1.0 404 Not Found\r\nContent-Length: 3\r\n\r\n"
+    );
+
+    // NewChunkedWriter和NewChunkedReader互为逆操作。
+    let mut wire = Vec::new();
+    let mut w = httputil::NewChunkedWriter(&mut wire);
+    w.write_all(b"hello, ").unwrap();
+    w.write_all(b"").unwrap();
+    w.write_all(b"world").unwrap();
+    w.Close().unwrap();
+    wire.extend_from_slice(b"\r\n");
+    assert_eq!(wire, b"7\r\nhello, \r\n5\r\nworld\r\n0\r\n\r\n");
+    let mut s = String::new();
+    httputil::NewChunkedReader(&wire[..])
+        .read_to_string(&mut s)
+        .unwrap();
+    assert_eq!(s, "hello, world");
+    assert!(httputil::NewChunkedReader(&b"zz\r\n"[..])
+        .read_to_string(&mut s)
+        .is_err());
+}
+
+#[test]
+fn Test_ParseForm() {
+    use http::multipart;
+
+    // body中的参数排在查询字符串之前，PostForm不包含查询参数。
+    let mut req = http::Request::New(
+        http::Method::Post,
+        "http://example.com/search?q=foo&q=bar&both=x",
+        Some("z=post&both=y&prio=2&empty=".into()),
+    )
+    .unwrap();
+    req.Header.Set(
+        "Content-Type",
+        "application/x-www-form-urlencoded; param=value",
+    );
+    req.ParseForm().unwrap();
+    assert_eq!(req.FormValue("q"), "foo");
+    assert_eq!(req.FormValue("both"), "y");
+    assert_eq!(req.FormValue("z"), "post");
+    assert_eq!(req.PostFormValue("q"), "");
+    assert_eq!(req.PostFormValue("prio"), "2");
+    assert!(req.Form.Has("empty"));
+    assert_eq!(
+        req.Form.Encode(),
+        "both=y&both=x&empty=&prio=2&q=foo&q=bar&z=post"
+    );
+    // 再次调用不会重复添加参数。
+    req.ParseForm().unwrap();
+    assert_eq!(
+        req.Form.Encode(),
+        "both=y&both=x&empty=&prio=2&q=foo&q=bar&z=post"
+    );
+
+    // GET请求不读取body，其他Content-Type的body不会被解析。
+    let mut req = http::Request::New(
+        http::Method::Get,
+        "http://example.com/?a=1",
+        Some("b=2".into()),
+    )
+    .unwrap();
+    req.Header
+        .Set("Content-Type", "application/x-www-form-urlencoded");
+    assert_eq!(req.FormValue("a"), "1");
+    assert_eq!(req.FormValue("b"), "");
+    let mut req = http::Request::New(
+        http::Method::Post,
+        "http://example.com/",
+        Some("b=2".into()),
+    )
+    .unwrap();
+    req.Header.Set("Content-Type", "text/plain");
+    req.ParseForm().unwrap();
+    assert_eq!(req.FormValue("b"), "");
+    assert!(matches!(
+        req.ParseMultipartForm(1 << 20),
+        Err(http::HTTPConnectError::ErrNotMultipart)
+    ));
+    assert!(matches!(
+        req.FormFile("f"),
+        Err(http::HTTPConnectError::ErrNotMultipart)
+    ));
+
+    // 超过10MB的urlencoded body返回ErrMessageTooLarge。
+    let mut req = http::Request::New(
+        http::Method::Post,
+        "http://example.com/",
+        Some(vec![b'a'; (10 << 20) + 1].into()),
+    )
+    .unwrap();
+    req.Header
+        .Set("Content-Type", "application/x-www-form-urlencoded");
+    assert!(matches!(
+        req.ParseForm(),
+        Err(http::HTTPConnectError::ErrMessageTooLarge)
+    ));
+
+    let mut req = http::Request::New(
+        http::Method::Post,
+        "http://example.com/",
+        Some("a=1".into()),
+    )
+    .unwrap();
+    req.Header.Set("Content-Type", "multipart/form-data");
+    assert!(matches!(
+        req.ParseMultipartForm(1 << 20),
+        Err(http::HTTPConnectError::ErrMissingBoundary)
+    ));
+}
+
+#[test]
+fn Test_ParseMultipartForm() {
+    use http::multipart;
+    use std::io::{Seek, SeekFrom};
+
+    let small = b"small file".to_vec();
+    // 内容中包含boundary的前缀，不能被当作分隔符。
+    let mut big = vec![b'x'; 100];
+    let mut buf = Vec::new();
+    let mut w = multipart::NewWriter(&mut buf);
+    w.SetBoundary("my boundary").unwrap();
+    big.extend_from_slice(b"\r\n--my boundaryX\r\n");
+    big.extend_from_slice(&[b'y'; 100]);
+    w.WriteField("title", "gopher").unwrap();
+    w.WriteField("title", "second").unwrap();
+    w.CreateFormFile("small", "dir/small.txt")
+        .unwrap()
+        .write_all(&small)
+        .unwrap();
+    w.CreateFormFile("big", "big.bin")
+        .unwrap()
+        .write_all(&big)
+        .unwrap();
+    let ct = w.FormDataContentType();
+    assert_eq!(ct, "multipart/form-data; boundary=\"my boundary\"");
+    assert!(w.SetBoundary("other").is_err());
+    w.Close().unwrap();
+
+    let mut req = http::Request::New(
+        http::Method::Post,
+        "http://example.com/upload?id=7",
+        Some(buf.clone().into()),
+    )
+    .unwrap();
+    req.Header.Set("Content-Type", &ct);
+    // 只有small文件能放进内存，big写入临时文件。
+    let tempFiles = || -> Vec<std::path::PathBuf> {
+        std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("multipart-"))
+            .map(|e| e.path())
+            .collect()
+    };
+    let before = tempFiles();
+    req.ParseMultipartForm(small.len() as i64 + 10).unwrap();
+    assert_eq!(req.FormValue("title"), "gopher");
+    assert_eq!(req.FormValue("id"), "7");
+    assert_eq!(req.PostFormValue("title"), "gopher");
+    assert_eq!(req.PostFormValue("id"), "");
+    let form = req.MultipartForm.clone().unwrap();
+    assert_eq!(form.Value["title"], ["gopher", "second"]);
+
+    let (mut f, fh) = req.FormFile("small").unwrap();
+    assert_eq!(fh.Filename, "small.txt");
+    assert_eq!(fh.Size, small.len() as i64);
+    assert_eq!(fh.Header.Get("Content-Type"), "application/octet-stream");
+    let mut got = Vec::new();
+    f.read_to_end(&mut got).unwrap();
+    assert_eq!(got, small);
+
+    let (mut f, fh) = req.FormFile("big").unwrap();
+    assert_eq!(fh.Size, big.len() as i64);
+    let mut got = Vec::new();
+    f.read_to_end(&mut got).unwrap();
+    assert_eq!(got, big);
+    f.seek(SeekFrom::Start(100)).unwrap();
+    let mut crlf = [0u8; 2];
+    f.read_exact(&mut crlf).unwrap();
+    assert_eq!(&crlf, b"\r\n");
+    drop(f);
+    assert!(matches!(
+        req.FormFile("title"),
+        Err(http::HTTPConnectError::ErrMissingFile)
+    ));
+
+    // RemoveAll删除临时文件。
+    let tmp: Vec<_> = tempFiles()
+        .into_iter()
+        .filter(|p| !before.contains(p))
+        .collect();
+    assert_eq!(tmp.len(), 1);
+    form.RemoveAll().unwrap();
+    assert!(tmp.iter().all(|p| !p.exists()));
+    assert!(req.FormFile("big").is_err());
+
+    // 直接用Reader逐个读取part。
+    let mut r = multipart::NewReader(&buf[..], "my boundary");
+    let mut names = Vec::new();
+    while let Some(mut p) = r.NextPart().unwrap() {
+        let mut content = Vec::new();
+        p.read_to_end(&mut content).unwrap();
+        names.push((p.FormName(), p.FileName(), content.len()));
+    }
+    assert_eq!(
+        names,
+        [
+            ("title".to_string(), "".to_string(), 6),
+            ("title".to_string(), "".to_string(), 6),
+            ("small".to_string(), "small.txt".to_string(), small.len()),
+            ("big".to_string(), "big.bin".to_string(), big.len()),
+        ]
+    );
+
+    // 非文件字段超过maxMemory+10MB时返回ErrMessageTooLarge。
+    let mut buf = Vec::new();
+    let mut w = multipart::NewWriter(&mut buf);
+    w.WriteField("v", &"a".repeat((10 << 20) + 1)).unwrap();
+    w.Close().unwrap();
+    let boundary = w.Boundary().to_string();
+    assert!(matches!(
+        multipart::NewReader(&buf[..], &boundary).ReadForm(0),
+        Err(http::HTTPConnectError::ErrMessageTooLarge)
+    ));
+    // 截断的消息返回错误。
+    assert!(multipart::NewReader(&buf[..buf.len() - 20], &boundary)
+        .ReadForm(1 << 30)
+        .is_err());
+}
+
+#[test]
+fn Test_ResponseWrite() {
+    use std::io::Cursor;
+
+    // chunked响应：Write写出trailer，ReadResponse把Trailer头声明的key放入Trailer。
+    let req = http::Request::New(http::Method::Get, "http://example.com/a/b", None).unwrap();
+    let mut resp = http::Response {
+        StatusCode: 200,
+        ProtoMajor: 1,
+        ProtoMinor: 1,
+        ContentLength: -1,
+        TransferEncoding: vec!["chunked".to_string()],
+        Body: http::Body::New(Cursor::new(b"hello world".to_vec())),
+        ..Default::default()
+    };
+    resp.Header.Set("Content-Type", "text/plain");
+    resp.Trailer.lock().unwrap().Set("X-Checksum", "abc");
+    let mut wire = Vec::new();
+    resp.Write(&mut wire).unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&wire),
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\nTrailer: X-Checksum\r\n\r\nb\r\nhello world\r\n0\r\nX-Checksum: abc\r\n\r\n"
+    );
+    let mut got = http::ReadResponse(Cursor::new(wire), &req).unwrap();
+    assert_eq!(got.TransferEncoding, ["chunked"]);
+    assert_eq!(got.Header.Get("Trailer"), "");
+    assert!(got.Trailer.lock().unwrap().0.contains_key("X-Checksum"));
+    assert_eq!(got.Body.Trailer().Get("X-Checksum"), "");
+    assert_eq!(got.Trailer.lock().unwrap().Get("X-Checksum"), "");
+    // 直接读取Body到EOF也会填入共享的Trailer。
+    let mut body = String::new();
+    got.Body.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "hello world");
+    assert_eq!(got.Body.Trailer().Get("X-Checksum"), "abc");
+    assert_eq!(got.Trailer.lock().unwrap().Get("X-Checksum"), "abc");
+    assert!(matches!(
+        got.Location(),
+        Err(http::HTTPConnectError::ErrNoLocation)
+    ));
+
+    // 读取到的响应可以原样写回，trailer的值取自Body.Trailer()。
+    let mut got = http::ReadResponse(
+        Cursor::new(
+            b"HTTP/1.1 200 OK\r\nTrailer: X-Sum\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\nX-Sum: 3\r\n\r\n".to_vec(),
+        ),
+        &req,
+    )
+    .unwrap();
+    let mut wire = Vec::new();
+    got.Write(&mut wire).unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&wire),
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: X-Sum\r\n\r\n3\r\nabc\r\n0\r\nX-Sum: 3\r\n\r\n"
+    );
+    assert_eq!(got.Trailer.lock().unwrap().Get("X-Sum"), "3");
+
+    // Location以请求的URL为基准解析相对地址。
+    let mut resp = http::ReadResponse(
+        Cursor::new(
+            b"HTTP/1.0 302 Found\r\nLocation: ../c?d=1\r\nContent-Length: 0\r\n\r\n".to_vec(),
+        ),
+        &req,
+    )
+    .unwrap();
+    assert_eq!(
+        resp.Location().unwrap().String(),
+        "http://example.com/c?d=1"
+    );
+    assert!(resp.ProtoAtLeast(1, 0));
+    assert!(!resp.ProtoAtLeast(1, 1));
+    resp.Header.Set("Location", "https://other.example/x");
+    assert_eq!(resp.Location().unwrap().Host, "other.example");
+
+    // ContentLength为0但body不为空：按长度未知处理，加上Connection: close。
+    let mut resp = http::Response {
+        StatusCode: 404,
+        Status: "404 Gone Fishing".to_string(),
+        ProtoMajor: 1,
+        ProtoMinor: 1,
+        Body: http::Body::New(Cursor::new(b"nope".to_vec())),
+        ..Default::default()
+    };
+    let mut wire = Vec::new();
+    resp.Write(&mut wire).unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&wire),
+        "HTTP/1.1 404 Gone Fishing\r\nConnection: close\r\n\r\nnope"
+    );
+
+    // 204没有body；HEAD请求的响应只写header。
+    let mut resp = http::Response {
+        StatusCode: 204,
+        ProtoMajor: 1,
+        ProtoMinor: 1,
+        ..Default::default()
+    };
+    let mut wire = Vec::new();
+    resp.Write(&mut wire).unwrap();
+    assert_eq!(wire, b"HTTP/1.1 204 No Content\r\n\r\n");
+    let mut resp = http::Response {
+        StatusCode: 200,
+        ProtoMajor: 1,
+        ProtoMinor: 1,
+        ContentLength: 5,
+        Body: "hello".into(),
+        Request: http::Request::New(http::Method::Head, "http://example.com/", None).unwrap(),
+        ..Default::default()
+    };
+    let mut wire = Vec::new();
+    resp.Write(&mut wire).unwrap();
+    assert_eq!(wire, b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n");
+
+    // body长度与ContentLength不一致时返回错误。
+    let mut resp = http::Response {
+        StatusCode: 200,
+        ProtoMajor: 1,
+        ProtoMinor: 1,
+        ContentLength: 10,
+        Body: "short".into(),
+        ..Default::default()
+    };
+    let err = resp.Write(&mut Vec::new()).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("ContentLength=10 with Body length 5"));
+}
+
+#[test]
+fn Test_FileServer() {
+    use http::fs;
+    use http::httptest;
+    use http::server::Handler;
+
+    let root = std::env::temp_dir().join(format!("fileserver-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    std::fs::create_dir_all(root.join("site")).unwrap();
+    std::fs::write(root.join("hello.txt"), "hello, world").unwrap();
+    std::fs::write(root.join("a&b.bin"), [0u8, 1, 2]).unwrap();
+    std::fs::write(root.join("docs/readme"), "<html><p>doc</p></html>").unwrap();
+    std::fs::write(root.join("site/index.html"), "<p>index</p>").unwrap();
+
+    let handler = fs::FileServer(fs::Dir(root.clone()));
+    let serve = |method: &str, target: &str, header: &[(&str, &str)]| {
+        let mut req = httptest::NewRequest(method, target, None);
+        for (k, v) in header {
+            req.Header.Set(k, v);
+        }
+        let mut w = httptest::NewRecorder();
+        handler.ServeHttp(&mut w, &mut req);
+        w
+    };
+
+    // 文件：按扩展名设置Content-Type，Last-Modified使用TimeFormat。
+    let w = serve("GET", "/hello.txt", &[]);
+    assert_eq!(w.Code, 200);
+    assert_eq!(w.Body, b"hello, world");
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "text/plain; charset=utf-8");
+    assert_eq!(w.HeaderMap.Get("Content-Length"), "12");
+    assert_eq!(w.HeaderMap.Get("Accept-Ranges"), "bytes");
+    let lastModified = w.HeaderMap.Get("Last-Modified");
+    assert!(lastModified.ends_with(" GMT"), "{}", lastModified);
+    assert!(gostd_time::Parse(http::TimeFormat, &lastModified).is_ok());
+
+    // 没有扩展名时嗅探内容。
+    let w = serve("GET", "/docs/readme", &[]);
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "text/html; charset=utf-8");
+    assert_eq!(w.Body, b"<html><p>doc</p></html>");
+
+    // If-Modified-Since不早于修改时间时返回304。
+    let w = serve("GET", "/hello.txt", &[("If-Modified-Since", &lastModified)]);
+    assert_eq!(w.Code, 304);
+    assert!(w.Body.is_empty());
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "");
+    let w = serve(
+        "GET",
+        "/hello.txt",
+        &[("If-Modified-Since", "Mon, 02 Jan 2006 15:04:05 GMT")],
+    );
+    assert_eq!(w.Code, 200);
+
+    // 目录列表按名称排序，名称经过转义，子目录带/。
+    let w = serve("GET", "/", &[]);
+    assert_eq!(w.Code, 200);
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "text/html; charset=utf-8");
+    assert_eq!(
+        String::from_utf8_lossy(&w.Body),
+        "<!doctype html>\n<meta name=\"viewport\" content=\"width=device-width\">\n<pre>\n\
+         <a href=\"a&amp;b.bin\">a&amp;b.bin</a>\n\
+         <a href=\"docs/\">docs/</a>\n\
+         <a href=\"hello.txt\">hello.txt</a>\n\
+         <a href=\"site/\">site/</a>\n\
+         </pre>\n"
+    );
+
+    // 目录中有index.html时提供它；规范路径的重定向保留查询参数。
+    let w = serve("GET", "/site/", &[]);
+    assert_eq!(w.Body, b"<p>index</p>");
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "text/html; charset=utf-8");
+    let w = serve("GET", "/site?x=1", &[]);
+    assert_eq!(w.Code, 301);
+    assert_eq!(w.HeaderMap.Get("Location"), "site/?x=1");
+    let w = serve("GET", "/site/index.html", &[]);
+    assert_eq!(w.Code, 301);
+    assert_eq!(w.HeaderMap.Get("Location"), "./");
+    let w = serve("GET", "/hello.txt/", &[]);
+    assert_eq!(w.Code, 301);
+    assert_eq!(w.HeaderMap.Get("Location"), "../hello.txt");
+
+    // 不存在的文件返回404，".."不能离开根目录。
+    let w = serve("GET", "/missing.txt", &[]);
+    assert_eq!(w.Code, 404);
+    assert_eq!(w.Body, b"404 page not found\n");
+    let w = serve("GET", "/../hello.txt", &[]);
+    assert_eq!(w.Body, b"hello, world");
+
+    // ServeFile拒绝包含".."的请求路径。
+    let mut req = httptest::NewRequest("GET", "/x/../hello.txt", None);
+    req.URL.Path = "/x/../hello.txt".to_string();
+    let mut w = httptest::NewRecorder();
+    fs::ServeFile(&mut w, &req, root.join("hello.txt").to_str().unwrap());
+    assert_eq!(w.Code, 400);
+    let req = httptest::NewRequest("HEAD", "/anything", None);
+    let mut w = httptest::NewRecorder();
+    fs::ServeFile(&mut w, &req, root.join("hello.txt").to_str().unwrap());
+    assert_eq!(w.Code, 200);
+    assert_eq!(w.HeaderMap.Get("Content-Length"), "12");
+    assert!(w.Body.is_empty());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn Test_ServeContent() {
+    use http::fs;
+    use http::httptest;
+    use http::multipart;
+    use std::io::Cursor;
+
+    let modtime = gostd_time::Unix(1_700_000_000, 0);
+    let lastModified = "Tue, 14 Nov 2023 22:13:20 GMT";
+    let serve = |header: &[(&str, &str)], etag: &str| {
+        let mut req = httptest::NewRequest("GET", "/data.bin", None);
+        for (k, v) in header {
+            req.Header.Set(k, v);
+        }
+        let mut w = httptest::NewRecorder();
+        if !etag.is_empty() {
+            w.HeaderMap.Set("Etag", etag);
+        }
+        let mut content = Cursor::new(b"0123456789abcdef".to_vec());
+        fs::ServeContent(&mut w, &req, "data", &modtime, &mut content);
+        w
+    };
+
+    let w = serve(&[], "");
+    assert_eq!(w.Code, 200);
+    assert_eq!(w.HeaderMap.Get("Last-Modified"), lastModified);
+    assert_eq!(w.HeaderMap.Get("Content-Type"), "text/plain; charset=utf-8");
+    assert_eq!(w.Body, b"0123456789abcdef");
+
+    // 单个范围和后缀范围。
+    let w = serve(&[("Range", "bytes=2-5")], "");
+    assert_eq!(w.Code, 206);
+    assert_eq!(w.HeaderMap.Get("Content-Range"), "bytes 2-5/16");
+    assert_eq!(w.HeaderMap.Get("Content-Length"), "4");
+    assert_eq!(w.Body, b"2345");
+    let w = serve(&[("Range", "bytes=-3")], "");
+    assert_eq!(w.HeaderMap.Get("Content-Range"), "bytes 13-15/16");
+    assert_eq!(w.Body, b"def");
+
+    // 多个范围返回multipart/byteranges，Content-Length与body一致。
+    let w = serve(&[("Range", "bytes=0-1,10-")], "");
+    assert_eq!(w.Code, 206);
+    let ct = w.HeaderMap.Get("Content-Type");
+    let boundary = ct.strip_prefix("multipart/byteranges; boundary=").unwrap();
+    assert_eq!(w.HeaderMap.Get("Content-Length"), w.Body.len().to_string());
+    let mut mr = multipart::NewReader(&w.Body[..], boundary);
+    let mut parts = Vec::new();
+    while let Some(mut p) = mr.NextPart().unwrap() {
+        let mut body = String::new();
+        p.read_to_string(&mut body).unwrap();
+        parts.push((
+            p.Header.Get("Content-Range"),
+            p.Header.Get("Content-Type"),
+            body,
+        ));
+    }
+    assert_eq!(
+        parts,
+        [
+            (
+                "bytes 0-1/16".to_string(),
+                "text/plain; charset=utf-8".to_string(),
+                "01".to_string()
+            ),
+            (
+                "bytes 10-15/16".to_string(),
+                "text/plain; charset=utf-8".to_string(),
+                "abcdef".to_string()
+            ),
+        ]
+    );
+
+    // 范围完全在内容之外时返回416。
+    let w = serve(&[("Range", "bytes=100-")], "");
+    assert_eq!(w.Code, 416);
+    assert_eq!(w.HeaderMap.Get("Content-Range"), "bytes */16");
+
+    // ETag条件请求。
+    let w = serve(&[("If-None-Match", "W/\"v1\", \"v0\"")], "\"v1\"");
+    assert_eq!(w.Code, 304);
+    assert_eq!(w.HeaderMap.Get("Last-Modified"), "");
+    let w = serve(&[("If-None-Match", "\"v0\"")], "\"v1\"");
+    assert_eq!(w.Code, 200);
+    let w = serve(&[("If-Match", "\"v0\"")], "\"v1\"");
+    assert_eq!(w.Code, 412);
+    let w = serve(
+        &[("If-Unmodified-Since", "Mon, 02 Jan 2006 15:04:05 GMT")],
+        "",
+    );
+    assert_eq!(w.Code, 412);
+
+    // If-Range不匹配时忽略Range，发送全部内容。
+    let w = serve(&[("Range", "bytes=0-1"), ("If-Range", "\"v0\"")], "\"v1\"");
+    assert_eq!(w.Code, 200);
+    assert_eq!(w.Body.len(), 16);
+    let w = serve(&[("Range", "bytes=0-1"), ("If-Range", lastModified)], "");
+    assert_eq!(w.Code, 206);
+    assert_eq!(w.Body, b"01");
+}
+
+// trickle connects to addr and writes head, then data one byte at a time, each within the server's read timeout, until the server closes the connection. It returns how long that took.
+fn trickle(addr: std::net::SocketAddr, head: &[u8], data: &[u8]) -> std::time::Duration {
+    use std::time::{Duration, Instant};
+
+    let mut c = TcpStream::connect(addr).unwrap();
+    c.write_all(head).unwrap();
+    c.set_read_timeout(Some(Duration::from_millis(20))).unwrap();
+    let start = Instant::now();
+    for b in data.iter().cycle() {
+        if start.elapsed() > Duration::from_secs(5) || c.write_all(&[*b]).is_err() {
+            break;
+        }
+        let mut buf = [0u8; 256];
+        match c.read(&mut buf) {
+            Ok(0) => break,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            _ => break,
+        }
+        thread::sleep(Duration::from_millis(30));
+    }
+    start.elapsed()
+}
+
+#[test]
+fn Test_ServerReadTimeout() {
+    use http::server::{HandlerFunc, ResponseWriter, Server};
+    use std::time::{Duration, Instant};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let tx = std::sync::Mutex::new(tx);
+    let mut srv = Server::New(
+        "",
+        HandlerFunc(move |w: &mut dyn ResponseWriter, r: &mut http::Request| {
+            let read = r.Body.as_mut().map(|b| b.ReadAll());
+            let _ = tx.lock().unwrap().send(read.is_some_and(|r| r.is_err()));
+            let _ = w.Write(b"ok");
+        }),
+    );
+    srv.ReadTimeout = gostd_time::Duration::new(300 * gostd_time::Millisecond);
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap();
+    thread::spawn(move || srv.Serve(l));
+
+    // 逐字节发送请求头：每次读取都不超时，但整个请求超过了ReadTimeout。
+    let took = trickle(addr, b"GET / HTTP/1.1\r\nX-Slow: ", b"a");
+    assert!(took < Duration::from_secs(2), "{:?}", took);
+
+    // 逐字节发送body：handler读取body失败，连接被关闭。
+    let took = trickle(
+        addr,
+        b"POST / HTTP/1.1\r\nHost: h\r\nContent-Length: 1000\r\n\r\n",
+        b"b",
+    );
+    assert!(took < Duration::from_secs(2), "{:?}", took);
+    assert!(rx.recv_timeout(Duration::from_secs(1)).unwrap());
+}
+
+#[cfg(feature = "async-http")]
+#[test]
+fn Test_AsyncServerReadTimeout() {
+    use http::async_server::{ServeMux, Server};
+    use std::time::Duration;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let tx = std::sync::Mutex::new(tx);
+    let mut mux = ServeMux::default();
+    mux.HandleFunc("/", move |w, r| {
+        let tx = tx.lock().unwrap().clone();
+        Box::pin(async move {
+            let read = r.Body.as_mut().unwrap().ReadAllAsync().await;
+            let _ = tx.send(read.is_err());
+            let _ = w.Write(b"ok").await;
+        })
+    });
+    let mut srv = Server::New("", mux);
+    srv.ReadTimeout = gostd_time::Duration::new(300 * gostd_time::Millisecond);
+    let l = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = l.local_addr().unwrap();
+    thread::spawn(move || blockOn(async move { srv.Serve(l).await }));
+
+    // 请求头和body都受同一个从请求开始计算的期限限制。
+    let took = trickle(addr, b"GET / HTTP/1.1\r\nX-Slow: ", b"a");
+    assert!(took < Duration::from_secs(2), "{:?}", took);
+    let took = trickle(
+        addr,
+        b"POST / HTTP/1.1\r\nHost: h\r\nContent-Length: 1000\r\n\r\n",
+        b"b",
+    );
+    assert!(took < Duration::from_secs(2), "{:?}", took);
+    assert!(rx.recv_timeout(Duration::from_secs(1)).unwrap());
+}
@@ -0,0 +1,584 @@
+//! Package tls holds the TLS settings a Transport uses for HTTPS connections and a Server uses to terminate them, mirroring Go's crypto/tls.Config.
+//!
+//! <details class="rustdoc-toggle top-doc">
+//! <summary class="docblock">zh-cn</summary>
+//! tls包保存Transport建立HTTPS连接以及Server终止HTTPS连接时使用的TLS配置，对应Go语言的crypto/tls.Config。
+//! </details>
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::iter::FromIterator;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use gostd_builtin::*;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{
+    ClientConfig, DigitallySignedStruct, DistinguishedName, RootCertStore, ServerConfig,
+    SignatureScheme,
+};
+use webpki_roots::TLS_SERVER_ROOTS;
+
+use crate::error::{HTTPConnectError, HttpResult};
+
+/// CertPool is a set of certificates, used as the trusted roots of a Config.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// CertPool是一组证书，用作Config信任的根证书。
+/// </details>
+#[derive(Default, Debug, Clone)]
+pub struct CertPool {
+    certs: Vec<CertificateDer<'static>>,
+}
+
+/// NewCertPool returns a new, empty CertPool.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// NewCertPool返回一个新的空CertPool。
+/// </details>
+pub fn NewCertPool() -> CertPool {
+    CertPool::default()
+}
+
+impl CertPool {
+    /// AddCert adds a DER encoded certificate to the pool.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// AddCert向证书池添加一个DER编码的证书。
+    /// </details>
+    pub fn AddCert(&mut self, der: &[u8]) {
+        self.certs.push(CertificateDer::from(der.to_vec()));
+    }
+
+    /// AppendCertsFromPEM attempts to parse a series of PEM encoded certificates. It appends any certificates found to the pool, and reports whether any certificates were successfully parsed.
+    /// <details class="rustdoc-toggle top-doc">
+    /// <summary class="docblock">zh-cn</summary>
+    /// AppendCertsFromPEM解析一系列PEM编码的证书，把找到的证书加入证书池，返回是否成功解析了至少一个证书。
+    /// </details>
+    pub fn AppendCertsFromPEM(&mut self, pemCerts: &[u8]) -> bool {
+        let n = len!(self.certs);
+        self.certs
+            .extend(CertificateDer::pem_slice_iter(pemCerts).filter_map(Result::ok));
+        len!(self.certs) > n
+    }
+}
+
+/// A Certificate is a chain of one or more certificates, leaf first, together with the private key of the leaf.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Certificate是由一个或多个证书组成的证书链(叶子证书在前)，以及叶子证书的私钥。
+/// </details>
+pub struct Certificate {
+    pub Certificate: Vec<CertificateDer<'static>>,
+    pub PrivateKey: PrivateKeyDer<'static>,
+}
+
+impl Clone for Certificate {
+    fn clone(&self) -> Self {
+        Certificate {
+            Certificate: self.Certificate.clone(),
+            PrivateKey: self.PrivateKey.clone_key(),
+        }
+    }
+}
+
+impl fmt::Debug for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Certificate")
+            .field("Certificate", &self.Certificate)
+            .finish_non_exhaustive()
+    }
+}
+
+/// X509KeyPair parses a public/private key pair from a pair of PEM encoded data.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// X509KeyPair从一对PEM编码的数据中解析公钥/私钥对。
+/// </details>
+pub fn X509KeyPair(certPEMBlock: &[u8], keyPEMBlock: &[u8]) -> HttpResult<Certificate> {
+    let chain: Vec<CertificateDer<'static>> = CertificateDer::pem_slice_iter(certPEMBlock)
+        .filter_map(Result::ok)
+        .collect();
+    if chain.is_empty() {
+        return Err("tls: failed to find any PEM data in certificate input".into());
+    }
+    let key = PrivateKeyDer::from_pem_slice(keyPEMBlock)
+        .map_err(|_| HTTPConnectError::from("tls: failed to find any PEM data in key input"))?;
+    Ok(Certificate {
+        Certificate: chain,
+        PrivateKey: key,
+    })
+}
+
+/// LoadX509KeyPair reads and parses a public/private key pair from a pair of files. The files must contain PEM encoded data. The certificate file may contain intermediate certificates following the leaf certificate to form a certificate chain.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// LoadX509KeyPair从一对文件中读取并解析公钥/私钥对。文件必须是PEM编码，证书文件可以在叶子证书后面包含中间证书以组成证书链。
+/// </details>
+pub fn LoadX509KeyPair(certFile: &str, keyFile: &str) -> HttpResult<Certificate> {
+    let certPEMBlock = fs::read(certFile)?;
+    let keyPEMBlock = fs::read(keyFile)?;
+    X509KeyPair(&certPEMBlock, &keyPEMBlock)
+}
+
+/// A Config structure is used to configure a TLS client or server. After it has been passed to a Transport or a Server it must not be modified; the underlying rustls configuration is built once and reused for every connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// Config用于配置TLS客户端或服务器。交给Transport或Server后不能再修改，底层的rustls配置只构建一次，所有连接共用。
+/// </details>
+///
+/// # Example
+///
+/// ```no_run
+/// use gostd_http as http;
+/// use http::tls;
+/// use http::RoundTripper;
+///
+/// let mut roots = tls::NewCertPool();
+/// roots.AppendCertsFromPEM(&std::fs::read("ca.pem").unwrap());
+/// let cert = tls::LoadX509KeyPair("client.pem", "client.key").unwrap();
+///
+/// let mut tr = http::Transport::default();
+/// tr.TLSClientConfig = Some(tls::Config {
+///     RootCAs: Some(roots),
+///     Certificates: vec![cert],
+///     ..Default::default()
+/// });
+/// let req = http::Request::New(http::Method::Get, "https://internal.example.com/", None).unwrap();
+/// let resp = tr.RoundTrip(&req).unwrap();
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct Config {
+    /// RootCAs defines the set of root certificate authorities that clients use when verifying server certificates. If RootCAs is None, the bundled Mozilla root set (webpki-roots) is used.
+    pub RootCAs: Option<CertPool>,
+    /// Certificates contains one or more certificate chains to present to the other side of the connection.
+    ///
+    /// A client presents the first entry to servers that ask for a client certificate. A server presents the first certificate whose leaf is valid for the name the client sent through SNI, or the first entry if none is, or the client sent no name.
+    pub Certificates: Vec<Certificate>,
+    /// ServerName is used to verify the hostname on the returned certificates and is sent in the SNI extension. If empty, the host of the dialed URL is used.
+    pub ServerName: String,
+    /// NextProtos is a list of supported application level protocols, in order of preference, offered through ALPN. A Server always adds "http/1.1".
+    pub NextProtos: Vec<String>,
+    /// InsecureSkipVerify controls whether a client verifies the server's certificate chain and host name. If InsecureSkipVerify is true, any certificate presented by the server and any host name in that certificate is accepted. In this mode, TLS is susceptible to machine-in-the-middle attacks. This should be used only for testing.
+    pub InsecureSkipVerify: bool,
+    /// ClientAuth determines the server's policy for TLS Client Authentication. The default is NoClientCert.
+    pub ClientAuth: ClientAuthType,
+    /// ClientCAs defines the set of root certificate authorities that servers use if required to verify a client certificate by the policy in ClientAuth. If ClientCAs is None, the bundled Mozilla root set (webpki-roots) is used.
+    pub ClientCAs: Option<CertPool>,
+}
+
+/// ClientAuthType declares the policy the server will follow for TLS Client Authentication.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ClientAuthType声明服务器进行TLS客户端认证时遵循的策略。
+/// </details>
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientAuthType {
+    /// NoClientCert indicates that no client certificate should be requested during the handshake, and if any certificates are sent they will not be verified.
+    #[default]
+    NoClientCert,
+    /// RequestClientCert indicates that a client certificate should be requested during the handshake, but does not require that the client send any certificates.
+    RequestClientCert,
+    /// RequireAnyClientCert indicates that a client certificate should be requested during the handshake, and that at least one certificate is required to be sent by the client, but that certificate is not required to be valid.
+    RequireAnyClientCert,
+    /// VerifyClientCertIfGiven indicates that a client certificate should be requested during the handshake, but does not require that the client sends a certificate. If the client does send a certificate it is required to be valid.
+    VerifyClientCertIfGiven,
+    /// RequireAndVerifyClientCert indicates that a client certificate should be requested during the handshake, and that at least one valid certificate is required to be sent by the client.
+    RequireAndVerifyClientCert,
+}
+
+/// ConnectionState records basic TLS details about the connection.
+/// <details class="rustdoc-toggle top-doc">
+/// <summary class="docblock">zh-cn</summary>
+/// ConnectionState记录TLS连接的基本信息。
+/// </details>
+#[derive(Default, Debug, Clone)]
+pub struct ConnectionState {
+    /// Version is the TLS version used by the connection (e.g. 0x0304 for TLS 1.3).
+    pub Version: u16,
+    /// HandshakeComplete is true if the handshake has concluded.
+    pub HandshakeComplete: bool,
+    /// CipherSuite is the cipher suite negotiated for the connection.
+    pub CipherSuite: u16,
+    /// NegotiatedProtocol is the application protocol negotiated with ALPN.
+    pub NegotiatedProtocol: String,
+    /// ServerName is the value of the Server Name Indication extension sent by the client.
+    pub ServerName: String,
+    /// PeerCertificates are the certificates sent by the peer, in the order in which they were sent. The first element is the leaf certificate that the connection is verified against.
+    pub PeerCertificates: Vec<CertificateDer<'static>>,
+}
+
+// connectionState returns the state of conn, a client connection to serverName or a server connection that received serverName through SNI.
+pub(crate) fn connectionState(conn: &rustls::CommonState, serverName: &str) -> ConnectionState {
+    ConnectionState {
+        Version: conn.protocol_version().map(u16::from).unwrap_or(0),
+        HandshakeComplete: !conn.is_handshaking(),
+        CipherSuite: conn
+            .negotiated_cipher_suite()
+            .map(|cs| u16::from(cs.suite()))
+            .unwrap_or(0),
+        NegotiatedProtocol: String::from_utf8_lossy(conn.alpn_protocol().unwrap_or_default())
+            .into_owned(),
+        ServerName: serverName.to_string(),
+        PeerCertificates: conn.peer_certificates().unwrap_or_default().to_vec(),
+    }
+}
+
+// serverName returns the name to verify and send as SNI when dialing host with cfg.
+pub(crate) fn serverName(cfg: Option<&Config>, host: &str) -> String {
+    match cfg {
+        Some(cfg) if !cfg.ServerName.is_empty() => cfg.ServerName.clone(),
+        _ => host.to_string(),
+    }
+}
+
+// configCache holds a TLS configuration built on first use. Clones share the cached value, like clones of a Transport share their connection pool.
+pub(crate) struct configCache<T>(Arc<Mutex<Option<Arc<T>>>>);
+
+impl<T> Default for configCache<T> {
+    fn default() -> Self {
+        configCache(Arc::default())
+    }
+}
+
+impl<T> Clone for configCache<T> {
+    fn clone(&self) -> Self {
+        configCache(self.0.clone())
+    }
+}
+
+impl<T> configCache<T> {
+    pub(crate) fn get(&self, build: impl FnOnce() -> HttpResult<Arc<T>>) -> HttpResult<Arc<T>> {
+        let mut cached = self.0.lock().unwrap();
+        if let Some(c) = &*cached {
+            return Ok(c.clone());
+        }
+        let c = build()?;
+        *cached = Some(c.clone());
+        Ok(c)
+    }
+}
+
+// defaultClientConfig is shared by every Transport without a TLSClientConfig.
+static defaultClientConfig: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+
+// clientConfig builds the rustls client configuration described by cfg.
+pub(crate) fn clientConfig(cfg: Option<&Config>) -> HttpResult<Arc<ClientConfig>> {
+    let cfg = match cfg {
+        Some(cfg) => cfg,
+        None => {
+            return Ok(defaultClientConfig
+                .get_or_init(|| {
+                    Arc::new(
+                        ClientConfig::builder()
+                            .with_root_certificates(RootCertStore::from_iter(
+                                TLS_SERVER_ROOTS.iter().cloned(),
+                            ))
+                            .with_no_client_auth(),
+                    )
+                })
+                .clone())
+        }
+    };
+    let builder = ClientConfig::builder();
+    let builder = if cfg.InsecureSkipVerify {
+        let provider = builder.crypto_provider().clone();
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(insecureVerifier(provider)))
+    } else {
+        let mut roots = RootCertStore::empty();
+        match &cfg.RootCAs {
+            Some(pool) => {
+                roots.add_parsable_certificates(pool.certs.iter().cloned());
+            }
+            None => roots.extend(TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        builder.with_root_certificates(roots)
+    };
+    let mut config = match cfg.Certificates.first() {
+        Some(cert) => {
+            builder.with_client_auth_cert(cert.Certificate.clone(), cert.PrivateKey.clone_key())?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = cfg
+        .NextProtos
+        .iter()
+        .map(|p| p.as_bytes().to_vec())
+        .collect();
+    Ok(Arc::new(config))
+}
+
+// insecureVerifier accepts any server certificate, see Config.InsecureSkipVerify. Handshake signatures are still checked.
+#[derive(Debug)]
+struct insecureVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for insecureVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// serverConfig builds the rustls server configuration described by cfg.
+pub(crate) fn serverConfig(cfg: &Config) -> HttpResult<Arc<ServerConfig>> {
+    if cfg.Certificates.is_empty() {
+        return Err("tls: no certificates configured".into());
+    }
+    let builder = ServerConfig::builder();
+    let provider = builder.crypto_provider().clone();
+    let mut keys = Vec::with_capacity(len!(cfg.Certificates));
+    for cert in &cfg.Certificates {
+        let key = provider
+            .key_provider
+            .load_private_key(cert.PrivateKey.clone_key())?;
+        keys.push(Arc::new(CertifiedKey::new(cert.Certificate.clone(), key)));
+    }
+    let clientRoots = || {
+        let mut roots = RootCertStore::empty();
+        match &cfg.ClientCAs {
+            Some(pool) => {
+                roots.add_parsable_certificates(pool.certs.iter().cloned());
+            }
+            None => roots.extend(TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        Arc::new(roots)
+    };
+    let verifierErr = |err: rustls::server::VerifierBuilderError| {
+        HTTPConnectError::ConnectionFailure(format!("tls: {}", err))
+    };
+    let builder = match cfg.ClientAuth {
+        ClientAuthType::NoClientCert => builder.with_no_client_auth(),
+        ClientAuthType::RequestClientCert | ClientAuthType::RequireAnyClientCert => builder
+            .with_client_cert_verifier(Arc::new(anyClientCertVerifier {
+                provider,
+                mandatory: cfg.ClientAuth == ClientAuthType::RequireAnyClientCert,
+            })),
+        ClientAuthType::VerifyClientCertIfGiven => builder.with_client_cert_verifier(
+            WebPkiClientVerifier::builder(clientRoots())
+                .allow_unauthenticated()
+                .build()
+                .map_err(verifierErr)?,
+        ),
+        ClientAuthType::RequireAndVerifyClientCert => builder.with_client_cert_verifier(
+            WebPkiClientVerifier::builder(clientRoots())
+                .build()
+                .map_err(verifierErr)?,
+        ),
+    };
+    let mut config = builder.with_cert_resolver(Arc::new(sniResolver(keys)));
+    config.alpn_protocols = cfg
+        .NextProtos
+        .iter()
+        .map(|p| p.as_bytes().to_vec())
+        .collect();
+    if !cfg.NextProtos.iter().any(|p| p == "http/1.1") {
+        config.alpn_protocols.push(b"http/1.1".to_vec());
+    }
+    Ok(Arc::new(config))
+}
+
+// sniResolver picks the certificate to present from the server name the client sent, see Config.Certificates.
+#[derive(Debug)]
+struct sniResolver(Vec<Arc<CertifiedKey>>);
+
+impl ResolvesServerCert for sniResolver {
+    fn resolve(&self, hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        if let Some(name) = hello
+            .server_name()
+            .and_then(|n| ServerName::try_from(n).ok())
+        {
+            for ck in &self.0 {
+                let valid = ck.end_entity_cert().is_ok_and(|leaf| {
+                    webpki::EndEntityCert::try_from(leaf)
+                        .is_ok_and(|c| c.verify_is_valid_for_subject_name(&name).is_ok())
+                });
+                if valid {
+                    return Some(ck.clone());
+                }
+            }
+        }
+        self.0.first().cloned()
+    }
+}
+
+// anyClientCertVerifier asks for a client certificate and accepts any, see RequestClientCert and RequireAnyClientCert. Handshake signatures are still checked.
+#[derive(Debug)]
+struct anyClientCertVerifier {
+    provider: Arc<CryptoProvider>,
+    mandatory: bool,
+}
+
+impl ClientCertVerifier for anyClientCertVerifier {
+    fn client_auth_mandatory(&self) -> bool {
+        self.mandatory
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+// async-tls is still on rustls 0.21, so the async-std runtime needs its own copy of the configuration.
+#[cfg(feature = "async-std-runtime")]
+pub(crate) fn asyncStdClientConfig(
+    cfg: Option<&Config>,
+) -> HttpResult<Arc<rustls_021::ClientConfig>> {
+    use rustls_021::{Certificate, OwnedTrustAnchor, PrivateKey};
+
+    let tlsErr =
+        |err: rustls_021::Error| HTTPConnectError::ConnectionFailure(format!("tls: {}", err));
+    let default = Config::default();
+    let cfg = cfg.unwrap_or(&default);
+    let mut roots = rustls_021::RootCertStore::empty();
+    match &cfg.RootCAs {
+        Some(pool) => {
+            for c in &pool.certs {
+                roots.add(&Certificate(c.to_vec())).map_err(tlsErr)?;
+            }
+        }
+        None => roots.add_trust_anchors(TLS_SERVER_ROOTS.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject.as_ref(),
+                ta.subject_public_key_info.as_ref(),
+                ta.name_constraints.as_ref().map(|nc| nc.as_ref()),
+            )
+        })),
+    }
+    let builder = rustls_021::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+    let mut config = match cfg.Certificates.first() {
+        Some(cert) => builder
+            .with_client_auth_cert(
+                cert.Certificate
+                    .iter()
+                    .map(|c| Certificate(c.to_vec()))
+                    .collect(),
+                PrivateKey(cert.PrivateKey.secret_der().to_vec()),
+            )
+            .map_err(tlsErr)?,
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = cfg
+        .NextProtos
+        .iter()
+        .map(|p| p.as_bytes().to_vec())
+        .collect();
+    if cfg.InsecureSkipVerify {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(insecureVerifier021));
+    }
+    Ok(Arc::new(config))
+}
+
+#[cfg(feature = "async-std-runtime")]
+struct insecureVerifier021;
+
+#[cfg(feature = "async-std-runtime")]
+impl rustls_021::client::ServerCertVerifier for insecureVerifier021 {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls_021::Certificate,
+        _intermediates: &[rustls_021::Certificate],
+        _server_name: &rustls_021::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<rustls_021::client::ServerCertVerified, rustls_021::Error> {
+        Ok(rustls_021::client::ServerCertVerified::assertion())
+    }
+}
@@ -0,0 +1,429 @@
+#![allow(unused)]
+// #![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+use std::cmp;
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use gostd_builtin::*;
+
+#[cfg(feature = "async-std-runtime")]
+use async_std::io::BufRead as AsyncBufRead;
+#[cfg(feature = "tokio-runtime")]
+use tokio::io::AsyncBufRead;
+
+use crate::body::Body;
+use crate::error::{HTTPConnectError, HttpResult};
+use crate::header::Header;
+use crate::request::Request;
+use crate::response::Response;
+
+// maxLineLength is the maximum length of a chunk size line or trailer line.
+const maxLineLength: usize = 4096;
+
+// transferState tracks how much of a message body is left to read according to its framing: a fixed Content-Length, chunked transfer coding, or everything until the connection is closed.
+#[derive(Debug, Clone)]
+pub(crate) enum transferState {
+    ContentLength(u64),
+    Chunked(chunkedState),
+    UntilEOF,
+    Done,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct chunkedState {
+    phase: chunkedPhase,
+    remaining: u64,
+    line: Vec<u8>,
+    // Trailer holds the raw trailer lines that followed the last chunk.
+    pub(crate) Trailer: Vec<String>,
+    // trailer, if set, receives the parsed trailer fields once the last chunk has been read.
+    trailer: Option<Arc<Mutex<Header>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum chunkedPhase {
+    #[default]
+    SizeLine,
+    Data,
+    DataCRLF,
+    Trailer,
+}
+
+// parseContentLength trims whitespace from cl and returns its value, or None if it is empty. Only plain decimal digits are accepted, as RFC 9110 section 8.6 requires, so that a proxy in front of us cannot disagree about where the body ends.
+pub(crate) fn parseContentLength(cl: &str) -> HttpResult<Option<u64>> {
+    let cl = cl.trim_matches([' ', '\t']);
+    if cl.is_empty() {
+        return Ok(None);
+    }
+    match cl.parse::<u64>() {
+        Ok(n) if cl.bytes().all(|b| b.is_ascii_digit()) && n <= int64::MAX as u64 => Ok(Some(n)),
+        _ => Err(HTTPConnectError::ProtocolError(format!(
+            "bad Content-Length {:?}",
+            cl
+        ))),
+    }
+}
+
+impl transferState {
+    // forResponse determines the body framing of resp, a response to req whose header has just been read. See RFC 7230, section 3.3.3.
+    pub(crate) fn forResponse(resp: &Response, req: &Request) -> HttpResult<transferState> {
+        if !bodyAllowedForStatus(resp.StatusCode)
+            || req.Method == "HEAD"
+            || (req.Method == "CONNECT" && resp.StatusCode / 100 == 2)
+        {
+            // HEAD请求、CONNECT成功的响应以及1xx、204、304响应没有body，Content-Length只是描述性的。
+            return Ok(transferState::Done);
+        }
+        transferState::forHeader(&resp.Header, true)
+    }
+
+    // forHeader determines the framing announced by a message header. Without Transfer-Encoding or Content-Length, a response body lasts until EOF while a request has no body at all. A malformed Content-Length is an error.
+    pub(crate) fn forHeader(header: &Header, isResponse: bool) -> HttpResult<transferState> {
+        if isChunked(header) {
+            return Ok(transferState::chunkedWithTrailer(Arc::new(Mutex::new(
+                Header::default(),
+            ))));
+        }
+        Ok(match parseContentLength(&header.Get("Content-Length"))? {
+            Some(0) => transferState::Done,
+            Some(n) => transferState::ContentLength(n),
+            None if isResponse => transferState::UntilEOF,
+            None => transferState::Done,
+        })
+    }
+
+    // chunkedWithTrailer returns the state of a chunked body whose trailer fields are stored in trailer when the body ends.
+    pub(crate) fn chunkedWithTrailer(trailer: Arc<Mutex<Header>>) -> transferState {
+        transferState::Chunked(chunkedState {
+            trailer: Some(trailer),
+            ..Default::default()
+        })
+    }
+
+    // trailer returns where the trailer fields of a chunked body are collected, if anywhere.
+    pub(crate) fn trailer(&self) -> Option<Arc<Mutex<Header>>> {
+        match self {
+            transferState::Chunked(c) => c.trailer.clone(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn isDone(&self) -> bool {
+        matches!(self, transferState::Done)
+    }
+
+    // read is the blocking form of pollRead.
+    pub(crate) fn read<R: BufRead>(&mut self, r: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+        let mut cx = Context::from_waker(Waker::noop());
+        match self.pollRead(&mut syncSource(r), &mut cx, buf) {
+            Poll::Ready(res) => res,
+            Poll::Pending => Err(io::Error::from(ErrorKind::WouldBlock)),
+        }
+    }
+
+    // pollReadAsync is the async form of read.
+    #[cfg(feature = "async-http")]
+    pub(crate) fn pollReadAsync<R: AsyncBufRead + Unpin>(
+        &mut self,
+        r: &mut R,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.pollRead(&mut asyncSource(r), cx, buf)
+    }
+
+    // pollRead reads body bytes from src into buf, decoding the framing on the way. It returns Ok(0) once the whole body has been consumed.
+    fn pollRead<S: bufSource>(
+        &mut self,
+        src: &mut S,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match self {
+                transferState::Done => return Poll::Ready(Ok(0)),
+                transferState::ContentLength(remaining) => {
+                    let avail = match src.pollFillBuf(cx) {
+                        Poll::Ready(Ok(avail)) => avail,
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if avail.is_empty() {
+                        return Poll::Ready(Err(io::Error::from(ErrorKind::UnexpectedEof)));
+                    }
+                    let n = cmp::min(cmp::min(buf.len(), avail.len()), *remaining as usize);
+                    buf[..n].copy_from_slice(&avail[..n]);
+                    src.consumeBuf(n);
+                    *remaining -= n as u64;
+                    if *remaining == 0 {
+                        *self = transferState::Done;
+                    }
+                    return Poll::Ready(Ok(n));
+                }
+                transferState::UntilEOF => {
+                    let avail = match src.pollFillBuf(cx) {
+                        Poll::Ready(Ok(avail)) => avail,
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if avail.is_empty() {
+                        *self = transferState::Done;
+                        return Poll::Ready(Ok(0));
+                    }
+                    let n = cmp::min(buf.len(), avail.len());
+                    buf[..n].copy_from_slice(&avail[..n]);
+                    src.consumeBuf(n);
+                    return Poll::Ready(Ok(n));
+                }
+                transferState::Chunked(c) => match c.phase {
+                    chunkedPhase::Data => {
+                        let avail = match src.pollFillBuf(cx) {
+                            Poll::Ready(Ok(avail)) => avail,
+                            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                            Poll::Pending => return Poll::Pending,
+                        };
+                        if avail.is_empty() {
+                            return Poll::Ready(Err(io::Error::from(ErrorKind::UnexpectedEof)));
+                        }
+                        let n = cmp::min(cmp::min(buf.len(), avail.len()), c.remaining as usize);
+                        buf[..n].copy_from_slice(&avail[..n]);
+                        src.consumeBuf(n);
+                        c.remaining -= n as u64;
+                        if c.remaining == 0 {
+                            c.phase = chunkedPhase::DataCRLF;
+                        }
+                        return Poll::Ready(Ok(n));
+                    }
+                    phase => {
+                        match pollReadLine(src, cx, &mut c.line) {
+                            Poll::Ready(Ok(())) => (),
+                            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                            Poll::Pending => return Poll::Pending,
+                        }
+                        let line = String::from_utf8_lossy(&c.line)
+                            .trim_end_matches(['\r', '\n'])
+                            .to_string();
+                        c.line.clear();
+                        match phase {
+                            chunkedPhase::SizeLine => {
+                                // 16进制chunk大小字符串,忽略 ;ext 形式的chunk扩展
+                                let size = line.split(';').next().unwrap_or("").trim();
+                                let size = u64::from_str_radix(size, 16).map_err(|_| {
+                                    io::Error::new(
+                                        ErrorKind::InvalidData,
+                                        "invalid byte in chunk length",
+                                    )
+                                })?;
+                                if size == 0 {
+                                    c.phase = chunkedPhase::Trailer;
+                                } else {
+                                    c.remaining = size;
+                                    c.phase = chunkedPhase::Data;
+                                }
+                            }
+                            chunkedPhase::DataCRLF => {
+                                if !line.is_empty() {
+                                    return Poll::Ready(Err(io::Error::new(
+                                        ErrorKind::InvalidData,
+                                        "malformed chunked encoding",
+                                    )));
+                                }
+                                c.phase = chunkedPhase::SizeLine;
+                            }
+                            _ => {
+                                // 读取trailer部分，直到空行，保证连接上剩余的数据属于下一个消息。
+                                if line.is_empty() {
+                                    if let Some(t) = &c.trailer {
+                                        let mut t = t.lock().unwrap_or_else(|e| e.into_inner());
+                                        for l in &c.Trailer {
+                                            if let Some((k, v)) = l.split_once(':') {
+                                                t.Add(k.trim(), v.trim());
+                                            }
+                                        }
+                                    }
+                                    *self = transferState::Done;
+                                } else {
+                                    c.Trailer.push(line);
+                                }
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+// pollReadLine appends bytes from src to line up to and including the next '\n'.
+fn pollReadLine<S: bufSource>(
+    src: &mut S,
+    cx: &mut Context<'_>,
+    line: &mut Vec<u8>,
+) -> Poll<io::Result<()>> {
+    loop {
+        let avail = match src.pollFillBuf(cx) {
+            Poll::Ready(Ok(avail)) => avail,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+        if avail.is_empty() {
+            return Poll::Ready(Err(io::Error::from(ErrorKind::UnexpectedEof)));
+        }
+        match avail.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                line.extend_from_slice(&avail[..=i]);
+                src.consumeBuf(i + 1);
+                return Poll::Ready(Ok(()));
+            }
+            None => {
+                let n = avail.len();
+                line.extend_from_slice(avail);
+                src.consumeBuf(n);
+            }
+        }
+        if line.len() > maxLineLength {
+            return Poll::Ready(Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "header line too long",
+            )));
+        }
+    }
+}
+
+// bufSource is the buffered input a transferState decodes from, implemented for both blocking and async readers.
+trait bufSource {
+    fn pollFillBuf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>>;
+    fn consumeBuf(&mut self, amt: usize);
+}
+
+struct syncSource<'a, R>(&'a mut R);
+
+impl<R: BufRead> bufSource for syncSource<'_, R> {
+    fn pollFillBuf(&mut self, _: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(self.0.fill_buf())
+    }
+
+    fn consumeBuf(&mut self, amt: usize) {
+        self.0.consume(amt)
+    }
+}
+
+#[cfg(feature = "async-http")]
+struct asyncSource<'a, R>(&'a mut R);
+
+#[cfg(feature = "async-http")]
+impl<R: AsyncBufRead + Unpin> bufSource for asyncSource<'_, R> {
+    fn pollFillBuf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut *self.0).poll_fill_buf(cx)
+    }
+
+    fn consumeBuf(&mut self, amt: usize) {
+        Pin::new(&mut *self.0).consume(amt)
+    }
+}
+
+// transferReader reads a message body framed by state off r.
+pub(crate) struct transferReader<R> {
+    pub(crate) r: R,
+    pub(crate) state: transferState,
+}
+
+impl<R> transferReader<R> {
+    pub(crate) fn new(r: R, state: transferState) -> transferReader<R> {
+        transferReader { r, state }
+    }
+}
+
+impl<R: BufRead> Read for transferReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.state.read(&mut self.r, buf)
+    }
+}
+
+#[cfg(feature = "async-http")]
+impl<R: AsyncBufRead + Unpin + Send> crate::body::asyncBodyReader for transferReader<R> {
+    fn pollRead(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        this.state.pollReadAsync(&mut this.r, cx, buf)
+    }
+}
+
+// writeBody copies body to w. A length of -1 sends the body with chunked transfer encoding; otherwise exactly length bytes must be sent.
+pub(crate) fn writeBody(w: &mut impl Write, body: &mut Body, length: int64) -> HttpResult<()> {
+    let mut buf = vec![0u8; 32 << 10];
+    if length < 0 {
+        loop {
+            let n = body.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            write!(w, "{:x}\r\n", n)?;
+            w.write_all(&buf[..n])?;
+            w.write_all(b"\r\n")?;
+        }
+        // 长度为0的chunk表示body结束，之后是空的trailer。
+        w.write_all(b"0\r\n\r\n")?;
+        return Ok(());
+    }
+    let mut ncopy: int64 = 0;
+    while ncopy < length {
+        let max = cmp::min(buf.len() as int64, length - ncopy) as usize;
+        let n = body.read(&mut buf[..max])?;
+        if n == 0 {
+            break;
+        }
+        w.write_all(&buf[..n])?;
+        ncopy += n as int64;
+    }
+    if ncopy != length {
+        return Err(HTTPConnectError::ConnectionFailure(format!(
+            "http: ContentLength={} with Body length {}",
+            length, ncopy
+        )));
+    }
+    Ok(())
+}
+
+// bodyAllowedForStatus reports whether a given response status code permits a body. See RFC 7230, section 3.3.
+pub(crate) fn bodyAllowedForStatus(status: int) -> bool {
+    !((100..=199).contains(&status) || status == 204 || status == 304)
+}
+
+fn isChunked(header: &Header) -> bool {
+    header
+        .0
+        .get("Transfer-Encoding")
+        .is_some_and(|te| headerValuesContainsToken(te, "chunked"))
+}
+
+// shouldClose determines whether to hang up after sending a request and body, or receiving a response and body.
+pub(crate) fn shouldClose(major: int, minor: int, header: &Header) -> bool {
+    if major < 1 {
+        return true;
+    }
+    let conv = header.Values("Connection");
+    let hasClose = headerValuesContainsToken(conv, "close");
+    if major == 1 && minor == 0 {
+        return hasClose || !headerValuesContainsToken(conv, "keep-alive");
+    }
+    hasClose
+}
+
+// headerValuesContainsToken reports whether any string in values contains the provided token, ASCII case-insensitively.
+pub(crate) fn headerValuesContainsToken(values: &[String], token: &str) -> bool {
+    values
+        .iter()
+        .any(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+}